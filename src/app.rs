@@ -1,27 +1,63 @@
+#[cfg(target_arch = "wasm32")]
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::path::PathBuf;
+#[cfg(target_arch = "wasm32")]
+use std::rc::Rc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::Ordering;
+use std::sync::{mpsc, Arc, Mutex};
 use std::time::Duration;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 
+use egui::text::LayoutJob;
 use egui::{
-    Align2, Color32, NumExt, Pos2, Rect, RichText, ScrollArea, Slider, Stroke, TextStyle, Vec2,
+    Align2, Color32, NumExt, Pos2, Rect, RichText, ScrollArea, Stroke, TextFormat, TextStyle, Vec2,
 };
 use egui_extras::{Column, TableBuilder};
-#[cfg(not(target_arch = "wasm32"))]
 use itertools::Itertools;
 use percentage::{Percentage, PercentageInteger};
 use regex::{escape, Regex};
 use serde::{Deserialize, Serialize};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::archive_data::{ArchiveProgress, DataSourceArchiveWriter};
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+use crate::cache_data::CachingDeferredDataSource;
+use crate::compact_data::CompactSlotMetaTileData;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::crop_data::CropDeferredDataSource;
 use crate::data::{
-    DataSourceInfo, EntryID, EntryIndex, EntryInfo, Field, FieldID, FieldSchema, ItemLink,
-    ItemMeta, ItemUID, SlotMetaTileData, SlotTileData, SummaryTileData, TileID, TileSet, UtilPoint,
+    DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID, EntryIDSlug, EntryIndex,
+    EntryInfo, Field, FieldFormat, FieldID, FieldSchema, Item, ItemLink, ItemMeta, ItemUID,
+    SearchMatch, SearchQuery, SearchResults, SlotMetaTile, SlotMetaTileData, SlotTile,
+    SlotTileData, SummaryTileData, TileID, TileIDSlug, TileSet, UtilPoint, PROTOCOL_VERSION,
 };
-use crate::deferred_data::{CountingDeferredDataSource, DeferredDataSource};
-use crate::timestamp::{
-    Interval, Timestamp, TimestampDisplay, TimestampParseError, TimestampUnits,
+use crate::deferred_data::DeferredDataSourceWrapper;
+use crate::deferred_data::{
+    CountingDeferredDataSource, DeferredDataSource, FailedRequest, RequestError, RequestKind,
+    RequestStatus,
+};
+#[cfg(target_arch = "wasm32")]
+use crate::directory_data::{self, DirectoryDataSource};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::file_data::FileDataSource;
+use crate::filter_data::EntryPattern;
+#[cfg(feature = "client")]
+use crate::http::client::{HTTPClientDataSource, HTTPTimeouts};
+use crate::http::tls::TlsOptions;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::item_export::{
+    export_items, export_utilization, ExportFormat, ExportedField, ExportedItem,
 };
+use crate::memory_data::MemoryDataSource;
+use crate::timestamp::{DisplayUnits, Interval, Timestamp, TimestampDisplay, TimestampParseError};
+use crate::view_link::{LinkedItem, ViewLink};
 
 /// Overview:
 ///   ProfApp -> Context, Window *
@@ -58,7 +94,247 @@ struct Summary {
     entry_id: EntryID,
     color: Color32,
     tiles: BTreeMap<TileID, Option<SummaryTileData>>,
-    last_view_interval: Option<Interval>,
+    // Tiles that failed to load. They stay requested (i.e., None above) so
+    // that they aren't requested again until the user retries them
+    failed: BTreeMap<TileID, RequestError>,
+    views: [ViewTiles; MAX_VIEWS],
+    // Bytes held by the tiles, as of the last change to them
+    memory: Option<usize>,
+    // Result of the last export of the utilization in view
+    #[cfg(not(target_arch = "wasm32"))]
+    export_status: Option<String>,
+}
+
+// Utilization of one kind of slot (e.g., CPU), averaged across the selected
+// nodes. Computed from the per-node summary tiles
+#[derive(Debug, Clone)]
+struct KindAggregate {
+    kind: String,
+    color: Color32,
+    nodes: usize,
+    // View interval, number of samples, number of nodes and number of loaded
+    // tiles the points were computed from, so they are only recomputed when
+    // one of these changes
+    key: (Interval, usize, usize, usize),
+    // Evenly spaced across the view interval
+    points: Vec<UtilPoint>,
+    complete: bool,
+}
+
+// Items of one tile regrouped by title, each title packed into as few rows
+// as it takes for its items not to overlap
+type PackedTitles = BTreeMap<String, Vec<Vec<(Item, ItemMeta)>>>;
+
+// Values of one field for the items of a tile, by row and item
+type FieldValues = Vec<Vec<Option<f64>>>;
+
+// Alternate layout of a slot that keeps each item on the same row across
+// tiles and zoom levels, in place of the rows from the data source. Items are
+// packed in order of first appearance, each on the topmost row that is free
+// for its whole (unclipped) interval. Like title lanes, tiles are re-laid out
+// once both they and their meta tiles have loaded
+#[derive(Debug, Clone, Default)]
+struct StableRows {
+    // Row of each item seen so far, from the top
+    assigned: BTreeMap<ItemUID, usize>,
+    // Intervals taken on each row, as start -> stop
+    occupied: Vec<BTreeMap<Timestamp, Timestamp>>,
+    // Tiles (along with their meta tiles) that have been re-laid out
+    regrouped: BTreeSet<TileID>,
+}
+
+impl StableRows {
+    // Start over after this many items, to bound memory
+    const MAX_ITEMS: usize = 1_000_000;
+
+    fn rows(&self) -> usize {
+        self.occupied.len()
+    }
+
+    // Approximate bytes held, not counting the overhead of the maps' nodes
+    fn heap_size(&self) -> usize {
+        let occupied: usize = self.occupied.iter().map(BTreeMap::len).sum();
+        self.assigned.len() * std::mem::size_of::<(ItemUID, usize)>()
+            + self.occupied.capacity() * std::mem::size_of::<BTreeMap<Timestamp, Timestamp>>()
+            + occupied * std::mem::size_of::<(Timestamp, Timestamp)>()
+            + self.regrouped.len() * std::mem::size_of::<TileID>()
+    }
+
+    fn is_free(row: &BTreeMap<Timestamp, Timestamp>, interval: Interval) -> bool {
+        // Intervals on a row don't overlap, so only the last one starting
+        // before the interval and the first one starting within it can
+        let before = row
+            .range(..interval.start)
+            .next_back()
+            .is_some_and(|(_, stop)| *stop > interval.start);
+        let within = row
+            .range(interval.start..)
+            .next()
+            .is_some_and(|(start, _)| *start < interval.stop || *start == interval.start);
+        !before && !within
+    }
+
+    // Forget every assignment once there are too many. Returns whether it
+    // did, in which case every tile must be fit in again
+    fn reset_if_full(&mut self) -> bool {
+        if self.assigned.len() <= Self::MAX_ITEMS {
+            return false;
+        }
+        self.assigned.clear();
+        self.occupied.clear();
+        true
+    }
+
+    // Assign rows to the items of a tile that haven't been seen before, in
+    // order of start time. Returns whether the number of rows grew, in which
+    // case every tile must be placed again
+    fn fit(&mut self, meta: &CompactSlotMetaTileData) -> bool {
+        let old_rows = self.rows();
+        let mut fresh: Vec<_> = meta
+            .iter()
+            .map(|(_, item_meta)| (item_meta.original_interval, item_meta.item_uid))
+            .filter(|(_, item_uid)| !self.assigned.contains_key(item_uid))
+            .collect();
+        fresh.sort_by_key(|(interval, item_uid)| (interval.start, *item_uid));
+        fresh.dedup_by_key(|(_, item_uid)| *item_uid);
+        for (interval, item_uid) in fresh {
+            let free = self
+                .occupied
+                .iter()
+                .position(|row| Self::is_free(row, interval));
+            let row = free.unwrap_or_else(|| {
+                self.occupied.push(BTreeMap::new());
+                self.occupied.len() - 1
+            });
+            self.occupied[row].insert(interval.start, interval.stop);
+            self.assigned.insert(item_uid, row);
+        }
+        self.rows() != old_rows
+    }
+
+    fn place(
+        &self,
+        tile: &SlotTileData,
+        meta: &CompactSlotMetaTileData,
+    ) -> (SlotTileData, CompactSlotMetaTileData) {
+        let rows = self.rows();
+        let mut placed: Vec<Vec<(Item, ItemMeta)>> = vec![Vec::new(); rows];
+        let items = tile
+            .items
+            .iter()
+            .enumerate()
+            .flat_map(|(row, items)| items.iter().map(move |item| (row, item)));
+        for ((row, item), (meta_row, item_meta)) in items.zip(meta.iter()) {
+            // A meta tile that failed to load may not match the tile
+            if row != meta_row || item.item_uid != item_meta.item_uid {
+                continue;
+            }
+            if let Some(row) = self.assigned.get(&item.item_uid) {
+                // Rows are counted from the bottom
+                placed[rows - row - 1].push((item.clone(), item_meta));
+            }
+        }
+
+        let mut items = Vec::with_capacity(rows);
+        let mut metas = Vec::with_capacity(rows);
+        for mut row in placed {
+            row.sort_by_key(|(item, _)| item.interval.start);
+            let (row_items, row_metas) = row.into_iter().unzip();
+            items.push(row_items);
+            metas.push(row_metas);
+        }
+        let meta = CompactSlotMetaTileData::new(&SlotMetaTileData { items: metas });
+        (SlotTileData { items }, meta)
+    }
+}
+
+// Alternate layout of a slot with one lane per item title, in place of the
+// rows from the data source, so that recurring items line up. Tiles are
+// regrouped once both they and their meta tiles have loaded
+#[derive(Debug, Clone, Default)]
+struct TitleLanes {
+    // Title and number of rows of each lane, from the top, in order of
+    // first appearance
+    lanes: Vec<(String, usize)>,
+    // Tiles (along with their meta tiles) that have been regrouped
+    regrouped: BTreeSet<TileID>,
+}
+
+impl TitleLanes {
+    fn rows(&self) -> usize {
+        self.lanes.iter().map(|(_, rows)| rows).sum()
+    }
+
+    fn pack(tile: &SlotTileData, meta: &CompactSlotMetaTileData) -> PackedTitles {
+        let mut titles: BTreeMap<String, Vec<(Item, ItemMeta)>> = BTreeMap::new();
+        let items = tile
+            .items
+            .iter()
+            .enumerate()
+            .flat_map(|(row, items)| items.iter().map(move |item| (row, item)));
+        for ((row, item), (meta_row, item_meta)) in items.zip(meta.iter()) {
+            // A meta tile that failed to load may not match the tile
+            if row != meta_row || item.item_uid != item_meta.item_uid {
+                continue;
+            }
+            let title = titles.entry(item_meta.title.clone()).or_default();
+            title.push((item.clone(), item_meta));
+        }
+
+        titles
+            .into_iter()
+            .map(|(title, mut items)| {
+                items.sort_by_key(|(item, _)| item.interval.start);
+                let mut rows: Vec<Vec<(Item, ItemMeta)>> = Vec::new();
+                for (item, item_meta) in items {
+                    let start = item.interval.start;
+                    match rows
+                        .iter_mut()
+                        .find(|row| row.last().unwrap().0.interval.stop <= start)
+                    {
+                        Some(row) => row.push((item, item_meta)),
+                        None => rows.push(vec![(item, item_meta)]),
+                    }
+                }
+                (title, rows)
+            })
+            .collect()
+    }
+
+    // Make room for the titles of a tile
+    fn fit(&mut self, packed: &PackedTitles) {
+        for (title, rows) in packed {
+            match self.lanes.iter_mut().find(|(t, _)| t == title) {
+                Some((_, lane_rows)) => *lane_rows = (*lane_rows).max(rows.len()),
+                None => self.lanes.push((title.clone(), rows.len())),
+            }
+        }
+    }
+
+    fn place(&self, mut packed: PackedTitles) -> (SlotTileData, CompactSlotMetaTileData) {
+        let rows = self.rows();
+        let mut items = vec![Vec::new(); rows];
+        let mut metas = vec![Vec::new(); rows];
+        let mut irow = 0;
+        for (title, lane_rows) in &self.lanes {
+            for (i, row) in packed
+                .remove(title)
+                .unwrap_or_default()
+                .into_iter()
+                .enumerate()
+            {
+                // Rows are counted from the bottom
+                let row_index = rows - (irow + i) - 1;
+                for (item, item_meta) in row {
+                    items[row_index].push(item);
+                    metas[row_index].push(item_meta);
+                }
+            }
+            irow += lane_rows;
+        }
+        let meta = CompactSlotMetaTileData::new(&SlotMetaTileData { items: metas });
+        (SlotTileData { items }, meta)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -68,10 +344,182 @@ struct Slot {
     long_name: String,
     expanded: bool,
     max_rows: u64,
-    tile_ids: Vec<TileID>,
+    // From the data source, indexed like the rows of the tiles
+    row_names: Vec<String>,
+    // Slot tiles are not compacted: every visible tile is drawn each frame
+    // (so it would be decoded each frame), and an Item is only a UID, an
+    // interval and a color, much smaller than its metadata
     tiles: BTreeMap<TileID, Option<SlotTileData>>,
-    tile_metas: BTreeMap<TileID, Option<SlotMetaTileData>>,
-    last_view_interval: Option<Interval>,
+    // Meta tiles are only needed on interaction (e.g., search), so they are
+    // kept compact and decoded item by item
+    tile_metas: BTreeMap<TileID, Option<CompactSlotMetaTileData>>,
+    // Slot and meta tiles that failed to load, as for summaries
+    failed: BTreeMap<(TileID, RequestKind), RequestError>,
+    // Individually fetched item metadata, so that tooltips don't require
+    // the whole meta tile
+    item_metas: BTreeMap<(TileID, ItemUID), Option<ItemMeta>>,
+    // Item selected with the keyboard, as (row, item), where rows are NOT
+    // reversed (i.e., row 0 is at the bottom of the slot)
+    focused_item: Option<(usize, ItemUID)>,
+    views: [ViewTiles; MAX_VIEWS],
+    // Cached item geometry, keyed by view, staleness and tile
+    meshes: BTreeMap<(usize, bool, TileID), TileMesh>,
+    mesh_generation: u64,
+    // Meshes of the current tiles of each view, merged
+    slot_meshes: BTreeMap<usize, SlotMesh>,
+    // Rasterized dense tiles, keyed by view and tile
+    textures: BTreeMap<(usize, TileID), TileTexture>,
+    // Values of the field items are colored by, by tile (and then by row
+    // and item), taken from the meta tiles
+    color_values: BTreeMap<TileID, (FieldID, FieldValues)>,
+    // Frame in which the slot was last drawn (and when), so that the tiles
+    // of slots that have been out of view the longest are evicted first
+    last_shown: u64,
+    last_shown_time: f64,
+    // Memory usage as of the last change to the tiles (or the meshes and
+    // other caches built from them), or None if it must be measured again
+    memory: Option<MemoryUsage>,
+    // Rows with items in the tiles last drawn in each view, for fitting the
+    // slot to its content
+    occupied_rows: [u64; MAX_VIEWS],
+    // First row (counted from the top) of the page shown when the slot has
+    // more rows than the cap
+    first_row: u64,
+    // Set while the slot is shown with one lane per item title
+    title_lanes: Option<TitleLanes>,
+    stable_rows: Option<StableRows>,
+    // Set while the slot is shown as a single density strip
+    heatmap: bool,
+    // Fraction of each view the slot is busy, shown in its label, along
+    // with the view interval and number of loaded tiles it was computed
+    // from, so that it is only recomputed when one of these changes
+    busy_fractions: [Option<((Interval, usize), f32)>; MAX_VIEWS],
+}
+
+// Approximate bytes held by a window, shown in the debug HUD
+#[derive(Debug, Copy, Clone, Default)]
+struct MemoryUsage {
+    // Summary and slot tiles, along with their meshes
+    tiles: usize,
+    metas: usize,
+    search: usize,
+}
+
+impl MemoryUsage {
+    fn total(self) -> usize {
+        self.tiles + self.metas + self.search
+    }
+}
+
+impl std::ops::AddAssign for MemoryUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.tiles += other.tiles;
+        self.metas += other.metas;
+        self.search += other.search;
+    }
+}
+
+fn item_meta_size(item_meta: &ItemMeta) -> usize {
+    std::mem::size_of::<ItemMeta>()
+        + item_meta.title.capacity()
+        + item_meta.fields.capacity() * std::mem::size_of::<(FieldID, Field, Option<Color32>)>()
+}
+
+// Items narrower than this many pixels are candidates for merging
+const MIN_ITEM_WIDTH: f32 = 1.0;
+
+// A run of sub-pixel items within one pixel column of a row
+struct MergedItems {
+    column: f32,
+    rect: Rect,
+    color: Color32,
+    count: usize,
+    // Total width of the items, in pixels
+    covered: f32,
+    highlight: bool,
+}
+
+impl MergedItems {
+    fn add(&mut self, rect: Rect, highlight: bool) {
+        self.rect = self.rect.union(rect);
+        self.count += 1;
+        self.covered += rect.width();
+        self.highlight |= highlight;
+    }
+
+    fn draw(self, add_rect: &mut impl FnMut(Rect, Color32, bool)) {
+        if self.count == 1 {
+            add_rect(self.rect, self.color, self.highlight);
+            return;
+        }
+        // Fill the whole pixel, shaded by how much of it is covered
+        let mut rect = self.rect;
+        rect.min.x = self.column;
+        rect.max.x = self.column + MIN_ITEM_WIDTH;
+        let density = (self.covered / MIN_ITEM_WIDTH).clamp(0.0, 1.0);
+        let color = Color32::GRAY.gamma_multiply(0.25 + 0.75 * density);
+        add_rect(rect, color, self.highlight);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TileMeshKey {
+    view_interval: Interval,
+    size: Vec2,
+    rows: u64,
+    highlight: u64,
+    dim: bool,
+    // Field and range items are colored by, and whether the tile's values
+    // had loaded
+    color: Option<(FieldID, f64, f64, bool)>,
+}
+
+#[derive(Debug, Clone)]
+struct TileMesh {
+    key: TileMeshKey,
+    // Tells rebuilds of the same tile's meshes apart (e.g., after the tile
+    // was updated)
+    generation: u64,
+    // One mesh per row, relative to the slot rect
+    meshes: Vec<egui::Mesh>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TileTextureKey {
+    // Log2 of the nanoseconds per texel
+    zoom: i32,
+    height: usize,
+    rows: u64,
+    highlight: u64,
+}
+
+#[derive(Clone)]
+struct TileTexture {
+    key: TileTextureKey,
+    texture: egui::TextureHandle,
+}
+
+impl fmt::Debug for TileTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TileTexture")
+            .field("key", &self.key)
+            .field("texture", &self.texture.id())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SlotMeshKey {
+    // Tiles drawn, with the generation of their meshes
+    tiles: Vec<(TileID, u64)>,
+    visible_rows: Range<u64>,
+}
+
+#[derive(Debug, Clone)]
+struct SlotMesh {
+    key: SlotMeshKey,
+    // Visible rows of every tile, relative to the slot rect
+    mesh: egui::Mesh,
 }
 
 #[derive(Debug, Clone)]
@@ -83,6 +531,10 @@ struct Panel<S: Entry> {
 
     summary: Option<Summary>,
     slots: Vec<S>,
+
+    slot_order: SlotOrder,
+    // Indices of the slots in the order they are shown, as of the last frame
+    display_order: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -95,6 +547,30 @@ struct ItemLocator {
     // If we can't find the item on the initial attempt, we track the ItemUID
     // and attempt to find it once the tile loads
     item_uid: ItemUID,
+
+    // Source locator of the profile containing the item, if it lives in a
+    // different profile from the one that linked to it
+    profile: Option<Vec<String>>,
+}
+
+// Name, value, color and format of a row in the item details table
+type FieldRow<'a> = (&'a str, &'a Field, Option<Color32>, Option<FieldFormat>);
+
+// Navigation requested by clicking on a button in an item's fields
+#[derive(Debug, Clone)]
+enum FieldTarget {
+    Item(ItemLocator, Interval),
+    Time(Timestamp),
+}
+
+// An item pinned by the user, with enough information to list it and jump to
+// it even when none of its tiles are loaded
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct WatchedItem {
+    entry_id: EntryID,
+    irow: Option<usize>,
+    title: String,
+    interval: Interval,
 }
 
 #[derive(Debug, Clone)]
@@ -102,6 +578,250 @@ struct ItemDetail {
     // We populate metadata lazily, so there can be a delay until this is full
     meta: Option<ItemMeta>,
     loc: ItemLocator,
+
+    // Case insensitive filter on field names and values
+    field_filter: String,
+}
+
+#[derive(Debug, Clone)]
+struct IdleGapAnalysis {
+    entry_id: EntryID,
+    long_name: String,
+
+    // Interval the analysis was computed over
+    interval: Interval,
+
+    // Largest gaps first
+    gaps: Vec<Interval>,
+
+    // False if some tiles were still loading when the analysis ran
+    complete: bool,
+}
+
+// Items colored by the value of a numeric field, on a gradient over the
+// range of values seen so far
+#[derive(Debug, Clone)]
+struct ColorBy {
+    field_id: FieldID,
+    min: f64,
+    max: f64,
+}
+
+impl ColorBy {
+    // Viridis, which stays readable for most kinds of color blindness
+    const GRADIENT: [Color32; 5] = [
+        Color32::from_rgb(68, 1, 84),
+        Color32::from_rgb(59, 82, 139),
+        Color32::from_rgb(33, 145, 140),
+        Color32::from_rgb(94, 201, 98),
+        Color32::from_rgb(253, 231, 37),
+    ];
+    const MISSING: Color32 = Color32::DARK_GRAY;
+
+    fn new(field_id: FieldID) -> Self {
+        Self {
+            field_id,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn has_values(&self) -> bool {
+        self.min <= self.max
+    }
+
+    fn include(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn gradient(t: f32) -> Color32 {
+        let t = t.clamp(0.0, 1.0) * (Self::GRADIENT.len() - 1) as f32;
+        let i = (t as usize).min(Self::GRADIENT.len() - 2);
+        let (a, b) = (Self::GRADIENT[i], Self::GRADIENT[i + 1]);
+        let f = t - i as f32;
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+        Color32::from_rgb(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+    }
+
+    fn color(&self, value: Option<f64>) -> Color32 {
+        let Some(value) = value else {
+            return Self::MISSING;
+        };
+        let t = if self.max > self.min {
+            (value - self.min) / (self.max - self.min)
+        } else {
+            0.5
+        };
+        Self::gradient(t as f32)
+    }
+}
+
+// Time spent in a slot over an interval, by item title, computed from its
+// meta tiles
+#[derive(Debug, Clone)]
+struct TimeBreakdown {
+    entry_id: EntryID,
+    long_name: String,
+    interval: Interval,
+
+    // Full meta tiles covering the interval, fetched for the breakdown
+    // alone, since the slot's own may be sampled (see Context::full_tiles)
+    tiles: BTreeMap<TileID, Option<CompactSlotMetaTileData>>,
+    // Set if one of the tiles failed to load
+    error: Option<String>,
+
+    // As (title, time within the interval, number of items), most time
+    // first. None until the meta tiles have loaded
+    titles: Option<Vec<(String, i64, usize)>>,
+}
+
+impl TimeBreakdown {
+    fn new(entry_id: EntryID, long_name: String, interval: Interval) -> Self {
+        Self {
+            entry_id,
+            long_name,
+            interval,
+            tiles: BTreeMap::new(),
+            error: None,
+            titles: None,
+        }
+    }
+
+    fn is_pending(&self, kind: RequestKind, entry_id: &EntryID, tile_id: TileID) -> bool {
+        kind == RequestKind::SlotMetaTile
+            && self.entry_id == *entry_id
+            && matches!(self.tiles.get(&tile_id), Some(None))
+    }
+
+    // Returns false if the tile wasn't requested for the breakdown
+    fn receive(&mut self, tile: &SlotMetaTile) -> bool {
+        if !self.is_pending(RequestKind::SlotMetaTile, &tile.entry_id, tile.tile_id) {
+            return false;
+        }
+        let data = CompactSlotMetaTileData::new(&tile.data);
+        self.tiles.insert(tile.tile_id, Some(data));
+        true
+    }
+
+    // Request the tiles, and once they have all loaded, total the time by
+    // title. Items in multiple tiles are only counted once
+    fn update(&mut self, config: &mut Config) {
+        if self.titles.is_some() || self.error.is_some() {
+            return;
+        }
+        if self.tiles.is_empty() {
+            for tile_id in config.full_tile_ids(self.interval) {
+                config
+                    .data_source
+                    .fetch_slot_meta_tile(&self.entry_id, tile_id, true);
+                self.tiles.insert(tile_id, None);
+            }
+        }
+        if self.tiles.values().any(Option::is_none) {
+            return;
+        }
+
+        let mut seen = BTreeSet::new();
+        let mut titles: BTreeMap<String, (i64, usize)> = BTreeMap::new();
+        for tile in self.tiles.values().flatten() {
+            for (_, item) in tile.iter() {
+                let overlap = item.original_interval.intersection(self.interval);
+                let duration = overlap.duration_ns();
+                if duration <= 0 || !seen.insert(item.item_uid) {
+                    continue;
+                }
+                let entry = titles.entry(item.title).or_default();
+                entry.0 += duration;
+                entry.1 += 1;
+            }
+        }
+        let mut result: Vec<_> = titles
+            .into_iter()
+            .map(|(title, (duration, count))| (title, duration, count))
+            .collect();
+        result.sort_by_key(|(_, duration, _)| std::cmp::Reverse(*duration));
+        self.titles = Some(result);
+    }
+}
+
+// Chain of items found by repeatedly following one link field (e.g., the
+// item that created each item) back from a selected item
+#[derive(Debug, Clone)]
+struct OriginTrace {
+    field_id: FieldID,
+    field_name: String,
+
+    // Starting with the selected item, as (locator, title, interval)
+    hops: Vec<(ItemLocator, String, Interval)>,
+    item_uids: BTreeSet<ItemUID>,
+
+    // Why the walk stopped, or None while it's still going
+    end: Option<String>,
+
+    // Outline the items of the chain on the timeline
+    highlight: bool,
+}
+
+impl OriginTrace {
+    fn new(field_id: FieldID, field_name: &str, loc: ItemLocator, meta: &ItemMeta) -> Self {
+        Self {
+            field_id,
+            field_name: field_name.to_owned(),
+            item_uids: BTreeSet::from([loc.item_uid]),
+            hops: vec![(loc, meta.title.clone(), meta.original_interval)],
+            end: None,
+            highlight: true,
+        }
+    }
+
+    // Follow the link field of the last item in the chain, given its
+    // metadata
+    fn advance(&mut self, meta: &ItemMeta, source_locator: &[String]) {
+        const MAX_HOPS: usize = 100;
+
+        let link = meta
+            .fields
+            .iter()
+            .find_map(|(field_id, field, _)| match field {
+                Field::ItemLink(link) if *field_id == self.field_id => Some(link),
+                _ => None,
+            });
+        let Some(link) = link else {
+            self.end = Some("Reached the origin.".to_owned());
+            return;
+        };
+        if link.profile.as_ref().is_some_and(|p| p != source_locator) {
+            self.end = Some(format!("Continues in another profile at {}.", link.title));
+        } else if self.item_uids.contains(&link.item_uid) {
+            self.end = Some(format!("Stopped at a cycle back to {}.", link.title));
+        } else if self.hops.len() >= MAX_HOPS {
+            self.end = Some(format!("Stopped after {MAX_HOPS} items."));
+        } else {
+            let loc = ItemLocator {
+                entry_id: link.entry_id.clone(),
+                irow: None,
+                item_uid: link.item_uid,
+                profile: None,
+            };
+            self.item_uids.insert(link.item_uid);
+            self.hops.push((loc, link.title.clone(), link.interval));
+        }
+    }
+}
+
+// Statistics of a profile over a selected interval, computed from the loaded
+// tiles of the visible slots
+#[derive(Debug, Clone, Default)]
+struct IntervalStats {
+    full_items: usize,
+    partial_items: usize,
+
+    // Busy fraction of each visible slot
+    slots: Vec<(String, f32)>,
+
+    // False if some tiles covering the interval are not loaded
+    complete: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -119,6 +839,20 @@ struct SearchCacheItem {
     irow: usize,
 }
 
+// Progress of the search by the data source, for data sources that support
+// it (see DataSourceInfo::supports_search)
+#[derive(Debug, Clone, PartialEq)]
+enum RemoteSearch {
+    // Not requested yet for the current search, e.g., because the view is
+    // still moving
+    Idle,
+    Pending(SearchQuery, Interval),
+    Done,
+    // The data source failed, or found too many matches, so the meta tiles
+    // are searched here as well
+    Incomplete,
+}
+
 #[derive(Debug, Clone)]
 struct SearchState {
     title_field: FieldID,
@@ -133,24 +867,151 @@ struct SearchState {
     last_word_regex: Option<Regex>,
     include_collapsed_entries: bool,
     last_include_collapsed_entries: bool,
+    // Slots picked by the user, and whether the search is restricted to
+    // them (expanded or not), so that a question about a few known slots
+    // doesn't wait on the whole profile
+    scope: BTreeSet<EntryID>,
+    scope_only: bool,
+    last_scope: Option<BTreeSet<EntryID>>,
     last_view_interval: Option<Interval>,
+    // Incremented every time the cache is cleared
+    generation: u64,
+
+    // Meta tiles are fetched incrementally, visible entries first. These
+    // track the current pass and the progress of the search
+    meta_budget: usize,
+    meta_pass_visible: bool,
+    walk_visible: bool,
+    searched_entries: u64,
+    total_entries: u64,
+
+    // If the data source can search, its matches are merged with the ones
+    // found in the meta tiles that are already loaded, and the rest of the
+    // meta tiles need not be fetched. Matches wait here (by slot) until the
+    // walk over the slots reaches them, so that they are filtered like the
+    // others
+    remote_search: bool,
+    remote_status: RemoteSearch,
+    remote_matches: BTreeMap<EntryID, Vec<SearchMatch>>,
+
+    // Kept across searches, as long as the field stays the same
+    known_values: KnownValues,
 
     // Cache of matching items
     result_set: BTreeSet<ItemUID>,
     result_cache: BTreeMap<EntryID, BTreeMap<TileID, BTreeMap<ItemUID, SearchCacheItem>>>,
+    // Bytes held by the cached items, counted as they are inserted
+    cache_bytes: usize,
     entry_tree: BTreeMap<u64, BTreeMap<u64, BTreeSet<u64>>>,
+
+    // Results in the order shown in the table, and the generation, result
+    // count and sort order they were sorted for
+    table_rows: Vec<(EntryID, TileID, ItemUID)>,
+    table_key: Option<(u64, usize, SearchColumn, bool)>,
+
+    // Results by title, longest total duration first, and the generation
+    // and result count they were grouped for
+    title_groups: Vec<SearchTitleGroup>,
+    title_groups_key: Option<(u64, usize)>,
+
+    // Durations of the results, for the same generation and result count
+    stats: Option<SearchStats>,
+    stats_key: Option<(u64, usize)>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SearchStats {
+    // All in nanoseconds
+    total: i64,
+    mean: i64,
+    median: i64,
+    p90: i64,
+    p99: i64,
+    max: i64,
+}
+
+impl SearchStats {
+    fn new(mut durations: Vec<i64>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_unstable();
+        let count = durations.len();
+        let total: i64 = durations.iter().sum();
+        // Nearest rank
+        let percentile = |p: usize| durations[(p * count).div_ceil(100).max(1) - 1];
+        Some(Self {
+            total,
+            mean: total / count as i64,
+            median: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: durations[count - 1],
+        })
+    }
+}
+
+// Distinct values of the search field in the meta tiles loaded so far, so
+// that they can be offered while typing a query. Collected a tile at a time,
+// and given up on for fields with too many values to be worth picking from
+#[derive(Debug, Clone, Default)]
+struct KnownValues {
+    field: Option<FieldID>,
+    tiles: BTreeMap<EntryID, BTreeSet<TileID>>,
+    values: BTreeSet<String>,
+    too_many: bool,
+    // Set when meta tiles arrive, so that the slots are only walked then
+    stale: bool,
+}
+
+impl KnownValues {
+    const MAX_VALUES: usize = 1000;
+
+    fn insert(&mut self, values: impl IntoIterator<Item = String>) {
+        self.values.extend(values);
+        if self.values.len() > Self::MAX_VALUES {
+            self.values.clear();
+            self.too_many = true;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SearchTitleGroup {
+    title: String,
+    total: i64,
+    // In order of start time
+    items: Vec<(EntryID, TileID, ItemUID)>,
 }
 
 struct Config {
+    description: DataSourceDescription,
+
+    // Name given to the profile by the user for this session
+    name: Option<String>,
+    // Text being edited while renaming the profile
+    rename_buffer: Option<String>,
+
     field_schema: FieldSchema,
 
     // Node selection
-    min_node: u64,
-    max_node: u64,
+    nodes: u64,
+    selected_nodes: BTreeSet<u64>,
+    node_query: String,
 
     // Kind selection
     kinds: Vec<String>,
     kind_filter: BTreeSet<String>,
+    // When set, kinds in the filter are hidden rather than shown
+    kind_filter_exclude: bool,
+
+    // Slot name filter (case insensitive)
+    name_filter: String,
+    name_filter_regex: Option<Regex>,
+
+    // Named view intervals saved by the user
+    zoom_presets: BTreeMap<String, Interval>,
+    zoom_preset_name: String,
 
     // This is just for the local profile
     interval: Interval,
@@ -158,26 +1019,134 @@ struct Config {
     warning_message: Option<String>,
 
     data_source: CountingDeferredDataSource<Box<dyn DeferredDataSource>>,
+    // Whether tiles are requested full (see Context::full_tiles)
+    full_tiles: bool,
 
     search_state: SearchState,
 
     // When the user clicks on an item, we put it here
     items_selected: BTreeMap<ItemUID, ItemDetail>,
 
-    // When the user clicks "Zoom to Item" or a search result, we put it here
+    // Items pinned to the watch list
+    watched_items: BTreeMap<ItemUID, WatchedItem>,
+
+    // When the user requests idle gaps for a slot, we put them here
+    idle_gaps: Option<IdleGapAnalysis>,
+
+    // When the user asks where a slot spent its time, we put it here
+    time_breakdown: Option<TimeBreakdown>,
+
+    // When the user traces an item back to its origin, we put it here
+    origin_trace: Option<OriginTrace>,
+
+    // Color items by a field instead of their own colors
+    color_by: Option<ColorBy>,
+
+    // Window listing the busiest slots in the view interval
+    show_overview: bool,
+    overview_count: usize,
+
+    // Show the utilization of each kind across the selected nodes above the
+    // timeline
+    show_kind_aggregates: bool,
+
+    // Summaries plotted together above the timeline, in the order they
+    // were picked
+    overlay_summaries: Vec<EntryID>,
+
+    // When the user clicks "Zoom to Item" or a search result, we put it here
     scroll_to_item: Option<ItemLocator>,
     // Sometimes, we cannot find the correct row to scroll to. In this case we
     // populate the following field to track the re-scroll when the item is found
     scroll_to_item_retry: Option<ItemLocator>,
 
+    // When the user clicks on an entry in an item's breadcrumb, we put it here
+    scroll_to_entry: Option<EntryID>,
+
     last_request_interval: Option<Interval>,
     request_tile_cache: Vec<TileID>,
+    // Width of the timeline on screen, in points, or 0 if not known yet
+    view_width: f32,
+
+    // Changes whenever item highlighting (selection or search) changes, so
+    // that cached tile meshes can be invalidated
+    highlight_key: u64,
 }
 
 struct Window {
     panel: Panel<Panel<Panel<Slot>>>, // nodes -> kind -> proc/chan/mem
     index: u64,
     config: Config,
+
+    // Vertical scroll position, shared by both halves of the split view
+    scroll_offset: f32,
+
+    // Set when the profile is shown in its own OS window
+    detached: Option<Detached>,
+    // Set by the pop out/dock button, handled at the end of the frame
+    toggle_detached: bool,
+
+    // As of the start of the frame
+    memory_usage: MemoryUsage,
+
+    kind_aggregates: Vec<KindAggregate>,
+    overlay_curves: Vec<OverlayCurve>,
+
+    // Debug window listing recent requests to the data source
+    show_request_log: bool,
+
+    // Result of the last export of the selected and watched items
+    #[cfg(not(target_arch = "wasm32"))]
+    items_export_status: Option<String>,
+
+    // Archive being written from the selected interval, if any
+    #[cfg(not(target_arch = "wasm32"))]
+    archive_export: Option<ArchiveExport>,
+
+    // Node selection and kind filter as of the last check, and the entries
+    // they left active, as last passed to the data source
+    active_filter: Option<(BTreeSet<u64>, BTreeSet<String>, bool)>,
+    active_entries: Option<BTreeSet<EntryID>>,
+
+    // Tiles that were still being fetched when they were invalidated. Their
+    // responses are stale, so they are dropped, and the tiles are fetched
+    // again once they arrive
+    stale_requests: BTreeSet<(RequestKind, EntryID, TileID)>,
+
+    // Fields seen with numeric values in the meta tiles loaded so far, which
+    // are the ones items can be colored by
+    numeric_fields: BTreeSet<FieldID>,
+}
+
+// An archive written on a worker thread (see Window::export_archive)
+#[cfg(not(target_arch = "wasm32"))]
+struct ArchiveExport {
+    progress: Arc<ArchiveProgress>,
+    result: mpsc::Receiver<std::io::Result<PathBuf>>,
+}
+
+// State of a profile popped out into its own OS window
+struct Detached {
+    view: ViewState,
+    // Zoom and pan independently of the main window
+    independent: bool,
+}
+
+// The subset of Config that persists across sessions
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct ConfigState {
+    hidden_nodes: BTreeSet<u64>,
+    kind_filter: BTreeSet<String>,
+    kind_filter_exclude: bool,
+    name_filter: String,
+
+    // Entries whose expansion differs from the default
+    toggled_entries: BTreeSet<EntryID>,
+
+    zoom_presets: BTreeMap<String, Interval>,
+
+    watched_items: BTreeMap<ItemUID, WatchedItem>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
@@ -198,6 +1167,10 @@ enum IntervalSelectError {
     InvalidValue,
     NoUnit,
     InvalidUnit,
+    InvalidClock,
+    ClockOutOfRange,
+    EmptyOffset,
+    UnknownKeyword,
     StartAfterStop,
     StartAfterEnd,
     StopBeforeStart,
@@ -209,6 +1182,10 @@ impl From<TimestampParseError> for IntervalSelectError {
             TimestampParseError::InvalidValue => IntervalSelectError::InvalidValue,
             TimestampParseError::NoUnit => IntervalSelectError::NoUnit,
             TimestampParseError::InvalidUnit => IntervalSelectError::InvalidUnit,
+            TimestampParseError::InvalidClock => IntervalSelectError::InvalidClock,
+            TimestampParseError::ClockOutOfRange => IntervalSelectError::ClockOutOfRange,
+            TimestampParseError::EmptyOffset => IntervalSelectError::EmptyOffset,
+            TimestampParseError::UnknownKeyword => IntervalSelectError::UnknownKeyword,
         }
     }
 }
@@ -217,8 +1194,19 @@ impl fmt::Display for IntervalSelectError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             IntervalSelectError::InvalidValue => write!(f, "invalid value"),
-            IntervalSelectError::NoUnit => write!(f, "no unit"),
-            IntervalSelectError::InvalidUnit => write!(f, "invalid unit"),
+            IntervalSelectError::NoUnit => write!(f, "no unit (e.g. 1.5 ms)"),
+            IntervalSelectError::InvalidUnit => write!(f, "invalid unit (use ns, us, ms or s)"),
+            IntervalSelectError::InvalidClock => {
+                write!(f, "invalid clock time (use [HH:]MM:SS[.frac])")
+            }
+            IntervalSelectError::ClockOutOfRange => {
+                write!(
+                    f,
+                    "clock field out of range (minutes and seconds must be below 60)"
+                )
+            }
+            IntervalSelectError::EmptyOffset => write!(f, "missing offset after + (e.g. +500 us)"),
+            IntervalSelectError::UnknownKeyword => write!(f, "unknown keyword (use start or end)"),
             IntervalSelectError::StartAfterStop => write!(f, "start after stop"),
             IntervalSelectError::StartAfterEnd => write!(f, "start after end"),
             IntervalSelectError::StopBeforeStart => write!(f, "stop before start"),
@@ -237,1481 +1225,7527 @@ struct IntervalSelectState {
     stop_error: Option<IntervalSelectError>,
 }
 
-#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
-enum ItemLinkNavigationMode {
-    #[default]
-    Zoom,
-    Pan,
-}
-
-impl ItemLinkNavigationMode {
-    fn label_text(&self) -> &'static str {
-        match *self {
-            ItemLinkNavigationMode::Zoom => "Zoom to Item",
-            ItemLinkNavigationMode::Pan => "Pan to Item",
-        }
-    }
-}
+const UI_SCALE_PRESETS: [f32; 8] = [0.5, 0.75, 1.0, 1.25, 1.5, 2.0, 2.5, 3.0];
+const FONT_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.0;
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
-struct Context {
-    #[serde(skip)]
-    row_height: f32,
-    #[serde(skip)]
-    scale_factor: f32,
+// Requests sent to a data source at once (the rest are queued)
+const MAX_REQUESTS_RANGE: std::ops::RangeInclusive<u64> = 1..=1024;
+const DEFAULT_MAX_REQUESTS: u64 = 64;
 
-    #[serde(skip)]
-    row_scroll_delta: i32,
+// Budget for the tiles held by each window, in MiB
+const MEMORY_BUDGET_RANGE: std::ops::RangeInclusive<u64> = 64..=65536;
 
-    #[serde(skip)]
-    subheading_size: f32,
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_ARCHIVE_NAME: &str = "legion_prof_crop";
+const DEFAULT_MEMORY_BUDGET: u64 = 2048;
+
+const MAX_SLOT_ROWS_RANGE: std::ops::RangeInclusive<u64> = 4..=1024;
+const DEFAULT_MAX_SLOT_ROWS: u64 = 32;
+
+// Limits on the level of a static tile set chosen for a view, when the data
+// source has estimates for its levels (see Config::fit_static_level): tiles
+// narrower than this on screen aren't worth a request each, ...
+const MIN_PIXELS_PER_TILE: f64 = 64.0;
+// ... items packed more densely than this can't be told apart anyway, ...
+const MAX_ITEMS_PER_PIXEL: f64 = 2.0;
+// ... and the tiles for the view of a slot must fit in this many bytes
+const TILE_PAYLOAD_BUDGET: u64 = 256 * 1024;
+// The level is only chosen again when the width of the view changes by more
+// than this fraction, so that resizing the window doesn't refetch every frame
+const VIEW_WIDTH_HYSTERESIS: f32 = 0.1;
+
+// Tiles with at least this many items may be drawn from a texture instead
+// of a mesh, and the largest texture side
+const DENSE_TILE_ITEMS: usize = 50_000;
+const MAX_TILE_TEXTURE_SIZE: usize = 4096;
+
+// Repaint interval while requests are outstanding. Once a fetch has been
+// pending for a while, redrawing at full speed only burns power
+const LOADING_REPAINT: Duration = Duration::from_millis(50);
+const LONG_FETCH_SECONDS: f64 = 2.0;
+const LONG_FETCH_REPAINT: Duration = Duration::from_millis(250);
+
+// Commands from a ViewerHandle wait at most this long for profiles to load
+const COMMAND_HOLD_SECONDS: f64 = 30.0;
+// Slowest repaint interval in power saving mode, while the window is not
+// focused. Otherwise, profiles that are changing wake the viewer up when
+// there are updates (see DeferredDataSource::set_waker)
+const UNFOCUSED_LOADING_REPAINT: Duration = Duration::from_millis(500);
+
+// Maximum number of views (the main view, the split view, the magnifier and
+// the independent view of a popped out profile)
+const MAX_VIEWS: usize = 4;
+const MAGNIFIER_VIEW: usize = 2;
+const DETACHED_VIEW: usize = 3;
+
+// Number of profiles remembered in File > Open Recent
+const MAX_RECENT_SOURCES: usize = 10;
 
-    // This is across all profiles
-    #[serde(skip)]
-    total_interval: Interval,
+#[derive(Debug, Clone, Default)]
+struct ViewState {
+    // Index of this view, used to keep per-view tile requests apart
+    index: usize,
 
     // Visible time range
-    #[serde(skip)]
-    view_interval: Interval,
+    interval: Interval,
+
+    // Time range that tiles are requested for. While the user is rapidly
+    // zooming or panning, this trails the visible range to avoid flooding
+    // the data source with requests for transient intervals
+    request_interval: Interval,
+    last_interval: Interval,
+    last_interval_change: f64,
+
+    history: IntervalState,
+    interval_select_state: IntervalSelectState,
 
-    #[serde(skip)]
     drag_origin: Option<Pos2>,
+    drag_action: Option<DragAction>,
 
     // Hack: We need to track the screenspace rect where slot/summary
     // data gets drawn. This gets used rendering the cursor, but we
     // only know it when we render slots. So stash it here.
-    #[serde(skip)]
     slot_rect: Option<Rect>,
 
-    item_link_mode: ItemLinkNavigationMode,
-
-    toggle_dark_mode: bool,
-
-    debug: bool,
-
-    #[serde(skip)]
-    show_controls: bool,
-
-    #[serde(skip)]
-    view_interval_history: IntervalState,
-    #[serde(skip)]
-    interval_select_state: IntervalSelectState,
+    // Slot under the pointer, found while drawing slots, as its name, the
+    // row under the pointer (if expanded) and the row's name (if any).
+    // Shown by the cursor
+    hover_slot: Option<(String, Option<u64>, Option<String>)>,
 }
 
-#[derive(Default, Deserialize, Serialize)]
-#[serde(default)] // deserialize missing fields as default value
-struct ProfApp {
-    // Data sources waiting to be turned into windows.
-    #[serde(skip)]
-    pending_data_sources: VecDeque<Box<dyn DeferredDataSource>>,
-
-    #[serde(skip)]
-    windows: Vec<Window>,
-
-    cx: Context,
-
-    #[cfg(not(target_arch = "wasm32"))]
-    #[serde(skip)]
-    last_update: Option<Instant>,
+impl ViewState {
+    // Take over the time range of another view, keeping our own UI state
+    fn follow(&mut self, other: &ViewState) {
+        self.interval = other.interval;
+        self.request_interval = other.request_interval;
+        self.last_interval = other.last_interval;
+        self.last_interval_change = other.last_interval_change;
+        self.history = other.history.clone();
+    }
 }
 
-trait Entry {
-    fn new(info: &EntryInfo, entry_id: EntryID) -> Self;
-
-    fn entry_id(&self) -> &EntryID;
-    fn label_text(&self) -> &str;
-    fn hover_text(&self) -> &str;
-
-    fn find_slot(&self, entry_id: &EntryID, level: u64) -> Option<&Slot>;
-    fn find_slot_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Slot>;
-    fn find_summary_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Summary>;
-
-    fn expand_slot(&mut self, entry_id: &EntryID, level: u64);
-
-    fn inflate_meta(&mut self, config: &mut Config, cx: &mut Context);
+// Tiles requested by one view. Tile data is shared between views, so
+// each view only tracks which tiles it needs.
+#[derive(Debug, Clone, Default)]
+struct ViewTiles {
+    tile_ids: Vec<TileID>,
+    // Previously displayed tiles, kept until the current ones have loaded
+    stale_tile_ids: Vec<TileID>,
+    last_view_interval: Option<Interval>,
+}
 
-    fn search(&mut self, config: &mut Config);
+// State of a tile as shown by the debug overlay
+enum TileDebugState {
+    // Not requested, e.g., released before it loaded
+    Missing,
+    Requested,
+    Failed,
+    // With a summary of the contents
+    Loaded(String),
+}
 
-    fn label(&mut self, ui: &mut egui::Ui, rect: Rect, cx: &Context) {
-        let response = ui.allocate_rect(
-            rect,
-            if self.is_expandable() {
-                egui::Sense::click()
+impl ViewTiles {
+    // In debug mode, mark the boundaries, load state and duration of each
+    // tile, so that data source authors can see what was requested and
+    // received. Stale tiles are labeled at the bottom, current ones at the
+    // top
+    fn render_debug(
+        &self,
+        state: impl Fn(&TileID) -> TileDebugState,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        cx: &Context,
+    ) {
+        let font_id = TextStyle::Small.resolve(ui.style());
+        let text_color = ui.visuals().strong_text_color();
+        let stale = self.stale_tile_ids.iter().map(|tile_id| (tile_id, true));
+        let current = self.tile_ids.iter().map(|tile_id| (tile_id, false));
+        for (tile_id, is_stale) in stale.chain(current) {
+            if !cx.view.interval.overlaps(tile_id.0) {
+                continue;
+            }
+            let to_x = |time: Timestamp| {
+                let x = rect.lerp_inside(Vec2::new(cx.view.interval.unlerp(time), 0.0));
+                x.x.clamp(rect.min.x, rect.max.x)
+            };
+            let tile_rect =
+                Rect::from_x_y_ranges(to_x(tile_id.0.start)..=to_x(tile_id.0.stop), rect.y_range());
+
+            let (color, label) = match state(tile_id) {
+                TileDebugState::Missing => (Color32::RED, "missing".to_owned()),
+                TileDebugState::Requested => (Color32::YELLOW, "requested".to_owned()),
+                TileDebugState::Failed => (Color32::LIGHT_RED, "failed".to_owned()),
+                TileDebugState::Loaded(_) if is_stale => (Color32::GRAY, "stale".to_owned()),
+                TileDebugState::Loaded(contents) => (Color32::GREEN, contents),
+            };
+            ui.painter()
+                .rect_filled(tile_rect, 0.0, color.gamma_multiply(0.15));
+            let stroke = Stroke::new(1.0, color);
+            ui.painter().vline(tile_rect.min.x, rect.y_range(), stroke);
+            ui.painter().vline(tile_rect.max.x, rect.y_range(), stroke);
+
+            let duration = cx
+                .display_units
+                .timestamp(Timestamp(tile_id.0.duration_ns()));
+            let (pos, align) = if is_stale {
+                (tile_rect.left_bottom(), Align2::LEFT_BOTTOM)
             } else {
-                egui::Sense::hover()
-            },
-        );
-
-        let style = ui.style();
-        let font_id = TextStyle::Body.resolve(style);
-        let visuals = if self.is_expandable() {
-            style.interact_selectable(&response, false)
-        } else {
-            *style.noninteractive()
-        };
-
-        ui.painter()
-            .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
-        ui.painter().text(
-            rect.min + style.spacing.item_spacing * Vec2::new(1.0, cx.scale_factor),
-            Align2::LEFT_TOP,
-            self.label_text(),
-            font_id,
-            visuals.text_color(),
-        );
-
-        if response.clicked() {
-            // This will take effect next frame because we can't redraw this widget now
-            self.toggle_expanded();
-        } else if response.hovered() {
-            response.on_hover_text(self.hover_text());
+                (tile_rect.left_top(), Align2::LEFT_TOP)
+            };
+            ui.painter().text(
+                pos + Vec2::new(2.0, 0.0),
+                align,
+                format!("{duration}: {label}"),
+                font_id.clone(),
+                text_color,
+            );
         }
     }
 
-    fn content(
-        &mut self,
+    // Mark the current tiles that failed to load, each with a button to
+    // retry it. Returns the tiles to retry
+    fn render_failures(
+        &self,
+        error: impl Fn(&TileID) -> Option<String>,
         ui: &mut egui::Ui,
         rect: Rect,
-        viewport: Rect,
-        config: &mut Config,
-        cx: &mut Context,
-    );
+        cx: &Context,
+    ) -> Vec<TileID> {
+        let mut retry = Vec::new();
+        for tile_id in &self.tile_ids {
+            let Some(error) = error(tile_id) else {
+                continue;
+            };
+            if !cx.view.interval.overlaps(tile_id.0) {
+                continue;
+            }
+            let to_x = |time: Timestamp| {
+                let x = rect.lerp_inside(Vec2::new(cx.view.interval.unlerp(time), 0.0));
+                x.x.clamp(rect.min.x, rect.max.x)
+            };
+            let tile_rect =
+                Rect::from_x_y_ranges(to_x(tile_id.0.start)..=to_x(tile_id.0.stop), rect.y_range());
+            ui.painter()
+                .rect_filled(tile_rect, 0.0, Color32::RED.gamma_multiply(0.2));
+
+            let button_rect = Rect::from_center_size(tile_rect.center(), Vec2::new(60.0, 18.0));
+            let button = egui::Button::new(egui::RichText::new("⟳ Retry").small());
+            let response = ui
+                .put(button_rect, button)
+                .on_hover_text(format!("Unable to load this part of the profile: {error}"));
+            if response.clicked() {
+                retry.push(*tile_id);
+            }
+        }
+        retry
+    }
 
-    fn height(&self, prefix: Option<&EntryID>, config: &Config, cx: &Context) -> f32;
+    // Returns true if the view's tiles changed
+    fn update(&mut self, config: &mut Config, view_interval: Interval) -> bool {
+        if self.last_view_interval == Some(view_interval) {
+            return false;
+        }
+        self.last_view_interval = Some(view_interval);
+        let old_tile_ids =
+            std::mem::replace(&mut self.tile_ids, config.request_tiles(view_interval));
+        // If the last set of tiles never finished loading, keep showing the
+        // set before it
+        if self.stale_tile_ids.is_empty() {
+            self.stale_tile_ids = old_tile_ids;
+        }
+        true
+    }
 
-    fn is_expandable(&self) -> bool;
+    fn needs(&self, tile_id: &TileID) -> bool {
+        self.tile_ids.contains(tile_id) || self.stale_tile_ids.contains(tile_id)
+    }
 
-    fn toggle_expanded(&mut self);
+    // Drops the stale tiles once all current tiles have loaded. Returns true
+    // if any were dropped
+    fn retire_stale(&mut self, loaded: impl Fn(&TileID) -> bool) -> bool {
+        if self.stale_tile_ids.is_empty() || !self.tile_ids.iter().all(loaded) {
+            return false;
+        }
+        self.stale_tile_ids.clear();
+        true
+    }
 }
 
-impl Summary {
-    fn clear(&mut self) {
-        self.tiles.clear();
-    }
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum ItemLinkNavigationMode {
+    #[default]
+    Zoom,
+    Pan,
+}
 
-    fn inflate(&mut self, config: &mut Config, cx: &mut Context) {
-        for tile_id in config.request_tiles(cx.view_interval) {
-            config
-                .data_source
-                .fetch_summary_tile(&self.entry_id, tile_id, false);
-            self.tiles.insert(tile_id, None);
+impl ItemLinkNavigationMode {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            ItemLinkNavigationMode::Zoom => "Zoom to Item",
+            ItemLinkNavigationMode::Pan => "Pan to Item",
         }
     }
 }
 
-impl Entry for Summary {
-    fn new(info: &EntryInfo, entry_id: EntryID) -> Self {
-        if let EntryInfo::Summary { color } = info {
-            Self {
-                entry_id,
-                color: *color,
-                tiles: BTreeMap::new(),
-                last_view_interval: None,
-            }
-        } else {
-            unreachable!()
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum FieldSortOrder {
+    // As sent by the data source
+    #[default]
+    Item,
+    Schema,
+    Alphabetical,
+}
+
+impl FieldSortOrder {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            FieldSortOrder::Item => "Item Order",
+            FieldSortOrder::Schema => "Schema Order",
+            FieldSortOrder::Alphabetical => "Alphabetical",
         }
     }
+}
 
-    fn entry_id(&self) -> &EntryID {
-        &self.entry_id
-    }
-    fn label_text(&self) -> &str {
-        "avg"
-    }
-    fn hover_text(&self) -> &str {
-        "Utilization Plot of Average Usage Over Time"
-    }
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum HeatmapMetric {
+    // Relative to the busiest pixel column in the slot
+    #[default]
+    Count,
+    // Relative to all rows being busy
+    BusyTime,
+}
 
-    fn find_slot(&self, _entry_id: &EntryID, _level: u64) -> Option<&Slot> {
-        unreachable!()
+impl HeatmapMetric {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            HeatmapMetric::Count => "Number of Items",
+            HeatmapMetric::BusyTime => "Busy Time",
+        }
     }
+}
 
-    fn find_slot_mut(&mut self, _entry_id: &EntryID, _level: u64) -> Option<&mut Slot> {
-        unreachable!()
-    }
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum SearchResultsView {
+    // Collapsing sections by node, kind and processor
+    #[default]
+    Tree,
+    Table,
+    // One section per title, with the count and durations of its items
+    Grouped,
+}
 
-    fn find_summary_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Summary> {
-        assert_eq!(entry_id.level(), level);
-        assert_eq!(entry_id.index(level - 1)?, EntryIndex::Summary);
-        Some(self)
+impl SearchResultsView {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            SearchResultsView::Tree => "Tree",
+            SearchResultsView::Table => "Table",
+            SearchResultsView::Grouped => "By Title",
+        }
     }
+}
 
-    fn expand_slot(&mut self, _entry_id: &EntryID, _level: u64) {
-        unreachable!()
-    }
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum SearchColumn {
+    Title,
+    Node,
+    #[default]
+    Processor,
+    Start,
+    Duration,
+}
 
-    fn inflate_meta(&mut self, _config: &mut Config, _cx: &mut Context) {
-        unreachable!()
+impl SearchColumn {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            SearchColumn::Title => "Title",
+            SearchColumn::Node => "Node",
+            SearchColumn::Processor => "Processor",
+            SearchColumn::Start => "Start",
+            SearchColumn::Duration => "Duration",
+        }
     }
+}
 
-    fn search(&mut self, _config: &mut Config) {
-        unreachable!()
-    }
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum SlotOrder {
+    // As sent by the data source
+    #[default]
+    Index,
+    Name,
+    // Busiest first, within the view interval. Only loaded tiles count
+    BusyTime,
+}
 
-    fn content(
-        &mut self,
-        ui: &mut egui::Ui,
-        rect: Rect,
-        _viewport: Rect,
-        config: &mut Config,
-        cx: &mut Context,
-    ) {
-        cx.slot_rect = Some(rect); // Save slot rect for use later
+impl SlotOrder {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            SlotOrder::Index => "Index",
+            SlotOrder::Name => "Name",
+            SlotOrder::BusyTime => "Busy Time",
+        }
+    }
+}
 
-        const TOOLTIP_RADIUS: f32 = 4.0;
-        let response = ui.allocate_rect(rect, egui::Sense::hover());
-        let hover_pos = response.hover_pos(); // where is the mouse hovering?
+// Guides drawn between the rows of expanded slots, to help follow long
+// items across slots with many rows
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum RowGuides {
+    #[default]
+    None,
+    Separators,
+    Stripes,
+}
 
-        if self
-            .last_view_interval
-            .map_or(true, |i| i != cx.view_interval)
-        {
-            self.clear();
-        }
-        self.last_view_interval = Some(cx.view_interval);
-        if self.tiles.is_empty() {
-            self.inflate(config, cx);
+impl RowGuides {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            RowGuides::None => "None",
+            RowGuides::Separators => "Separators",
+            RowGuides::Stripes => "Alternating Shading",
         }
+    }
+}
 
-        let style = ui.style();
-        let visuals = style.interact_selectable(&response, false);
-        ui.painter()
-            .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
-
-        let stroke = Stroke::new(visuals.bg_stroke.width, self.color);
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+enum PlaybackMode {
+    // Sweep a cursor across the view, paging forward when it reaches the end
+    #[default]
+    Cursor,
+    // Slide the view interval itself
+    SlideView,
+}
 
-        // Conversions to and from screen space coordinates
-        let util_to_screen = |util: &UtilPoint| {
-            let time = cx.view_interval.unlerp(util.time);
-            rect.lerp_inside(Vec2::new(time, 1.0 - util.util))
-        };
-        let screen_to_util = |screen: Pos2| UtilPoint {
-            time: cx
-                .view_interval
-                .lerp((screen.x - rect.left()) / rect.width()),
-            util: 1.0 - (screen.y - rect.top()) / rect.height(),
-        };
+impl PlaybackMode {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            PlaybackMode::Cursor => "Sweep Cursor",
+            PlaybackMode::SlideView => "Slide View",
+        }
+    }
+}
 
-        // Linear interpolation along the line from p1 to p2
-        let interpolate = |p1: Pos2, p2: Pos2, x: f32| {
-            let ratio = (x - p1.x) / (p2.x - p1.x);
-            Rect::from_min_max(p1, p2).lerp_inside(Vec2::new(ratio, ratio))
-        };
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+enum MouseButton {
+    Primary,
+    Secondary,
+    Middle,
+}
 
-        let mut last_util: Option<&UtilPoint> = None;
-        let mut last_point: Option<Pos2> = None;
-        let mut hover_util = None;
-        for tile in self.tiles.values().flatten() {
-            for util in &tile.utilization {
-                let mut point = util_to_screen(util);
-                if let Some(mut last) = last_point {
-                    let last_util = last_util.unwrap();
-                    if cx
-                        .view_interval
-                        .overlaps(Interval::new(last_util.time, util.time))
-                    {
-                        // Interpolate when out of view
-                        if last.x < rect.min.x {
-                            last = interpolate(last, point, rect.min.x);
-                        }
-                        if point.x > rect.max.x {
-                            point = interpolate(last, point, rect.max.x);
-                        }
+impl MouseButton {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            MouseButton::Primary => "Left Button",
+            MouseButton::Secondary => "Right Button",
+            MouseButton::Middle => "Middle Button",
+        }
+    }
 
-                        ui.painter().line_segment([last, point], stroke);
+    fn pointer_button(&self) -> egui::PointerButton {
+        match *self {
+            MouseButton::Primary => egui::PointerButton::Primary,
+            MouseButton::Secondary => egui::PointerButton::Secondary,
+            MouseButton::Middle => egui::PointerButton::Middle,
+        }
+    }
+}
 
-                        if let Some(hover) = hover_pos {
-                            if last.x <= hover.x && hover.x < point.x {
-                                let interp = interpolate(last, point, hover.x);
-                                ui.painter().circle_stroke(
-                                    interp,
-                                    TOOLTIP_RADIUS,
-                                    visuals.fg_stroke,
-                                );
-                                hover_util = Some(screen_to_util(interp));
-                            }
-                        }
-                    }
-                }
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+enum MouseModifier {
+    None,
+    Alt,
+    Shift,
+    Ctrl,
+}
 
-                last_point = Some(point);
-                last_util = Some(util);
-            }
+impl MouseModifier {
+    fn label_text(&self) -> &'static str {
+        match *self {
+            MouseModifier::None => "No Modifier",
+            MouseModifier::Alt => "Alt",
+            MouseModifier::Shift => "Shift",
+            MouseModifier::Ctrl => "Ctrl",
         }
+    }
 
-        if let Some(util) = hover_util {
-            let time = cx.view_interval.unlerp(util.time);
-            let util_rect = Rect::from_min_max(
-                rect.lerp_inside(Vec2::new(time - 0.05, 0.0)),
-                rect.lerp_inside(Vec2::new(time + 0.05, 1.0)),
-            );
-            ui.show_tooltip(
-                "utilization_tooltip",
-                &util_rect,
-                format!("{:.0}% Utilization", util.util * 100.0),
-            );
+    // Exactly this modifier is held, so that e.g. Alt + drag is not also a
+    // plain drag
+    fn is_held(&self, modifiers: egui::Modifiers) -> bool {
+        let held = (modifiers.alt, modifiers.shift, modifiers.command);
+        held == match *self {
+            MouseModifier::None => (false, false, false),
+            MouseModifier::Alt => (true, false, false),
+            MouseModifier::Shift => (false, true, false),
+            MouseModifier::Ctrl => (false, false, true),
         }
     }
+}
 
-    fn height(&self, prefix: Option<&EntryID>, _config: &Config, cx: &Context) -> f32 {
-        assert!(prefix.is_none());
-        const ROWS: u64 = 4;
-        ROWS as f32 * cx.row_height
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct MouseBinding {
+    button: MouseButton,
+    modifier: MouseModifier,
+}
+
+impl MouseBinding {
+    const fn new(button: MouseButton, modifier: MouseModifier) -> Self {
+        Self { button, modifier }
     }
 
-    fn is_expandable(&self) -> bool {
-        false
+    fn label_text(&self) -> String {
+        match self.modifier {
+            MouseModifier::None => self.button.label_text().to_owned(),
+            modifier => format!("{} + {}", modifier.label_text(), self.button.label_text()),
+        }
     }
 
-    fn toggle_expanded(&mut self) {
-        unreachable!();
+    fn drag_started(&self, response: &egui::Response, modifiers: egui::Modifiers) -> bool {
+        response.drag_started_by(self.button.pointer_button()) && self.modifier.is_held(modifiers)
     }
-}
 
-impl fmt::Display for Field {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Field::I64(value) => write!(f, "{value}"),
-            Field::U64(value) => write!(f, "{value}"),
-            Field::String(value) => write!(f, "{value}"),
-            Field::Interval(value) => write!(f, "{value}"),
-            Field::ItemLink(ItemLink { title, .. }) => write!(f, "{title}"),
-            Field::Vec(fields) => {
-                for (i, field) in fields.iter().enumerate() {
-                    write!(f, "{field}")?;
-                    if i < fields.len() {
-                        write!(f, ", ")?;
-                    }
+    fn clicked(&self, response: &egui::Response, modifiers: egui::Modifiers) -> bool {
+        response.clicked_by(self.button.pointer_button()) && self.modifier.is_held(modifiers)
+    }
+
+    fn edit(&mut self, ui: &mut egui::Ui, id: &str, buttons: &[MouseButton]) {
+        egui::ComboBox::from_id_source((id, "modifier"))
+            .selected_text(self.modifier.label_text())
+            .show_ui(ui, |ui| {
+                for modifier in [
+                    MouseModifier::None,
+                    MouseModifier::Alt,
+                    MouseModifier::Shift,
+                    MouseModifier::Ctrl,
+                ] {
+                    ui.selectable_value(&mut self.modifier, modifier, modifier.label_text());
                 }
-                Ok(())
-            }
-            Field::Empty => write!(f, ""),
-        }
+            });
+        egui::ComboBox::from_id_source((id, "button"))
+            .selected_text(self.button.label_text())
+            .show_ui(ui, |ui| {
+                for button in buttons {
+                    ui.selectable_value(&mut self.button, *button, button.label_text());
+                }
+            });
     }
 }
 
-struct FieldWithName<'a>(&'a str, &'a Field);
+// Mouse buttons (and modifiers) for each action on the timeline, since some
+// window managers take over e.g. Alt + drag
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+struct MouseBindings {
+    zoom: MouseBinding,
+    measure: MouseBinding,
+    pan: MouseBinding,
+    // Clicking the left button also expands and collapses entries, so it
+    // can't open the context menu
+    context_menu: MouseBinding,
+}
 
-impl<'a> fmt::Display for FieldWithName<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let FieldWithName(name, value) = self;
-        match value {
-            Field::Empty => write!(f, "{name}"),
-            _ => write!(f, "{name}: {value}"),
+impl Default for MouseBindings {
+    fn default() -> Self {
+        Self {
+            zoom: MouseBinding::new(MouseButton::Primary, MouseModifier::None),
+            measure: MouseBinding::new(MouseButton::Primary, MouseModifier::Alt),
+            pan: MouseBinding::new(MouseButton::Middle, MouseModifier::None),
+            context_menu: MouseBinding::new(MouseButton::Secondary, MouseModifier::None),
         }
     }
 }
 
-impl Slot {
-    fn rows(&self) -> u64 {
-        const UNEXPANDED_ROWS: u64 = 2;
-        if self.expanded {
-            self.max_rows.at_least(UNEXPANDED_ROWS)
-        } else {
-            UNEXPANDED_ROWS
-        }
+impl MouseBindings {
+    // The first matching action wins, so flag bindings that are shadowed
+    fn has_conflicts(&self) -> bool {
+        let drags = [self.zoom, self.measure, self.pan];
+        drags
+            .iter()
+            .enumerate()
+            .any(|(i, a)| drags[..i].contains(a))
     }
+}
 
-    fn clear(&mut self) {
-        self.tile_ids.clear();
-        self.tiles.clear();
-        self.tile_metas.clear();
-    }
+// Action of the drag in progress, decided when it starts
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DragAction {
+    Zoom,
+    Measure,
+    Pan,
+}
 
-    fn inflate(&mut self, config: &mut Config, cx: &mut Context) {
-        for tile_id in config.request_tiles(cx.view_interval) {
-            config
-                .data_source
-                .fetch_slot_tile(&self.entry_id, tile_id, false);
-            self.tile_ids.push(tile_id);
-            self.tiles.insert(tile_id, None);
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+struct PlaybackState {
+    #[serde(skip)]
+    playing: bool,
+    #[serde(skip)]
+    position: Option<Timestamp>,
+
+    // Profile time elapsed per second of wall clock time
+    speed: f64,
+    mode: PlaybackMode,
+}
+
+impl Default for PlaybackState {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            position: None,
+            speed: 1.0,
+            mode: PlaybackMode::default(),
         }
     }
+}
 
-    fn fetch_meta_tile(
-        &mut self,
-        tile_id: TileID,
-        config: &mut Config,
-    ) -> Option<&SlotMetaTileData> {
-        self.tile_metas
-            .entry(tile_id)
-            .or_insert_with(|| {
-                config
-                    .data_source
-                    .fetch_slot_meta_tile(&self.entry_id, tile_id, false);
-                None
-            })
-            .as_ref()
-    }
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct Context {
+    #[serde(skip)]
+    row_height: f32,
+    #[serde(skip)]
+    scale_factor: f32,
 
-    #[allow(clippy::too_many_arguments)]
-    fn render_tile(
-        &mut self,
-        tile_index: usize,
-        rows: u64,
-        mut hover_pos: Option<Pos2>,
-        ui: &mut egui::Ui,
-        rect: Rect,
-        viewport: Rect,
-        config: &mut Config,
-        cx: &mut Context,
-    ) -> Option<Pos2> {
-        // Hack: can't pass this as an argument because it aliases self.
-        let tile_id = self.tile_ids[tile_index];
-        let tile = self.tiles.get(&tile_id).unwrap();
+    #[serde(skip)]
+    row_scroll_delta: i32,
 
-        if !tile.is_some() {
-            // Tile hasn't finished loading.
-            return hover_pos;
-        }
-        let tile = tile.as_ref().unwrap();
+    #[serde(skip)]
+    subheading_size: f32,
 
-        if !cx.view_interval.overlaps(tile_id.0) {
-            return hover_pos;
-        }
+    // This is across all profiles
+    #[serde(skip)]
+    total_interval: Interval,
 
-        // Track which item, if any, we're interacting with
-        let mut interact_item = None;
+    // The view currently being rendered. While the split view is being
+    // rendered, the two views are swapped.
+    #[serde(skip)]
+    view: ViewState,
 
-        for (row, row_items) in tile.items.iter().enumerate() {
-            // Need to reverse the rows because we're working in screen space
-            let irow = rows - (row as u64) - 1;
+    // Second, independently zoomed view shown below the first
+    #[serde(skip)]
+    split_view: Option<ViewState>,
 
-            // We want to do this first on rows, so that we can cut the
-            // entire row if we don't need it
+    item_link_mode: ItemLinkNavigationMode,
 
-            // Compute bounds for the whole row
-            let row_min = rect.lerp_inside(Vec2::new(0.0, (irow as f32 + 0.05) / rows as f32));
-            let row_max = rect.lerp_inside(Vec2::new(1.0, (irow as f32 + 0.95) / rows as f32));
+    // Order of the fields in item details
+    field_sort_order: FieldSortOrder,
 
-            // Cull if out of bounds
-            // Note: need to shift by rect.min to get to viewport space
-            if row_max.y - rect.min.y < viewport.min.y {
-                break;
-            } else if row_min.y - rect.min.y > viewport.max.y {
-                continue;
-            }
+    // How search results are listed, and the column the table is sorted by
+    search_results_view: SearchResultsView,
+    search_sort_column: SearchColumn,
+    search_sort_descending: bool,
 
-            // Check if mouse is hovering over this row
-            let row_rect = Rect::from_min_max(row_min, row_max);
-            let row_hover = hover_pos.map_or(false, |h| row_rect.contains(h));
+    // Last title sent to the native window
+    #[serde(skip)]
+    native_title: String,
 
-            // Now handle the items
-            for (item_idx, item) in row_items.iter().enumerate() {
-                if !cx.view_interval.overlaps(item.interval) {
-                    continue;
-                }
+    toggle_dark_mode: bool,
 
-                // Note: the interval is EXCLUSIVE. This turns out to be what
-                // we want here, because in screen coordinates interval.stop
-                // is the BEGINNING of the interval.stop nanosecond.
-                let start = cx.view_interval.unlerp(item.interval.start).at_least(0.0);
-                let stop = cx.view_interval.unlerp(item.interval.stop).at_most(1.0);
-                let min = rect.lerp_inside(Vec2::new(start, (irow as f32 + 0.05) / rows as f32));
-                let max = rect.lerp_inside(Vec2::new(stop, (irow as f32 + 0.95) / rows as f32));
+    // Set when embedded in another application (see ProfApp::with_context),
+    // in which case the host's style and keys are left alone, and keys are
+    // only handled after a click inside the viewer, until a click elsewhere
+    #[serde(skip)]
+    embedded: bool,
+    #[serde(skip)]
+    embedded_focus: bool,
 
-                let item_rect = Rect::from_min_max(min, max);
-                if row_hover && hover_pos.map_or(false, |h| item_rect.contains(h)) {
-                    hover_pos = None;
-                    interact_item = Some((row, item_idx, item_rect, tile_id));
-                }
+    debug: bool,
 
-                let highlight = config.items_selected.contains_key(&item.item_uid);
+    // Render a utilization sparkline in place of collapsed slots
+    collapsed_sparklines: bool,
 
-                let mut color = item.color;
-                if !config.search_state.query.is_empty() {
-                    if config.search_state.result_set.contains(&item.item_uid) || highlight {
-                        color = Color32::RED;
-                    } else {
-                        color = color.gamma_multiply(0.2);
-                    }
-                } else if highlight {
-                    color = Color32::RED;
-                }
+    // Size expanded slots to the rows used in the view, rather than to the
+    // rows used anywhere in the profile
+    fit_rows: bool,
 
-                ui.painter().rect(item_rect, 0.0, color, Stroke::NONE);
-            }
-        }
+    // Lay out the items of each slot into rows in the viewer, so that items
+    // stay on the same row across tiles and zoom levels
+    stable_rows: bool,
 
-        if let Some((row, item_idx, item_rect, tile_id)) = interact_item {
-            // Hack: clone here  to avoid mutability conflict.
-            let entry_id = self.entry_id.clone();
-            if let Some(tile_meta) = self.fetch_meta_tile(tile_id, config) {
-                let item_meta = &tile_meta.items[row][item_idx];
-                ui.show_tooltip_ui("task_tooltip", &item_rect, |ui| {
-                    ui.label(&item_meta.title);
-                    if cx.debug {
-                        ui.label(format!("Item UID: {}", item_meta.item_uid.0));
-                    }
-                    for (field_id, field, color) in &item_meta.fields {
-                        let name = config.field_schema.get_name(*field_id).unwrap();
-                        let text = format!("{}", FieldWithName(name, field));
-                        if let Some(color) = color {
-                            ui.label(RichText::new(text).color(*color));
-                        } else {
-                            ui.label(text);
-                        }
-                    }
-                    ui.label("(Click to show details.)");
-                });
+    // Show at most max_slot_rows rows of each slot, one page at a time, so
+    // that a single very tall slot doesn't take over the layout
+    cap_slot_rows: bool,
+    max_slot_rows: u64,
 
-                // Also mark task as selected if the mouse has been clicked
-                ui.input(|i| {
-                    // A "click" is measured on *release*, assuming certain
-                    // properties hold (e.g., the button was held less than
-                    // some duration, and it moved less than some amount).
-                    if i.pointer.any_click() && i.pointer.primary_released() {
-                        let irow = Some(rows as usize - row - 1);
-                        match config.items_selected.entry(item_meta.item_uid) {
-                            std::collections::btree_map::Entry::Vacant(e) => {
-                                e.insert(ItemDetail {
-                                    meta: Some(item_meta.clone()),
-                                    loc: ItemLocator {
-                                        entry_id,
-                                        irow,
-                                        item_uid: item_meta.item_uid,
-                                    },
-                                });
-                            }
-                            std::collections::btree_map::Entry::Occupied(e) => {
-                                e.remove_entry();
-                            }
-                        }
-                    }
-                });
-            }
-        }
+    row_guides: RowGuides,
 
-        hover_pos
-    }
+    // What the shading of slots shown as heatmaps stands for
+    heatmap_metric: HeatmapMetric,
+
+    #[serde(skip)]
+    show_controls: bool,
+
+    // Whether a widget had keyboard focus at the end of the last frame
+    #[serde(skip)]
+    had_focus: bool,
+
+    // Esc clears the search query along with the selection
+    escape_clears_search: bool,
+
+    // Interval selected by the last drag, and the view it was made in
+    #[serde(skip)]
+    selected_interval: Option<(Interval, usize)>,
+
+    // Result of the last archive export from the selected interval
+    #[serde(skip)]
+    export_status: Option<String>,
+
+    // Name of the next archive exported from the selected interval
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    archive_name: String,
+
+    // Where exports are written, or the current directory if None
+    #[cfg(not(target_arch = "wasm32"))]
+    export_dir: Option<PathBuf>,
+
+    // Show a magnified strip of the timeline around the cursor
+    #[serde(skip)]
+    magnifier: bool,
+
+    playback: PlaybackState,
+
+    // Dim old tiles that are still shown while new ones load
+    dim_stale_tiles: bool,
+
+    // Repaint less often while the window is not focused
+    power_saving: bool,
+
+    // Draw dense tiles from textures, so that panning doesn't rebuild their
+    // meshes every frame
+    rasterize_dense_tiles: bool,
+
+    // Request full tiles, with every item in the view, rather than tiles in
+    // which the data source may sample or merge items that are too short to
+    // see. Full tiles can be far larger, so this is never saved
+    #[serde(skip)]
+    full_tiles: bool,
+
+    // When requests started being outstanding, for throttling repaints
+    // during long fetches
+    #[serde(skip)]
+    loading_since: Option<f64>,
+
+    mouse_bindings: MouseBindings,
+
+    // Name of the link field last followed to trace an item's origin
+    trace_field: String,
+
+    // Show cursor times relative to the start of the view
+    relative_time: bool,
+
+    display_units: DisplayUnits,
+
+    // Zoom factor for the entire UI, and an additional factor for text only
+    ui_scale: f32,
+    font_scale: f32,
+    #[serde(skip)]
+    applied_font_scale: f32,
+
+    // Cap on requests sent to each data source at once
+    max_requests: u64,
+
+    // Tiles of offscreen slots are evicted from windows that exceed this
+    // budget (in MiB)
+    memory_budget: u64,
+
+    // For profiles opened by URL from within the viewer
+    sources: SourceOptions,
+
+    // Number of frames drawn so far, and when the current one started (in
+    // seconds, as egui::InputState::time)
+    #[serde(skip)]
+    frame: u64,
+    #[serde(skip)]
+    frame_time: f64,
+
+    // When commands started waiting for profiles to load
+    #[serde(skip)]
+    commands_held_since: Option<f64>,
 }
 
-impl Entry for Slot {
-    fn new(info: &EntryInfo, entry_id: EntryID) -> Self {
-        if let EntryInfo::Slot {
-            short_name,
-            long_name,
-            max_rows,
-        } = info
-        {
-            Self {
-                entry_id,
-                short_name: short_name.to_owned(),
-                long_name: long_name.to_owned(),
-                expanded: true,
-                max_rows: *max_rows,
-                tile_ids: Vec::new(),
-                tiles: BTreeMap::new(),
-                tile_metas: BTreeMap::new(),
-                last_view_interval: None,
-            }
-        } else {
-            unreachable!()
-        }
+// How profiles are opened by URL (see ProfApp::open_data_source)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SourceOptions {
+    pub tls: TlsOptions,
+    // Everything fetched is kept here (natively), so that profiles can be
+    // reopened quickly, or when their server is gone
+    pub cache_dir: Option<PathBuf>,
+    // Override the defaults of the HTTP client (see HTTPTimeouts)
+    pub connect_timeout: Option<Duration>,
+    pub read_timeout: Option<Duration>,
+}
+
+// Commands sent to a running viewer through a ViewerHandle
+#[derive(Debug)]
+pub enum ViewerCommand {
+    SetInterval(Interval),
+    // Only items in tiles that have already been loaded can be found, so set
+    // the interval to cover the item first
+    SelectItem(ItemUID),
+    Search(String),
+    Snapshot(mpsc::Sender<ViewerSnapshot>),
+    Subscribe(mpsc::Sender<ViewerEvent>),
+    // Open another profile, given a URL or the path of an archive
+    Open(String),
+    // For profiles opened from now on
+    SetTlsOptions(TlsOptions),
+    SetCacheDir(Option<PathBuf>),
+    // Connect and read timeouts, or None for the defaults
+    SetTimeouts(Option<Duration>, Option<Duration>),
+    // Expand the entries matching a pattern like "node3/cpu/*" (see
+    // Window::expand_matching)
+    Expand(String),
+    // Open the profiles in a link (if any), then restore the view it
+    // describes once they have loaded
+    OpenLink(ViewLink),
+    // Apply the command, then report whether it succeeded
+    Acknowledged(Box<ViewerCommand>, mpsc::Sender<Result<(), String>>),
+}
+
+// Changes made by the user (or through a ViewerHandle), reported to hosts
+// that subscribe to them
+#[derive(Debug, Clone)]
+pub enum ViewerEvent {
+    ItemSelected {
+        source_locator: Vec<String>,
+        entry_id: EntryID,
+        item_uid: ItemUID,
+    },
+    IntervalChanged(Interval),
+    Search {
+        source_locator: Vec<String>,
+        query: String,
+    },
+}
+
+type EventCallback = Box<dyn FnMut(&ViewerEvent)>;
+
+// State as of the last reported events, to tell what changed since
+#[derive(Default)]
+struct EventState {
+    interval: Option<Interval>,
+    items_selected: Vec<BTreeSet<ItemUID>>,
+    queries: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSnapshot {
+    pub source_locator: Vec<String>,
+    pub items_selected: Vec<ItemUID>,
+    pub search_query: String,
+    pub search_results: Vec<ItemUID>,
+    // Whether any tiles are still being fetched
+    pub loading: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ViewerSnapshot {
+    pub interval: Interval,
+    pub total_interval: Interval,
+    pub profiles: Vec<ProfileSnapshot>,
+}
+
+// Lets the program embedding the viewer drive it while the UI runs. The
+// context is filled in once the UI starts, so that commands wake it up
+type SharedContext = Arc<Mutex<Option<egui::Context>>>;
+
+#[derive(Clone)]
+pub struct ViewerHandle {
+    sender: mpsc::Sender<ViewerCommand>,
+    ctx: SharedContext,
+}
+
+struct ViewerControl {
+    receiver: mpsc::Receiver<ViewerCommand>,
+    ctx: SharedContext,
+}
+
+impl ViewerHandle {
+    fn new() -> (Self, ViewerControl) {
+        let (sender, receiver) = mpsc::channel();
+        let ctx = SharedContext::default();
+        let control = ViewerControl {
+            receiver,
+            ctx: ctx.clone(),
+        };
+        (Self { sender, ctx }, control)
     }
 
-    fn entry_id(&self) -> &EntryID {
-        &self.entry_id
+    // Returns false if the viewer has been closed
+    pub fn send(&self, command: ViewerCommand) -> bool {
+        let result = self.sender.send(command).is_ok();
+        if let Some(ctx) = self.ctx.lock().unwrap().as_ref() {
+            ctx.request_repaint();
+        }
+        result
     }
-    fn label_text(&self) -> &str {
-        &self.short_name
+
+    pub fn set_interval(&self, interval: Interval) -> bool {
+        self.send(ViewerCommand::SetInterval(interval))
     }
-    fn hover_text(&self) -> &str {
-        &self.long_name
+
+    pub fn select_item(&self, item_uid: ItemUID) -> bool {
+        self.send(ViewerCommand::SelectItem(item_uid))
     }
 
-    fn find_slot(&self, entry_id: &EntryID, level: u64) -> Option<&Slot> {
-        assert_eq!(entry_id.level(), level);
-        assert!(entry_id.slot_index(level - 1).is_some());
-        Some(self)
+    pub fn search(&self, query: impl Into<String>) -> bool {
+        self.send(ViewerCommand::Search(query.into()))
     }
 
-    fn find_slot_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Slot> {
-        assert_eq!(entry_id.level(), level);
-        assert!(entry_id.slot_index(level - 1).is_some());
-        Some(self)
+    pub fn open(&self, locator: impl Into<String>) -> bool {
+        self.send(ViewerCommand::Open(locator.into()))
     }
 
-    fn find_summary_mut(&mut self, _entry_id: &EntryID, _level: u64) -> Option<&mut Summary> {
-        unreachable!()
+    pub fn set_tls_options(&self, tls: TlsOptions) -> bool {
+        self.send(ViewerCommand::SetTlsOptions(tls))
     }
 
-    fn expand_slot(&mut self, entry_id: &EntryID, level: u64) {
-        assert_eq!(entry_id.level(), level);
-        assert!(entry_id.slot_index(level - 1).is_some());
-        self.expanded = true;
+    pub fn set_cache_dir(&self, cache_dir: Option<PathBuf>) -> bool {
+        self.send(ViewerCommand::SetCacheDir(cache_dir))
     }
 
-    fn inflate_meta(&mut self, config: &mut Config, cx: &mut Context) {
-        for tile_id in config.request_tiles(cx.view_interval) {
-            self.fetch_meta_tile(tile_id, config);
-        }
+    pub fn set_timeouts(&self, connect: Option<Duration>, read: Option<Duration>) -> bool {
+        self.send(ViewerCommand::SetTimeouts(connect, read))
     }
 
-    fn search(&mut self, config: &mut Config) {
-        if !config.search_state.start_entry(self) {
-            return;
-        }
+    pub fn expand(&self, pattern: impl Into<String>) -> bool {
+        self.send(ViewerCommand::Expand(pattern.into()))
+    }
 
-        for (tile_id, tile) in &self.tile_metas {
-            if let Some(tile) = tile {
-                if !config.search_state.start_tile(self, *tile_id) {
-                    continue;
-                }
+    pub fn open_link(&self, link: ViewLink) -> bool {
+        self.send(ViewerCommand::OpenLink(link))
+    }
 
-                for (row, row_items) in tile.items.iter().enumerate() {
-                    for item in row_items {
-                        if config.search_state.is_match(item) {
-                            // Reverse rows because we're in screen space
-                            let irow = tile.items.len() - row - 1;
-                            config.search_state.insert(self, *tile_id, irow, item);
-                        }
-                    }
-                }
-            }
-        }
+    // The snapshot is taken on the next frame
+    // Like send, but the result of the command can be waited for
+    pub fn send_acknowledged(&self, command: ViewerCommand) -> mpsc::Receiver<Result<(), String>> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(ViewerCommand::Acknowledged(Box::new(command), sender));
+        receiver
     }
 
-    fn content(
-        &mut self,
-        ui: &mut egui::Ui,
-        rect: Rect,
-        viewport: Rect,
-        config: &mut Config,
-        cx: &mut Context,
-    ) {
-        cx.slot_rect = Some(rect); // Save slot rect for use later
+    pub fn snapshot(&self) -> mpsc::Receiver<ViewerSnapshot> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(ViewerCommand::Snapshot(sender));
+        receiver
+    }
 
-        let response = ui.allocate_rect(rect, egui::Sense::hover());
-        let mut hover_pos = response.hover_pos(); // where is the mouse hovering?
+    pub fn subscribe(&self) -> mpsc::Receiver<ViewerEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(ViewerCommand::Subscribe(sender));
+        receiver
+    }
+}
 
-        if self.expanded {
-            if self
-                .last_view_interval
-                .map_or(true, |i| i != cx.view_interval)
-            {
-                self.clear();
-            }
-            self.last_view_interval = Some(cx.view_interval);
-            if self.tiles.is_empty() {
-                self.inflate(config, cx);
-            }
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum OpenTarget {
+    File,
+    #[cfg(feature = "client")]
+    Url,
+    Link,
+}
 
-            let style = ui.style();
-            let visuals = style.interact_selectable(&response, false);
-            ui.painter()
-                .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+// State of the File > Open dialogs
+#[cfg(not(target_arch = "wasm32"))]
+struct OpenDialog {
+    target: OpenTarget,
+    input: String,
+    error: Option<String>,
+}
 
-            let rows = self.rows();
-            for tile_index in 0..self.tile_ids.len() {
-                hover_pos =
-                    self.render_tile(tile_index, rows, hover_pos, ui, rect, viewport, config, cx);
-            }
+#[cfg(not(target_arch = "wasm32"))]
+impl OpenDialog {
+    fn new(target: OpenTarget) -> Self {
+        Self {
+            target,
+            input: String::new(),
+            error: None,
         }
     }
 
-    fn height(&self, _prefix: Option<&EntryID>, _config: &Config, cx: &Context) -> f32 {
-        self.rows() as f32 * cx.row_height
+    fn title(&self) -> &'static str {
+        match self.target {
+            OpenTarget::File => "Open File",
+            #[cfg(feature = "client")]
+            OpenTarget::Url => "Open URL",
+            OpenTarget::Link => "Open View Link",
+        }
     }
 
-    fn is_expandable(&self) -> bool {
-        true
+    // Catch obvious mistakes here, where they can be fixed, rather than
+    // opening a window that only says the profile is unavailable
+    fn validate(&self) -> Result<String, String> {
+        let input = self.input.trim();
+        match self.target {
+            OpenTarget::File => {
+                let path = std::path::Path::new(input);
+                if !path.exists() {
+                    return Err(format!("{input} does not exist"));
+                }
+                if path.is_dir() && !path.join("info").is_file() {
+                    return Err(format!("{input} is not a profile archive"));
+                }
+                // Keep relative paths valid in the recent sources
+                let path = std::fs::canonicalize(path).map_err(|e| e.to_string())?;
+                Ok(path.to_string_lossy().into_owned())
+            }
+            #[cfg(feature = "client")]
+            OpenTarget::Url => match url::Url::parse(input) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+                    Ok(input.to_owned())
+                }
+                _ => Err(format!("{input} is not an HTTP or HTTPS URL")),
+            },
+            OpenTarget::Link => ViewLink::parse_token(input).map(|_| input.to_owned()),
+        }
     }
 
-    fn toggle_expanded(&mut self) {
-        self.expanded = !self.expanded;
+    // Lists the directory being typed, so that archives can be found
+    // without typing their entire path. Archives are marked, and clicking
+    // an entry fills it in
+    fn browse(&mut self, ui: &mut egui::Ui) {
+        let path = std::path::Path::new(self.input.trim());
+        let dir = if path.is_dir() {
+            path
+        } else {
+            match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => std::path::Path::new("."),
+            }
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut entries: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                !path
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+            })
+            .collect();
+        entries.sort_by_key(|path| (!path.is_dir(), path.clone()));
+
+        let mut selected = None;
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                if let Some(parent) = dir.parent() {
+                    if ui.selectable_label(false, "📁 ..").clicked() {
+                        selected = Some(parent.to_path_buf());
+                    }
+                }
+                for path in entries {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let icon = if path.join("info").is_file() {
+                        "📊"
+                    } else if path.is_dir() {
+                        "📁"
+                    } else {
+                        "🗋"
+                    };
+                    if ui
+                        .selectable_label(false, format!("{icon} {name}"))
+                        .clicked()
+                    {
+                        selected = Some(path.clone());
+                    }
+                }
+            });
+        if let Some(selected) = selected {
+            self.input = selected.to_string_lossy().into_owned();
+            self.error = None;
+        }
     }
 }
 
-impl<S: Entry> Panel<S> {
-    fn render<T: Entry>(
-        ui: &mut egui::Ui,
-        rect: Rect,
-        viewport: Rect,
-        slot: &mut T,
-        y: &mut f32,
-        config: &mut Config,
-        cx: &mut Context,
-    ) -> bool {
-        const LABEL_WIDTH: f32 = 60.0;
-        const COL_PADDING: f32 = 4.0;
-        const ROW_PADDING: f32 = 4.0;
-
-        // Compute the size of this slot
-        // This is in screen (i.e., rect) space
-        let min_y = *y;
-        let max_y = min_y + slot.height(None, config, cx);
-        *y = max_y + ROW_PADDING;
+// The viewer, either run as an eframe app by start(), or embedded as a
+// widget in another egui application via with_context() and update_ui()
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)] // deserialize missing fields as default value
+pub struct ProfApp {
+    // Data sources waiting to be turned into windows.
+    #[serde(skip)]
+    pending_data_sources: VecDeque<Box<dyn DeferredDataSource>>,
+    // Directories opened from the browser, which arrive asynchronously
+    #[cfg(target_arch = "wasm32")]
+    #[serde(skip)]
+    picked_directories: Rc<RefCell<Vec<DirectoryDataSource>>>,
 
-        // Cull if out of bounds
-        // Note: need to shift by rect.min to get to viewport space
-        if max_y - rect.min.y < viewport.min.y {
-            return false;
-        } else if min_y - rect.min.y > viewport.max.y {
-            return true;
-        }
+    #[serde(skip)]
+    windows: Vec<Window>,
 
-        // Draw label and content
-        let label_min = rect.min.x;
-        let label_max = (rect.min.x + LABEL_WIDTH).at_most(rect.max.x);
-        let content_min = (label_max + COL_PADDING).at_most(rect.max.x);
-        let content_max = rect.max.x;
+    cx: Context,
 
-        let label_subrect =
-            Rect::from_min_max(Pos2::new(label_min, min_y), Pos2::new(label_max, max_y));
-        let content_subrect =
-            Rect::from_min_max(Pos2::new(content_min, min_y), Pos2::new(content_max, max_y));
+    // Per-profile configuration, keyed by the data source description
+    saved_configs: BTreeMap<String, ConfigState>,
 
-        // Shift viewport up by the amount consumed
-        // Invariant: (0, 0) in viewport is rect.min
-        //   (i.e., subtracting rect.min gets us from screen space to viewport space)
-        // Note: viewport.min is NOT necessarily (0, 0)
-        let content_viewport = viewport.translate(Vec2::new(0.0, rect.min.y - min_y));
+    // Locators of the profiles opened by the user, most recent first
+    recent_sources: Vec<String>,
 
-        slot.content(ui, content_subrect, content_viewport, config, cx);
-        slot.label(ui, label_subrect, cx);
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    open_dialog: Option<OpenDialog>,
 
-        false
-    }
+    // View to restore once the profiles it refers to have loaded, along
+    // with the index of the window of its first profile
+    #[serde(skip)]
+    pending_view: Option<(ViewLink, usize)>,
 
-    fn is_slot_visible(slot: &S, config: &Config) -> bool {
-        let level = slot.entry_id().level();
-        if level == 1 {
-            // Apply node filter.
-            let index = slot.entry_id().last_slot_index().unwrap();
-            index >= config.min_node && index <= config.max_node
-        } else if level == 2 {
-            // Apply kind filter.
-            let kind = slot.label_text();
-            config.kind_filter.is_empty() || config.kind_filter.contains(kind)
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    last_update: Option<Instant>,
+
+    #[serde(skip)]
+    control: Option<ViewerControl>,
+
+    #[serde(skip)]
+    subscribers: Vec<EventCallback>,
+    #[serde(skip)]
+    event_state: EventState,
+}
+
+trait Entry {
+    fn new(info: &EntryInfo, entry_id: EntryID) -> Self;
+
+    fn entry_id(&self) -> &EntryID;
+    fn label_text(&self) -> &str;
+    fn hover_text(&self) -> &str;
+
+    fn find_slot(&self, entry_id: &EntryID, level: u64) -> Option<&Slot>;
+    fn find_slot_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Slot>;
+    fn find_summary_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Summary>;
+
+    fn expand_slot(&mut self, entry_id: &EntryID, level: u64);
+
+    fn inflate_meta(&mut self, config: &mut Config, cx: &mut Context);
+
+    fn search(&mut self, config: &mut Config);
+
+    fn label(&mut self, ui: &mut egui::Ui, rect: Rect, config: &mut Config, cx: &Context) {
+        let mut response = ui.allocate_rect(
+            rect,
+            if self.is_expandable() || self.has_context_menu() {
+                egui::Sense::click()
+            } else {
+                egui::Sense::hover()
+            },
+        );
+
+        let style = ui.style();
+        let font_id = TextStyle::Body.resolve(style);
+        let visuals = if self.is_expandable() {
+            style.interact_selectable(&response, false)
         } else {
-            true
+            *style.noninteractive()
+        };
+
+        ui.painter()
+            .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+        ui.painter().text(
+            rect.min + style.spacing.item_spacing * Vec2::new(1.0, cx.scale_factor),
+            Align2::LEFT_TOP,
+            self.label_text(),
+            font_id,
+            visuals.text_color(),
+        );
+        if let Some(badge) = self.label_badge(cx) {
+            ui.painter().text(
+                rect.right_bottom() - style.spacing.item_spacing * Vec2::new(1.0, cx.scale_factor),
+                Align2::RIGHT_BOTTOM,
+                badge,
+                TextStyle::Small.resolve(style),
+                style.visuals.weak_text_color(),
+            );
         }
-    }
-}
 
-impl<S: Entry> Entry for Panel<S> {
-    fn new(info: &EntryInfo, entry_id: EntryID) -> Self {
-        if let EntryInfo::Panel {
-            short_name,
-            long_name,
-            summary,
-            slots,
-        } = info
-        {
-            let expanded = entry_id.level() != 2;
-            let summary = summary
-                .as_ref()
-                .map(|s| Summary::new(s, entry_id.summary()));
-            let slots = slots
-                .iter()
-                .enumerate()
-                .map(|(i, s)| S::new(s, entry_id.child(i as u64)))
-                .collect();
-            Self {
-                entry_id,
-                short_name: short_name.to_owned(),
-                long_name: long_name.to_owned(),
-                expanded,
-                summary,
-                slots,
+        if self.has_context_menu() {
+            let binding = cx.mouse_bindings.context_menu;
+            if binding == MouseBindings::default().context_menu {
+                response = response.context_menu(|ui| self.context_menu(ui, config, cx));
+            } else {
+                // Anything else is shown as a popup below the label
+                let popup_id = response.id.with("context_menu");
+                if binding.clicked(&response, ui.input(|i| i.modifiers)) {
+                    ui.memory_mut(|m| m.toggle_popup(popup_id));
+                }
+                egui::popup_below_widget(ui, popup_id, &response, |ui| {
+                    ui.set_min_width(150.0);
+                    self.context_menu(ui, config, cx);
+                });
             }
-        } else {
-            unreachable!()
+        }
+
+        if response.clicked() && self.is_expandable() {
+            // This will take effect next frame because we can't redraw this widget now
+            self.toggle_expanded();
+        } else if response.hovered() {
+            response.on_hover_text(self.hover_text());
         }
     }
 
-    fn entry_id(&self) -> &EntryID {
-        &self.entry_id
+    fn content(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    );
+
+    fn height(&self, prefix: Option<&EntryID>, config: &Config, cx: &Context) -> f32;
+
+    fn is_expandable(&self) -> bool;
+
+    fn toggle_expanded(&mut self);
+
+    fn has_context_menu(&self) -> bool {
+        false
     }
-    fn label_text(&self) -> &str {
-        &self.short_name
+
+    fn context_menu(&mut self, _ui: &mut egui::Ui, _config: &mut Config, _cx: &Context) {}
+
+    // Total time the entry is busy within the interval, in the loaded tiles
+    fn busy_time(&self, _interval: Interval, _view: usize) -> i64 {
+        0
     }
-    fn hover_text(&self) -> &str {
-        &self.long_name
+
+    // Short text drawn in the corner of the label, if any
+    fn label_badge(&self, _cx: &Context) -> Option<String> {
+        None
     }
 
-    fn find_slot(&self, entry_id: &EntryID, level: u64) -> Option<&Slot> {
-        self.slots
-            .get(entry_id.slot_index(level)? as usize)?
-            .find_slot(entry_id, level + 1)
+    // Number of entries directly below this one
+    fn child_count(&self) -> usize {
+        0
     }
 
-    fn find_slot_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Slot> {
-        self.slots
-            .get_mut(entry_id.slot_index(level)? as usize)?
-            .find_slot_mut(entry_id, level + 1)
+    // Utilization in each of the buckets evenly dividing the view interval,
+    // requesting summary tiles as needed (slots only use tiles they already
+    // have). Drawn in place of collapsed panels that have no summary of
+    // their own. None until something has loaded
+    fn activity(
+        &mut self,
+        _buckets: usize,
+        _config: &mut Config,
+        _cx: &mut Context,
+    ) -> Option<Vec<f32>> {
+        None
     }
+}
 
-    fn find_summary_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Summary> {
-        if level < entry_id.level() - 1 {
-            self.slots
-                .get_mut(entry_id.slot_index(level)? as usize)?
-                .find_summary_mut(entry_id, level + 1)
-        } else {
-            self.summary.as_mut()?.find_summary_mut(entry_id, level + 1)
+impl Summary {
+    fn memory_usage(&mut self) -> usize {
+        *self.memory.get_or_insert_with(|| {
+            self.tiles
+                .values()
+                .flatten()
+                .map(|tile| tile.utilization.capacity() * std::mem::size_of::<UtilPoint>())
+                .sum()
+        })
+    }
+
+    // Request the tiles covering the view, if they changed
+    fn update_view(&mut self, config: &mut Config, cx: &Context) {
+        let tiles = &self.tiles;
+        let loaded = |tile_id: &TileID| tiles.get(tile_id).is_some_and(|t| t.is_some());
+        let view = &mut self.views[cx.view.index];
+        let mut changed = view.update(config, cx.view.request_interval);
+        changed |= view.retire_stale(loaded);
+        if changed {
+            self.inflate(config);
         }
     }
 
-    fn expand_slot(&mut self, entry_id: &EntryID, level: u64) {
-        self.slots
-            .get_mut(entry_id.slot_index(level).unwrap() as usize)
-            .unwrap()
-            .expand_slot(entry_id, level + 1);
-        self.expanded = true;
+    fn loaded_tiles(&self, view: usize) -> usize {
+        let tiles = &self.tiles;
+        self.views[view]
+            .tile_ids
+            .iter()
+            .filter(|tile_id| tiles.get(tile_id).is_some_and(|t| t.is_some()))
+            .count()
     }
 
-    fn inflate_meta(&mut self, config: &mut Config, cx: &mut Context) {
-        let force = config.search_state.include_collapsed_entries;
-        if self.expanded || force {
-            for slot in &mut self.slots {
-                // Apply visibility settings
-                if !force && !Self::is_slot_visible(slot, config) {
-                    continue;
-                }
+    // Points of the view's tiles, in order. None until they have loaded
+    fn view_points(&self, view: usize) -> Option<Vec<UtilPoint>> {
+        let mut points: Vec<UtilPoint> = Vec::new();
+        for tile_id in &self.views[view].tile_ids {
+            points.extend(&self.tiles.get(tile_id)?.as_ref()?.utilization);
+        }
+        Some(points)
+    }
 
-                slot.inflate_meta(config, cx);
+    // Utilization at each of the given times (in increasing order), as
+    // drawn, i.e., interpolated between points. None until the view's
+    // tiles have loaded
+    fn sample(&self, times: &[Timestamp], view: usize) -> Option<Vec<f32>> {
+        let points = self.view_points(view)?;
+
+        let mut i = 0;
+        let mut result = Vec::with_capacity(times.len());
+        for &time in times {
+            while i + 1 < points.len() && points[i + 1].time <= time {
+                i += 1;
             }
+            let util = match (points.get(i), points.get(i + 1)) {
+                (Some(a), Some(b)) if a.time <= time => {
+                    let ratio = (time.0 - a.time.0) as f32 / (b.time.0 - a.time.0).max(1) as f32;
+                    a.util + (b.util - a.util) * ratio.min(1.0)
+                }
+                (Some(a), None) if a.time == time => a.util,
+                _ => 0.0,
+            };
+            result.push(util);
         }
+        Some(result)
     }
 
-    fn search(&mut self, config: &mut Config) {
-        let force = config.search_state.include_collapsed_entries;
-        if self.expanded || force {
-            for slot in &mut self.slots {
-                // Apply visibility settings
-                if !force && !Self::is_slot_visible(slot, config) {
-                    continue;
-                }
+    fn inflate(&mut self, config: &mut Config) {
+        // Drop tiles no view needs any more, then fetch the missing ones
+        self.memory = None;
+        let views = &self.views;
+        self.tiles
+            .retain(|tile_id, _| views.iter().any(|v| v.needs(tile_id)));
+        self.failed
+            .retain(|tile_id, _| views.iter().any(|v| v.needs(tile_id)));
+        for tile_id in views.iter().flat_map(|v| &v.tile_ids) {
+            self.tiles.entry(*tile_id).or_insert_with(|| {
+                config
+                    .data_source
+                    .fetch_summary_tile(&self.entry_id, *tile_id, config.full_tiles);
+                None
+            });
+        }
+    }
 
-                slot.search(config);
+    fn retry(&mut self, tile_ids: Vec<TileID>, config: &mut Config) {
+        for tile_id in tile_ids {
+            if self.failed.remove(&tile_id).is_some() {
+                self.tiles.remove(&tile_id);
             }
         }
+        self.inflate(config);
     }
+}
 
-    fn content(
-        &mut self,
-        ui: &mut egui::Ui,
-        rect: Rect,
-        viewport: Rect,
-        config: &mut Config,
-        cx: &mut Context,
-    ) {
-        let mut y = rect.min.y;
-        if let Some(summary) = &mut self.summary {
-            Self::render(ui, rect, viewport, summary, &mut y, config, cx);
+// One curve of the summary overlay, which plots the utilization of several
+// summaries together so they can be compared directly
+#[derive(Debug, Clone)]
+struct OverlayCurve {
+    entry_id: EntryID,
+    name: String,
+    color: Color32,
+    // View interval, number of samples and number of loaded tiles the points
+    // were computed from, so they are only recomputed when one of these
+    // changes
+    key: (Interval, usize, usize),
+    // Evenly spaced across the view interval
+    points: Vec<UtilPoint>,
+    complete: bool,
+}
+
+impl OverlayCurve {
+    // Used when a summary's own color is already taken by another curve
+    const PALETTE: [Color32; 8] = [
+        Color32::from_rgb(31, 119, 180),
+        Color32::from_rgb(255, 127, 14),
+        Color32::from_rgb(44, 160, 44),
+        Color32::from_rgb(214, 39, 40),
+        Color32::from_rgb(148, 103, 189),
+        Color32::from_rgb(140, 86, 75),
+        Color32::from_rgb(227, 119, 194),
+        Color32::from_rgb(23, 190, 207),
+    ];
+
+    // Keep each summary's own color where possible, so that the overlay
+    // matches the timeline
+    fn pick_color(own: Color32, used: &[Color32]) -> Color32 {
+        if !used.contains(&own) {
+            return own;
         }
+        Self::PALETTE
+            .iter()
+            .copied()
+            .find(|color| !used.contains(color))
+            .unwrap_or(Self::PALETTE[used.len() % Self::PALETTE.len()])
+    }
 
-        if self.expanded {
-            for slot in &mut self.slots {
-                // Apply visibility settings
-                if !Self::is_slot_visible(slot, config) {
-                    continue;
+    fn render_all(curves: &[Self], ui: &mut egui::Ui, cx: &Context) {
+        const ROWS: u64 = 4;
+
+        ui.horizontal_wrapped(|ui| {
+            for curve in curves {
+                let mut color = curve.color;
+                if !curve.complete && cx.dim_stale_tiles {
+                    color = color.gamma_multiply(0.5);
                 }
+                ui.label(RichText::new(format!("\u{2014} {}", curve.name)).color(color));
+            }
+        });
 
-                if Self::render(ui, rect, viewport, slot, &mut y, config, cx) {
-                    break;
+        let size = Vec2::new(ui.available_width(), ROWS as f32 * cx.row_height);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let visuals = ui.style().noninteractive();
+        ui.painter()
+            .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+
+        let to_screen = |i: usize, last: f32, util: &UtilPoint| {
+            rect.lerp_inside(Vec2::new(i as f32 / last, 1.0 - util.util))
+        };
+        for curve in curves {
+            let mut color = curve.color;
+            if !curve.complete && cx.dim_stale_tiles {
+                color = color.gamma_multiply(0.5);
+            }
+            let stroke = Stroke::new(visuals.bg_stroke.width.max(1.0), color);
+            let last = curve.points.len().saturating_sub(1).max(1) as f32;
+            for (i, pair) in curve.points.windows(2).enumerate() {
+                ui.painter().line_segment(
+                    [
+                        to_screen(i, last, &pair[0]),
+                        to_screen(i + 1, last, &pair[1]),
+                    ],
+                    stroke,
+                );
+            }
+        }
+
+        let Some(hover) = response.hover_pos() else {
+            return;
+        };
+        let ratio = (hover.x - rect.min.x) / rect.width();
+        ui.painter()
+            .vline(hover.x, rect.y_range(), visuals.fg_stroke);
+        let mut text = cx
+            .display_units
+            .timestamp(cx.view.interval.lerp(ratio))
+            .to_string();
+        for curve in curves.iter().filter(|c| !c.points.is_empty()) {
+            let last = curve.points.len().saturating_sub(1).max(1) as f32;
+            let util = &curve.points[(ratio * last).round() as usize];
+            ui.painter().circle_stroke(
+                Pos2::new(hover.x, to_screen(0, last, util).y),
+                4.0,
+                Stroke::new(visuals.fg_stroke.width, curve.color),
+            );
+            text += &format!("\n{}: {:.0}%", curve.name, util.util * 100.0);
+        }
+        response.on_hover_text(text);
+    }
+}
+
+impl KindAggregate {
+    fn render(&self, ui: &mut egui::Ui, cx: &Context) {
+        const LABEL_WIDTH: f32 = 60.0;
+        const COL_PADDING: f32 = 4.0;
+        const ROWS: u64 = 2;
+
+        let size = Vec2::new(ui.available_width(), ROWS as f32 * cx.row_height);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let label_rect = Rect::from_min_max(
+            rect.min,
+            Pos2::new((rect.min.x + LABEL_WIDTH).at_most(rect.max.x), rect.max.y),
+        );
+        let content_rect = Rect::from_min_max(
+            Pos2::new(
+                (label_rect.max.x + COL_PADDING).at_most(rect.max.x),
+                rect.min.y,
+            ),
+            rect.max,
+        );
+
+        let style = ui.style();
+        let visuals = style.noninteractive();
+        ui.painter()
+            .rect(label_rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+        ui.painter().text(
+            label_rect.min + style.spacing.item_spacing * Vec2::new(1.0, cx.scale_factor),
+            Align2::LEFT_TOP,
+            &self.kind,
+            TextStyle::Body.resolve(style),
+            visuals.text_color(),
+        );
+        ui.painter()
+            .rect(content_rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+
+        let mut color = self.color;
+        if !self.complete && cx.dim_stale_tiles {
+            color = color.gamma_multiply(0.5);
+        }
+        let stroke = Stroke::new(visuals.bg_stroke.width, color);
+        let last = self.points.len().saturating_sub(1).max(1) as f32;
+        let to_screen = |i: usize, util: &UtilPoint| {
+            content_rect.lerp_inside(Vec2::new(i as f32 / last, 1.0 - util.util))
+        };
+        for (i, pair) in self.points.windows(2).enumerate() {
+            ui.painter()
+                .line_segment([to_screen(i, &pair[0]), to_screen(i + 1, &pair[1])], stroke);
+        }
+
+        let Some(hover) = response.hover_pos() else {
+            return;
+        };
+        if content_rect.contains(hover) && !self.points.is_empty() {
+            let ratio = (hover.x - content_rect.min.x) / content_rect.width();
+            let util = &self.points[(ratio * last).round() as usize];
+            ui.painter().circle_stroke(
+                Pos2::new(hover.x, to_screen(0, util).y),
+                4.0,
+                visuals.fg_stroke,
+            );
+            let nodes = if self.nodes == 1 { "node" } else { "nodes" };
+            response.on_hover_text(format!(
+                "{:.0}% average {} utilization across {} {nodes}{}",
+                util.util * 100.0,
+                self.kind,
+                self.nodes,
+                if self.complete { "" } else { " (loading)" },
+            ));
+        } else {
+            response.on_hover_text(format!(
+                "Average {} utilization across the selected nodes",
+                self.kind
+            ));
+        }
+    }
+}
+
+impl Entry for Summary {
+    fn new(info: &EntryInfo, entry_id: EntryID) -> Self {
+        if let EntryInfo::Summary { color } = info {
+            Self {
+                entry_id,
+                color: *color,
+                tiles: BTreeMap::new(),
+                failed: BTreeMap::new(),
+                views: Default::default(),
+                memory: None,
+                #[cfg(not(target_arch = "wasm32"))]
+                export_status: None,
+            }
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn entry_id(&self) -> &EntryID {
+        &self.entry_id
+    }
+    fn label_text(&self) -> &str {
+        "avg"
+    }
+    fn hover_text(&self) -> &str {
+        "Utilization Plot of Average Usage Over Time"
+    }
+
+    fn find_slot(&self, _entry_id: &EntryID, _level: u64) -> Option<&Slot> {
+        unreachable!()
+    }
+
+    fn find_slot_mut(&mut self, _entry_id: &EntryID, _level: u64) -> Option<&mut Slot> {
+        unreachable!()
+    }
+
+    fn find_summary_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Summary> {
+        assert_eq!(entry_id.level(), level);
+        assert_eq!(entry_id.index(level - 1)?, EntryIndex::Summary);
+        Some(self)
+    }
+
+    fn expand_slot(&mut self, _entry_id: &EntryID, _level: u64) {
+        unreachable!()
+    }
+
+    fn inflate_meta(&mut self, _config: &mut Config, _cx: &mut Context) {
+        unreachable!()
+    }
+
+    fn search(&mut self, _config: &mut Config) {
+        unreachable!()
+    }
+
+    fn content(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        _viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) {
+        cx.view.slot_rect = Some(rect); // Save slot rect for use later
+
+        const TOOLTIP_RADIUS: f32 = 4.0;
+        let response = ui.allocate_rect(rect, egui::Sense::hover());
+        let hover_pos = response.hover_pos(); // where is the mouse hovering?
+
+        self.update_view(config, cx);
+
+        let style = ui.style();
+        let visuals = style.interact_selectable(&response, false);
+        ui.painter()
+            .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+
+        // Until the current tiles have loaded, keep showing the old ones
+        let view = &self.views[cx.view.index];
+        let stale = !view.stale_tile_ids.is_empty();
+        let tile_ids = if stale {
+            &view.stale_tile_ids
+        } else {
+            &view.tile_ids
+        };
+
+        let mut color = self.color;
+        if stale && cx.dim_stale_tiles {
+            color = color.gamma_multiply(0.5);
+        }
+        let stroke = Stroke::new(visuals.bg_stroke.width, color);
+
+        // Conversions to and from screen space coordinates
+        let util_to_screen = |util: &UtilPoint| {
+            let time = cx.view.interval.unlerp(util.time);
+            rect.lerp_inside(Vec2::new(time, 1.0 - util.util))
+        };
+        let screen_to_util = |screen: Pos2| UtilPoint {
+            time: cx
+                .view
+                .interval
+                .lerp((screen.x - rect.left()) / rect.width()),
+            util: 1.0 - (screen.y - rect.top()) / rect.height(),
+        };
+
+        // Linear interpolation along the line from p1 to p2
+        let interpolate = |p1: Pos2, p2: Pos2, x: f32| {
+            let ratio = (x - p1.x) / (p2.x - p1.x);
+            Rect::from_min_max(p1, p2).lerp_inside(Vec2::new(ratio, ratio))
+        };
+
+        let mut last_util: Option<&UtilPoint> = None;
+        let mut last_point: Option<Pos2> = None;
+        let mut hover_util = None;
+        let tiles = tile_ids
+            .iter()
+            .filter_map(|tile_id| self.tiles.get(tile_id)?.as_ref());
+        for tile in tiles {
+            for util in &tile.utilization {
+                let mut point = util_to_screen(util);
+                if let Some(mut last) = last_point {
+                    let last_util = last_util.unwrap();
+                    if cx
+                        .view
+                        .interval
+                        .overlaps(Interval::new(last_util.time, util.time))
+                    {
+                        // Interpolate when out of view
+                        if last.x < rect.min.x {
+                            last = interpolate(last, point, rect.min.x);
+                        }
+                        if point.x > rect.max.x {
+                            point = interpolate(last, point, rect.max.x);
+                        }
+
+                        ui.painter().line_segment([last, point], stroke);
+
+                        if let Some(hover) = hover_pos {
+                            if last.x <= hover.x && hover.x < point.x {
+                                let interp = interpolate(last, point, hover.x);
+                                ui.painter().circle_stroke(
+                                    interp,
+                                    TOOLTIP_RADIUS,
+                                    visuals.fg_stroke,
+                                );
+                                hover_util = Some(screen_to_util(interp));
+                            }
+                        }
+                    }
+                }
+
+                last_point = Some(point);
+                last_util = Some(util);
+            }
+        }
+
+        if let Some(util) = hover_util {
+            let time = cx.view.interval.unlerp(util.time);
+            let util_rect = Rect::from_min_max(
+                rect.lerp_inside(Vec2::new(time - 0.05, 0.0)),
+                rect.lerp_inside(Vec2::new(time + 0.05, 1.0)),
+            );
+            ui.show_tooltip(
+                "utilization_tooltip",
+                &util_rect,
+                format!("{:.0}% Utilization", util.util * 100.0),
+            );
+        }
+
+        if cx.debug {
+            let state = |tile_id: &TileID| match self.tiles.get(tile_id) {
+                None => TileDebugState::Missing,
+                Some(None) if self.failed.contains_key(tile_id) => TileDebugState::Failed,
+                Some(None) => TileDebugState::Requested,
+                Some(Some(tile)) => {
+                    TileDebugState::Loaded(format!("{} points", tile.utilization.len()))
+                }
+            };
+            self.views[cx.view.index].render_debug(state, ui, rect, cx);
+        }
+
+        if !self.failed.is_empty() {
+            let error = |tile_id: &TileID| self.failed.get(tile_id).map(ToString::to_string);
+            let retry = self.views[cx.view.index].render_failures(error, ui, rect, cx);
+            if !retry.is_empty() {
+                self.retry(retry, config);
+            }
+        }
+    }
+
+    fn height(&self, prefix: Option<&EntryID>, _config: &Config, cx: &Context) -> f32 {
+        assert!(prefix.is_none());
+        const ROWS: u64 = 4;
+        ROWS as f32 * cx.row_height
+    }
+
+    fn activity(
+        &mut self,
+        buckets: usize,
+        config: &mut Config,
+        cx: &mut Context,
+    ) -> Option<Vec<f32>> {
+        self.update_view(config, cx);
+        let times: Vec<_> = (0..buckets)
+            .map(|i| cx.view.interval.lerp((i as f32 + 0.5) / buckets as f32))
+            .collect();
+        self.sample(&times, cx.view.index)
+    }
+
+    fn is_expandable(&self) -> bool {
+        false
+    }
+
+    fn toggle_expanded(&mut self) {
+        unreachable!();
+    }
+
+    fn has_context_menu(&self) -> bool {
+        cfg!(not(target_arch = "wasm32"))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn context_menu(&mut self, ui: &mut egui::Ui, _config: &mut Config, cx: &Context) {
+        let interval = cx.view.interval;
+        let points = self.view_points(cx.view.index).map(|mut points| {
+            // Including any point at the very end of the view
+            points.retain(|point| interval.start <= point.time && point.time <= interval.stop);
+            points
+        });
+        let button = ui
+            .add_enabled(
+                points.is_some(),
+                egui::Button::new("Export Utilization (CSV)"),
+            )
+            .on_hover_text(
+                "Write the utilization over the view, one point per row, to a new file in \
+                 the export directory (see Settings)",
+            )
+            .on_disabled_hover_text("Waiting for the view to load");
+        if let (true, Some(points)) = (button.clicked(), points) {
+            self.export_status = Some(
+                match export_utilization(cx.export_dir.as_deref(), &points) {
+                    Ok(path) => format!("Wrote {} points to {}", points.len(), path.display()),
+                    Err(e) => format!("Unable to export utilization: {e}"),
+                },
+            );
+        }
+        if let Some(status) = &self.export_status {
+            ui.label(status);
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::I64(value) => write!(f, "{value}"),
+            Field::U64(value) => write!(f, "{value}"),
+            Field::String(value) => write!(f, "{value}"),
+            Field::Interval(value) => write!(f, "{value}"),
+            Field::ItemLink(ItemLink { title, .. }) => write!(f, "{title}"),
+            Field::Vec(fields) => {
+                for (i, field) in fields.iter().enumerate() {
+                    write!(f, "{field}")?;
+                    if i < fields.len() {
+                        write!(f, ", ")?;
+                    }
+                }
+                Ok(())
+            }
+            Field::Empty => write!(f, ""),
+            Field::Markdown(value) => write!(f, "{value}"),
+            Field::Timestamp(value) => write!(f, "{value}"),
+            Field::Group(name, fields) => {
+                write!(f, "{name} (")?;
+                for (i, (_, field)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{field}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+struct FieldWithName<'a>(&'a str, &'a Field);
+
+impl<'a> fmt::Display for FieldWithName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let FieldWithName(name, value) = self;
+        match value {
+            Field::Empty => write!(f, "{name}"),
+            _ => write!(f, "{name}: {value}"),
+        }
+    }
+}
+
+// Lays out the Markdown subset supported by Field::Markdown
+fn markdown_layout_job(text: &str, style: &egui::Style, color: Option<Color32>) -> LayoutJob {
+    let visuals = &style.visuals;
+    let body = TextFormat {
+        font_id: TextStyle::Body.resolve(style),
+        color: color.unwrap_or(visuals.text_color()),
+        ..Default::default()
+    };
+    let code = TextFormat {
+        font_id: TextStyle::Monospace.resolve(style),
+        background: visuals.code_bg_color,
+        ..body.clone()
+    };
+    let heading = TextFormat {
+        font_id: TextStyle::Heading.resolve(style),
+        color: color.unwrap_or(visuals.strong_text_color()),
+        ..body.clone()
+    };
+
+    // Spans are only styled if they are closed on the same line
+    let append_inline = |job: &mut LayoutJob, line: &str, leading_space: f32| {
+        let mut leading_space = leading_space;
+        let (mut is_code, mut is_bold, mut is_italic) = (false, false, false);
+        let mut rest = line;
+        while !rest.is_empty() {
+            let marker = ["`", "**", "*"]
+                .into_iter()
+                .filter_map(|m| rest.find(m).map(|i| (i, m)))
+                .filter(|(i, m)| {
+                    let after = &rest[i + m.len()..];
+                    match *m {
+                        "`" => is_code || after.contains('`'),
+                        _ if is_code => false,
+                        "**" => is_bold || after.contains("**"),
+                        _ => is_italic || after.contains('*'),
+                    }
+                })
+                .min_by_key(|(i, m)| (*i, std::cmp::Reverse(m.len())));
+            let (text, next) = match marker {
+                Some((i, m)) => (&rest[..i], Some((i, m))),
+                None => (rest, None),
+            };
+            if !text.is_empty() {
+                let mut format = if is_code { code.clone() } else { body.clone() };
+                if is_bold {
+                    format.color = heading.color;
+                }
+                format.italics = is_italic;
+                job.append(text, leading_space, format);
+                leading_space = 0.0;
+            }
+            let Some((i, m)) = next else {
+                break;
+            };
+            match m {
+                "`" => is_code = !is_code,
+                "**" => is_bold = !is_bold,
+                _ => is_italic = !is_italic,
+            }
+            rest = &rest[i + m.len()..];
+        }
+    };
+
+    let mut job = LayoutJob::default();
+    let mut in_code_block = false;
+    let mut first = true;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if !first {
+            job.append("\n", 0.0, body.clone());
+        }
+        first = false;
+
+        let indent = (line.len() - trimmed.len()) as f32 * 4.0;
+        if in_code_block {
+            job.append(line, 0.0, code.clone());
+        } else if let Some(rest) = trimmed.strip_prefix('#') {
+            job.append(rest.trim_start_matches('#').trim(), 0.0, heading.clone());
+        } else if let Some(rest) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            job.append("• ", indent + 8.0, body.clone());
+            append_inline(&mut job, rest, 0.0);
+        } else {
+            append_inline(&mut job, trimmed, indent);
+        }
+    }
+    job
+}
+
+fn format_bytes(value: i128) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut scaled = value as f64;
+    let mut unit = 0;
+    while scaled.abs() >= 1024.0 && unit + 1 < UNITS.len() {
+        scaled /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value} B")
+    } else {
+        format!("{scaled:.2} {}", UNITS[unit])
+    }
+}
+
+// Formats an integer field according to its schema format, if it has one
+fn format_field_value(field: &Field, format: FieldFormat, units: DisplayUnits) -> Option<String> {
+    let value = match field {
+        Field::I64(value) => *value as i128,
+        Field::U64(value) => *value as i128,
+        _ => return None,
+    };
+    Some(match format {
+        FieldFormat::Bytes => format_bytes(value),
+        FieldFormat::Percentage => format!("{value}%"),
+        FieldFormat::Nanoseconds => {
+            let ns = value.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+            units.timestamp(Timestamp(ns)).to_string()
+        }
+        FieldFormat::Hex if value < 0 => format!("-0x{:x}", value.unsigned_abs()),
+        FieldFormat::Hex => format!("0x{value:x}"),
+    })
+}
+
+// Shows the title and fields of an item in a tooltip
+fn show_item_tooltip(ui: &mut egui::Ui, item_meta: &ItemMeta, config: &Config, cx: &Context) {
+    ui.label(&item_meta.title);
+    if cx.debug {
+        ui.label(format!("Item UID: {}", item_meta.item_uid.0));
+    }
+    for (field_id, field, color) in &item_meta.fields {
+        let name = config.field_schema.get_name(*field_id).unwrap();
+        let format = config.field_schema.get_format(*field_id);
+        show_tooltip_field(
+            ui,
+            name,
+            field,
+            *color,
+            format,
+            &config.field_schema,
+            cx.display_units,
+        );
+    }
+}
+
+// Shows a field on its own line in a hover tooltip, with groups expanded
+// underneath their name since tooltips cannot be interacted with
+fn show_tooltip_field(
+    ui: &mut egui::Ui,
+    name: &str,
+    field: &Field,
+    color: Option<Color32>,
+    format: Option<FieldFormat>,
+    field_schema: &FieldSchema,
+    units: DisplayUnits,
+) {
+    let label = |ui: &mut egui::Ui, text: RichText| {
+        if let Some(color) = color {
+            ui.label(text.color(color));
+        } else {
+            ui.label(text);
+        }
+    };
+    if let Field::Group(group_name, fields) = field {
+        label(ui, RichText::new(group_name).strong());
+        ui.indent(group_name, |ui| {
+            for (field_id, field) in fields {
+                let name = field_schema.get_name(*field_id).unwrap();
+                let format = field_schema.get_format(*field_id);
+                show_tooltip_field(ui, name, field, color, format, field_schema, units);
+            }
+        });
+    } else if let Some(value) = format.and_then(|f| format_field_value(field, f, units)) {
+        label(ui, RichText::new(format!("{name}: {value}")));
+    } else {
+        label(ui, RichText::new(format!("{}", FieldWithName(name, field))));
+    }
+}
+
+// Updates may send items again (e.g., ones that grew), so a new copy of an
+// item replaces the old one rather than being added next to it
+fn merge_row<T>(
+    row: &mut Vec<T>,
+    new: impl Iterator<Item = T>,
+    uid: impl Fn(&T) -> ItemUID,
+    start: impl Fn(&T) -> Timestamp,
+) {
+    let new: Vec<T> = new.collect();
+    let replaced: BTreeSet<ItemUID> = new.iter().map(&uid).collect();
+    row.retain(|x| !replaced.contains(&uid(x)));
+    row.extend(new);
+    row.sort_by_key(start);
+}
+
+// Union of the intervals of the items in the tiles, clipped to the interval
+fn merge_busy<'a>(
+    tiles: impl Iterator<Item = &'a SlotTileData>,
+    interval: Interval,
+) -> Vec<Interval> {
+    let mut busy: Vec<_> = tiles
+        .flat_map(|tile| tile.items.iter().flatten())
+        .filter(|item| interval.overlaps(item.interval))
+        .map(|item| item.interval.intersection(interval))
+        .collect();
+    busy.sort();
+
+    let mut result: Vec<Interval> = Vec::new();
+    for next in busy {
+        if let Some(last) = result.last_mut() {
+            if next.start <= last.stop {
+                last.stop = last.stop.max(next.stop);
+                continue;
+            }
+        }
+        result.push(next);
+    }
+    result
+}
+
+// Fraction of each of the buckets evenly dividing the interval that is busy
+fn bucket_utilization(busy: Vec<Interval>, interval: Interval, buckets: usize) -> Vec<f32> {
+    let mut result = vec![0.0; buckets];
+    let bucket_ns = interval.duration_ns() as f32 / buckets as f32;
+    if bucket_ns <= 0.0 {
+        return result;
+    }
+    for busy in busy {
+        let first = ((interval.unlerp(busy.start) * buckets as f32) as usize).min(buckets - 1);
+        let last = ((interval.unlerp(busy.stop) * buckets as f32) as usize).min(buckets - 1);
+        for (i, util) in result.iter_mut().enumerate().take(last + 1).skip(first) {
+            let bucket = Interval::new(
+                interval.lerp(i as f32 / buckets as f32),
+                interval.lerp((i + 1) as f32 / buckets as f32),
+            );
+            if bucket.overlaps(busy) {
+                *util += bucket.intersection(busy).duration_ns() as f32 / bucket_ns;
+            }
+        }
+    }
+    for util in &mut result {
+        *util = util.clamp(0.0, 1.0);
+    }
+    result
+}
+
+impl Slot {
+    // Merge items added to the slot (see DataSourceUpdate::Items) into the
+    // tiles already loaded. Tiles still loading may or may not include the
+    // new items, so they are fetched again instead
+    fn merge_items(&mut self, tile: SlotTile, meta: SlotMetaTile) {
+        self.memory = None;
+        // Regrouped tiles can't be merged into, so start over with fresh ones
+        if self.title_lanes.is_some() || self.stable_rows.is_some() {
+            self.evict(false);
+            return;
+        }
+
+        let interval = tile.tile_id.0;
+        let rows = tile.data.items.len();
+        self.max_rows = self.max_rows.max(rows as u64);
+
+        let overlaps = |tile_id: &TileID| tile_id.0.overlaps(interval);
+        self.tiles
+            .retain(|tile_id, data| data.is_some() || !overlaps(tile_id));
+        self.tile_metas
+            .retain(|tile_id, data| data.is_some() || !overlaps(tile_id));
+        self.meshes.retain(|(_, _, tile_id), _| !overlaps(tile_id));
+        self.textures.retain(|(_, tile_id), _| !overlaps(tile_id));
+        self.color_values.retain(|tile_id, _| !overlaps(tile_id));
+
+        // Clip the new items to each tile, keeping them paired with their
+        // metadata
+        let new_row = |row: usize, tile_id: TileID| {
+            let metas = meta.data.items.get(row).into_iter().flatten();
+            tile.data.items[row]
+                .iter()
+                .zip(metas)
+                .filter(move |(item, _)| item.interval.overlaps(tile_id.0))
+                .map(move |(item, item_meta)| {
+                    let item = Item {
+                        interval: item.interval.intersection(tile_id.0),
+                        ..item.clone()
+                    };
+                    (item, item_meta.clone())
+                })
+        };
+
+        let tile_ids: BTreeSet<TileID> = self
+            .tiles
+            .keys()
+            .chain(self.tile_metas.keys())
+            .filter(|tile_id| overlaps(tile_id))
+            .copied()
+            .collect();
+        for tile_id in tile_ids {
+            let data = self.tiles.get_mut(&tile_id).and_then(Option::as_mut);
+            let compact = self.tile_metas.get_mut(&tile_id).and_then(Option::as_mut);
+            match (data, compact) {
+                (Some(data), Some(compact)) => {
+                    let mut metas = compact.decode();
+                    data.items.resize_with(rows.max(data.items.len()), Vec::new);
+                    metas.items.resize_with(data.items.len(), Vec::new);
+                    for (row, (items, item_metas)) in
+                        data.items.iter_mut().zip(&mut metas.items).enumerate()
+                    {
+                        let mut pairs: Vec<_> = std::mem::take(items)
+                            .into_iter()
+                            .zip(std::mem::take(item_metas))
+                            .collect();
+                        if row < rows {
+                            merge_row(
+                                &mut pairs,
+                                new_row(row, tile_id),
+                                |(item, _)| item.item_uid,
+                                |(item, _)| item.interval.start,
+                            );
+                        }
+                        (*items, *item_metas) = pairs.into_iter().unzip();
+                    }
+                    *compact = CompactSlotMetaTileData::new(&metas);
+                }
+                (Some(data), None) => {
+                    data.items.resize_with(rows.max(data.items.len()), Vec::new);
+                    for (row, items) in data.items.iter_mut().enumerate().take(rows) {
+                        merge_row(
+                            items,
+                            new_row(row, tile_id).map(|(item, _)| item),
+                            |item| item.item_uid,
+                            |item| item.interval.start,
+                        );
+                    }
+                }
+                (None, Some(compact)) => {
+                    let mut metas = compact.decode();
+                    metas
+                        .items
+                        .resize_with(rows.max(metas.items.len()), Vec::new);
+                    for (row, item_metas) in metas.items.iter_mut().enumerate().take(rows) {
+                        merge_row(
+                            item_metas,
+                            new_row(row, tile_id).map(|(_, item_meta)| item_meta),
+                            |item_meta| item_meta.item_uid,
+                            |item_meta| item_meta.original_interval.start.max(tile_id.0.start),
+                        );
+                    }
+                    *compact = CompactSlotMetaTileData::new(&metas);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    fn memory_usage(&mut self) -> MemoryUsage {
+        if let Some(usage) = self.memory {
+            return usage;
+        }
+        let usage = self.measure_memory();
+        self.memory = Some(usage);
+        usage
+    }
+
+    fn measure_memory(&self) -> MemoryUsage {
+        let tiles: usize = self
+            .tiles
+            .values()
+            .flatten()
+            .flat_map(|tile| &tile.items)
+            .map(|row| {
+                std::mem::size_of::<Vec<Item>>() + row.capacity() * std::mem::size_of::<Item>()
+            })
+            .sum();
+        let meshes: usize = self
+            .meshes
+            .values()
+            .flat_map(|mesh| &mesh.meshes)
+            .chain(self.slot_meshes.values().map(|mesh| &mesh.mesh))
+            .map(|mesh| {
+                mesh.vertices.capacity() * std::mem::size_of::<egui::epaint::Vertex>()
+                    + mesh.indices.capacity() * std::mem::size_of::<u32>()
+            })
+            .sum();
+        let textures: usize = self.textures.values().map(|t| t.texture.byte_size()).sum();
+        let tile_metas: usize = self
+            .tile_metas
+            .values()
+            .flatten()
+            .map(|t| t.heap_size())
+            .sum();
+        let item_metas: usize = self.item_metas.values().flatten().map(item_meta_size).sum();
+        let color_values: usize = self
+            .color_values
+            .values()
+            .flat_map(|(_, values)| values)
+            .map(|row| {
+                std::mem::size_of::<Vec<Option<f64>>>()
+                    + row.capacity() * std::mem::size_of::<Option<f64>>()
+            })
+            .sum();
+        let stable_rows = self.stable_rows.as_ref().map_or(0, StableRows::heap_size);
+        MemoryUsage {
+            tiles: tiles + meshes + textures + stable_rows,
+            metas: tile_metas + item_metas + color_values,
+            search: 0,
+        }
+    }
+
+    // Drop all tiles, to be fetched again when the slot is next drawn.
+    // Meta tiles may be kept, e.g., while a search needs them
+    fn evict(&mut self, keep_metas: bool) {
+        self.memory = None;
+        self.tiles.clear();
+        self.failed.clear();
+        self.meshes.clear();
+        self.slot_meshes.clear();
+        self.textures.clear();
+        self.color_values.clear();
+        self.item_metas.clear();
+        if !keep_metas {
+            self.tile_metas.clear();
+        }
+        self.views = Default::default();
+    }
+
+    // Release what the slot no longer needs: everything once it has not been
+    // drawn for a while (e.g., filtered out or scrolled away), and the meta
+    // tiles as soon as it is collapsed
+    fn release_hidden(&mut self, keep_metas: bool, cx: &Context) {
+        // The viewer only repaints as needed, so this is in seconds rather
+        // than frames. Slots drawn in the last frame are still in view, no
+        // matter how long ago that was
+        const HIDDEN_SECONDS: f64 = 5.0;
+        let hidden =
+            self.last_shown + 1 < cx.frame && cx.frame_time - self.last_shown_time > HIDDEN_SECONDS;
+        if hidden || (!self.expanded && !cx.collapsed_sparklines) {
+            self.evict(keep_metas);
+        } else if !self.expanded {
+            // Sparklines only need the tiles themselves
+            self.memory = None;
+            self.item_metas.clear();
+            if !keep_metas {
+                self.tile_metas.clear();
+            }
+        }
+    }
+
+    fn rows(&self, cx: &Context) -> u64 {
+        const UNEXPANDED_ROWS: u64 = 2;
+        if !self.expanded || self.heatmap {
+            // The heatmap is a single strip, as tall as a collapsed slot
+            UNEXPANDED_ROWS
+        } else if let Some(lanes) = &self.title_lanes {
+            (lanes.rows() as u64).at_least(UNEXPANDED_ROWS)
+        } else if let Some(stable) = &self.stable_rows {
+            (stable.rows() as u64).at_least(UNEXPANDED_ROWS)
+        } else if cx.fit_rows {
+            self.occupied_rows[cx.view.index]
+                .min(self.max_rows)
+                .at_least(UNEXPANDED_ROWS)
+        } else {
+            self.max_rows.at_least(UNEXPANDED_ROWS)
+        }
+    }
+
+    // Rows drawn at once. With the row cap, taller slots show one page of
+    // rows at a time
+    fn shown_rows(&self, cx: &Context) -> u64 {
+        let rows = self.rows(cx);
+        if self.expanded && cx.cap_slot_rows {
+            rows.min(cx.max_slot_rows)
+        } else {
+            rows
+        }
+    }
+
+    // Turn to the page containing irow (counted from the top of the slot as
+    // drawn), keeping the page within the slot
+    fn turn_to_row(&mut self, irow: u64, rows: u64, shown: u64) {
+        if irow < self.first_row || irow >= self.first_row + shown {
+            self.first_row = irow.saturating_sub(shown / 2);
+        }
+        self.first_row = self.first_row.min(rows - shown);
+    }
+
+    // Recount the rows with items in the tiles being drawn. Until some have
+    // loaded, keep the previous count, to avoid jumping around
+    fn update_occupied_rows(&mut self, view: usize) {
+        let view_tiles = &self.views[view];
+        let mut loaded = false;
+        let mut rows = 0;
+        for tile_id in view_tiles.tile_ids.iter().chain(&view_tiles.stale_tile_ids) {
+            if let Some(Some(tile)) = self.tiles.get(tile_id) {
+                loaded = true;
+                let occupied = tile.items.iter().rposition(|row| !row.is_empty());
+                rows = rows.max(occupied.map_or(0, |row| row as u64 + 1));
+            }
+        }
+        if loaded {
+            self.occupied_rows[view] = rows;
+        }
+    }
+
+    // Converts a row counted from the top of the tiles (which have all
+    // max_rows rows) to one counted from the top of the slot as drawn,
+    // turning to the page containing it
+    fn fit_irow(&mut self, irow: usize, cx: &Context) -> usize {
+        let rows = self.rows(cx);
+        let hidden = if self.title_lanes.is_some() || self.stable_rows.is_some() {
+            0
+        } else {
+            self.max_rows.saturating_sub(rows)
+        };
+        let irow = (irow as u64).saturating_sub(hidden).min(rows - 1);
+        self.turn_to_row(irow, rows, self.shown_rows(cx));
+        (irow - self.first_row) as usize
+    }
+
+    // Rect and viewport in which to draw all rows, such that the current
+    // page lines up with the slot's own rect
+    fn page_rect(&mut self, rows: u64, shown: u64, rect: Rect, viewport: Rect) -> (Rect, Rect) {
+        self.first_row = self.first_row.min(rows - shown);
+        let row_height = rect.height() / shown as f32;
+        let offset = Vec2::new(0.0, self.first_row as f32 * row_height);
+        let page_rect = Rect::from_min_size(
+            rect.min - offset,
+            Vec2::new(rect.width(), rows as f32 * row_height),
+        );
+        let page = Rect::from_min_size(
+            Pos2::new(viewport.min.x, 0.0),
+            Vec2::new(viewport.width(), rect.height()),
+        );
+        (page_rect, viewport.intersect(page).translate(offset))
+    }
+
+    fn render_row_guides(
+        &self,
+        rows: u64,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        cx: &Context,
+    ) {
+        if cx.row_guides == RowGuides::None {
+            return;
+        }
+
+        // Only the rows in the viewport
+        let row_height = rect.height() / rows as f32;
+        let first = (viewport.min.y / row_height).floor().at_least(0.0) as u64;
+        let last = ((viewport.max.y / row_height).ceil() as u64).min(rows);
+
+        let visuals = ui.visuals();
+        let stripe_color = visuals.faint_bg_color;
+        let stroke = Stroke::new(
+            visuals.widgets.noninteractive.bg_stroke.width,
+            visuals
+                .widgets
+                .noninteractive
+                .bg_stroke
+                .color
+                .gamma_multiply(0.5),
+        );
+        for irow in first..last {
+            let top = rect.top() + irow as f32 * row_height;
+            match cx.row_guides {
+                RowGuides::None => {}
+                RowGuides::Separators => {
+                    if irow > 0 {
+                        ui.painter().hline(rect.x_range(), top, stroke);
+                    }
+                }
+                RowGuides::Stripes => {
+                    // Count from the bottom, so stripes stay put when rows
+                    // are fit to the view
+                    if (rows - irow - 1) % 2 == 1 {
+                        let row_rect = Rect::from_min_size(
+                            Pos2::new(rect.left(), top),
+                            Vec2::new(rect.width(), row_height),
+                        );
+                        ui.painter().rect_filled(row_rect, 0.0, stripe_color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_pager(&mut self, rows: u64, shown: u64, ui: &mut egui::Ui, rect: Rect) {
+        let row_height = rect.height() / shown as f32;
+        let pager_rect = Rect::from_min_max(
+            Pos2::new(rect.left(), rect.top()),
+            Pos2::new(rect.right(), rect.top() + row_height),
+        );
+        let last_page = rows - shown;
+        ui.allocate_ui_at_rect(pager_rect, |ui| {
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let down =
+                    ui.add_enabled(self.first_row < last_page, egui::Button::new("⏷").small());
+                let up = ui.add_enabled(self.first_row > 0, egui::Button::new("⏶").small());
+                if down.on_hover_text("Next page of rows").clicked() {
+                    self.first_row = (self.first_row + shown).min(last_page);
+                }
+                if up.on_hover_text("Previous page of rows").clicked() {
+                    self.first_row = self.first_row.saturating_sub(shown);
+                }
+                let label = format!(
+                    "rows {}–{} of {}",
+                    self.first_row + 1,
+                    self.first_row + shown,
+                    rows
+                );
+                ui.label(
+                    egui::RichText::new(label)
+                        .small()
+                        .background_color(ui.visuals().extreme_bg_color),
+                );
+            });
+        });
+    }
+
+    fn inflate(&mut self, config: &mut Config) {
+        // Drop tiles no view needs any more, then fetch the missing ones
+        self.memory = None;
+        let views = &self.views;
+        let needed = |tile_id: &TileID| views.iter().any(|v| v.needs(tile_id));
+        self.tiles.retain(|tile_id, _| needed(tile_id));
+        self.tile_metas.retain(|tile_id, _| needed(tile_id));
+        self.failed.retain(|(tile_id, _), _| needed(tile_id));
+        self.item_metas.retain(|(tile_id, _), _| needed(tile_id));
+        self.meshes.retain(|(_, _, tile_id), _| needed(tile_id));
+        self.textures.retain(|(_, tile_id), _| needed(tile_id));
+        self.color_values.retain(|tile_id, _| needed(tile_id));
+        for tile_id in views.iter().flat_map(|v| &v.tile_ids) {
+            self.tiles.entry(*tile_id).or_insert_with(|| {
+                config
+                    .data_source
+                    .fetch_slot_tile(&self.entry_id, *tile_id, config.full_tiles);
+                None
+            });
+        }
+    }
+
+    // Forget that the tiles failed, so that they are requested again: slot
+    // tiles right away, and meta tiles when next needed
+    fn retry(&mut self, tile_ids: Vec<TileID>, config: &mut Config) {
+        for tile_id in tile_ids {
+            if self
+                .failed
+                .remove(&(tile_id, RequestKind::SlotTile))
+                .is_some()
+            {
+                self.tiles.remove(&tile_id);
+            }
+            if self
+                .failed
+                .remove(&(tile_id, RequestKind::SlotMetaTile))
+                .is_some()
+            {
+                self.tile_metas.remove(&tile_id);
+            }
+        }
+        self.inflate(config);
+    }
+
+    fn tile_error(&self, tile_id: &TileID) -> Option<&RequestError> {
+        self.failed
+            .get(&(*tile_id, RequestKind::SlotTile))
+            .or_else(|| self.failed.get(&(*tile_id, RequestKind::SlotMetaTile)))
+    }
+
+    // Tiles requested by the given view, including those still loading
+    fn view_tiles(&self, view: usize) -> impl Iterator<Item = &Option<SlotTileData>> {
+        self.views[view]
+            .tile_ids
+            .iter()
+            .filter_map(|tile_id| self.tiles.get(tile_id))
+    }
+
+    // Hidden until every tile of the view has loaded, as the items loaded
+    // so far would only show part of the busy time
+    fn update_busy_fraction(&mut self, config: &Config, cx: &Context) {
+        let view = cx.view.index;
+        let interval = cx.view.interval.intersection(config.interval);
+        let loaded = self.view_tiles(view).flatten().count();
+        let complete = loaded > 0 && loaded == self.views[view].tile_ids.len();
+        if !complete || interval.duration_ns() <= 0 {
+            self.busy_fractions[view] = None;
+            return;
+        }
+        let key = (interval, loaded);
+        if self.busy_fractions[view].is_some_and(|(k, _)| k == key) {
+            return;
+        }
+        let fraction = self.busy_time(interval, view) as f32 / interval.duration_ns() as f32;
+        self.busy_fractions[view] = Some((key, fraction));
+    }
+
+    // Merged, sorted intervals during which at least one loaded item is
+    // running, clipped to the given interval
+    fn busy_intervals(&self, interval: Interval, view: usize) -> Vec<Interval> {
+        merge_busy(self.view_tiles(view).flatten(), interval)
+    }
+
+    // Summed duration of the loaded items within the interval, along with
+    // whether any of the view's tiles have loaded
+    fn item_time(&self, interval: Interval, view: usize) -> (bool, i64) {
+        let mut loaded = false;
+        let mut duration = 0;
+        for tile in self.view_tiles(view).flatten() {
+            loaded = true;
+            duration += tile
+                .items
+                .iter()
+                .flatten()
+                .filter(|item| interval.overlaps(item.interval))
+                .map(|item| item.interval.intersection(interval).duration_ns())
+                .sum::<i64>();
+        }
+        (loaded, duration)
+    }
+
+    // Number of loaded items fully and partially inside the interval
+    fn count_items(&self, interval: Interval, view: usize) -> (usize, usize) {
+        // Items may be sliced across tiles, so reassemble them first
+        let mut items: BTreeMap<ItemUID, Interval> = BTreeMap::new();
+        for item in self
+            .view_tiles(view)
+            .flatten()
+            .flat_map(|tile| tile.items.iter().flatten())
+        {
+            items
+                .entry(item.item_uid)
+                .and_modify(|i| *i = i.union(item.interval))
+                .or_insert(item.interval);
+        }
+        let overlapping: Vec<_> = items.values().filter(|i| i.overlaps(interval)).collect();
+        let full = overlapping
+            .iter()
+            .filter(|i| i.start >= interval.start && i.stop <= interval.stop)
+            .count();
+        (full, overlapping.len() - full)
+    }
+
+    // True if the loaded tiles of the view cover the whole interval
+    fn covers(&self, interval: Interval, view: usize) -> bool {
+        let tile_ids = &self.views[view].tile_ids;
+        tile_ids
+            .first()
+            .is_some_and(|t| t.0.start <= interval.start)
+            && tile_ids.last().is_some_and(|t| t.0.stop >= interval.stop)
+            && self.view_tiles(view).all(|t| t.is_some())
+    }
+
+    // Busy fraction of each of `buckets` equal-width buckets spanning the
+    // interval, computed from loaded tiles
+    fn utilization(&self, interval: Interval, view: usize, buckets: usize) -> Vec<f32> {
+        bucket_utilization(self.busy_intervals(interval, view), interval, buckets)
+    }
+
+    // Request the tiles covering the view, if they changed
+    fn update_view(&mut self, config: &mut Config, cx: &Context) {
+        self.set_stable_rows(cx.stable_rows);
+        self.update_title_lanes(config, cx.view.index);
+        self.update_stable_rows(config, cx.view.index);
+        let tiles = &self.tiles;
+        let loaded = |tile_id: &TileID| tiles.get(tile_id).is_some_and(|t| t.is_some());
+        let view = &mut self.views[cx.view.index];
+        let mut changed = view.update(config, cx.view.request_interval);
+        changed |= view.retire_stale(loaded);
+        if changed {
+            self.inflate(config);
+        }
+    }
+
+    fn set_title_lanes(&mut self, enabled: bool) {
+        if enabled {
+            // Title lanes take the place of the stable rows, which can be
+            // regrouped as they are
+            self.heatmap = false;
+            self.stable_rows = None;
+            self.title_lanes = Some(TitleLanes::default());
+        } else {
+            // Go back to the tiles as sent by the data source
+            self.title_lanes = None;
+            self.evict(false);
+        }
+    }
+
+    // With one lane per title, fetch the meta tiles of the view and regroup
+    // the tiles that have loaded
+    fn update_title_lanes(&mut self, config: &mut Config, view: usize) {
+        if self.title_lanes.is_none() {
+            return;
+        }
+        for tile_id in self.views[view].tile_ids.clone() {
+            self.fetch_meta_tile(tile_id, config);
+        }
+
+        let lanes = self.title_lanes.as_mut().unwrap();
+        let tiles = &mut self.tiles;
+        let metas = &mut self.tile_metas;
+        let fresh = Self::fresh_tiles(&self.entry_id, tiles, metas, &mut lanes.regrouped, config);
+        if fresh.is_empty() {
+            return;
+        }
+        self.memory = None;
+
+        // New titles (or more overlap) change the lanes of every tile
+        let old_lanes = lanes.lanes.clone();
+        let mut packed = Vec::new();
+        for tile_id in fresh {
+            let tile = tiles[&tile_id].as_ref().unwrap();
+            let meta = metas[&tile_id].as_ref().unwrap();
+            let titles = TitleLanes::pack(tile, meta);
+            lanes.fit(&titles);
+            packed.push((tile_id, titles));
+        }
+        if lanes.lanes != old_lanes {
+            for tile_id in &lanes.regrouped {
+                let tile = tiles[tile_id].as_ref().unwrap();
+                let meta = metas[tile_id].as_ref().unwrap();
+                packed.push((*tile_id, TitleLanes::pack(tile, meta)));
+            }
+        }
+        for (tile_id, titles) in packed {
+            let (tile, meta) = lanes.place(titles);
+            tiles.insert(tile_id, Some(tile));
+            metas.insert(tile_id, Some(meta));
+            lanes.regrouped.insert(tile_id);
+            self.meshes.retain(|(_, _, t), _| *t != tile_id);
+            self.color_values.remove(&tile_id);
+        }
+    }
+
+    // Tiles that have loaded along with their meta tiles, but haven't been
+    // regrouped yet
+    fn fresh_tiles(
+        entry_id: &EntryID,
+        tiles: &mut BTreeMap<TileID, Option<SlotTileData>>,
+        metas: &mut BTreeMap<TileID, Option<CompactSlotMetaTileData>>,
+        regrouped: &mut BTreeSet<TileID>,
+        config: &mut Config,
+    ) -> Vec<TileID> {
+        let loaded = |tiles: &BTreeMap<TileID, Option<SlotTileData>>,
+                      metas: &BTreeMap<TileID, Option<CompactSlotMetaTileData>>,
+                      tile_id: &TileID| {
+            matches!(tiles.get(tile_id), Some(Some(_)))
+                && matches!(metas.get(tile_id), Some(Some(_)))
+        };
+
+        // Regrouped tiles can't be told apart from fresh ones, so if one
+        // half of a regrouped tile was released, release the other half too
+        let released: Vec<_> = regrouped
+            .iter()
+            .filter(|tile_id| !loaded(tiles, metas, tile_id))
+            .copied()
+            .collect();
+        for tile_id in &released {
+            regrouped.remove(tile_id);
+            metas.remove(tile_id);
+            if let Some(Some(_)) = tiles.get(tile_id) {
+                tiles.insert(*tile_id, None);
+                config
+                    .data_source
+                    .fetch_slot_tile(entry_id, *tile_id, config.full_tiles);
+            }
+        }
+
+        tiles
+            .keys()
+            .filter(|tile_id| loaded(tiles, metas, tile_id) && !regrouped.contains(tile_id))
+            .copied()
+            .collect()
+    }
+
+    // Follow the stable row layout setting, unless the slot has one lane
+    // per title
+    fn set_stable_rows(&mut self, enabled: bool) {
+        let enabled = enabled && self.title_lanes.is_none();
+        if enabled == self.stable_rows.is_some() {
+            return;
+        }
+        if enabled {
+            self.stable_rows = Some(StableRows::default());
+        } else {
+            // Go back to the tiles as sent by the data source
+            self.stable_rows = None;
+            self.evict(false);
+        }
+    }
+
+    // With stable rows, fetch the meta tiles of the view and re-lay out the
+    // tiles that have loaded
+    fn update_stable_rows(&mut self, config: &mut Config, view: usize) {
+        if self.stable_rows.is_none() {
+            return;
+        }
+        for tile_id in self.views[view].tile_ids.clone() {
+            self.fetch_meta_tile(tile_id, config);
+        }
+
+        let stable = self.stable_rows.as_mut().unwrap();
+        let tiles = &mut self.tiles;
+        let metas = &mut self.tile_metas;
+        let fresh = Self::fresh_tiles(&self.entry_id, tiles, metas, &mut stable.regrouped, config);
+        if fresh.is_empty() {
+            return;
+        }
+        self.memory = None;
+
+        // Fit in tiles in order of time, so that the layout doesn't depend
+        // on the order they loaded in (within a frame)
+        let mut placed: BTreeSet<_> = fresh.into_iter().collect();
+        let reset = stable.reset_if_full();
+        if reset {
+            placed.extend(&stable.regrouped);
+        }
+        let mut grew = false;
+        for tile_id in &placed {
+            grew |= stable.fit(metas[tile_id].as_ref().unwrap());
+        }
+        if grew {
+            placed.extend(&stable.regrouped);
+        }
+        for tile_id in placed {
+            let tile = tiles[&tile_id].as_ref().unwrap();
+            let meta = metas[&tile_id].as_ref().unwrap();
+            let (tile, meta) = stable.place(tile, meta);
+            tiles.insert(tile_id, Some(tile));
+            metas.insert(tile_id, Some(meta));
+            stable.regrouped.insert(tile_id);
+            self.meshes.retain(|(_, _, t), _| *t != tile_id);
+            self.textures.retain(|(_, t), _| *t != tile_id);
+            self.color_values.remove(&tile_id);
+        }
+    }
+
+    // Name given to the row by the data source, if any. Rows are NOT
+    // reversed (i.e., row 0 is at the bottom of the slot)
+    fn row_name(&self, row: usize) -> Option<&str> {
+        if self.title_lanes.is_some() || self.stable_rows.is_some() {
+            return None;
+        }
+        self.row_names
+            .get(row)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    // Label the visible rows with their names at the left edge of the slot
+    fn render_row_labels(&self, rows: u64, ui: &mut egui::Ui, rect: Rect, viewport: Rect) {
+        if self.row_names.is_empty() || self.title_lanes.is_some() || self.stable_rows.is_some() {
+            return;
+        }
+        let row_height = rect.height() / rows as f32;
+        let color = ui.visuals().weak_text_color();
+        let font_id = TextStyle::Small.resolve(ui.style());
+        let first = (viewport.min.y / row_height).floor().at_least(0.0) as u64;
+        let last = ((viewport.max.y / row_height).ceil() as u64).min(rows);
+        for irow in first..last {
+            let Some(name) = self.row_name((rows - irow - 1) as usize) else {
+                continue;
+            };
+            // Keep the labels in view when scrolled horizontally
+            let pos = Pos2::new(
+                rect.left() + viewport.min.x.at_least(0.0) + 2.0,
+                rect.top() + irow as f32 * row_height,
+            );
+            ui.painter()
+                .text(pos, Align2::LEFT_TOP, name, font_id.clone(), color);
+        }
+    }
+
+    fn render_lane_labels(&self, rows: u64, ui: &mut egui::Ui, rect: Rect, viewport: Rect) {
+        let Some(lanes) = &self.title_lanes else {
+            return;
+        };
+        let row_height = rect.height() / rows as f32;
+        let visuals = ui.visuals();
+        let color = visuals.weak_text_color();
+        let stroke = visuals.widgets.noninteractive.bg_stroke;
+        let font_id = TextStyle::Small.resolve(ui.style());
+        let mut irow = 0;
+        for (title, lane_rows) in &lanes.lanes {
+            let top = irow as f32 * row_height;
+            irow += lane_rows;
+            if top > viewport.max.y || (irow as f32 * row_height) < viewport.min.y {
+                continue;
+            }
+            if top > 0.0 {
+                ui.painter().hline(rect.x_range(), rect.top() + top, stroke);
+            }
+            // Keep the labels in view when scrolled horizontally
+            let pos = Pos2::new(
+                rect.left() + viewport.min.x.at_least(0.0) + 2.0,
+                rect.top() + top,
+            );
+            ui.painter()
+                .text(pos, Align2::LEFT_TOP, title, font_id.clone(), color);
+        }
+    }
+
+    // Draw the items of every row as one strip, shaded by the number of
+    // items (or the busy time) in each pixel column
+    fn render_heatmap(&self, hover_pos: Option<Pos2>, ui: &mut egui::Ui, rect: Rect, cx: &Context) {
+        let interval = cx.view.interval;
+        let columns = (rect.width().ceil() as usize).at_least(1);
+        let ns_per_pixel = interval.duration_ns() as f64 / rect.width() as f64;
+        let mut counts = vec![0u64; columns];
+        let mut busy = vec![0.0; columns];
+        for tile_id in &self.views[cx.view.index].tile_ids {
+            let Some(Some(tile)) = self.tiles.get(tile_id) else {
+                continue;
+            };
+            for row_items in &tile.items {
+                for item in &row_items[Self::visible_items(row_items, interval)] {
+                    let start = (item.interval.start.0 - interval.start.0) as f64 / ns_per_pixel;
+                    let stop = (item.interval.stop.0 - interval.start.0) as f64 / ns_per_pixel;
+                    let first = start.max(0.0) as usize;
+                    if first >= columns {
+                        continue;
+                    }
+                    let last = (stop.ceil() as usize).clamp(first + 1, columns);
+                    for column in first..last {
+                        counts[column] += 1;
+                        let covered = stop.min(column as f64 + 1.0) - start.max(column as f64);
+                        busy[column] += covered.max(0.0);
+                    }
+                }
+            }
+        }
+
+        // Busy time is relative to every row being busy for the column
+        let rows = self.max_rows.at_least(1) as f64;
+        let max_count = counts.iter().copied().max().unwrap_or(0).at_least(1) as f64;
+        let intensity = |column: usize| match cx.heatmap_metric {
+            HeatmapMetric::Count => counts[column] as f64 / max_count,
+            HeatmapMetric::BusyTime => busy[column] / rows,
+        };
+
+        let mut mesh = egui::Mesh::default();
+        for column in (0..columns).filter(|column| counts[*column] > 0) {
+            let min = Pos2::new(rect.left() + column as f32, rect.top());
+            let max = Pos2::new((min.x + 1.0).at_most(rect.right()), rect.bottom());
+            let color = ColorBy::gradient(intensity(column) as f32);
+            mesh.add_colored_rect(Rect::from_min_max(min, max), color);
+        }
+        ui.painter().add(egui::Shape::mesh(mesh));
+
+        let Some(hover) = hover_pos.filter(|hover| rect.contains(*hover)) else {
+            return;
+        };
+        let column = ((hover.x - rect.left()) as usize).min(columns - 1);
+        let column_rect = Rect::from_min_max(
+            Pos2::new(rect.left() + column as f32, rect.top()),
+            Pos2::new(rect.left() + column as f32 + 1.0, rect.bottom()),
+        );
+        ui.show_tooltip_ui("heatmap_tooltip", &column_rect, |ui| {
+            ui.label(format!("{} items", counts[column]));
+            ui.label(format!("{:.0}% busy", busy[column] / rows * 100.0));
+        });
+    }
+
+    fn render_sparkline(&self, ui: &mut egui::Ui, rect: Rect, cx: &Context) {
+        // One bucket every few pixels is plenty for a sparkline
+        const PIXELS_PER_BUCKET: f32 = 2.0;
+        let buckets = ((rect.width() / PIXELS_PER_BUCKET) as usize).at_least(1);
+        let stroke = ui.style().visuals.widgets.noninteractive.fg_stroke;
+        let points: Vec<_> = self
+            .utilization(cx.view.interval, cx.view.index, buckets)
+            .into_iter()
+            .enumerate()
+            .map(|(i, util)| {
+                rect.lerp_inside(Vec2::new(
+                    (i as f32 + 0.5) / buckets as f32,
+                    0.95 - 0.9 * util,
+                ))
+            })
+            .collect();
+        ui.painter().add(egui::Shape::line(points, stroke));
+    }
+
+    // Render the previous tiles in the parts of the view where the current
+    // tiles are still loading
+    fn render_stale_tiles(
+        &mut self,
+        rows: u64,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) {
+        let view = &self.views[cx.view.index];
+        if view.stale_tile_ids.is_empty() {
+            return;
+        }
+        let pending: Vec<_> = view
+            .tile_ids
+            .iter()
+            .filter(|tile_id| !self.tiles.get(tile_id).is_some_and(|t| t.is_some()))
+            .map(|tile_id| tile_id.0)
+            .collect();
+
+        let clip_rect = ui.clip_rect();
+        for interval in pending {
+            if !cx.view.interval.overlaps(interval) {
+                continue;
+            }
+            let start = cx.view.interval.unlerp(interval.start).at_least(0.0);
+            let stop = cx.view.interval.unlerp(interval.stop).at_most(1.0);
+            let pending_rect = Rect::from_min_max(
+                rect.lerp_inside(Vec2::new(start, 0.0)),
+                rect.lerp_inside(Vec2::new(stop, 1.0)),
+            );
+            ui.set_clip_rect(clip_rect.intersect(pending_rect));
+            for tile_index in 0..self.views[cx.view.index].stale_tile_ids.len() {
+                self.render_tile(tile_index, true, rows, None, ui, rect, viewport, config, cx);
+            }
+        }
+        ui.set_clip_rect(clip_rect);
+    }
+
+    fn render_magnifier(
+        &mut self,
+        hover: Pos2,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) {
+        const MAGNIFICATION: i64 = 10;
+        const LENS_WIDTH: f32 = 400.0;
+        const MAX_LENS_HEIGHT: f32 = 200.0;
+        const HOVER_RADIUS: f32 = 8.0;
+
+        let time = cx
+            .view
+            .interval
+            .lerp((hover.x - rect.left()) / rect.width());
+
+        // Snap the lens to multiples of half its width so that small mouse
+        // movements don't trigger new tile requests
+        let half = (cx.view.interval.duration_ns() / MAGNIFICATION / 2).max(1);
+        let center = Timestamp(time.0 / half * half);
+        let interval = Interval::new(center, center).grow(half);
+
+        // Outline the magnified region on the timeline itself
+        let start = cx.view.interval.unlerp(interval.start).at_least(0.0);
+        let stop = cx.view.interval.unlerp(interval.stop).at_most(1.0);
+        let outline = Rect::from_min_max(
+            rect.lerp_inside(Vec2::new(start, 0.0)),
+            rect.lerp_inside(Vec2::new(stop, 1.0)),
+        );
+        let stroke = ui.style().visuals.widgets.active.fg_stroke;
+        ui.painter().rect_stroke(outline, 0.0, stroke);
+
+        // Hack: render the lens as its own view, so that it gets its own
+        // (finer) tiles
+        let mut lens_view = ViewState {
+            index: MAGNIFIER_VIEW,
+            interval,
+            request_interval: interval,
+            ..Default::default()
+        };
+        std::mem::swap(&mut cx.view, &mut lens_view);
+
+        if self.views[MAGNIFIER_VIEW].update(config, interval) {
+            self.inflate(config);
+        }
+
+        let rows = self.rows(cx);
+        let hover_rect = Rect::from_center_size(hover, Vec2::splat(HOVER_RADIUS));
+        ui.show_tooltip_ui("magnifier", &hover_rect, |ui| {
+            ui.label(format!("{interval} (×{MAGNIFICATION})"));
+
+            let size = Vec2::new(LENS_WIDTH, rect.height().at_most(MAX_LENS_HEIGHT));
+            let (lens_rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            let visuals = ui.style().visuals.widgets.noninteractive;
+            ui.painter()
+                .rect(lens_rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+
+            let viewport = Rect::from_min_size(Pos2::ZERO, size);
+            for tile_index in 0..self.views[MAGNIFIER_VIEW].tile_ids.len() {
+                self.render_tile(
+                    tile_index, false, rows, None, ui, lens_rect, viewport, config, cx,
+                );
+            }
+        });
+
+        std::mem::swap(&mut cx.view, &mut lens_view);
+    }
+
+    fn idle_gaps(&self, interval: Interval, view: usize) -> IdleGapAnalysis {
+        const MAX_IDLE_GAPS: usize = 20;
+
+        let mut gaps = Vec::new();
+        let mut start = interval.start;
+        for busy in self.busy_intervals(interval, view) {
+            if busy.start > start {
+                gaps.push(Interval::new(start, busy.start));
+            }
+            start = start.max(busy.stop);
+        }
+        if start < interval.stop {
+            gaps.push(Interval::new(start, interval.stop));
+        }
+        gaps.sort_by_key(|gap| std::cmp::Reverse(gap.duration_ns()));
+        gaps.truncate(MAX_IDLE_GAPS);
+
+        IdleGapAnalysis {
+            entry_id: self.entry_id.clone(),
+            long_name: self.long_name.clone(),
+            interval,
+            gaps,
+            complete: self.view_tiles(view).count() > 0
+                && self.view_tiles(view).all(|t| t.is_some()),
+        }
+    }
+
+    fn fetch_meta_tiles(&mut self, config: &mut Config, cx: &Context) {
+        for tile_id in config.request_tiles(cx.view.interval) {
+            self.fetch_meta_tile(tile_id, config);
+        }
+    }
+
+    fn fetch_meta_tile(
+        &mut self,
+        tile_id: TileID,
+        config: &mut Config,
+    ) -> Option<&CompactSlotMetaTileData> {
+        self.tile_metas
+            .entry(tile_id)
+            .or_insert_with(|| {
+                config
+                    .data_source
+                    .fetch_slot_meta_tile(&self.entry_id, tile_id, config.full_tiles);
+                None
+            })
+            .as_ref()
+    }
+
+    fn fetch_meta_item(
+        &mut self,
+        tile_id: TileID,
+        row: usize,
+        item_idx: usize,
+        item_uid: ItemUID,
+        config: &mut Config,
+    ) -> Option<ItemMeta> {
+        // Use the meta tile if it's already loaded, but don't request it
+        // just for one item
+        if let Some(Some(tile_meta)) = self.tile_metas.get(&tile_id) {
+            // A meta tile that failed to load may not match the tile
+            return tile_meta.get(row, item_idx);
+        }
+        self.item_metas
+            .entry((tile_id, item_uid))
+            .or_insert_with(|| {
+                config
+                    .data_source
+                    .fetch_slot_meta_item(&self.entry_id, tile_id, item_uid);
+                None
+            })
+            .clone()
+    }
+
+    // Items of a row in the loaded tiles of a view, along with the tile and
+    // index where each item first appears. Items sliced across tile
+    // boundaries are merged back together
+    fn row_items(&self, row: usize, view_index: usize) -> Vec<(TileID, usize, Item)> {
+        let mut result: Vec<(TileID, usize, Item)> = Vec::new();
+        for tile_id in &self.views[view_index].tile_ids {
+            let Some(Some(tile)) = self.tiles.get(tile_id) else {
+                continue;
+            };
+            let Some(row_items) = tile.items.get(row) else {
+                continue;
+            };
+            for (item_idx, item) in row_items.iter().enumerate() {
+                match result.last_mut() {
+                    Some((_, _, last)) if last.item_uid == item.item_uid => {
+                        last.interval = last.interval.union(item.interval);
+                    }
+                    _ => result.push((*tile_id, item_idx, item.clone())),
+                }
+            }
+        }
+        result
+    }
+
+    // Move the keyboard focus by dx items within the row or drow rows
+    // (positive is up). Returns true if the focused item changed
+    fn move_focus(&mut self, dx: i32, drow: i32, cx: &mut Context) -> bool {
+        let rows = self.max_rows as usize;
+        let view_index = cx.view.index;
+        let current = self.focused_item.and_then(|(row, item_uid)| {
+            let items = self.row_items(row, view_index);
+            let index = items.iter().position(|(_, _, i)| i.item_uid == item_uid)?;
+            Some((row, items, index))
+        });
+
+        let target = match current {
+            Some((row, items, index)) if drow == 0 => {
+                let next = index.saturating_add_signed(dx as isize);
+                if next >= items.len() || (index == 0 && dx < 0) {
+                    // Ran off the loaded tiles, so bring more into view
+                    let dir = if dx < 0 {
+                        PanDirection::Left
+                    } else {
+                        PanDirection::Right
+                    };
+                    ProfApp::pan(cx, Percentage::from(50), dir);
+                    return false;
+                }
+                Some((row, items[next].2.clone()))
+            }
+            Some((row, items, index)) => {
+                // Move to the closest item in time in the next non-empty row
+                let time = items[index].2.interval.start;
+                let mut next = row;
+                loop {
+                    next = match next.checked_add_signed(drow as isize) {
+                        Some(next) if next < rows => next,
+                        _ => break None,
+                    };
+                    let items = self.row_items(next, view_index);
+                    if let Some(last) = items.len().checked_sub(1) {
+                        let index = items
+                            .partition_point(|(_, _, i)| i.interval.stop <= time)
+                            .min(last);
+                        break Some((next, items[index].2.clone()));
+                    }
+                }
+            }
+            None => {
+                // Start from the first visible item, searching from the top
+                (0..rows).rev().find_map(|row| {
+                    self.row_items(row, view_index)
+                        .into_iter()
+                        .find(|(_, _, i)| i.interval.overlaps(cx.view.interval))
+                        .map(|(_, _, i)| (row, i))
+                })
+            }
+        };
+
+        let Some((row, item)) = target else {
+            return false;
+        };
+        if !cx.view.interval.overlaps(item.interval) {
+            // Keep the current window size, centered on the item
+            let offset = item.interval.center().0 - cx.view.interval.center().0;
+            ProfApp::zoom(cx, cx.view.interval.saturating_translate(offset));
+        }
+        let changed = self.focused_item != Some((row, item.item_uid));
+        self.focused_item = Some((row, item.item_uid));
+        changed
+    }
+
+    fn keyboard(
+        &mut self,
+        response: &mut egui::Response,
+        ui: &mut egui::Ui,
+        rows: u64,
+        rect: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) {
+        // Keep arrow keys for moving between items while focused
+        ui.memory_mut(|m| {
+            m.set_focus_lock_filter(
+                response.id,
+                egui::EventFilter {
+                    horizontal_arrows: true,
+                    vertical_arrows: true,
+                    ..Default::default()
+                },
+            )
+        });
+        let (dx, drow, open) = ui.input(|i| {
+            let dx = i.key_pressed(egui::Key::ArrowRight) as i32
+                - i.key_pressed(egui::Key::ArrowLeft) as i32;
+            let drow = i.key_pressed(egui::Key::ArrowUp) as i32
+                - i.key_pressed(egui::Key::ArrowDown) as i32;
+            (dx, drow, i.key_pressed(egui::Key::Enter))
+        });
+        if (dx != 0 || drow != 0 || self.focused_item.is_none()) && self.move_focus(dx, drow, cx) {
+            response.mark_changed();
+        }
+
+        let Some((row, item_uid)) = self.focused_item else {
+            return;
+        };
+        let items = self.row_items(row, cx.view.index);
+        let Some((tile_id, item_idx, item)) = items.into_iter().find(|x| x.2.item_uid == item_uid)
+        else {
+            return;
+        };
+
+        let irow = rows - (row as u64) - 1;
+        let item_rect = Self::item_rect(&item, irow, rows, rect, cx);
+        ui.painter()
+            .rect_stroke(item_rect.expand(1.0), 0.0, ui.visuals().selection.stroke);
+
+        // Hack: clone here  to avoid mutability conflict.
+        let entry_id = self.entry_id.clone();
+        let item_meta = self.fetch_meta_item(tile_id, row, item_idx, item_uid, config);
+        if let Some(item_meta) = &item_meta {
+            ui.show_tooltip_ui("focus_tooltip", &item_rect, |ui| {
+                show_item_tooltip(ui, item_meta, config, cx);
+                ui.label("(Press Enter to show details.)");
+            });
+        }
+
+        if open {
+            config
+                .items_selected
+                .entry(item_uid)
+                .or_insert_with(|| ItemDetail {
+                    meta: item_meta,
+                    loc: ItemLocator {
+                        entry_id,
+                        irow: Some(irow as usize),
+                        item_uid,
+                        profile: None,
+                    },
+                    field_filter: String::new(),
+                });
+        }
+    }
+
+    // Label read out by screen readers
+    fn accessible_label(&self, cx: &Context) -> String {
+        let Some((row, item_uid)) = self.focused_item else {
+            return self.long_name.clone();
+        };
+        let title = self
+            .tile_metas
+            .values()
+            .flatten()
+            .find_map(|tile| tile.find(item_uid))
+            .or_else(|| {
+                self.item_metas
+                    .values()
+                    .flatten()
+                    .find(|item| item.item_uid == item_uid)
+                    .cloned()
+            })
+            .map(|item| item.title);
+        let interval = self
+            .row_items(row, cx.view.index)
+            .into_iter()
+            .find(|(_, _, i)| i.item_uid == item_uid)
+            .map(|(_, _, i)| i.interval);
+        match (title, interval) {
+            (Some(title), Some(interval)) => format!(
+                "{}: {}, {}",
+                self.long_name,
+                title,
+                cx.display_units.interval(interval)
+            ),
+            (Some(title), None) => format!("{}: {}", self.long_name, title),
+            _ => format!("{}: loading item", self.long_name),
+        }
+    }
+
+    // Selected, found and traced items are drawn in red
+    fn is_highlighted(item_uid: ItemUID, config: &Config) -> bool {
+        config.items_selected.contains_key(&item_uid)
+            || (!config.search_state.query.is_empty()
+                && config.search_state.result_set.contains(&item_uid))
+            || config
+                .origin_trace
+                .as_ref()
+                .is_some_and(|trace| trace.highlight && trace.item_uids.contains(&item_uid))
+    }
+
+    // Draw the tile into an image at one texel per ns_per_texel. Items
+    // narrower than a texel that share a texel with other items are drawn
+    // in gray, like merged items in the meshes
+    fn rasterize_tile(
+        tile: &SlotTileData,
+        start: Timestamp,
+        ns_per_texel: f64,
+        size: [usize; 2],
+        rows: u64,
+        config: &Config,
+    ) -> egui::ColorImage {
+        let [width, height] = size;
+        let mut image = egui::ColorImage::new(size, Color32::TRANSPARENT);
+        let texel = |time: Timestamp| (time.0 - start.0) as f64 / ns_per_texel;
+        for (row, row_items) in tile.items.iter().enumerate() {
+            // Need to reverse the rows because we're working in screen space
+            let irow = rows - (row as u64) - 1;
+            // Same insets as item_rect
+            let y = |t: f32| ((irow as f32 + t) / rows as f32 * height as f32).round() as usize;
+            let top = y(0.05).min(height - 1);
+            let bottom = y(0.95).clamp(top + 1, height);
+
+            let mut last_column = None;
+            for item in row_items {
+                let x0 = texel(item.interval.start).max(0.0) as usize;
+                if x0 >= width {
+                    break;
+                }
+                let x1 = (texel(item.interval.stop).ceil() as usize).clamp(x0 + 1, width);
+                let merged = x1 - x0 == 1 && last_column == Some(x0);
+                last_column = Some(x1 - 1);
+
+                let color = if Self::is_highlighted(item.item_uid, config) {
+                    Color32::RED
+                } else if merged {
+                    Color32::GRAY
+                } else if !config.search_state.query.is_empty() {
+                    item.color.gamma_multiply(0.2)
+                } else {
+                    item.color
+                };
+                for y in top..bottom {
+                    image.pixels[y * width + x0..y * width + x1].fill(color);
+                }
+            }
+        }
+        image
+    }
+
+    // Draw a dense tile from a texture, which is reused (stretched) for as
+    // long as the zoom level stays within the same power of two. Returns
+    // false if the tile should be drawn as a mesh instead
+    fn render_tile_texture(
+        &mut self,
+        tile_id: TileID,
+        rows: u64,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        config: &Config,
+        cx: &Context,
+    ) -> bool {
+        let Some(Some(tile)) = self.tiles.get(&tile_id) else {
+            return false;
+        };
+        // Textures don't carry the colors by field
+        if self.title_lanes.is_some()
+            || config.color_by.is_some()
+            || !cx.view.interval.overlaps(tile_id.0)
+        {
+            return false;
+        }
+        let items: usize = tile.items.iter().map(Vec::len).sum();
+        if items < DENSE_TILE_ITEMS {
+            return false;
+        }
+
+        // Round the zoom level to the next power of two nanoseconds per
+        // pixel, so that the texture is at most stretched by a factor of two
+        let ns_per_pixel = cx.view.interval.duration_ns() as f64 / rect.width() as f64;
+        let zoom = ns_per_pixel.max(1.0).log2().ceil() as i32;
+        let ns_per_texel = 2f64.powi(zoom);
+        let width = (tile_id.0.duration_ns() as f64 / ns_per_texel).ceil() as usize;
+        let height = rect.height().round() as usize;
+        if width == 0 || height == 0 || width.max(height) > MAX_TILE_TEXTURE_SIZE {
+            return false;
+        }
+
+        let key = TileTextureKey {
+            zoom,
+            height,
+            rows,
+            highlight: config.highlight_key,
+        };
+        let cache_key = (cx.view.index, tile_id);
+        if !self.textures.get(&cache_key).is_some_and(|t| t.key == key) {
+            let size = [width, height];
+            let image =
+                Self::rasterize_tile(tile, tile_id.0.start, ns_per_texel, size, rows, config);
+            let name = format!("{} {}", self.long_name, tile_id.0);
+            let texture = ui
+                .ctx()
+                .load_texture(name, image, egui::TextureOptions::NEAREST);
+            self.textures
+                .insert(cache_key, TileTexture { key, texture });
+            self.memory = None;
+        }
+
+        let texture = &self.textures[&cache_key].texture;
+        let stop = tile_id.0.start.0 + (width as f64 * ns_per_texel) as i64;
+        let start = cx.view.interval.unlerp(tile_id.0.start);
+        let stop = cx.view.interval.unlerp(Timestamp(stop));
+        let image_rect = Rect::from_min_max(
+            rect.lerp_inside(Vec2::new(start, 0.0)),
+            rect.lerp_inside(Vec2::new(stop, 1.0)),
+        );
+        let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+        ui.painter()
+            .image(texture.id(), image_rect, uv, Color32::WHITE);
+        true
+    }
+
+    // Build one mesh per row of the tile, relative to the slot rect
+    fn build_tile_mesh(
+        &self,
+        tile_id: TileID,
+        stale: bool,
+        rows: u64,
+        size: Vec2,
+        config: &Config,
+        cx: &Context,
+    ) -> Vec<egui::Mesh> {
+        let tile = self.tiles[&tile_id].as_ref().unwrap();
+        let color_values = config.color_by.as_ref().and_then(|color_by| {
+            let (field_id, values) = self.color_values.get(&tile_id)?;
+            (*field_id == color_by.field_id).then_some((color_by, values))
+        });
+        let rect = Rect::from_min_size(Pos2::ZERO, size);
+        let mut meshes = Vec::with_capacity(tile.items.len());
+        for (row, row_items) in tile.items.iter().enumerate() {
+            // Need to reverse the rows because we're working in screen space
+            let irow = rows - (row as u64) - 1;
+
+            let mut mesh = egui::Mesh::default();
+            let mut add_rect = |item_rect, color, highlight| {
+                let mut color = color;
+                if highlight {
+                    color = Color32::RED;
+                } else if !config.search_state.query.is_empty() {
+                    color = color.gamma_multiply(0.2);
+                }
+                if stale && cx.dim_stale_tiles {
+                    color = color.gamma_multiply(0.5);
+                }
+                mesh.add_colored_rect(item_rect, color);
+            };
+
+            // Items narrower than a pixel are merged with the other items
+            // in the same pixel column and drawn as a single rect
+            let mut merged: Option<MergedItems> = None;
+            let visible = Self::visible_items(row_items, cx.view.interval);
+            for (item_idx, item) in visible.clone().zip(&row_items[visible]) {
+                let item_rect = Self::item_rect(item, irow, rows, rect, cx);
+
+                let highlight = Self::is_highlighted(item.item_uid, config);
+                let item_color = match color_values {
+                    Some((color_by, values)) => {
+                        let value = values.get(row).and_then(|row| row.get(item_idx));
+                        color_by.color(value.copied().flatten())
+                    }
+                    None => item.color,
+                };
+
+                if item_rect.width() >= MIN_ITEM_WIDTH {
+                    if let Some(m) = merged.take() {
+                        m.draw(&mut add_rect);
+                    }
+                    add_rect(item_rect, item_color, highlight);
+                    continue;
+                }
+
+                let column = item_rect.min.x.floor();
+                match &mut merged {
+                    Some(m) if m.column == column => m.add(item_rect, highlight),
+                    _ => {
+                        if let Some(m) = merged.take() {
+                            m.draw(&mut add_rect);
+                        }
+                        merged = Some(MergedItems {
+                            column,
+                            rect: item_rect,
+                            color: item_color,
+                            count: 1,
+                            covered: item_rect.width(),
+                            highlight,
+                        });
+                    }
+                }
+            }
+            if let Some(m) = merged.take() {
+                m.draw(&mut add_rect);
+            }
+            meshes.push(mesh);
+        }
+        meshes
+    }
+
+    // Range of items in the row that overlap the interval. Items within a
+    // row are sorted by start time and never overlap, so we can binary
+    // search for both ends.
+    fn visible_items(row_items: &[Item], interval: Interval) -> Range<usize> {
+        let first = row_items.partition_point(|item| item.interval.stop <= interval.start);
+        let last = row_items.partition_point(|item| item.interval.start < interval.stop);
+        first..last.max(first)
+    }
+
+    fn item_rect(item: &Item, irow: u64, rows: u64, rect: Rect, cx: &Context) -> Rect {
+        // Note: the interval is EXCLUSIVE. This turns out to be what
+        // we want here, because in screen coordinates interval.stop
+        // is the BEGINNING of the interval.stop nanosecond.
+        let start = cx.view.interval.unlerp(item.interval.start).at_least(0.0);
+        let stop = cx.view.interval.unlerp(item.interval.stop).at_most(1.0);
+        let min = rect.lerp_inside(Vec2::new(start, (irow as f32 + 0.05) / rows as f32));
+        let max = rect.lerp_inside(Vec2::new(stop, (irow as f32 + 0.95) / rows as f32));
+        Rect::from_min_max(min, max)
+    }
+
+    // Bring the tile's cached meshes up to date. Returns the generation of
+    // the meshes, or None if the tile isn't drawn
+    fn update_tile_mesh(
+        &mut self,
+        tile_id: TileID,
+        stale: bool,
+        rows: u64,
+        size: Vec2,
+        config: &mut Config,
+        cx: &Context,
+    ) -> Option<u64> {
+        let Some(Some(_)) = self.tiles.get(&tile_id) else {
+            // Tile hasn't finished loading.
+            return None;
+        };
+        if let Some(lanes) = &self.title_lanes {
+            if !lanes.regrouped.contains(&tile_id) {
+                // Still waiting for the meta tile
+                return None;
+            }
+        }
+        if let Some(stable) = &self.stable_rows {
+            if !stable.regrouped.contains(&tile_id) {
+                return None;
+            }
+        }
+        if !cx.view.interval.overlaps(tile_id.0) {
+            return None;
+        }
+
+        // Items keep their own colors until the values to color them by load
+        let color = config
+            .color_by
+            .as_ref()
+            .map(|c| c.field_id)
+            .map(|field_id| {
+                // Stale tiles are on their way out, so don't fetch for them
+                let loaded = if stale {
+                    let values = self.color_values.get(&tile_id);
+                    values.is_some_and(|(f, _)| *f == field_id)
+                } else {
+                    self.update_color_values(tile_id, field_id, config)
+                };
+                let color_by = config.color_by.as_ref().unwrap();
+                (field_id, color_by.min, color_by.max, loaded)
+            });
+
+        // Rebuild the cached meshes only if something they depend on changed
+        let key = TileMeshKey {
+            view_interval: cx.view.interval,
+            size,
+            rows,
+            highlight: config.highlight_key,
+            dim: stale && cx.dim_stale_tiles,
+            color,
+        };
+        let cache_key = (cx.view.index, stale, tile_id);
+        match self.meshes.get(&cache_key) {
+            Some(tile_mesh) if tile_mesh.key == key => Some(tile_mesh.generation),
+            _ => {
+                let meshes = self.build_tile_mesh(tile_id, stale, rows, size, config, cx);
+                self.mesh_generation += 1;
+                let generation = self.mesh_generation;
+                let tile_mesh = TileMesh {
+                    key,
+                    generation,
+                    meshes,
+                };
+                self.meshes.insert(cache_key, tile_mesh);
+                self.memory = None;
+                Some(generation)
+            }
+        }
+    }
+
+    // Take the values of the field to color by from the tile's meta tile,
+    // widening the range of the gradient to fit. Returns false while the
+    // meta tile loads
+    fn update_color_values(
+        &mut self,
+        tile_id: TileID,
+        field_id: FieldID,
+        config: &mut Config,
+    ) -> bool {
+        if self
+            .color_values
+            .get(&tile_id)
+            .is_some_and(|(f, _)| *f == field_id)
+        {
+            return true;
+        }
+        let Some(meta) = self.fetch_meta_tile(tile_id, config) else {
+            return false;
+        };
+        let values = meta.numeric_field(field_id);
+        let color_by = config.color_by.as_mut().unwrap();
+        for value in values.iter().flatten().flatten() {
+            color_by.include(*value);
+        }
+        self.color_values.insert(tile_id, (field_id, values));
+        self.memory = None;
+        true
+    }
+
+    // Rows (counted from the top) that are at least partly in the viewport
+    fn visible_rows(rows: u64, rect: Rect, viewport: Rect) -> Range<u64> {
+        // Items only cover the middle 90% of their row (see item_rect)
+        let row_height = rect.height() / rows as f32;
+        let first = (viewport.min.y / row_height - 0.95).ceil().max(0.0) as u64;
+        let last = (viewport.max.y / row_height - 0.05).floor() + 1.0;
+        let first = first.min(rows);
+        first..(last.max(0.0) as u64).clamp(first, rows)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_tile(
+        &mut self,
+        tile_index: usize,
+        stale: bool,
+        rows: u64,
+        hover_pos: Option<Pos2>,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) -> Option<Pos2> {
+        // Hack: can't pass this as an argument because it aliases self.
+        let view = &self.views[cx.view.index];
+        let tile_id = if stale {
+            view.stale_tile_ids[tile_index]
+        } else {
+            view.tile_ids[tile_index]
+        };
+        if self
+            .update_tile_mesh(tile_id, stale, rows, rect.size(), config, cx)
+            .is_none()
+        {
+            return hover_pos;
+        }
+
+        let visible = Self::visible_rows(rows, rect, viewport);
+        let tile_mesh = &self.meshes[&(cx.view.index, stale, tile_id)];
+        for (row, row_mesh) in tile_mesh.meshes.iter().enumerate() {
+            // Need to reverse the rows because we're working in screen space
+            if visible.contains(&(rows - (row as u64) - 1)) {
+                let mut mesh = row_mesh.clone();
+                mesh.translate(rect.min.to_vec2());
+                ui.painter().add(egui::Shape::mesh(mesh));
+            }
+        }
+
+        self.interact_tile(tile_id, rows, hover_pos, ui, rect, viewport, config, cx)
+    }
+
+    // Draw the current tiles of the view. Their meshes are merged into one,
+    // which is drawn as is for as long as none of the tiles change (e.g.,
+    // while only the cursor moves)
+    #[allow(clippy::too_many_arguments)]
+    fn render_tiles(
+        &mut self,
+        rows: u64,
+        mut hover_pos: Option<Pos2>,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) {
+        let tile_ids = self.views[cx.view.index].tile_ids.clone();
+        let mut tiles = Vec::with_capacity(tile_ids.len());
+        let mut textured = Vec::new();
+        for tile_id in tile_ids {
+            if cx.rasterize_dense_tiles
+                && self.render_tile_texture(tile_id, rows, ui, rect, config, cx)
+            {
+                textured.push(tile_id);
+            } else if let Some(generation) =
+                self.update_tile_mesh(tile_id, false, rows, rect.size(), config, cx)
+            {
+                tiles.push((tile_id, generation));
+            }
+        }
+
+        let key = SlotMeshKey {
+            tiles,
+            visible_rows: Self::visible_rows(rows, rect, viewport),
+        };
+        let view = cx.view.index;
+        if !self.slot_meshes.get(&view).is_some_and(|m| m.key == key) {
+            let mut mesh = egui::Mesh::default();
+            for (tile_id, _) in &key.tiles {
+                let tile_mesh = &self.meshes[&(view, false, *tile_id)];
+                for (row, row_mesh) in tile_mesh.meshes.iter().enumerate() {
+                    if key.visible_rows.contains(&(rows - (row as u64) - 1)) {
+                        mesh.append_ref(row_mesh);
+                    }
+                }
+            }
+            self.slot_meshes.insert(view, SlotMesh { key, mesh });
+            self.memory = None;
+        }
+        let slot_mesh = &self.slot_meshes[&view];
+        let mut mesh = slot_mesh.mesh.clone();
+        mesh.translate(rect.min.to_vec2());
+        ui.painter().add(egui::Shape::mesh(mesh));
+
+        let meshed = slot_mesh.key.tiles.iter().map(|(t, _)| *t);
+        let tile_ids: Vec<_> = meshed.chain(textured).collect();
+        for tile_id in tile_ids {
+            hover_pos =
+                self.interact_tile(tile_id, rows, hover_pos, ui, rect, viewport, config, cx);
+        }
+    }
+
+    // Show the tooltip of the hovered item (or merged items) in the tile,
+    // and select the item on click
+    #[allow(clippy::too_many_arguments)]
+    fn interact_tile(
+        &mut self,
+        tile_id: TileID,
+        rows: u64,
+        mut hover_pos: Option<Pos2>,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) -> Option<Pos2> {
+        if hover_pos.is_none() {
+            return hover_pos;
+        }
+        let tile = self.tiles[&tile_id].as_ref().unwrap();
+        let visible = Self::visible_rows(rows, rect, viewport);
+
+        // Track which item, if any, we're interacting with
+        let mut interact_item = None;
+
+        for (row, row_items) in tile.items.iter().enumerate() {
+            // Need to reverse the rows because we're working in screen space
+            let irow = rows - (row as u64) - 1;
+            if !visible.contains(&irow) {
+                continue;
+            }
+
+            // Compute bounds for the whole row
+            let row_min = rect.lerp_inside(Vec2::new(0.0, (irow as f32 + 0.05) / rows as f32));
+            let row_max = rect.lerp_inside(Vec2::new(1.0, (irow as f32 + 0.95) / rows as f32));
+
+            // Check if mouse is hovering over this row
+            let row_rect = Rect::from_min_max(row_min, row_max);
+            if !hover_pos.is_some_and(|h| row_rect.contains(h)) {
+                continue;
+            }
+
+            // Now find the hovered item, if any
+            for item_idx in Self::visible_items(row_items, cx.view.interval) {
+                let item = &row_items[item_idx];
+                let item_rect = Self::item_rect(item, irow, rows, rect, cx);
+                if hover_pos.is_some_and(|h| item_rect.contains(h)) {
+                    hover_pos = None;
+                    interact_item = Some((row, item_idx, item.item_uid, item_rect, tile_id));
+                    break;
+                }
+            }
+
+            // Otherwise, check if we're hovering over merged items
+            if let Some(hover) = hover_pos {
+                let column = (hover.x - rect.left()).floor();
+                let column_interval = Interval::new(
+                    cx.view.interval.lerp(column / rect.width()),
+                    cx.view
+                        .interval
+                        .lerp((column + MIN_ITEM_WIDTH) / rect.width()),
+                );
+                let count = row_items[Self::visible_items(row_items, column_interval)]
+                    .iter()
+                    .filter(|item| {
+                        Self::item_rect(item, irow, rows, rect, cx).width() < MIN_ITEM_WIDTH
+                    })
+                    .count();
+                if count > 1 {
+                    hover_pos = None;
+                    let column_rect = Rect::from_min_max(
+                        Pos2::new(rect.left() + column, row_min.y),
+                        Pos2::new(rect.left() + column + MIN_ITEM_WIDTH, row_max.y),
+                    );
+                    ui.show_tooltip_ui("merged_tooltip", &column_rect, |ui| {
+                        ui.label(format!("{count} items"));
+                        ui.label("(Zoom in to see individual items.)");
+                    });
+                }
+            }
+        }
+
+        if let Some((row, item_idx, item_uid, item_rect, tile_id)) = interact_item {
+            // Hack: clone here  to avoid mutability conflict.
+            let entry_id = self.entry_id.clone();
+            if let Some(item_meta) = self.fetch_meta_item(tile_id, row, item_idx, item_uid, config)
+            {
+                let row_name = self.row_name(row);
+                ui.show_tooltip_ui("task_tooltip", &item_rect, |ui| {
+                    show_item_tooltip(ui, &item_meta, config, cx);
+                    if let Some(row_name) = row_name {
+                        ui.label(format!("Row: {row_name}"));
+                    }
+                    ui.label("(Click to show details.)");
+                });
+
+                // Also mark task as selected if the mouse has been clicked
+                ui.input(|i| {
+                    // A "click" is measured on *release*, assuming certain
+                    // properties hold (e.g., the button was held less than
+                    // some duration, and it moved less than some amount).
+                    if i.pointer.any_click() && i.pointer.primary_released() {
+                        let irow = Some(rows as usize - row - 1);
+                        match config.items_selected.entry(item_meta.item_uid) {
+                            std::collections::btree_map::Entry::Vacant(e) => {
+                                e.insert(ItemDetail {
+                                    meta: Some(item_meta.clone()),
+                                    loc: ItemLocator {
+                                        entry_id,
+                                        irow,
+                                        item_uid: item_meta.item_uid,
+                                        profile: None,
+                                    },
+                                    field_filter: String::new(),
+                                });
+                            }
+                            std::collections::btree_map::Entry::Occupied(e) => {
+                                e.remove_entry();
+                            }
+                        }
+                    }
+                });
+            }
+        }
+
+        hover_pos
+    }
+}
+
+impl Entry for Slot {
+    fn new(info: &EntryInfo, entry_id: EntryID) -> Self {
+        if let EntryInfo::Slot {
+            short_name,
+            long_name,
+            max_rows,
+            row_names,
+        } = info
+        {
+            Self {
+                entry_id,
+                short_name: short_name.to_owned(),
+                long_name: long_name.to_owned(),
+                expanded: true,
+                max_rows: *max_rows,
+                row_names: row_names.clone(),
+                tiles: BTreeMap::new(),
+                tile_metas: BTreeMap::new(),
+                failed: BTreeMap::new(),
+                item_metas: BTreeMap::new(),
+                focused_item: None,
+                views: Default::default(),
+                meshes: BTreeMap::new(),
+                mesh_generation: 0,
+                slot_meshes: BTreeMap::new(),
+                textures: BTreeMap::new(),
+                color_values: BTreeMap::new(),
+                last_shown: 0,
+                last_shown_time: 0.0,
+                memory: None,
+                occupied_rows: [*max_rows; MAX_VIEWS],
+                first_row: 0,
+                title_lanes: None,
+                stable_rows: None,
+                heatmap: false,
+                busy_fractions: Default::default(),
+            }
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn entry_id(&self) -> &EntryID {
+        &self.entry_id
+    }
+    fn label_text(&self) -> &str {
+        &self.short_name
+    }
+    fn hover_text(&self) -> &str {
+        &self.long_name
+    }
+
+    fn find_slot(&self, entry_id: &EntryID, level: u64) -> Option<&Slot> {
+        assert_eq!(entry_id.level(), level);
+        assert!(entry_id.slot_index(level - 1).is_some());
+        Some(self)
+    }
+
+    fn find_slot_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Slot> {
+        assert_eq!(entry_id.level(), level);
+        assert!(entry_id.slot_index(level - 1).is_some());
+        Some(self)
+    }
+
+    fn find_summary_mut(&mut self, _entry_id: &EntryID, _level: u64) -> Option<&mut Summary> {
+        unreachable!()
+    }
+
+    fn expand_slot(&mut self, entry_id: &EntryID, level: u64) {
+        assert_eq!(entry_id.level(), level);
+        assert!(entry_id.slot_index(level - 1).is_some());
+        self.expanded = true;
+    }
+
+    fn inflate_meta(&mut self, config: &mut Config, cx: &mut Context) {
+        // Only handle slots belonging to the current pass (visible first)
+        let search = &mut config.search_state;
+        if search.walk_visible != search.meta_pass_visible {
+            return;
+        }
+        search.total_entries += 1;
+
+        let mut loaded = true;
+        for tile_id in config.request_tiles(cx.view.interval) {
+            match self.tile_metas.get(&tile_id) {
+                Some(Some(_)) => {}
+                Some(None) => loaded = false,
+                None => {
+                    loaded = false;
+                    if config.search_state.meta_budget > 0 {
+                        config.search_state.meta_budget -= 1;
+                        self.fetch_meta_tile(tile_id, config);
+                    }
+                }
+            }
+        }
+        if loaded {
+            config.search_state.searched_entries += 1;
+        }
+    }
+
+    fn search(&mut self, config: &mut Config) {
+        if !config.search_state.start_entry(self) {
+            return;
+        }
+        config.search_state.insert_remote(self, self.max_rows);
+
+        for (tile_id, tile) in &self.tile_metas {
+            if let Some(tile) = tile {
+                if !config.search_state.start_tile(self, *tile_id) {
+                    continue;
+                }
+
+                for (row, item) in tile.iter() {
+                    let row_name = self.row_names.get(row).map(String::as_str);
+                    if config.search_state.is_match(&item, row_name) {
+                        // Reverse rows because we're in screen space
+                        let irow = tile.rows() - row - 1;
+                        config.search_state.insert(self, *tile_id, irow, &item);
+                    }
+                }
+            }
+        }
+    }
+
+    fn content(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) {
+        cx.view.slot_rect = Some(rect); // Save slot rect for use later
+        self.last_shown = cx.frame;
+        self.last_shown_time = cx.frame_time;
+
+        // Focusable so that items can be traversed with the keyboard
+        let sense = egui::Sense {
+            focusable: true,
+            ..egui::Sense::hover()
+        };
+        let mut response = ui.allocate_rect(rect, sense);
+        let mut hover_pos = response.hover_pos(); // where is the mouse hovering?
+
+        // Report the slot to the cursor, which is drawn over the slots (and
+        // so takes the hover)
+        let pointer = ui
+            .input(|i| i.pointer.hover_pos())
+            .filter(|pos| rect.contains(*pos) && ui.clip_rect().contains(*pos));
+        if pointer.is_some() {
+            cx.view.hover_slot = Some((self.long_name.clone(), None, None));
+        }
+
+        if self.expanded || cx.collapsed_sparklines {
+            self.update_view(config, cx);
+            self.update_busy_fraction(config, cx);
+        } else {
+            // The view's tiles aren't kept up to date
+            self.busy_fractions[cx.view.index] = None;
+        }
+
+        if self.expanded && self.heatmap {
+            let visuals = ui.style().interact_selectable(&response, false);
+            ui.painter()
+                .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+            self.render_heatmap(hover_pos, ui, rect, cx);
+        } else if self.expanded {
+            let style = ui.style();
+            let visuals = style.interact_selectable(&response, false);
+            ui.painter()
+                .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+
+            // The magnifier takes over hover from individual items
+            let magnifier_pos = if cx.magnifier { hover_pos.take() } else { None };
+
+            if cx.fit_rows {
+                self.update_occupied_rows(cx.view.index);
+            }
+            let rows = self.rows(cx);
+            let shown = self.shown_rows(cx);
+
+            // Draw every row, shifted to the page and clipped to the slot
+            let clip_rect = ui.clip_rect();
+            let (page_rect, page_viewport) = if shown < rows {
+                ui.set_clip_rect(clip_rect.intersect(rect));
+                self.page_rect(rows, shown, rect, viewport)
+            } else {
+                (rect, viewport)
+            };
+
+            if let Some(pointer) = pointer {
+                // Rows in the data source count from the bottom
+                let row_height = page_rect.height() / rows as f32;
+                let irow = ((pointer.y - page_rect.top()) / row_height) as u64;
+                let row = rows - irow.min(rows - 1) - 1;
+                let row_name = self.row_name(row as usize).map(str::to_owned);
+                cx.view.hover_slot = Some((self.long_name.clone(), Some(row), row_name));
+            }
+
+            self.render_row_guides(rows, ui, page_rect, page_viewport, cx);
+            self.render_stale_tiles(rows, ui, page_rect, page_viewport, config, cx);
+            self.render_tiles(rows, hover_pos, ui, page_rect, page_viewport, config, cx);
+
+            if response.has_focus() {
+                self.keyboard(&mut response, ui, rows, page_rect, config, cx);
+                // Follow the focus when it moves off the page
+                let focused_row = self.focused_item.filter(|_| response.changed());
+                if let Some((row, _)) = focused_row {
+                    self.turn_to_row(rows - row as u64 - 1, rows, shown);
+                }
+            }
+            self.render_lane_labels(rows, ui, page_rect, page_viewport);
+            self.render_row_labels(rows, ui, page_rect, page_viewport);
+            ui.set_clip_rect(clip_rect);
+
+            if shown < rows {
+                self.render_pager(rows, shown, ui, rect);
+            }
+
+            if let Some(hover) = magnifier_pos {
+                self.render_magnifier(hover, ui, rect, config, cx);
+            }
+        } else if cx.collapsed_sparklines {
+            self.render_sparkline(ui, rect, cx);
+        }
+
+        if cx.debug && self.expanded {
+            let state = |tile_id: &TileID| match self.tiles.get(tile_id) {
+                None => TileDebugState::Missing,
+                Some(None) if self.tile_error(tile_id).is_some() => TileDebugState::Failed,
+                Some(None) => TileDebugState::Requested,
+                Some(Some(tile)) => {
+                    let items: usize = tile.items.iter().map(Vec::len).sum();
+                    TileDebugState::Loaded(format!("{items} items"))
+                }
+            };
+            self.views[cx.view.index].render_debug(state, ui, rect, cx);
+        }
+
+        if !self.failed.is_empty() {
+            let error = |tile_id: &TileID| self.tile_error(tile_id).map(ToString::to_string);
+            let retry = self.views[cx.view.index].render_failures(error, ui, rect, cx);
+            if !retry.is_empty() {
+                self.retry(retry, config);
+            }
+        }
+
+        response.widget_info(|| {
+            egui::WidgetInfo::labeled(egui::WidgetType::Other, self.accessible_label(cx))
+        });
+    }
+
+    fn height(&self, _prefix: Option<&EntryID>, _config: &Config, cx: &Context) -> f32 {
+        self.shown_rows(cx) as f32 * cx.row_height
+    }
+
+    fn is_expandable(&self) -> bool {
+        true
+    }
+
+    fn toggle_expanded(&mut self) {
+        self.expanded = !self.expanded;
+    }
+
+    fn has_context_menu(&self) -> bool {
+        true
+    }
+
+    fn context_menu(&mut self, ui: &mut egui::Ui, config: &mut Config, cx: &Context) {
+        let mut title_lanes = self.title_lanes.is_some();
+        if ui
+            .checkbox(&mut title_lanes, "One Lane per Title")
+            .on_hover_text("Regroup items by title, so that recurring items line up")
+            .changed()
+        {
+            self.set_title_lanes(title_lanes);
+            ui.close_menu();
+        }
+        if ui
+            .checkbox(&mut self.heatmap, "Heatmap")
+            .on_hover_text("Collapse the rows into one strip, shaded by activity")
+            .changed()
+        {
+            if self.heatmap && self.title_lanes.is_some() {
+                self.set_title_lanes(false);
+            }
+            ui.close_menu();
+        }
+        let mut in_scope = config.search_state.scope.contains(&self.entry_id);
+        if ui
+            .checkbox(&mut in_scope, "Search This Slot")
+            .on_hover_text("Restrict the search to the slots picked this way")
+            .changed()
+        {
+            config.search_state.toggle_scope(&self.entry_id);
+            ui.close_menu();
+        }
+        if ui.button("Find Idle Gaps").clicked() {
+            let interval = cx.view.interval.intersection(config.interval);
+            config.idle_gaps = Some(self.idle_gaps(interval, cx.view.index));
+            ui.close_menu();
+        }
+        if ui
+            .button("Time Breakdown by Title")
+            .on_hover_text("Over the selected interval, if any, or else the view")
+            .clicked()
+        {
+            let interval = match cx.selected_interval {
+                Some((selected, view)) if view == cx.view.index => selected,
+                _ => cx.view.interval,
+            };
+            config.time_breakdown = Some(TimeBreakdown::new(
+                self.entry_id.clone(),
+                self.long_name.clone(),
+                interval.intersection(config.interval),
+            ));
+            ui.close_menu();
+        }
+    }
+
+    fn label_badge(&self, cx: &Context) -> Option<String> {
+        let (_, fraction) = self.busy_fractions[cx.view.index]?;
+        Some(format!("{:.0}%", fraction * 100.0))
+    }
+
+    fn busy_time(&self, interval: Interval, view: usize) -> i64 {
+        self.busy_intervals(interval, view)
+            .iter()
+            .map(|busy| busy.duration_ns())
+            .sum()
+    }
+
+    fn activity(
+        &mut self,
+        buckets: usize,
+        _config: &mut Config,
+        cx: &mut Context,
+    ) -> Option<Vec<f32>> {
+        // Only from the tiles already loaded (e.g., from when the slot was
+        // last drawn). Slots under collapsed panels are neither fetched nor
+        // kept around for this, so they are released as usual
+        let interval = cx.view.interval;
+        let mut tiles = self
+            .tiles
+            .iter()
+            .filter(|(tile_id, _)| tile_id.0.overlaps(interval))
+            .filter_map(|(_, tile)| tile.as_ref())
+            .peekable();
+        tiles.peek()?;
+        Some(bucket_utilization(
+            merge_busy(tiles, interval),
+            interval,
+            buckets,
+        ))
+    }
+}
+
+impl<S: Entry> Panel<S> {
+    fn render<T: Entry>(
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        slot: &mut T,
+        y: &mut f32,
+        config: &mut Config,
+        cx: &mut Context,
+    ) -> bool {
+        const LABEL_WIDTH: f32 = 60.0;
+        const COL_PADDING: f32 = 4.0;
+        const ROW_PADDING: f32 = 4.0;
+
+        // Compute the size of this slot
+        // This is in screen (i.e., rect) space
+        let min_y = *y;
+        let max_y = min_y + slot.height(None, config, cx);
+        *y = max_y + ROW_PADDING;
+
+        // Cull if out of bounds
+        // Note: need to shift by rect.min to get to viewport space
+        if max_y - rect.min.y < viewport.min.y {
+            return false;
+        } else if min_y - rect.min.y > viewport.max.y {
+            return true;
+        }
+
+        // Draw label and content
+        let label_min = rect.min.x;
+        let label_max = (rect.min.x + LABEL_WIDTH).at_most(rect.max.x);
+        let content_min = (label_max + COL_PADDING).at_most(rect.max.x);
+        let content_max = rect.max.x;
+
+        let label_subrect =
+            Rect::from_min_max(Pos2::new(label_min, min_y), Pos2::new(label_max, max_y));
+        let content_subrect =
+            Rect::from_min_max(Pos2::new(content_min, min_y), Pos2::new(content_max, max_y));
+
+        // Shift viewport up by the amount consumed
+        // Invariant: (0, 0) in viewport is rect.min
+        //   (i.e., subtracting rect.min gets us from screen space to viewport space)
+        // Note: viewport.min is NOT necessarily (0, 0)
+        let content_viewport = viewport.translate(Vec2::new(0.0, rect.min.y - min_y));
+
+        slot.content(ui, content_subrect, content_viewport, config, cx);
+        slot.label(ui, label_subrect, config, cx);
+
+        false
+    }
+
+    // Collapsed panels without a summary show the combined utilization of
+    // their slots, rather than nothing at all. Returns the average
+    // utilization over the view, once something has loaded
+    fn render_activity(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) -> Option<f32> {
+        const PIXELS_PER_BUCKET: f32 = 2.0;
+        let buckets = ((rect.width() / PIXELS_PER_BUCKET) as usize).at_least(1);
+        let visuals = ui.style().visuals.widgets.noninteractive;
+        ui.painter()
+            .rect(rect, 0.0, visuals.bg_fill, visuals.bg_stroke);
+        let activity = self.activity(buckets, config, cx)?;
+        let average = activity.iter().sum::<f32>() / buckets as f32;
+        let color = visuals.fg_stroke.color.gamma_multiply(0.5);
+        for (i, util) in activity.into_iter().enumerate() {
+            if util <= 0.0 {
+                continue;
+            }
+            let bar = Rect::from_min_max(
+                rect.lerp_inside(Vec2::new(i as f32 / buckets as f32, 1.0 - util)),
+                rect.lerp_inside(Vec2::new((i + 1) as f32 / buckets as f32, 1.0)),
+            );
+            ui.painter().rect_filled(bar, 0.0, color);
+        }
+        Some(average)
+    }
+
+    // Collapsed nodes are summarized by what they contain, e.g., "32 CPUs ·
+    // 4 GPUs · 61% busy", so that they can be triaged without expanding
+    // them. Kinds hidden by the kind filter are left out
+    fn render_rollup(&self, busy: Option<f32>, ui: &mut egui::Ui, rect: Rect, config: &Config) {
+        let mut parts: Vec<_> = self
+            .slots
+            .iter()
+            .filter(|kind| Self::is_slot_visible(kind, config) && kind.child_count() > 0)
+            .map(|kind| {
+                let count = kind.child_count();
+                let name = kind.label_text();
+                if count == 1 {
+                    format!("{count} {name}")
+                } else if let Some(stem) = name.strip_suffix('y') {
+                    format!("{count} {stem}ies")
+                } else {
+                    format!("{count} {name}s")
+                }
+            })
+            .collect();
+        if let Some(busy) = busy {
+            parts.push(format!("{:.0}% busy", busy * 100.0));
+        }
+        if parts.is_empty() {
+            return;
+        }
+        let style = ui.style();
+        ui.painter().text(
+            rect.left_top() + style.spacing.item_spacing * 0.5,
+            Align2::LEFT_TOP,
+            parts.join(" · "),
+            TextStyle::Small.resolve(style),
+            style.visuals.text_color(),
+        );
+    }
+
+    // Busy time changes as tiles load, so this is done every frame
+    fn sort_slots(&mut self, config: &Config, cx: &Context) {
+        let slots = &self.slots;
+        let order = &mut self.display_order;
+        order.clear();
+        order.extend(0..slots.len());
+        match self.slot_order {
+            SlotOrder::Index => {}
+            SlotOrder::Name => order.sort_by_cached_key(|&i| slots[i].label_text().to_lowercase()),
+            SlotOrder::BusyTime => {
+                let interval = cx.view.interval.intersection(config.interval);
+                order.sort_by_cached_key(|&i| {
+                    std::cmp::Reverse(slots[i].busy_time(interval, cx.view.index))
+                });
+            }
+        }
+    }
+
+    fn is_slot_visible(slot: &S, config: &Config) -> bool {
+        let level = slot.entry_id().level();
+        if level == 1 {
+            // Apply node filter.
+            let index = slot.entry_id().last_slot_index().unwrap();
+            config.selected_nodes.contains(&index)
+        } else if level == 2 {
+            // Apply kind filter.
+            let kind = slot.label_text();
+            config.kind_filter.is_empty()
+                || config.kind_filter.contains(kind) != config.kind_filter_exclude
+        } else if let Some(regex) = &config.name_filter_regex {
+            // Apply name filter.
+            regex.is_match(slot.label_text()) || regex.is_match(slot.hover_text())
+        } else {
+            true
+        }
+    }
+}
+
+impl<S: Entry> Entry for Panel<S> {
+    fn new(info: &EntryInfo, entry_id: EntryID) -> Self {
+        if let EntryInfo::Panel {
+            short_name,
+            long_name,
+            summary,
+            slots,
+        } = info
+        {
+            let expanded = default_expanded(&entry_id);
+            let summary = summary
+                .as_ref()
+                .map(|s| Summary::new(s, entry_id.summary()));
+            let slots: Vec<_> = slots
+                .iter()
+                .enumerate()
+                .map(|(i, s)| S::new(s, entry_id.child(i as u64)))
+                .collect();
+            Self {
+                entry_id,
+                short_name: short_name.to_owned(),
+                long_name: long_name.to_owned(),
+                expanded,
+                summary,
+                display_order: (0..slots.len()).collect(),
+                slots,
+                slot_order: SlotOrder::default(),
+            }
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn entry_id(&self) -> &EntryID {
+        &self.entry_id
+    }
+    fn label_text(&self) -> &str {
+        &self.short_name
+    }
+    fn hover_text(&self) -> &str {
+        &self.long_name
+    }
+
+    fn find_slot(&self, entry_id: &EntryID, level: u64) -> Option<&Slot> {
+        self.slots
+            .get(entry_id.slot_index(level)? as usize)?
+            .find_slot(entry_id, level + 1)
+    }
+
+    fn find_slot_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Slot> {
+        self.slots
+            .get_mut(entry_id.slot_index(level)? as usize)?
+            .find_slot_mut(entry_id, level + 1)
+    }
+
+    fn find_summary_mut(&mut self, entry_id: &EntryID, level: u64) -> Option<&mut Summary> {
+        if level < entry_id.level() - 1 {
+            self.slots
+                .get_mut(entry_id.slot_index(level)? as usize)?
+                .find_summary_mut(entry_id, level + 1)
+        } else {
+            self.summary.as_mut()?.find_summary_mut(entry_id, level + 1)
+        }
+    }
+
+    fn expand_slot(&mut self, entry_id: &EntryID, level: u64) {
+        self.slots
+            .get_mut(entry_id.slot_index(level).unwrap() as usize)
+            .unwrap()
+            .expand_slot(entry_id, level + 1);
+        self.expanded = true;
+    }
+
+    fn inflate_meta(&mut self, config: &mut Config, cx: &mut Context) {
+        let force = config.search_state.searches_collapsed();
+        if self.expanded || force {
+            // Hack: track whether the slots are visible on the way down
+            let parent_visible = config.search_state.walk_visible;
+            for slot in &mut self.slots {
+                // Apply visibility settings
+                let visible = Self::is_slot_visible(slot, config);
+                if (!force && !visible) || !config.search_state.in_scope(slot.entry_id()) {
+                    continue;
+                }
+
+                config.search_state.walk_visible = parent_visible && self.expanded && visible;
+                slot.inflate_meta(config, cx);
+            }
+            config.search_state.walk_visible = parent_visible;
+        }
+    }
+
+    fn search(&mut self, config: &mut Config) {
+        let force = config.search_state.searches_collapsed();
+        if self.expanded || force {
+            for slot in &mut self.slots {
+                // Apply visibility settings
+                if (!force && !Self::is_slot_visible(slot, config))
+                    || !config.search_state.in_scope(slot.entry_id())
+                {
+                    continue;
+                }
+
+                slot.search(config);
+            }
+        }
+    }
+
+    fn content(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: Rect,
+        viewport: Rect,
+        config: &mut Config,
+        cx: &mut Context,
+    ) {
+        let mut y = rect.min.y;
+        if let Some(summary) = &mut self.summary {
+            Self::render(ui, rect, viewport, summary, &mut y, config, cx);
+        }
+
+        if !self.expanded && self.summary.is_none() {
+            let busy = self.render_activity(ui, rect, config, cx);
+            if self.entry_id.level() == 1 {
+                self.render_rollup(busy, ui, rect, config);
+            }
+        } else if self.expanded {
+            self.sort_slots(config, cx);
+            for &index in &self.display_order {
+                let slot = &mut self.slots[index];
+                // Apply visibility settings
+                if !Self::is_slot_visible(slot, config) {
+                    continue;
+                }
+
+                if Self::render(ui, rect, viewport, slot, &mut y, config, cx) {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn height(&self, prefix: Option<&EntryID>, config: &Config, cx: &Context) -> f32 {
+        const UNEXPANDED_ROWS: u64 = 2;
+        const ROW_PADDING: f32 = 4.0;
+
+        let mut total = 0.0;
+        let mut rows: i64 = 0;
+        if let Some(summary) = &self.summary {
+            total += summary.height(None, config, cx);
+            rows += 1;
+        } else if !self.expanded {
+            // Need some minimum space if this panel has no summary and is collapsed
+            total += UNEXPANDED_ROWS as f32 * cx.row_height;
+            rows += 1;
+        }
+
+        if self.expanded {
+            for slot in self.display_order.iter().map(|&index| &self.slots[index]) {
+                if let Some(prefix) = prefix {
+                    // If this is our entry, stop
+                    if slot.entry_id() == prefix {
+                        break;
+                    }
+                }
+
+                // Apply visibility settings
+                if !Self::is_slot_visible(slot, config) {
+                    continue;
+                }
+
+                total += slot.height(prefix, config, cx);
+
+                if let Some(prefix) = prefix {
+                    // If we're a prefix of the entry, recurse and then stop
+                    if prefix.has_prefix(slot.entry_id()) {
+                        break;
+                    }
+                }
+
+                rows += 1;
+            }
+        }
+
+        total += (rows - 1).at_least(0) as f32 * ROW_PADDING;
+
+        total
+    }
+
+    fn is_expandable(&self) -> bool {
+        !self.slots.is_empty()
+    }
+
+    fn toggle_expanded(&mut self) {
+        self.expanded = !self.expanded;
+    }
+
+    fn has_context_menu(&self) -> bool {
+        self.slots.len() > 1
+    }
+
+    fn child_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn context_menu(&mut self, ui: &mut egui::Ui, _config: &mut Config, _cx: &Context) {
+        ui.label("Sort By");
+        for order in [SlotOrder::Index, SlotOrder::Name, SlotOrder::BusyTime] {
+            if ui
+                .radio_value(&mut self.slot_order, order, order.label_text())
+                .clicked()
+            {
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn busy_time(&self, interval: Interval, view: usize) -> i64 {
+        self.slots
+            .iter()
+            .map(|slot| slot.busy_time(interval, view))
+            .sum()
+    }
+
+    fn activity(
+        &mut self,
+        buckets: usize,
+        config: &mut Config,
+        cx: &mut Context,
+    ) -> Option<Vec<f32>> {
+        if let Some(summary) = &mut self.summary {
+            return summary.activity(buckets, config, cx);
+        }
+
+        // Average of the visible slots that have loaded
+        let mut total = vec![0.0; buckets];
+        let mut count = 0;
+        for slot in &mut self.slots {
+            if !Self::is_slot_visible(slot, config) {
+                continue;
+            }
+            if let Some(activity) = slot.activity(buckets, config, cx) {
+                for (total, util) in total.iter_mut().zip(activity) {
+                    *total += util;
+                }
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        for total in &mut total {
+            *total /= count as f32;
+        }
+        Some(total)
+    }
+}
+
+// Kinds start collapsed, everything else starts expanded
+fn default_expanded(entry_id: &EntryID) -> bool {
+    entry_id.level() != 2
+}
+
+// Explains a mismatch between the data source's protocol and the viewer's.
+// The info itself was readable, so the profile is still shown.
+fn protocol_warning(version: u32) -> Option<String> {
+    if version == PROTOCOL_VERSION {
+        None
+    } else if version == 0 {
+        Some(format!(
+            "The data source does not report a protocol version (this viewer uses \
+             version {PROTOCOL_VERSION}). It may be outdated, and some data may not \
+             display correctly."
+        ))
+    } else {
+        let newer = if version > PROTOCOL_VERSION {
+            "newer"
+        } else {
+            "older"
+        };
+        Some(format!(
+            "The data source uses {newer} protocol version {version} (this viewer uses \
+             version {PROTOCOL_VERSION}). Some data may not display correctly."
+        ))
+    }
+}
+
+// Key used to look up persisted state for a given profile
+fn profile_key(description: &DataSourceDescription) -> String {
+    description.source_locator.join("\n")
+}
+
+impl SearchState {
+    fn new(title_id: FieldID, remote_search: bool) -> Self {
+        Self {
+            title_field: title_id,
+
+            query: "".to_owned(),
+            last_query: "".to_owned(),
+            search_field: title_id,
+            last_search_field: title_id,
+            whole_word: false,
+            last_whole_word: false,
+            last_word_regex: None,
+            include_collapsed_entries: false,
+            last_include_collapsed_entries: false,
+            scope: BTreeSet::new(),
+            scope_only: false,
+            last_scope: None,
+            last_view_interval: None,
+            generation: 0,
+
+            meta_budget: 0,
+            meta_pass_visible: true,
+            walk_visible: true,
+            searched_entries: 0,
+            total_entries: 0,
+
+            remote_search,
+            remote_status: RemoteSearch::Idle,
+            remote_matches: BTreeMap::new(),
+
+            known_values: KnownValues::default(),
+
+            result_set: BTreeSet::new(),
+            result_cache: BTreeMap::new(),
+            cache_bytes: 0,
+            entry_tree: BTreeMap::new(),
+
+            table_rows: Vec::new(),
+            table_key: None,
+
+            title_groups: Vec::new(),
+            title_groups_key: None,
+
+            stats: None,
+            stats_key: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.remote_status = RemoteSearch::Idle;
+        self.remote_matches.clear();
+        self.result_set.clear();
+        self.result_cache.clear();
+        self.cache_bytes = 0;
+        self.entry_tree.clear();
+        self.table_rows.clear();
+        self.table_key = None;
+        self.title_groups.clear();
+        self.title_groups_key = None;
+        self.stats = None;
+        self.stats_key = None;
+        self.generation += 1;
+    }
+
+    fn ensure_valid_cache(&mut self, cx: &Context) {
+        let mut invalidate = false;
+
+        // Invalidate when the search query changes.
+        if self.query != self.last_query {
+            invalidate = true;
+            self.last_query.clone_from(&self.query);
+        }
+
+        // Invalidate when the search field changes.
+        if self.search_field != self.last_search_field {
+            invalidate = true;
+            self.last_search_field = self.search_field;
+        }
+
+        // Invalidate when the whole word setting changes.
+        if self.whole_word != self.last_whole_word {
+            invalidate = true;
+            self.last_whole_word = self.whole_word;
+        }
+
+        // Invalidate when EXCLUDING collapsed entries. (I.e., because the
+        // searched set shrinks. Growing is ok because search is monotonic.)
+        if self.include_collapsed_entries != self.last_include_collapsed_entries
+            && !self.include_collapsed_entries
+        {
+            invalidate = true;
+            self.last_include_collapsed_entries = self.include_collapsed_entries;
+        }
+
+        // Invalidate when the slots searched are restricted differently
+        let scope = self.scope_only.then_some(&self.scope);
+        if self.last_scope.as_ref() != scope {
+            invalidate = true;
+            self.last_scope = scope.cloned();
+        }
+
+        // Invalidate when the view interval changes.
+        if self.last_view_interval != Some(cx.view.interval) {
+            invalidate = true;
+            self.last_view_interval = Some(cx.view.interval);
+        }
+
+        if invalidate {
+            if self.whole_word {
+                let regex_string = format!("\\b{}\\b", escape(&self.query));
+                self.last_word_regex = Some(Regex::new(&regex_string).unwrap());
+            }
+
+            self.clear();
+        }
+    }
+
+    // Whether the walk goes into collapsed entries
+    fn searches_collapsed(&self) -> bool {
+        self.include_collapsed_entries || self.scope_only
+    }
+
+    // True if the entry is in the scope, or is above an entry in it
+    fn in_scope(&self, entry_id: &EntryID) -> bool {
+        !self.scope_only || self.scope.iter().any(|slot| slot.has_prefix(entry_id))
+    }
+
+    fn toggle_scope(&mut self, entry_id: &EntryID) {
+        if !self.scope.remove(entry_id) {
+            self.scope.insert(entry_id.clone());
+            self.scope_only = true;
+        }
+        self.scope_only &= !self.scope.is_empty();
+    }
+
+    fn is_string_match(&self, s: &str) -> bool {
+        if self.whole_word {
+            let Some(regex) = &self.last_word_regex else {
+                unreachable!();
+            };
+            regex.is_match(s)
+        } else {
+            s.contains(&self.query)
+        }
+    }
+
+    fn is_field_match(&self, field: &Field) -> bool {
+        match field {
+            Field::String(s) | Field::Markdown(s) => self.is_string_match(s),
+            Field::ItemLink(ItemLink { title, .. }) => self.is_string_match(title),
+            Field::Vec(fields) => fields.iter().any(|f| self.is_field_match(f)),
+            Field::Group(_, fields) => fields.iter().any(|(_, f)| self.is_field_match(f)),
+            _ => false,
+        }
+    }
+
+    // Finds the field with the given ID, looking inside groups as well
+    fn find_field(field_id: FieldID, value: &Field, target: FieldID) -> Option<&Field> {
+        if field_id == target {
+            return Some(value);
+        }
+        let Field::Group(_, fields) = value else {
+            return None;
+        };
+        fields
+            .iter()
+            .find_map(|(field_id, value)| Self::find_field(*field_id, value, target))
+    }
+
+    // Searching titles also finds items on rows whose names match
+    fn is_match(&self, item: &ItemMeta, row_name: Option<&str>) -> bool {
+        let field = self.search_field;
+        if field == self.title_field {
+            self.is_string_match(&item.title)
+                || row_name.is_some_and(|name| self.is_string_match(name))
+        } else if let Some(value) = item
+            .fields
+            .iter()
+            .find_map(|(field_id, value, _)| Self::find_field(*field_id, value, field))
+        {
+            self.is_field_match(value)
+        } else {
+            false
+        }
+    }
+
+    const MAX_SEARCH_RESULTS: usize = 100_000;
+
+    // Ask the data source to search the view, once per search. Every move of
+    // the view starts a new search, so wait for it to settle (see
+    // ProfApp::debounce_view) rather than searching every interval passed
+    // through while panning
+    fn start_remote(&mut self, data_source: &mut impl DeferredDataSource, view: &ViewState) {
+        if !self.remote_search || self.remote_status != RemoteSearch::Idle {
+            return;
+        }
+        if view.request_interval != view.interval {
+            return;
+        }
+        let interval = view.interval;
+        let query = SearchQuery {
+            text: self.query.clone(),
+            // The title field is the viewer's own
+            field: (self.search_field != self.title_field).then_some(self.search_field),
+            whole_word: self.whole_word,
+        };
+        data_source.fetch_search(&query, interval, Self::MAX_SEARCH_RESULTS);
+        self.remote_status = RemoteSearch::Pending(query, interval);
+    }
+
+    // True if the meta tiles that aren't loaded yet need not be fetched
+    // (for now), since the data source is searching them, or will be once
+    // the view settles
+    fn is_remote(&self) -> bool {
+        self.remote_search && self.remote_status != RemoteSearch::Incomplete
+    }
+
+    // True while the data source's matches are still to come
+    fn is_remote_pending(&self) -> bool {
+        self.remote_search
+            && matches!(
+                self.remote_status,
+                RemoteSearch::Idle | RemoteSearch::Pending(..)
+            )
+    }
+
+    fn receive_remote(&mut self, results: SearchResults) {
+        // Results of an earlier search are dropped
+        let RemoteSearch::Pending(query, interval) = &self.remote_status else {
+            return;
+        };
+        if results.query != *query || results.interval != *interval {
+            return;
+        }
+
+        if let Some(error) = results.error {
+            log::warn!("search by data source failed, searching locally: {error}");
+            self.remote_status = RemoteSearch::Incomplete;
+            return;
+        }
+        self.remote_status = if results.truncated {
+            RemoteSearch::Incomplete
+        } else {
+            RemoteSearch::Done
+        };
+        for m in results.matches {
+            self.remote_matches
+                .entry(m.entry_id.clone())
+                .or_default()
+                .push(m);
+        }
+    }
+
+    // Called after start_entry, with the number of rows of the slot
+    fn insert_remote<E: Entry>(&mut self, entry: &E, max_rows: u64) {
+        let Some(matches) = self.remote_matches.remove(entry.entry_id()) else {
+            return;
+        };
+        for m in matches {
+            if self.result_set.len() >= Self::MAX_SEARCH_RESULTS {
+                return;
+            }
+            if self.result_set.insert(m.item_uid) {
+                self.cache_bytes += std::mem::size_of::<SearchCacheItem>() + m.title.capacity();
+                let cache = self.result_cache.get_mut(entry.entry_id()).unwrap();
+                cache.entry(m.tile_id).or_default().insert(
+                    m.item_uid,
+                    SearchCacheItem {
+                        item_uid: m.item_uid,
+                        // Reverse rows because we're in screen space
+                        irow: max_rows.saturating_sub(m.row + 1) as usize,
+                        interval: m.original_interval,
+                        title: m.title,
+                    },
+                );
+            }
+        }
+    }
+
+    fn start_entry<E: Entry>(&mut self, entry: &E) -> bool {
+        // Early exit if we found enough items.
+        if self.result_set.len() >= Self::MAX_SEARCH_RESULTS {
+            return false;
+        }
+
+        // Double lookup is better than cloning unconditionally.
+        if !self.result_cache.contains_key(entry.entry_id()) {
+            self.result_cache
+                .entry(entry.entry_id().clone())
+                .or_default();
+        }
+
+        // Always recurse into tiles, because results can be fetched
+        // asynchronously.
+        true
+    }
+
+    fn start_tile<E: Entry>(&mut self, entry: &E, tile_id: TileID) -> bool {
+        // Early exit if we found enough items.
+        if self.result_set.len() >= Self::MAX_SEARCH_RESULTS {
+            return false;
+        }
+
+        let mut result = true;
+        // Always called second, so we know the entry exists.
+        let cache = self.result_cache.get_mut(entry.entry_id()).unwrap();
+        cache
+            .entry(tile_id)
+            .and_modify(|_| {
+                result = false;
+            })
+            .or_default();
+        result
+    }
+
+    fn insert<E: Entry>(&mut self, entry: &E, tile_id: TileID, irow: usize, item: &ItemMeta) {
+        if self.result_set.len() >= Self::MAX_SEARCH_RESULTS {
+            return;
+        }
+
+        // We want each item to appear once, so check the result set first
+        // before inserting.
+        if self.result_set.insert(item.item_uid) {
+            self.cache_bytes += std::mem::size_of::<SearchCacheItem>() + item.title.len();
+            let cache = self.result_cache.get_mut(entry.entry_id()).unwrap();
+            let cache = cache.get_mut(&tile_id).unwrap();
+            cache
+                .entry(item.item_uid)
+                .or_insert_with(|| SearchCacheItem {
+                    item_uid: item.item_uid,
+                    irow,
+                    interval: item.original_interval,
+                    title: item.title.clone(),
+                });
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.cache_bytes
+            + self.result_set.len() * std::mem::size_of::<ItemUID>()
+            + self.table_rows.capacity() * std::mem::size_of::<(EntryID, TileID, ItemUID)>()
+            + self
+                .title_groups
+                .iter()
+                .map(|group| {
+                    std::mem::size_of::<SearchTitleGroup>()
+                        + group.title.capacity()
+                        + group.items.capacity() * std::mem::size_of::<(EntryID, TileID, ItemUID)>()
+                })
+                .sum::<usize>()
+    }
+
+    // Every result, once, as (slot, tile, item)
+    fn unique_results(&self) -> Vec<(&EntryID, TileID, &SearchCacheItem)> {
+        let mut rows: Vec<_> = self
+            .result_cache
+            .iter()
+            .flat_map(|(entry_id, cache)| {
+                cache.iter().flat_map(move |(tile_id, tile_cache)| {
+                    tile_cache
+                        .values()
+                        .map(move |item| (entry_id, *tile_id, item))
+                })
+            })
+            .collect();
+        // Items in multiple tiles are listed once
+        rows.sort_by_key(|(_, _, item)| item.item_uid);
+        rows.dedup_by_key(|(_, _, item)| item.item_uid);
+        rows
+    }
+
+    // Summarize the durations of the results, if they changed since the
+    // last summary
+    fn update_stats(&mut self) {
+        let key = (self.generation, self.result_set.len());
+        if self.stats_key == Some(key) {
+            return;
+        }
+        self.stats_key = Some(key);
+        let durations = self.unique_results();
+        let durations = durations
+            .iter()
+            .map(|(_, _, item)| item.interval.duration_ns());
+        self.stats = SearchStats::new(durations.collect());
+    }
+
+    // Group the results by title, if they changed since the last grouping
+    fn group_by_title(&mut self) {
+        let key = (self.generation, self.result_set.len());
+        if self.title_groups_key == Some(key) {
+            return;
+        }
+        self.title_groups_key = Some(key);
+
+        let mut rows = self.unique_results();
+        rows.sort_by_key(|(_, _, item)| item.interval.start);
+        let mut groups: BTreeMap<&str, SearchTitleGroup> = BTreeMap::new();
+        for (entry_id, tile_id, item) in rows {
+            let group = groups
+                .entry(&item.title)
+                .or_insert_with(|| SearchTitleGroup {
+                    title: item.title.clone(),
+                    total: 0,
+                    items: Vec::new(),
+                });
+            group.total += item.interval.duration_ns();
+            group.items.push((entry_id.clone(), tile_id, item.item_uid));
+        }
+        let mut groups: Vec<_> = groups.into_values().collect();
+        groups.sort_by_key(|group| std::cmp::Reverse(group.total));
+        self.title_groups = groups;
+    }
+
+    // Sort the results for the table, if they changed since the last sort
+    fn sort_table_rows(&mut self, column: SearchColumn, descending: bool) {
+        let key = (self.generation, self.result_set.len(), column, descending);
+        if self.table_key == Some(key) {
+            return;
+        }
+        self.table_key = Some(key);
+
+        let mut rows = self.unique_results();
+        rows.sort_by(|(a_entry, _, a), (b_entry, _, b)| {
+            let order = match column {
+                SearchColumn::Title => a.title.cmp(&b.title),
+                SearchColumn::Node => a_entry.slot_index(0).cmp(&b_entry.slot_index(0)),
+                SearchColumn::Processor => a_entry.cmp(b_entry),
+                SearchColumn::Start => a.interval.start.cmp(&b.interval.start),
+                SearchColumn::Duration => a.interval.duration_ns().cmp(&b.interval.duration_ns()),
+            };
+            let order = order.then(a.interval.start.cmp(&b.interval.start));
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        self.table_rows = rows
+            .into_iter()
+            .map(|(entry_id, tile_id, item)| (entry_id.clone(), tile_id, item.item_uid))
+            .collect();
+    }
+
+    fn build_entry_tree(&mut self) {
+        for (entry_id, cache) in &self.result_cache {
+            let cache_size: u64 = cache.values().map(|x| x.len() as u64).sum();
+            if cache_size == 0 {
+                continue;
+            }
+
+            let level0_index = entry_id.slot_index(0).unwrap();
+            let level1_index = entry_id.slot_index(1).unwrap();
+            let level2_index = entry_id.slot_index(2).unwrap();
+
+            let level0_subtree = self.entry_tree.entry(level0_index).or_default();
+            let level1_subtree = level0_subtree.entry(level1_index).or_default();
+            level1_subtree.insert(level2_index);
+        }
+    }
+}
+
+impl Config {
+    fn new(data_source: Box<dyn DeferredDataSource>, info: DataSourceInfo) -> Self {
+        let nodes = info.entry_info.nodes();
+        let kinds = info.entry_info.kinds();
+        let interval = info.interval;
+        let tile_set = info.tile_set;
+        let warning_message = match protocol_warning(info.protocol_version) {
+            Some(protocol) => Some(match info.warning_message {
+                Some(message) => format!("{message} {protocol}"),
+                None => protocol,
+            }),
+            None => info.warning_message,
+        };
+
+        let mut field_schema = info.field_schema;
+        assert!(!field_schema.contains_name("Title"));
+        let title_id = field_schema.insert("Title".to_owned(), true);
+        let search_state = SearchState::new(title_id, info.supports_search);
+
+        Self {
+            description: data_source.fetch_description(),
+            name: None,
+            rename_buffer: None,
+            field_schema,
+            nodes,
+            selected_nodes: (0..nodes).collect(),
+            node_query: String::new(),
+            kinds,
+            kind_filter: BTreeSet::new(),
+            kind_filter_exclude: false,
+            name_filter: String::new(),
+            name_filter_regex: None,
+            interval,
+            tile_set,
+            warning_message,
+            data_source: CountingDeferredDataSource::new(data_source),
+            full_tiles: false,
+            search_state,
+            items_selected: BTreeMap::new(),
+            watched_items: BTreeMap::new(),
+            idle_gaps: None,
+            time_breakdown: None,
+            origin_trace: None,
+            color_by: None,
+            show_overview: false,
+            overview_count: 10,
+            show_kind_aggregates: true,
+            overlay_summaries: Vec::new(),
+            scroll_to_item: None,
+            scroll_to_item_retry: None,
+            scroll_to_entry: None,
+            last_request_interval: None,
+            request_tile_cache: Vec::new(),
+            view_width: 0.0,
+            highlight_key: 0,
+            zoom_presets: BTreeMap::new(),
+            zoom_preset_name: String::new(),
+        }
+    }
+
+    fn update_highlight_key(&mut self) {
+        let mut hasher = DefaultHasher::new();
+        for item_uid in self.items_selected.keys() {
+            item_uid.0.hash(&mut hasher);
+        }
+        self.search_state.query.is_empty().hash(&mut hasher);
+        self.search_state.generation.hash(&mut hasher);
+        self.search_state.result_set.len().hash(&mut hasher);
+        if let Some(trace) = self.origin_trace.as_ref().filter(|trace| trace.highlight) {
+            for item_uid in &trace.item_uids {
+                item_uid.0.hash(&mut hasher);
+            }
+        }
+        self.highlight_key = hasher.finish();
+    }
+
+    fn request_tiles(&mut self, view_interval: Interval) -> Vec<TileID> {
+        let request_interval = view_interval.intersection(self.interval);
+        if self.last_request_interval == Some(request_interval) {
+            return self.request_tile_cache.clone();
+        }
+        self.last_request_interval = Some(request_interval);
+
+        if self.tile_set.tiles.is_empty() {
+            let duration = request_interval.duration_ns();
+            if duration <= 0 {
+                self.request_tile_cache = vec![TileID(request_interval, 0)];
+                return self.request_tile_cache.clone();
+            }
+
+            // For dynamic profiles, round the request out to power-of-two
+            // aligned tiles (at least half the request in size), so that
+            // panning and small zooms reuse tiles we already have.
+            let tile_ns = ((duration as u64 / 2).max(1).next_power_of_two()) as i64;
+            let first = request_interval.start.0.div_euclid(tile_ns);
+            let last = (request_interval.stop.0 - 1).div_euclid(tile_ns);
+            self.request_tile_cache = (first..=last)
+                .map(|i| {
+                    let tile = Interval::new(Timestamp(i * tile_ns), Timestamp((i + 1) * tile_ns));
+                    TileID(tile.intersection(self.interval), 0)
+                })
+                .collect();
+            return self.request_tile_cache.clone();
+        }
+
+        // We're in a static profile. Without estimates for the levels,
+        // guess the best zoom level, where "best" minimizes the ratio of the
+        // tile size to request size.
+        let request_duration = request_interval.duration_ns();
+        let chosen_level = if let Some(level) = self.fit_static_level(request_interval) {
+            &self.tile_set.tiles[level]
+        } else {
+            self.tile_set
+                .tiles
+                .iter()
+                .min_by_key(|level| {
+                    let d = level.first().unwrap().0.duration_ns();
+                    if d < request_duration {
+                        request_duration / d
+                    } else {
+                        d / request_duration
+                    }
+                })
+                .unwrap()
+        };
+
+        // Now filter to just tiles overlapping the requested interval.
+        self.request_tile_cache = chosen_level
+            .iter()
+            .filter(|tile| request_interval.overlaps(tile.0))
+            .copied()
+            .collect();
+        self.request_tile_cache.clone()
+    }
+
+    // Tiles covering the interval with every item in it (i.e., to be fetched
+    // full), leaving the tiles cached for the view alone. Only the finest
+    // level of a static tile set is complete
+    fn full_tile_ids(&self, interval: Interval) -> Vec<TileID> {
+        let interval = interval.intersection(self.interval);
+        if interval.duration_ns() <= 0 {
+            return Vec::new();
+        }
+        match self.tile_set.tiles.last() {
+            Some(level) => level
+                .iter()
+                .filter(|tile_id| interval.overlaps(tile_id.0))
+                .copied()
+                .collect(),
+            None => vec![TileID(interval, 0)],
+        }
+    }
+
+    // The finest level of the static tile set whose tiles are wide enough on
+    // screen, aren't packed with more items than can be seen, and together
+    // fit in the payload budget, or else the coarsest level. Sizing the
+    // tiles by pixels rather than by duration keeps very wide or narrow
+    // views from fetching far more or less than they can show. None if the
+    // data source has no estimates for its levels
+    fn fit_static_level(&self, request_interval: Interval) -> Option<usize> {
+        let levels = &self.tile_set.tiles;
+        let level_meta = &self.tile_set.level_meta;
+        if level_meta.len() != levels.len() || self.view_width <= 0.0 {
+            return None;
+        }
+        let request_duration = request_interval.duration_ns().max(1) as f64;
+        let fits = |level: usize| {
+            let tile_ids = &levels[level];
+            let Some(first) = tile_ids.first() else {
+                return false;
+            };
+            let meta = level_meta[level];
+            let pixels_per_tile =
+                self.view_width as f64 * first.0.duration_ns() as f64 / request_duration;
+            let tiles = tile_ids
+                .iter()
+                .filter(|tile| request_interval.overlaps(tile.0))
+                .count() as u64;
+            pixels_per_tile >= MIN_PIXELS_PER_TILE
+                && meta.items_per_tile as f64 <= pixels_per_tile * MAX_ITEMS_PER_PIXEL
+                && tiles.saturating_mul(meta.bytes_per_tile) <= TILE_PAYLOAD_BUDGET
+        };
+        Some(
+            (0..levels.len())
+                .rev()
+                .find(|level| fits(*level))
+                .unwrap_or(0),
+        )
+    }
+
+    fn set_view_width(&mut self, width: f32) {
+        let changed = if self.view_width > 0.0 && width > 0.0 {
+            (width - self.view_width).abs() > self.view_width * VIEW_WIDTH_HYSTERESIS
+        } else {
+            self.view_width != width
+        };
+        if changed {
+            self.view_width = width;
+            // The level chosen for the view may be different now (dynamic
+            // profiles don't have levels to choose from)
+            if !self.tile_set.tiles.is_empty() {
+                self.last_request_interval = None;
+            }
+        }
+    }
+
+    fn profile_key(&self) -> String {
+        profile_key(&self.description)
+    }
+
+    // Short name for headings: the user's name for the profile if any, else
+    // the last component of each path or URL it was loaded from
+    fn title(&self, index: u64) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let title = self
+            .description
+            .source_locator
+            .iter()
+            .filter_map(|locator| {
+                let locator = locator.trim_end_matches(['/', '\\']);
+                locator.rsplit(['/', '\\']).find(|x| !x.is_empty())
+            })
+            .join(", ");
+        if title.is_empty() {
+            format!("Profile {index}")
+        } else {
+            title
+        }
+    }
+
+    // Full description of where the profile came from, for tooltips
+    fn source_text(&self) -> String {
+        self.description.source_locator.join("\n")
+    }
+
+    // Short description of the active filters, if any
+    fn filter_summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        let nodes = self.selected_nodes.len();
+        let total_nodes = self.nodes;
+        if nodes as u64 != total_nodes {
+            parts.push(format!("{nodes} of {total_nodes} nodes"));
+        }
+        if !self.kind_filter.is_empty() {
+            let kinds = self.kind_filter.iter().join(", ");
+            if self.kind_filter_exclude {
+                parts.push(format!("all kinds except {kinds}"));
+            } else {
+                parts.push(format!("kinds {kinds}"));
+            }
+        }
+        if !self.name_filter.is_empty() {
+            parts.push(format!("names matching \"{}\"", self.name_filter));
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("Showing {}", parts.join("; ")))
+        }
+    }
+
+    fn set_name_filter(&mut self, name_filter: String) {
+        self.name_filter_regex = if name_filter.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&format!("(?i){}", escape(&name_filter))).unwrap())
+        };
+        self.name_filter = name_filter;
+    }
+
+    fn scroll_to_item(&mut self, item_loc: ItemLocator) {
+        self.scroll_to_item = Some(item_loc.clone());
+        self.scroll_to_item_retry = None;
+
+        self.items_selected
+            .entry(item_loc.item_uid)
+            .or_insert_with(|| ItemDetail {
+                meta: None,
+                loc: item_loc,
+                field_filter: String::new(),
+            });
+    }
+}
+
+impl Window {
+    fn new(data_source: Box<dyn DeferredDataSource>, info: DataSourceInfo, index: u64) -> Self {
+        Self {
+            panel: Panel::new(&info.entry_info, EntryID::root()),
+            index,
+            config: Config::new(data_source, info),
+            scroll_offset: 0.0,
+            detached: None,
+            toggle_detached: false,
+            memory_usage: MemoryUsage::default(),
+            kind_aggregates: Vec::new(),
+            overlay_curves: Vec::new(),
+            show_request_log: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            items_export_status: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            archive_export: None,
+            active_filter: None,
+            active_entries: None,
+            stale_requests: BTreeSet::new(),
+            numeric_fields: BTreeSet::new(),
+        }
+    }
+
+    fn save_config(&self) -> ConfigState {
+        let mut toggled_entries = BTreeSet::new();
+        for node in &self.panel.slots {
+            if node.expanded != default_expanded(&node.entry_id) {
+                toggled_entries.insert(node.entry_id.clone());
+            }
+            for kind in &node.slots {
+                if kind.expanded != default_expanded(&kind.entry_id) {
+                    toggled_entries.insert(kind.entry_id.clone());
+                }
+                for slot in &kind.slots {
+                    if slot.expanded != default_expanded(&slot.entry_id) {
+                        toggled_entries.insert(slot.entry_id.clone());
+                    }
+                }
+            }
+        }
+
+        ConfigState {
+            hidden_nodes: (0..self.panel.slots.len() as u64)
+                .filter(|i| !self.config.selected_nodes.contains(i))
+                .collect(),
+            kind_filter: self.config.kind_filter.clone(),
+            kind_filter_exclude: self.config.kind_filter_exclude,
+            name_filter: self.config.name_filter.clone(),
+            toggled_entries,
+            zoom_presets: self.config.zoom_presets.clone(),
+            watched_items: self.config.watched_items.clone(),
+        }
+    }
+
+    fn load_config(&mut self, state: &ConfigState) {
+        // The profile may have changed since the state was saved, so only
+        // restore what still makes sense.
+        for node in &state.hidden_nodes {
+            self.config.selected_nodes.remove(node);
+        }
+        self.config.kind_filter = state
+            .kind_filter
+            .iter()
+            .filter(|kind| self.config.kinds.contains(kind))
+            .cloned()
+            .collect();
+        self.config.kind_filter_exclude = state.kind_filter_exclude;
+        self.config.set_name_filter(state.name_filter.clone());
+        self.config.zoom_presets = state.zoom_presets.clone();
+        self.config.watched_items = state
+            .watched_items
+            .iter()
+            .filter(|(_, item)| {
+                item.entry_id.level() == 3 && self.find_slot(&item.entry_id).is_some()
+            })
+            .map(|(item_uid, item)| (*item_uid, item.clone()))
+            .collect();
+
+        for entry_id in &state.toggled_entries {
+            let toggled = !default_expanded(entry_id);
+            let node = entry_id
+                .slot_index(0)
+                .and_then(|i| self.panel.slots.get_mut(i as usize));
+            let Some(node) = node else {
+                continue;
+            };
+            if entry_id.level() == 1 {
+                node.expanded = toggled;
+                continue;
+            }
+            let kind = entry_id
+                .slot_index(1)
+                .and_then(|i| node.slots.get_mut(i as usize));
+            let Some(kind) = kind else {
+                continue;
+            };
+            if entry_id.level() == 2 {
+                kind.expanded = toggled;
+                continue;
+            }
+            let slot = entry_id
+                .slot_index(2)
+                .and_then(|i| kind.slots.get_mut(i as usize));
+            if let Some(slot) = slot {
+                slot.expanded = toggled;
+            }
+        }
+    }
+
+    fn find_slot(&self, entry_id: &EntryID) -> Option<&Slot> {
+        self.panel.find_slot(entry_id, 0)
+    }
+
+    fn find_slot_mut(&mut self, entry_id: &EntryID) -> Option<&mut Slot> {
+        self.panel.find_slot_mut(entry_id, 0)
+    }
+
+    fn find_summary_mut(&mut self, entry_id: &EntryID) -> Option<&mut Summary> {
+        self.panel.find_summary_mut(entry_id, 0)
+    }
+
+    fn expand_slot(&mut self, entry_id: &EntryID) {
+        self.panel.expand_slot(entry_id, 0);
+    }
+
+    // Names of the node, kind and slot (as far as the entry goes) leading to
+    // the entry, each paired with its own entry ID
+    fn entry_path(&self, entry_id: &EntryID) -> Vec<(EntryID, String, String)> {
+        let mut result = Vec::new();
+        let Some(node) = entry_id
+            .slot_index(0)
+            .and_then(|i| self.panel.slots.get(i as usize))
+        else {
+            return result;
+        };
+        result.push((
+            node.entry_id.clone(),
+            node.short_name.clone(),
+            node.long_name.clone(),
+        ));
+        let Some(kind) = entry_id
+            .slot_index(1)
+            .and_then(|i| node.slots.get(i as usize))
+        else {
+            return result;
+        };
+        result.push((
+            kind.entry_id.clone(),
+            kind.short_name.clone(),
+            kind.long_name.clone(),
+        ));
+        let Some(slot) = entry_id
+            .slot_index(2)
+            .and_then(|i| kind.slots.get(i as usize))
+        else {
+            return result;
+        };
+        result.push((
+            slot.entry_id.clone(),
+            slot.short_name.clone(),
+            slot.long_name.clone(),
+        ));
+        result
+    }
+
+    fn scroll_to_entry(&mut self, entry_id: &EntryID) {
+        // Make sure every enclosing panel is expanded so the entry is visible
+        if let Some(node) = entry_id
+            .slot_index(0)
+            .and_then(|i| self.panel.slots.get_mut(i as usize))
+        {
+            if entry_id.level() > 1 {
+                node.expanded = true;
+            }
+            if let Some(kind) = entry_id
+                .slot_index(1)
+                .and_then(|i| node.slots.get_mut(i as usize))
+            {
+                if entry_id.level() > 2 {
+                    kind.expanded = true;
+                }
+            }
+        }
+        self.config.scroll_to_entry = Some(entry_id.clone());
+    }
+
+    fn inflate_meta(&mut self, entry_id: &EntryID, cx: &mut Context) {
+        // Use the panel version directly to avoid a mutability conflict
+        let slot = self.panel.find_slot_mut(entry_id, 0).unwrap();
+        slot.fetch_meta_tiles(&mut self.config, cx);
+    }
+
+    fn fit_irow(&mut self, entry_id: &EntryID, irow: usize, cx: &Context) -> usize {
+        self.panel
+            .find_slot_mut(entry_id, 0)
+            .map_or(irow, |slot| slot.fit_irow(irow, cx))
+    }
+
+    fn find_item_irow(&self, entry_id: &EntryID, item_uid: ItemUID) -> Option<usize> {
+        let slot = self.find_slot(entry_id)?;
+        for tile in slot.tiles.values() {
+            let Some(tile) = tile else {
+                continue;
+            };
+            for (row, items) in tile.items.iter().enumerate() {
+                for item in items {
+                    if item.item_uid == item_uid {
+                        let rows = tile.items.len();
+                        return Some(rows - row - 1);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn find_item_meta(&self, entry_id: &EntryID, item_uid: ItemUID) -> Option<ItemMeta> {
+        let slot = self.find_slot(entry_id)?;
+        slot.tile_metas
+            .values()
+            .flatten()
+            .find_map(|tile| tile.find(item_uid))
+    }
+
+    fn content(&mut self, ui: &mut egui::Ui, cx: &mut Context, linked_scroll: bool) {
+        self.config.update_highlight_key();
+
+        ui.horizontal(|ui| {
+            self.title_heading(ui);
+            if let Some(detached) = &mut self.detached {
+                if ui.small_button("Dock").clicked() {
+                    self.toggle_detached = true;
+                }
+                ui.checkbox(&mut detached.independent, "Independent Interval");
+            } else if ui
+                .small_button("⏏")
+                .on_hover_text("Pop out into a separate window")
+                .clicked()
+            {
+                self.toggle_detached = true;
+            }
+            ui.label(cx.display_units.interval(cx.view.interval).to_string());
+            if let Some(summary) = self.config.filter_summary() {
+                ui.label(RichText::new(summary).italics());
+            }
+            if let Some(message) = &self.config.warning_message {
+                ui.label(RichText::new(message).color(Color32::RED));
+            }
+        });
+
+        if self.config.show_kind_aggregates {
+            // About one sample per pixel column
+            let samples = (ui.available_width() as usize).clamp(2, 4096);
+            self.update_kind_aggregates(samples, cx);
+            for aggregate in &self.kind_aggregates {
+                aggregate.render(ui, cx);
+            }
+        }
+
+        if !self.config.overlay_summaries.is_empty() {
+            let samples = (ui.available_width() as usize).clamp(2, 4096);
+            self.update_overlay_curves(samples, cx);
+            OverlayCurve::render_all(&self.overlay_curves, ui, cx);
+        }
+
+        let mut scroll_area = ScrollArea::vertical().auto_shrink([false; 2]);
+        if linked_scroll {
+            scroll_area = scroll_area.vertical_scroll_offset(self.scroll_offset);
+        }
+        let output = scroll_area.show_viewport(ui, |ui, viewport| {
+            let height = self.panel.height(None, &self.config, cx);
+            ui.set_height(height);
+            ui.set_width(ui.available_width());
+
+            let rect = Rect::from_min_size(ui.min_rect().min, viewport.size());
+
+            let scroll_to = |irow, prefix_height| {
+                let mut item_rect =
+                    rect.translate(Vec2::new(0.0, prefix_height + irow as f32 * cx.row_height));
+                item_rect.set_height(cx.row_height);
+                ui.scroll_to_rect(item_rect, Some(egui::Align::Center));
+            };
+
+            // First scroll attempt goes to the processor
+            if let Some(ItemLocator { entry_id, irow, .. }) = self.config.scroll_to_item.clone() {
+                let prefix_height = self.panel.height(Some(&entry_id), &self.config, cx);
+                scroll_to(
+                    self.fit_irow(&entry_id, irow.unwrap_or(0), cx),
+                    prefix_height,
+                );
+                if irow.is_none() {
+                    let mut item = None;
+                    std::mem::swap(&mut item, &mut self.config.scroll_to_item);
+                    self.config.scroll_to_item_retry = item;
+                }
+                self.config.scroll_to_item = None;
+            }
+
+            // If we're able to find the item, we do a second scroll to the item
+            let mut found_irow = None;
+            if let Some(ItemLocator {
+                ref entry_id,
+                irow,
+                item_uid,
+                ..
+            }) = self.config.scroll_to_item_retry
+            {
+                assert!(irow.is_none());
+                found_irow = self.find_item_irow(entry_id, item_uid);
+            }
+
+            if let Some(ItemLocator { entry_id, .. }) = self.config.scroll_to_item_retry.clone() {
+                if let Some(irow) = found_irow {
+                    let prefix_height = self.panel.height(Some(&entry_id), &self.config, cx);
+                    scroll_to(self.fit_irow(&entry_id, irow, cx), prefix_height);
+                    self.config.scroll_to_item_retry = None;
+                }
+            }
+
+            if let Some(entry_id) = self.config.scroll_to_entry.take() {
+                let prefix_height = self.panel.height(Some(&entry_id), &self.config, cx);
+                scroll_to(0, prefix_height);
+            }
+
+            // Root panel has no label
+            self.panel.content(ui, rect, viewport, &mut self.config, cx);
+        });
+        self.scroll_offset = output.state.offset.y;
+    }
+
+    fn node_selection(&mut self, ui: &mut egui::Ui, cx: &Context) {
+        ui.subheading("Node Selection", cx);
+
+        let query = self.config.node_query.to_lowercase();
+        let matches: Vec<_> = self
+            .panel
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                query.is_empty()
+                    || node.short_name.to_lowercase().contains(&query)
+                    || node.long_name.to_lowercase().contains(&query)
+            })
+            .map(|(i, node)| (i as u64, &node.long_name))
+            .collect();
+
+        let selected_nodes = &mut self.config.selected_nodes;
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            egui::TextEdit::singleline(&mut self.config.node_query)
+                .desired_width(ui.available_width() / 2.0)
+                .show(ui);
+            // Select/deselect applies only to the nodes matching the filter
+            if ui.button("All").clicked() {
+                selected_nodes.extend(matches.iter().map(|(i, _)| *i));
+            }
+            if ui.button("None").clicked() {
+                for (i, _) in &matches {
+                    selected_nodes.remove(i);
+                }
+            }
+        });
+        ui.label(format!(
+            "{} of {} nodes selected",
+            selected_nodes.len(),
+            self.panel.slots.len()
+        ));
+
+        const MAX_LIST_HEIGHT: f32 = 120.0;
+        let row_height = ui.spacing().interact_size.y;
+        ScrollArea::vertical()
+            .id_source("node_selection")
+            .max_height(MAX_LIST_HEIGHT)
+            .auto_shrink([false, true])
+            .show_rows(ui, row_height, matches.len(), |ui, range| {
+                for (i, name) in &matches[range] {
+                    let mut selected = selected_nodes.contains(i);
+                    if ui.checkbox(&mut selected, name.as_str()).changed() {
+                        if selected {
+                            selected_nodes.insert(*i);
+                        } else {
+                            selected_nodes.remove(i);
+                        }
+                    }
+                }
+            });
+    }
+
+    fn filter_by_kind(&mut self, ui: &mut egui::Ui, cx: &Context) {
+        ui.subheading("Filter by Kind", cx);
+        ui.horizontal_wrapped(|ui| {
+            for kind in &self.config.kinds {
+                let initial = self.config.kind_filter.contains(kind);
+                let mut enabled = initial;
+                ui.toggle_value(&mut enabled, kind);
+                if initial != enabled {
+                    if enabled {
+                        self.config.kind_filter.insert(kind.clone());
+                    } else {
+                        self.config.kind_filter.remove(kind);
+                    }
+                }
+            }
+        });
+        ui.checkbox(
+            &mut self.config.kind_filter_exclude,
+            "Exclude selected kinds",
+        );
+    }
+
+    fn filter_by_name(&mut self, ui: &mut egui::Ui, cx: &Context) {
+        ui.subheading("Filter by Name", cx);
+        let mut name_filter = self.config.name_filter.clone();
+        let response = ui
+            .horizontal(|ui| {
+                let response = ui.text_edit_singleline(&mut name_filter);
+                if ui.button("✖").clicked() {
+                    name_filter.clear();
+                }
+                response
+            })
+            .inner;
+        response.on_hover_text("Show only processors, channels and memories matching this name");
+        if name_filter != self.config.name_filter {
+            self.config.set_name_filter(name_filter);
+        }
+    }
+
+    fn overview_controls(&mut self, ui: &mut egui::Ui, cx: &Context) {
+        ui.subheading("Overview", cx);
+        ui.horizontal(|ui| {
+            ui.label("Busiest slots:");
+            ui.add(egui::DragValue::new(&mut self.config.overview_count).clamp_range(1..=100));
+            ui.toggle_value(&mut self.config.show_overview, "Show");
+        });
+        ui.checkbox(
+            &mut self.config.show_kind_aggregates,
+            "Utilization per kind across selected nodes",
+        );
+
+        ui.horizontal(|ui| {
+            let overlay = &mut self.config.overlay_summaries;
+            let text = match overlay.len() {
+                0 => "Overlay Summaries".to_owned(),
+                n => format!("Overlay Summaries ({n})"),
+            };
+            ui.menu_button(text, |ui| {
+                ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (name, summary) in Self::summaries_mut(&mut self.panel) {
+                        let position = overlay.iter().position(|e| *e == summary.entry_id);
+                        let mut checked = position.is_some();
+                        if ui.checkbox(&mut checked, name).changed() {
+                            match position {
+                                Some(i) => {
+                                    overlay.remove(i);
+                                }
+                                None => overlay.push(summary.entry_id.clone()),
+                            }
+                        }
+                    }
+                });
+            })
+            .response
+            .on_hover_text(
+                "Plot the utilization of several summaries (e.g., CPUs and GPUs) \
+                 together above the timeline",
+            );
+            if ui
+                .add_enabled(!overlay.is_empty(), egui::Button::new("Clear"))
+                .clicked()
+            {
+                overlay.clear();
+            }
+        });
+    }
+
+    // Selected and watched items, with all their fields. Selected items
+    // whose metadata hasn't loaded yet are left out, unless watched
+    #[cfg(not(target_arch = "wasm32"))]
+    fn exported_items(&self) -> Vec<ExportedItem> {
+        let selected = &self.config.items_selected;
+        let watched = &self.config.watched_items;
+        let item_uids: BTreeSet<_> = selected.keys().chain(watched.keys()).copied().collect();
+        item_uids
+            .into_iter()
+            .filter_map(|item_uid| {
+                let detail = selected.get(&item_uid);
+                let watched_item = watched.get(&item_uid);
+                let entry_id = detail.map_or_else(
+                    || watched_item.map(|item| &item.entry_id),
+                    |detail| Some(&detail.loc.entry_id),
+                )?;
+                let meta = detail
+                    .and_then(|detail| detail.meta.clone())
+                    .or_else(|| self.find_item_meta(entry_id, item_uid));
+                let (title, interval, fields) = match (meta, watched_item) {
+                    (Some(meta), _) => (meta.title, meta.original_interval, meta.fields),
+                    (None, Some(item)) => (item.title.clone(), item.interval, Vec::new()),
+                    (None, None) => return None,
+                };
+                let fields = fields
+                    .into_iter()
+                    .map(|(field_id, value, _)| ExportedField {
+                        name: self
+                            .config
+                            .field_schema
+                            .get_name(field_id)
+                            .unwrap_or_default()
+                            .to_owned(),
+                        value,
+                    })
+                    .collect();
+                Some(ExportedItem {
+                    item_uid: item_uid.0,
+                    slot: self
+                        .find_slot(entry_id)
+                        .map_or_else(String::new, |slot| slot.long_name.clone()),
+                    title,
+                    start: interval.start.0,
+                    stop: interval.stop.0,
+                    selected: detail.is_some(),
+                    watched: watched_item.is_some(),
+                    fields,
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_items_controls(&mut self, ui: &mut egui::Ui, cx: &Context) {
+        ui.subheading("Export Items", cx);
+        let selected = &self.config.items_selected;
+        let watched = self.config.watched_items.keys();
+        let count = selected.len() + watched.filter(|uid| !selected.contains_key(uid)).count();
+        ui.horizontal(|ui| {
+            ui.label(format!("{count} selected or watched:"));
+            for (format, label) in [(ExportFormat::Json, "JSON"), (ExportFormat::Csv, "CSV")] {
+                let button = ui
+                    .add_enabled(count > 0, egui::Button::new(label))
+                    .on_hover_text(
+                        "Write the selected and watched items, with all their fields, \
+                         to a new file in the export directory (see Settings)",
+                    );
+                if button.clicked() {
+                    let items = self.exported_items();
+                    let dir = cx.export_dir.as_deref();
+                    self.items_export_status = Some(match export_items(dir, &items, format) {
+                        Ok(path) => format!("Wrote {} items to {}", items.len(), path.display()),
+                        Err(e) => format!("Unable to export items: {e}"),
+                    });
+                }
+            }
+        });
+        if let Some(status) = &self.items_export_status {
+            ui.label(status);
+        }
+    }
+
+    fn color_by_controls(&mut self, ui: &mut egui::Ui, cx: &Context) {
+        const LEGEND_SIZE: Vec2 = Vec2::new(200.0, 12.0);
+        const LEGEND_STEPS: usize = 32;
+
+        ui.subheading("Color by Field", cx);
+        let schema = &self.config.field_schema;
+        let color_by = &mut self.config.color_by;
+        let selected = color_by.as_ref().map(|c| c.field_id);
+        let selected_text = selected
+            .and_then(|field_id| schema.get_name(field_id))
+            .unwrap_or("Item Colors");
+        egui::ComboBox::from_id_source(("color_by", self.index))
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                if ui
+                    .selectable_label(selected.is_none(), "Item Colors")
+                    .clicked()
+                {
+                    *color_by = None;
+                }
+                let numeric_fields = &self.numeric_fields;
+                let mut fields: Vec<_> = schema
+                    .fields()
+                    .filter(|(field_id, _)| {
+                        numeric_fields.contains(field_id) || selected == Some(*field_id)
+                    })
+                    .collect();
+                if fields.is_empty() {
+                    ui.weak("No numeric fields loaded yet");
+                }
+                fields.sort_by_key(|(_, name)| *name);
+                for (field_id, name) in fields {
+                    if ui
+                        .selectable_label(selected == Some(field_id), name)
+                        .clicked()
+                        && selected != Some(field_id)
+                    {
+                        *color_by = Some(ColorBy::new(field_id));
+                    }
+                }
+            })
+            .response
+            .on_hover_text(
+                "Color items by the value of a numeric field. Fields are listed \
+                 once items with numeric values for them have loaded. Items are \
+                 shown in their own colors until their metadata loads, and in \
+                 gray if they don't have the field",
+            );
+
+        let Some(color_by) = color_by else {
+            return;
+        };
+        if !color_by.has_values() {
+            ui.label("No values loaded yet.");
+            return;
+        }
+        let (rect, _) = ui.allocate_exact_size(LEGEND_SIZE, egui::Sense::hover());
+        let mut mesh = egui::Mesh::default();
+        for i in 0..LEGEND_STEPS {
+            let step = |i: usize| i as f32 / LEGEND_STEPS as f32;
+            let min = rect.lerp_inside(Vec2::new(step(i), 0.0));
+            let max = rect.lerp_inside(Vec2::new(step(i + 1), 1.0));
+            let color = ColorBy::gradient((i as f32 + 0.5) / LEGEND_STEPS as f32);
+            mesh.add_colored_rect(Rect::from_min_max(min, max), color);
+        }
+        ui.painter().add(egui::Shape::mesh(mesh));
+        ui.allocate_ui(Vec2::new(LEGEND_SIZE.x, 0.0), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}", color_by.min));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(format!("{}", color_by.max));
+                });
+            });
+        });
+    }
+
+    fn zoom_presets(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
+        ui.subheading("Zoom Presets", cx);
+        let name = self.config.zoom_preset_name.trim().to_owned();
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.config.zoom_preset_name)
+                .on_hover_text("Name for the current view interval");
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                .clicked()
+            {
+                self.config.zoom_presets.insert(name, cx.view.interval);
+                self.config.zoom_preset_name.clear();
+            }
+        });
+
+        if self.config.zoom_presets.is_empty() {
+            return;
+        }
+        let mut recall = None;
+        let mut remove = None;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(("zoom_presets", self.index))
+                .selected_text("Recall...")
+                .show_ui(ui, |ui| {
+                    for (name, interval) in &self.config.zoom_presets {
+                        if ui
+                            .selectable_label(false, name)
+                            .on_hover_text(cx.display_units.interval(*interval).to_string())
+                            .clicked()
+                        {
+                            recall = Some(*interval);
+                        }
+                    }
+                });
+            egui::ComboBox::from_id_source(("zoom_presets_remove", self.index))
+                .selected_text("Delete...")
+                .show_ui(ui, |ui| {
+                    for name in self.config.zoom_presets.keys() {
+                        if ui.selectable_label(false, name).clicked() {
+                            remove = Some(name.clone());
+                        }
+                    }
+                });
+        });
+        if let Some(interval) = recall {
+            ProfApp::zoom(cx, interval);
+        }
+        if let Some(name) = remove {
+            self.config.zoom_presets.remove(&name);
+        }
+    }
+
+    fn expand_nodes(&mut self, expanded: bool) {
+        for node in &mut self.panel.slots {
+            node.expanded = expanded;
+        }
+    }
+
+    fn expand_all(&mut self, expanded: bool) {
+        for node in &mut self.panel.slots {
+            node.expanded = expanded;
+            for kind in &mut node.slots {
+                kind.expanded = expanded;
+                for slot in &mut kind.slots {
+                    slot.expanded = expanded;
+                }
+            }
+        }
+    }
+
+    fn expand_collapse(&mut self, ui: &mut egui::Ui, cx: &Context) {
+        ui.subheading("Expand/Collapse", cx);
+        ui.horizontal(|ui| {
+            ui.label("All:");
+            if ui.button("Expand").clicked() {
+                self.expand_all(true);
+            }
+            if ui.button("Collapse").clicked() {
+                self.expand_all(false);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Nodes:");
+            if ui.button("Expand").clicked() {
+                self.expand_nodes(true);
+            }
+            if ui.button("Collapse").clicked() {
+                self.expand_nodes(false);
+            }
+        });
+
+        let mut toggle_all = |label, toggle| {
+            for node in &mut self.panel.slots {
+                for kind in &mut node.slots {
+                    if kind.expanded == toggle && kind.label_text() == label {
+                        kind.toggle_expanded();
+                    }
+                }
+            }
+        };
+
+        ui.label("Expand by kind:");
+        ui.horizontal_wrapped(|ui| {
+            for kind in &self.config.kinds {
+                if ui.button(kind).clicked() {
+                    toggle_all(kind.to_lowercase(), false);
+                }
+            }
+        });
+        ui.label("Collapse by kind:");
+        ui.horizontal_wrapped(|ui| {
+            for kind in &self.config.kinds {
+                if ui.button(kind).clicked() {
+                    toggle_all(kind.to_lowercase(), true);
                 }
             }
-        }
+        });
     }
 
-    fn height(&self, prefix: Option<&EntryID>, config: &Config, cx: &Context) -> f32 {
-        const UNEXPANDED_ROWS: u64 = 2;
-        const ROW_PADDING: f32 = 4.0;
+    fn select_interval(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
+        const INTERVAL_SELECT_HINT: &str = "Accepts 1.5 ms, 00:01:02.5, +500 us (from the \
+            current start), or the keywords start and end (of the profile)";
+        ui.subheading("Interval", cx);
+        let start_res = ui
+            .horizontal(|ui| {
+                ui.label("Start:");
+                ui.text_edit_singleline(&mut cx.view.interval_select_state.start_buffer)
+                    .on_hover_text(INTERVAL_SELECT_HINT)
+            })
+            .inner;
 
-        let mut total = 0.0;
-        let mut rows: i64 = 0;
-        if let Some(summary) = &self.summary {
-            total += summary.height(None, config, cx);
-            rows += 1;
-        } else if !self.expanded {
-            // Need some minimum space if this panel has no summary and is collapsed
-            total += UNEXPANDED_ROWS as f32 * cx.row_height;
-            rows += 1;
+        if let Some(error) = cx.view.interval_select_state.start_error {
+            ui.label(RichText::new(error.to_string()).color(Color32::RED));
         }
 
-        if self.expanded {
-            for slot in &self.slots {
-                if let Some(prefix) = prefix {
-                    // If this is our entry, stop
-                    if slot.entry_id() == prefix {
-                        break;
+        let stop_res = ui
+            .horizontal(|ui| {
+                ui.label("Stop:");
+                ui.text_edit_singleline(&mut cx.view.interval_select_state.stop_buffer)
+                    .on_hover_text(INTERVAL_SELECT_HINT)
+            })
+            .inner;
+
+        if let Some(error) = cx.view.interval_select_state.stop_error {
+            ui.label(RichText::new(error.to_string()).color(Color32::RED));
+        }
+
+        if start_res.lost_focus()
+            && cx.view.interval_select_state.start_buffer != cx.view.interval.start.to_string()
+        {
+            match Timestamp::parse_expr(
+                &cx.view.interval_select_state.start_buffer,
+                cx.view.interval.start,
+                cx.total_interval,
+            ) {
+                Ok(start) => {
+                    // validate timestamp
+                    if start > cx.view.interval.stop {
+                        cx.view.interval_select_state.start_error =
+                            Some(IntervalSelectError::StartAfterStop);
+                        return;
+                    }
+                    if start > cx.total_interval.stop {
+                        cx.view.interval_select_state.start_error =
+                            Some(IntervalSelectError::StartAfterEnd);
+                        return;
                     }
+                    let target = Interval::new(start, cx.view.interval.stop);
+                    ProfApp::zoom(cx, target);
                 }
-
-                // Apply visibility settings
-                if !Self::is_slot_visible(slot, config) {
-                    continue;
+                Err(e) => {
+                    cx.view.interval_select_state.start_error = Some(e.into());
                 }
-
-                total += slot.height(prefix, config, cx);
-
-                if let Some(prefix) = prefix {
-                    // If we're a prefix of the entry, recurse and then stop
-                    if prefix.has_prefix(slot.entry_id()) {
-                        break;
+            }
+        }
+        if stop_res.lost_focus()
+            && cx.view.interval_select_state.stop_buffer != cx.view.interval.stop.to_string()
+        {
+            match Timestamp::parse_expr(
+                &cx.view.interval_select_state.stop_buffer,
+                cx.view.interval.start,
+                cx.total_interval,
+            ) {
+                Ok(stop) => {
+                    // validate timestamp
+                    if stop < cx.view.interval.start {
+                        cx.view.interval_select_state.stop_error =
+                            Some(IntervalSelectError::StopBeforeStart);
+                        return;
                     }
+                    let target = Interval::new(cx.view.interval.start, stop);
+                    ProfApp::zoom(cx, target);
+                }
+                Err(e) => {
+                    cx.view.interval_select_state.stop_error = Some(e.into());
                 }
-
-                rows += 1;
             }
         }
+    }
 
-        total += (rows - 1).at_least(0) as f32 * ROW_PADDING;
+    fn title_heading(&mut self, ui: &mut egui::Ui) {
+        if let Some(buffer) = &mut self.config.rename_buffer {
+            let response = ui.add(egui::TextEdit::singleline(buffer).font(TextStyle::Heading));
+            response.request_focus();
+            if response.lost_focus() {
+                let cancel = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                let name = buffer.trim().to_owned();
+                if !cancel {
+                    // Clearing the name goes back to the default title
+                    self.config.name = (!name.is_empty()).then_some(name);
+                }
+                self.config.rename_buffer = None;
+            }
+            return;
+        }
 
-        total
+        let title = self.config.title(self.index);
+        let response = ui
+            .add(egui::Label::new(RichText::new(&title).heading()).sense(egui::Sense::click()))
+            .on_hover_text(format!(
+                "Profile {}: {}\n(Double-click to rename)",
+                self.index,
+                self.config.source_text()
+            ));
+        if response.double_clicked() {
+            self.config.rename_buffer = Some(title);
+        }
     }
 
-    fn is_expandable(&self) -> bool {
-        !self.slots.is_empty()
+    fn controls(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
+        const WIDGET_PADDING: f32 = 8.0;
+        ui.heading(format!("{}: Controls", self.config.title(self.index)))
+            .on_hover_text(self.config.source_text());
+        ui.add_space(WIDGET_PADDING);
+        self.node_selection(ui, cx);
+        ui.add_space(WIDGET_PADDING);
+        self.filter_by_kind(ui, cx);
+        ui.add_space(WIDGET_PADDING);
+        self.filter_by_name(ui, cx);
+        ui.add_space(WIDGET_PADDING);
+        self.expand_collapse(ui, cx);
+        ui.add_space(WIDGET_PADDING);
+        self.select_interval(ui, cx);
+        ui.add_space(WIDGET_PADDING);
+        self.zoom_presets(ui, cx);
+        ui.add_space(WIDGET_PADDING);
+        self.overview_controls(ui, cx);
+        ui.add_space(WIDGET_PADDING);
+        self.color_by_controls(ui, cx);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.add_space(WIDGET_PADDING);
+            self.export_items_controls(ui, cx);
+        }
+        if self.config.full_tiles {
+            let usage = self.memory_usage;
+            ui.add_space(WIDGET_PADDING);
+            let text = format!(
+                "⚠ Full tiles: {} loaded",
+                format_bytes((usage.tiles + usage.metas) as i128)
+            );
+            ui.colored_label(ui.visuals().warn_fg_color, text)
+                .on_hover_text(
+                    "Every item in the view is loaded, however short. Zoom in to keep \
+                     tiles small, or turn off Full Tiles in the settings",
+                );
+        }
+        if cx.debug {
+            let usage = self.memory_usage;
+            ui.add_space(WIDGET_PADDING);
+            ui.label(format!("Memory: {}", format_bytes(usage.total() as i128)))
+                .on_hover_text(format!(
+                    "Tiles: {}\nMetadata: {}\nSearch: {}",
+                    format_bytes(usage.tiles as i128),
+                    format_bytes(usage.metas as i128),
+                    format_bytes(usage.search as i128),
+                ));
+            ui.toggle_value(&mut self.show_request_log, "Request Log");
+        }
     }
 
-    fn toggle_expanded(&mut self) {
-        self.expanded = !self.expanded;
+    fn all_slots(&self) -> impl Iterator<Item = &Slot> {
+        self.panel
+            .slots
+            .iter()
+            .flat_map(|node| &node.slots)
+            .flat_map(|kind| &kind.slots)
     }
-}
-
-impl SearchState {
-    fn new(title_id: FieldID) -> Self {
-        Self {
-            title_field: title_id,
-
-            query: "".to_owned(),
-            last_query: "".to_owned(),
-            search_field: title_id,
-            last_search_field: title_id,
-            whole_word: false,
-            last_whole_word: false,
-            last_word_regex: None,
-            include_collapsed_entries: false,
-            last_include_collapsed_entries: false,
-            last_view_interval: None,
 
-            result_set: BTreeSet::new(),
-            result_cache: BTreeMap::new(),
-            entry_tree: BTreeMap::new(),
-        }
+    fn all_slots_mut(&mut self) -> impl Iterator<Item = &mut Slot> {
+        self.panel
+            .slots
+            .iter_mut()
+            .flat_map(|node| &mut node.slots)
+            .flat_map(|kind| &mut kind.slots)
     }
 
-    fn clear(&mut self) {
-        self.result_set.clear();
-        self.result_cache.clear();
-        self.entry_tree.clear();
+    // Every summary, with the name of the panel it summarizes
+    fn summaries_mut(
+        panel: &mut Panel<Panel<Panel<Slot>>>,
+    ) -> impl Iterator<Item = (&str, &mut Summary)> {
+        let root = std::iter::once((panel.long_name.as_str(), panel.summary.as_mut()));
+        let nodes = panel.slots.iter_mut().flat_map(|node| {
+            let kinds = node
+                .slots
+                .iter_mut()
+                .map(|kind| (kind.long_name.as_str(), kind.summary.as_mut()));
+            std::iter::once((node.long_name.as_str(), node.summary.as_mut())).chain(kinds)
+        });
+        root.chain(nodes)
+            .filter_map(|(name, summary)| Some((name, summary?)))
     }
 
-    fn ensure_valid_cache(&mut self, cx: &Context) {
-        let mut invalidate = false;
-
-        // Invalidate when the search query changes.
-        if self.query != self.last_query {
-            invalidate = true;
-            self.last_query.clone_from(&self.query);
-        }
+    // Recompute the curves of the summary overlay, requesting the summary
+    // tiles needed along the way
+    fn update_overlay_curves(&mut self, samples: usize, cx: &Context) {
+        let interval = cx.view.interval;
+        let times: Vec<_> = (0..samples)
+            .map(|i| interval.lerp(i as f32 / (samples - 1) as f32))
+            .collect();
 
-        // Invalidate when the search field changes.
-        if self.search_field != self.last_search_field {
-            invalidate = true;
-            self.last_search_field = self.search_field;
-        }
+        let mut curves = Vec::new();
+        let mut used_colors = Vec::new();
+        for (name, summary) in Self::summaries_mut(&mut self.panel) {
+            let Some(order) = self
+                .config
+                .overlay_summaries
+                .iter()
+                .position(|e| *e == summary.entry_id)
+            else {
+                continue;
+            };
+            summary.update_view(&mut self.config, cx);
+            let key = (interval, samples, summary.loaded_tiles(cx.view.index));
+            let old = self
+                .overlay_curves
+                .iter()
+                .position(|c| c.entry_id == summary.entry_id);
+            if let Some(old) = old.filter(|i| self.overlay_curves[*i].key == key) {
+                let mut curve = self.overlay_curves.swap_remove(old);
+                curve.color = summary.color;
+                curves.push((order, curve));
+                continue;
+            }
 
-        // Invalidate when the whole word setting changes.
-        if self.whole_word != self.last_whole_word {
-            invalidate = true;
-            self.last_whole_word = self.whole_word;
+            let utils = summary.sample(&times, cx.view.index);
+            let complete = utils.is_some();
+            let points = utils
+                .unwrap_or_default()
+                .into_iter()
+                .zip(&times)
+                .map(|(util, time)| UtilPoint { time: *time, util })
+                .collect();
+            curves.push((
+                order,
+                OverlayCurve {
+                    entry_id: summary.entry_id.clone(),
+                    name: name.to_owned(),
+                    color: summary.color,
+                    key,
+                    points,
+                    complete,
+                },
+            ));
         }
 
-        // Invalidate when EXCLUDING collapsed entries. (I.e., because the
-        // searched set shrinks. Growing is ok because search is monotonic.)
-        if self.include_collapsed_entries != self.last_include_collapsed_entries
-            && !self.include_collapsed_entries
-        {
-            invalidate = true;
-            self.last_include_collapsed_entries = self.include_collapsed_entries;
+        // Colors are assigned in the order the summaries were picked, so
+        // they don't change as more are added
+        curves.sort_by_key(|(order, _)| *order);
+        for (_, curve) in &mut curves {
+            curve.color = OverlayCurve::pick_color(curve.color, &used_colors);
+            used_colors.push(curve.color);
         }
+        self.overlay_curves = curves.into_iter().map(|(_, curve)| curve).collect();
+    }
 
-        // Invalidate when the view interval changes.
-        if self.last_view_interval != Some(cx.view_interval) {
-            invalidate = true;
-            self.last_view_interval = Some(cx.view_interval);
-        }
+    // Recompute the utilization of each kind across the selected nodes,
+    // requesting the summary tiles needed along the way
+    fn update_kind_aggregates(&mut self, samples: usize, cx: &Context) {
+        let interval = cx.view.interval;
+        let times: Vec<_> = (0..samples)
+            .map(|i| interval.lerp(i as f32 / (samples - 1) as f32))
+            .collect();
 
-        if invalidate {
-            if self.whole_word {
-                let regex_string = format!("\\b{}\\b", escape(&self.query));
-                self.last_word_regex = Some(Regex::new(&regex_string).unwrap());
+        let mut aggregates = Vec::new();
+        // Hack: clone to avoid a mutability conflict
+        for kind in self.config.kinds.clone() {
+            let label = kind.to_lowercase();
+            let mut summaries = Vec::new();
+            for node in &mut self.panel.slots {
+                if !Panel::is_slot_visible(node, &self.config) {
+                    continue;
+                }
+                for kind_panel in &mut node.slots {
+                    if kind_panel.label_text() != label
+                        || !Panel::is_slot_visible(kind_panel, &self.config)
+                    {
+                        continue;
+                    }
+                    if let Some(summary) = &mut kind_panel.summary {
+                        summary.update_view(&mut self.config, cx);
+                        summaries.push(&*summary);
+                    }
+                }
+            }
+            if summaries.is_empty() {
+                continue;
             }
 
-            self.clear();
-        }
-    }
+            let loaded = summaries
+                .iter()
+                .map(|s| s.loaded_tiles(cx.view.index))
+                .sum();
+            let key = (interval, samples, summaries.len(), loaded);
+            let old = self.kind_aggregates.iter().position(|a| a.kind == kind);
+            if let Some(old) = old.filter(|i| self.kind_aggregates[*i].key == key) {
+                aggregates.push(self.kind_aggregates.swap_remove(old));
+                continue;
+            }
 
-    fn is_string_match(&self, s: &str) -> bool {
-        if self.whole_word {
-            let Some(regex) = &self.last_word_regex else {
-                unreachable!();
+            let mut totals = vec![0.0; samples];
+            let mut count = 0;
+            for summary in &summaries {
+                if let Some(utils) = summary.sample(&times, cx.view.index) {
+                    for (total, util) in totals.iter_mut().zip(utils) {
+                        *total += util;
+                    }
+                    count += 1;
+                }
+            }
+            let points = if count > 0 {
+                times
+                    .iter()
+                    .zip(totals)
+                    .map(|(time, total)| UtilPoint {
+                        time: *time,
+                        util: total / count as f32,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
             };
-            regex.is_match(s)
-        } else {
-            s.contains(&self.query)
-        }
-    }
-
-    fn is_field_match(&self, field: &Field) -> bool {
-        match field {
-            Field::String(s) => self.is_string_match(s),
-            Field::ItemLink(ItemLink { title, .. }) => self.is_string_match(title),
-            Field::Vec(fields) => fields.iter().any(|f| self.is_field_match(f)),
-            _ => false,
+            aggregates.push(KindAggregate {
+                kind,
+                color: summaries[0].color,
+                nodes: summaries.len(),
+                key,
+                points,
+                complete: count == summaries.len(),
+            });
         }
+        self.kind_aggregates = aggregates;
     }
 
-    fn is_match(&self, item: &ItemMeta) -> bool {
-        let field = self.search_field;
-        if field == self.title_field {
-            self.is_string_match(&item.title)
-        } else if let Some((_, value, _)) = item.fields.iter().find(|(x, _, _)| *x == field) {
-            self.is_field_match(value)
-        } else {
-            false
+    // Only entries that changed since the last call are measured again (see
+    // Slot::memory)
+    fn update_memory_usage(&mut self) {
+        let mut usage = MemoryUsage::default();
+        let summaries = std::iter::once(&mut self.panel.summary)
+            .chain(self.panel.slots.iter_mut().flat_map(|node| {
+                std::iter::once(&mut node.summary)
+                    .chain(node.slots.iter_mut().map(|kind| &mut kind.summary))
+            }))
+            .flatten();
+        for summary in summaries {
+            usage.tiles += summary.memory_usage();
         }
-    }
-
-    const MAX_SEARCH_RESULTS: usize = 100_000;
-
-    fn start_entry<E: Entry>(&mut self, entry: &E) -> bool {
-        // Early exit if we found enough items.
-        if self.result_set.len() >= Self::MAX_SEARCH_RESULTS {
-            return false;
+        for slot in self.all_slots_mut() {
+            usage += slot.memory_usage();
         }
-
-        // Double lookup is better than cloning unconditionally.
-        if !self.result_cache.contains_key(entry.entry_id()) {
-            self.result_cache
-                .entry(entry.entry_id().clone())
-                .or_default();
+        if let Some(breakdown) = &self.config.time_breakdown {
+            let tiles = breakdown.tiles.values().flatten();
+            usage.metas += tiles.map(|tile| tile.heap_size()).sum::<usize>();
         }
-
-        // Always recurse into tiles, because results can be fetched
-        // asynchronously.
-        true
+        usage.search = self.config.search_state.memory_usage();
+        self.memory_usage = usage;
     }
 
-    fn start_tile<E: Entry>(&mut self, entry: &E, tile_id: TileID) -> bool {
-        // Early exit if we found enough items.
-        if self.result_set.len() >= Self::MAX_SEARCH_RESULTS {
-            return false;
-        }
+    // Meta tiles are kept while there is a search to run on them, or an
+    // origin trace to follow through them
+    fn needs_metas(&self) -> bool {
+        !self.config.search_state.query.is_empty()
+            || self
+                .config
+                .origin_trace
+                .as_ref()
+                .is_some_and(|trace| trace.end.is_none())
+    }
 
-        let mut result = true;
-        // Always called second, so we know the entry exists.
-        let cache = self.result_cache.get_mut(entry.entry_id()).unwrap();
-        cache
-            .entry(tile_id)
-            .and_modify(|_| {
-                result = false;
-            })
-            .or_default();
-        result
+    // Metadata of an item anywhere in the profile (not just in view),
+    // fetching the meta tiles around it as needed. None while they load, or
+    // an error once it's clear the item won't be found
+    fn fetch_item_meta_at(
+        &mut self,
+        entry_id: &EntryID,
+        item_uid: ItemUID,
+        interval: Interval,
+    ) -> Option<Result<ItemMeta, String>> {
+        let tile_ids = self.config.request_tiles(interval.saturating_grow(1));
+        let Some(slot) = self.panel.find_slot_mut(entry_id, 0) else {
+            return Some(Err("Stopped at an item in an unknown slot.".to_owned()));
+        };
+        for tile_id in &tile_ids {
+            slot.fetch_meta_tile(*tile_id, &mut self.config);
+        }
+        let found = slot
+            .tile_metas
+            .values()
+            .flatten()
+            .find_map(|tile| tile.find(item_uid));
+        if let Some(meta) = found {
+            return Some(Ok(meta));
+        }
+        for tile_id in &tile_ids {
+            if let Some(error) = slot.failed.get(&(*tile_id, RequestKind::SlotMetaTile)) {
+                return Some(Err(format!("Unable to load item {}: {error}", item_uid.0)));
+            }
+        }
+        let loaded = tile_ids
+            .iter()
+            .all(|tile_id| matches!(slot.tile_metas.get(tile_id), Some(Some(_))));
+        loaded.then(|| {
+            Err(format!(
+                "Stopped at item {}, which was not found.",
+                item_uid.0
+            ))
+        })
     }
 
-    fn insert<E: Entry>(&mut self, entry: &E, tile_id: TileID, irow: usize, item: &ItemMeta) {
-        if self.result_set.len() >= Self::MAX_SEARCH_RESULTS {
+    // Take one step of the origin trace, once the metadata of the last item
+    // in the chain has loaded
+    fn advance_origin_trace(&mut self) {
+        let Some(trace) = &self.config.origin_trace else {
+            return;
+        };
+        if trace.end.is_some() {
             return;
         }
-
-        // We want each item to appear once, so check the result set first
-        // before inserting.
-        if self.result_set.insert(item.item_uid) {
-            let cache = self.result_cache.get_mut(entry.entry_id()).unwrap();
-            let cache = cache.get_mut(&tile_id).unwrap();
-            cache
-                .entry(item.item_uid)
-                .or_insert_with(|| SearchCacheItem {
-                    item_uid: item.item_uid,
-                    irow,
-                    interval: item.original_interval,
-                    title: item.title.clone(),
-                });
+        let (loc, _, interval) = trace.hops.last().unwrap().clone();
+        let Some(result) = self.fetch_item_meta_at(&loc.entry_id, loc.item_uid, interval) else {
+            return;
+        };
+        let source_locator = &self.config.description.source_locator;
+        let trace = self.config.origin_trace.as_mut().unwrap();
+        match result {
+            Ok(meta) => trace.advance(&meta, source_locator),
+            // Ending the trace also lets go of the meta tiles (see
+            // needs_metas)
+            Err(end) => trace.end = Some(end),
         }
     }
 
-    fn build_entry_tree(&mut self) {
-        for (entry_id, cache) in &self.result_cache {
-            let cache_size: u64 = cache.values().map(|x| x.len() as u64).sum();
-            if cache_size == 0 {
-                continue;
-            }
+    fn time_breakdown_window(&mut self, ctx: &egui::Context, cx: &Context) {
+        const BAR_WIDTH: f32 = 120.0;
 
-            let level0_index = entry_id.slot_index(0).unwrap();
-            let level1_index = entry_id.slot_index(1).unwrap();
-            let level2_index = entry_id.slot_index(2).unwrap();
+        // Taken out while it fetches its tiles through the config
+        let Some(mut breakdown) = self.config.time_breakdown.take() else {
+            return;
+        };
+        breakdown.update(&mut self.config);
+        let breakdown = self.config.time_breakdown.insert(breakdown);
+
+        let mut enabled = true;
+        let mut refresh = false;
+        egui::Window::new(format!("Time Breakdown: {}", breakdown.long_name))
+            .id(egui::Id::new(("time_breakdown", self.index)))
+            .open(&mut enabled)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let interval = breakdown.interval;
+                ui.label(format!(
+                    "Time by title {}",
+                    cx.display_units.interval(interval)
+                ));
+                if ui.button("Refresh").clicked() {
+                    refresh = true;
+                }
+                if let Some(error) = &breakdown.error {
+                    ui.label(error);
+                    return;
+                }
+                let Some(titles) = &breakdown.titles else {
+                    ui.label("Loading...");
+                    return;
+                };
+                if titles.is_empty() {
+                    ui.label("No items found.");
+                    return;
+                }
+                ui.label(
+                    "Percentages are of the interval. Items in different rows \
+                     overlap, so they may add up to more than 100%.",
+                );
+
+                let total = interval.duration_ns().max(1) as f32;
+                let color = ui.visuals().selection.bg_fill;
+                TableBuilder::new(ui)
+                    .striped(true)
+                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                    .column(Column::remainder())
+                    .column(Column::exact(BAR_WIDTH))
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .header(20.0, |mut header| {
+                        for name in ["Title", "", "Time", "", "Items"] {
+                            header.col(|ui| {
+                                ui.strong(name);
+                            });
+                        }
+                    })
+                    .body(|mut body| {
+                        for (title, duration, count) in titles {
+                            let fraction = *duration as f32 / total;
+                            body.row(20.0, |mut row| {
+                                row.col(|ui| {
+                                    ui.label(title);
+                                });
+                                row.col(|ui| {
+                                    let (rect, _) = ui.allocate_exact_size(
+                                        Vec2::new(BAR_WIDTH, 12.0),
+                                        egui::Sense::hover(),
+                                    );
+                                    let mut bar = rect;
+                                    bar.set_width(rect.width() * fraction.at_most(1.0));
+                                    ui.painter().rect_filled(bar, 0.0, color);
+                                });
+                                row.col(|ui| {
+                                    ui.label(
+                                        cx.display_units
+                                            .timestamp(Timestamp(*duration))
+                                            .to_string(),
+                                    );
+                                });
+                                row.col(|ui| {
+                                    ui.label(format!("{:.1}%", fraction * 100.0));
+                                });
+                                row.col(|ui| {
+                                    ui.label(count.to_string());
+                                });
+                            });
+                        }
+                    });
+            });
 
-            let level0_subtree = self.entry_tree.entry(level0_index).or_default();
-            let level1_subtree = level0_subtree.entry(level1_index).or_default();
-            level1_subtree.insert(level2_index);
+        if refresh {
+            breakdown.tiles.clear();
+            breakdown.error = None;
+            breakdown.titles = None;
+        }
+        if !enabled {
+            self.config.time_breakdown = None;
         }
     }
-}
 
-impl Config {
-    fn new(data_source: Box<dyn DeferredDataSource>, info: DataSourceInfo) -> Self {
-        let max_node = info.entry_info.nodes();
-        let kinds = info.entry_info.kinds();
-        let interval = info.interval;
-        let tile_set = info.tile_set;
-        let warning_message = info.warning_message;
+    fn origin_trace_window(&mut self, ctx: &egui::Context, cx: &mut Context) {
+        let Some(trace) = &mut self.config.origin_trace else {
+            return;
+        };
 
-        let mut field_schema = info.field_schema;
-        assert!(!field_schema.contains_name("Title"));
-        let title_id = field_schema.insert("Title".to_owned(), true);
-        let search_state = SearchState::new(title_id);
+        let mut enabled = true;
+        let mut target = None;
+        egui::Window::new(format!("Trace to Origin: {}", trace.hops[0].1))
+            .id(egui::Id::new(("origin_trace", self.index)))
+            .open(&mut enabled)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(format!("Following {}", trace.field_name));
+                match &trace.end {
+                    Some(end) => ui.label(end),
+                    None => ui.label(format!("Loading item {}...", trace.hops.len())),
+                };
+                ui.checkbox(&mut trace.highlight, "Highlight on timeline");
+
+                TableBuilder::new(ui)
+                    .striped(true)
+                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                    .column(Column::auto())
+                    .column(Column::remainder())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .header(20.0, |mut header| {
+                        header.col(|ui| {
+                            ui.strong("Hop");
+                        });
+                        header.col(|ui| {
+                            ui.strong("Item");
+                        });
+                        header.col(|ui| {
+                            ui.strong("Duration");
+                        });
+                        header.col(|_| {});
+                    })
+                    .body(|mut body| {
+                        for (hop, (loc, title, interval)) in trace.hops.iter().enumerate() {
+                            body.row(20.0, |mut row| {
+                                row.col(|ui| {
+                                    ui.label(hop.to_string());
+                                });
+                                row.col(|ui| {
+                                    ui.label(title);
+                                });
+                                row.col(|ui| {
+                                    let duration = Timestamp(interval.duration_ns());
+                                    ui.label(cx.display_units.timestamp(duration).to_string());
+                                });
+                                row.col(|ui| {
+                                    if ui.button(cx.item_link_mode.label_text()).clicked() {
+                                        target = Some((loc.clone(), *interval));
+                                    }
+                                });
+                            });
+                        }
+                    });
+            });
 
-        Self {
-            field_schema,
-            min_node: 0,
-            max_node,
-            kinds,
-            kind_filter: BTreeSet::new(),
-            interval,
-            tile_set,
-            warning_message,
-            data_source: CountingDeferredDataSource::new(data_source),
-            search_state,
-            items_selected: BTreeMap::new(),
-            scroll_to_item: None,
-            scroll_to_item_retry: None,
-            last_request_interval: None,
-            request_tile_cache: Vec::new(),
+        if let Some((loc, interval)) = target {
+            ProfApp::navigate_to_item(cx, self, loc, interval);
+        }
+        if !enabled {
+            self.config.origin_trace = None;
         }
     }
 
-    fn request_tiles(&mut self, view_interval: Interval) -> Vec<TileID> {
-        let request_interval = view_interval.intersection(self.interval);
-        if self.last_request_interval == Some(request_interval) {
-            return self.request_tile_cache.clone();
+    fn release_hidden_tiles(&mut self, cx: &Context) {
+        let keep_metas = self.needs_metas();
+        for slot in self.all_slots_mut() {
+            slot.release_hidden(keep_metas, cx);
         }
+    }
 
-        if self.tile_set.tiles.is_empty() {
-            // For dynamic profiles, just return the request as one tile.
-            self.request_tile_cache = vec![TileID(request_interval)];
-            return self.request_tile_cache.clone();
+    // Evict the tiles of the slots that have been out of view the longest,
+    // until the window is back under budget. Slots drawn in the last frame
+    // are left alone, as are meta tiles while a search needs them
+    fn evict_tiles(&mut self, budget: usize, frame: u64) {
+        let mut excess = self.memory_usage.total().saturating_sub(budget);
+        if excess == 0 {
+            return;
+        }
+        let keep_metas = self.needs_metas();
+        let mut slots: Vec<_> = self
+            .all_slots_mut()
+            .filter(|slot| slot.last_shown + 1 < frame)
+            .collect();
+        slots.sort_by_key(|slot| slot.last_shown);
+        for slot in slots {
+            if excess == 0 {
+                break;
+            }
+            let before = slot.memory_usage().total();
+            slot.evict(keep_metas);
+            excess = excess.saturating_sub(before - slot.memory_usage().total());
         }
+        self.update_memory_usage();
+    }
 
-        // We're in a static profile. Estimate the best zoom level, where
-        // "best" minimizes the ratio of the tile size to request size.
-        let request_duration = request_interval.duration_ns();
-        let chosen_level = self
-            .tile_set
-            .tiles
-            .iter()
-            .min_by_key(|level| {
-                let d = level.first().unwrap().0.duration_ns();
-                if d < request_duration {
-                    request_duration / d
-                } else {
-                    d / request_duration
+    // Locate an item in the loaded tiles
+    fn find_item(&self, item_uid: ItemUID) -> Option<(ItemLocator, Interval)> {
+        for slot in self.all_slots() {
+            for tile in slot.tiles.values().flatten() {
+                for (row, items) in tile.items.iter().enumerate() {
+                    if let Some(item) = items.iter().find(|item| item.item_uid == item_uid) {
+                        let loc = ItemLocator {
+                            entry_id: slot.entry_id.clone(),
+                            irow: Some(tile.items.len() - row - 1),
+                            item_uid,
+                            profile: None,
+                        };
+                        return Some((loc, item.interval));
+                    }
                 }
-            })
-            .unwrap();
+            }
+        }
+        None
+    }
 
-        // Now filter to just tiles overlapping the requested interval.
-        self.request_tile_cache = chosen_level
+    // Slots that pass the filters, expanded or not
+    fn filtered_slots(&self) -> impl Iterator<Item = &Slot> {
+        let config = &self.config;
+        self.panel
+            .slots
             .iter()
-            .filter(|tile| request_interval.overlaps(tile.0))
-            .copied()
-            .collect();
-        self.request_tile_cache.clone()
+            .filter(|node| Panel::is_slot_visible(*node, config))
+            .flat_map(|node| &node.slots)
+            .filter(|kind| Panel::is_slot_visible(*kind, config))
+            .flat_map(|kind| &kind.slots)
+            .filter(|slot| Panel::is_slot_visible(*slot, config))
     }
 
-    fn scroll_to_item(&mut self, item_loc: ItemLocator) {
-        self.scroll_to_item = Some(item_loc.clone());
-        self.scroll_to_item_retry = None;
-
-        self.items_selected
-            .entry(item_loc.item_uid)
-            .or_insert_with(|| ItemDetail {
-                meta: None,
-                loc: item_loc,
-            });
+    // Slots that are expanded and pass the filters, along with their parents
+    fn visible_slots(&self) -> Vec<&Slot> {
+        let config = &self.config;
+        let mut result = Vec::new();
+        for node in &self.panel.slots {
+            if !node.expanded || !Panel::is_slot_visible(node, config) {
+                continue;
+            }
+            for kind in &node.slots {
+                if !kind.expanded || !Panel::is_slot_visible(kind, config) {
+                    continue;
+                }
+                for slot in &kind.slots {
+                    if slot.expanded && Panel::is_slot_visible(slot, config) {
+                        result.push(slot);
+                    }
+                }
+            }
+        }
+        result
     }
-}
 
-impl Window {
-    fn new(data_source: Box<dyn DeferredDataSource>, info: DataSourceInfo, index: u64) -> Self {
-        Self {
-            panel: Panel::new(&info.entry_info, EntryID::root()),
-            index,
-            config: Config::new(data_source, info),
+    // Expand the entries matching a pattern (see EntryPattern). Nodes that
+    // match are also added to the node filter. Returns true if any entries
+    // matched
+    fn expand_matching(&mut self, pattern: &str) -> bool {
+        let pattern = EntryPattern::parse(pattern);
+        let matches = |level, short_name: &str, long_name: &str| {
+            pattern.matches(level, short_name, long_name)
+        };
+
+        let mut found = false;
+        for (index, node) in self.panel.slots.iter_mut().enumerate() {
+            if !matches(0, &node.short_name, &node.long_name) {
+                continue;
+            }
+            node.expanded = true;
+            self.config.selected_nodes.insert(index as u64);
+            found = true;
+            if pattern.depth() < 2 {
+                continue;
+            }
+            for kind in &mut node.slots {
+                if !matches(1, &kind.short_name, &kind.long_name) {
+                    continue;
+                }
+                kind.expanded = true;
+                if pattern.depth() < 3 {
+                    continue;
+                }
+                for slot in &mut kind.slots {
+                    if matches(2, &slot.short_name, &slot.long_name) {
+                        slot.expanded = true;
+                    }
+                }
+            }
         }
+        found
     }
 
-    fn find_slot(&self, entry_id: &EntryID) -> Option<&Slot> {
-        self.panel.find_slot(entry_id, 0)
+    // Entries left by the node selection and kind filter, or None if nothing
+    // is filtered out. Nodes with all of their kinds left are named as a
+    // whole, to keep the set small
+    fn active_entries(&self) -> Option<BTreeSet<EntryID>> {
+        let config = &self.config;
+        let mut result = BTreeSet::new();
+        let mut filtered = false;
+        for node in &self.panel.slots {
+            if !Panel::is_slot_visible(node, config) {
+                filtered = true;
+                continue;
+            }
+            let kinds: Vec<_> = node
+                .slots
+                .iter()
+                .filter(|kind| Panel::is_slot_visible(*kind, config))
+                .collect();
+            if kinds.len() == node.slots.len() {
+                result.insert(node.entry_id.clone());
+            } else {
+                filtered = true;
+                result.extend(kinds.iter().map(|kind| kind.entry_id.clone()));
+            }
+        }
+        filtered.then_some(result)
     }
 
-    fn find_slot_mut(&mut self, entry_id: &EntryID) -> Option<&mut Slot> {
-        self.panel.find_slot_mut(entry_id, 0)
+    // Let the data source know whenever the node selection or kind filter
+    // changes which entries are active (see
+    // DeferredDataSource::set_active_entries)
+    fn update_active_entries(&mut self) {
+        let config = &self.config;
+        if let Some((nodes, kinds, exclude)) = &self.active_filter {
+            if *nodes == config.selected_nodes
+                && *kinds == config.kind_filter
+                && *exclude == config.kind_filter_exclude
+            {
+                return;
+            }
+        }
+        self.active_filter = Some((
+            config.selected_nodes.clone(),
+            config.kind_filter.clone(),
+            config.kind_filter_exclude,
+        ));
+
+        let entries = self.active_entries();
+        if entries != self.active_entries {
+            self.config.data_source.set_active_entries(entries.as_ref());
+            self.active_entries = entries;
+        }
     }
 
-    fn find_summary_mut(&mut self, entry_id: &EntryID) -> Option<&mut Summary> {
-        self.panel.find_summary_mut(entry_id, 0)
+    // Patterns that expand the same kinds as are expanded now (see
+    // expand_matching). Nodes and slots start out expanded, so only the
+    // kinds need to be named
+    fn expanded_patterns(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        for node in &self.panel.slots {
+            if !node.expanded {
+                continue;
+            }
+            for kind in &node.slots {
+                if kind.expanded {
+                    result.push(format!("{}/{}", node.short_name, kind.short_name));
+                }
+            }
+        }
+        result
     }
 
-    fn expand_slot(&mut self, entry_id: &EntryID) {
-        self.panel.expand_slot(entry_id, 0);
+    // Locator the profile can be reopened from, if there is one
+    fn shareable_locator(&self) -> Option<&str> {
+        let [locator] = &self.config.description.source_locator[..] else {
+            return None;
+        };
+        // The browser can only reopen profiles served over HTTP, not ones
+        // from dropped files or local directories
+        #[cfg(target_arch = "wasm32")]
+        if !locator.starts_with("http://") && !locator.starts_with("https://") {
+            return None;
+        }
+        Some(locator)
     }
 
-    fn inflate_meta(&mut self, entry_id: &EntryID, cx: &mut Context) {
-        // Use the panel version directly to avoid a mutability conflict
-        let slot = self.panel.find_slot_mut(entry_id, 0).unwrap();
-        slot.inflate_meta(&mut self.config, cx);
+    // Tiles are fetched again when switching to or from full tiles, since
+    // their items differ
+    fn set_full_tiles(&mut self, full: bool) {
+        if self.config.full_tiles != full {
+            self.config.full_tiles = full;
+            self.invalidate_tiles(&EntryID::root(), self.config.interval);
+        }
     }
 
-    fn find_item_irow(&self, entry_id: &EntryID, item_uid: ItemUID) -> Option<usize> {
-        let slot = self.find_slot(entry_id)?;
-        for tile in slot.tiles.values() {
-            let Some(tile) = tile else {
-                continue;
-            };
-            for (row, items) in tile.items.iter().enumerate() {
-                for item in items {
-                    if item.item_uid == item_uid {
-                        let rows = tile.items.len();
-                        return Some(rows - row - 1);
-                    }
+    // Drop tiles the data source reports as stale (see DataSourceUpdate), so
+    // that they are fetched again the next time they are shown
+    fn invalidate_tiles(&mut self, entry_id: &EntryID, interval: Interval) {
+        // Tiles still being fetched are kept (so they aren't requested
+        // again) until their stale responses arrive
+        fn retain_fresh<T>(
+            tiles: &mut BTreeMap<TileID, Option<T>>,
+            interval: Interval,
+            kind: RequestKind,
+            entry_id: &EntryID,
+            stale: &mut BTreeSet<(RequestKind, EntryID, TileID)>,
+        ) {
+            tiles.retain(|tile_id, tile| {
+                if !tile_id.0.overlaps(interval) {
+                    return true;
+                }
+                if tile.is_none() {
+                    stale.insert((kind, entry_id.clone(), *tile_id));
+                }
+                tile.is_none()
+            });
+        }
+
+        fn invalidate_summary(
+            summary: &mut Option<Summary>,
+            entry_id: &EntryID,
+            interval: Interval,
+            stale: &mut BTreeSet<(RequestKind, EntryID, TileID)>,
+        ) {
+            if let Some(summary) = summary {
+                if summary.entry_id.has_prefix(entry_id) {
+                    summary.memory = None;
+                    retain_fresh(
+                        &mut summary.tiles,
+                        interval,
+                        RequestKind::SummaryTile,
+                        &summary.entry_id,
+                        stale,
+                    );
+                    summary
+                        .failed
+                        .retain(|tile_id, _| !tile_id.0.overlaps(interval));
                 }
             }
         }
-        None
-    }
 
-    fn find_item_meta(&self, entry_id: &EntryID, item_uid: ItemUID) -> Option<&ItemMeta> {
-        let slot = self.find_slot(entry_id)?;
-        for tile in slot.tile_metas.values() {
-            let Some(tile) = tile else {
-                continue;
-            };
-            for items in &tile.items {
-                for item in items {
-                    if item.item_uid == item_uid {
-                        return Some(item);
+        let stale = &mut self.stale_requests;
+        invalidate_summary(&mut self.panel.summary, entry_id, interval, stale);
+        for node in &mut self.panel.slots {
+            invalidate_summary(&mut node.summary, entry_id, interval, stale);
+            for kind in &mut node.slots {
+                invalidate_summary(&mut kind.summary, entry_id, interval, stale);
+                for slot in &mut kind.slots {
+                    if !slot.entry_id.has_prefix(entry_id) {
+                        continue;
                     }
+                    slot.memory = None;
+                    let fresh = |tile_id: &TileID| !tile_id.0.overlaps(interval);
+                    retain_fresh(
+                        &mut slot.tiles,
+                        interval,
+                        RequestKind::SlotTile,
+                        &slot.entry_id,
+                        stale,
+                    );
+                    retain_fresh(
+                        &mut slot.tile_metas,
+                        interval,
+                        RequestKind::SlotMetaTile,
+                        &slot.entry_id,
+                        stale,
+                    );
+                    slot.failed.retain(|(tile_id, _), _| fresh(tile_id));
+                    slot.item_metas.retain(|(tile_id, _), _| fresh(tile_id));
+                    slot.meshes.retain(|(_, _, tile_id), _| fresh(tile_id));
+                    slot.textures.retain(|(_, tile_id), _| fresh(tile_id));
+                    slot.color_values.retain(|tile_id, _| fresh(tile_id));
                 }
             }
         }
-        None
     }
 
-    fn content(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
-        ui.horizontal(|ui| {
-            ui.heading(format!("Profile {}", self.index));
-            ui.label(cx.view_interval.to_string());
-            if let Some(message) = &self.config.warning_message {
-                ui.label(RichText::new(message).color(Color32::RED));
+    // Drop the response (or failure) of a tile invalidated while it was
+    // being fetched (see invalidate_tiles), so that it is fetched again
+    fn discard_stale(&mut self, kind: RequestKind, entry_id: &EntryID, tile_id: TileID) -> bool {
+        if !self
+            .stale_requests
+            .remove(&(kind, entry_id.clone(), tile_id))
+        {
+            return false;
+        }
+        match kind {
+            RequestKind::SummaryTile => {
+                if let Some(summary) = self.find_summary_mut(entry_id) {
+                    summary.tiles.remove(&tile_id);
+                }
             }
-        });
-
-        ScrollArea::vertical()
-            .auto_shrink([false; 2])
-            .show_viewport(ui, |ui, viewport| {
-                let height = self.panel.height(None, &self.config, cx);
-                ui.set_height(height);
-                ui.set_width(ui.available_width());
-
-                let rect = Rect::from_min_size(ui.min_rect().min, viewport.size());
-
-                let scroll_to = |irow, prefix_height| {
-                    let mut item_rect =
-                        rect.translate(Vec2::new(0.0, prefix_height + irow as f32 * cx.row_height));
-                    item_rect.set_height(cx.row_height);
-                    ui.scroll_to_rect(item_rect, Some(egui::Align::Center));
-                };
+            RequestKind::SlotTile => {
+                if let Some(slot) = self.find_slot_mut(entry_id) {
+                    slot.tiles.remove(&tile_id);
+                }
+            }
+            RequestKind::SlotMetaTile => {
+                if let Some(slot) = self.find_slot_mut(entry_id) {
+                    slot.tile_metas.remove(&tile_id);
+                }
+            }
+            RequestKind::Info | RequestKind::SlotMetaItem => {}
+        }
+        true
+    }
 
-                // First scroll attempt goes to the processor
-                if let Some(ItemLocator {
-                    ref entry_id, irow, ..
-                }) = self.config.scroll_to_item
-                {
-                    let prefix_height = self.panel.height(Some(entry_id), &self.config, cx);
-                    scroll_to(irow.unwrap_or(0), prefix_height);
-                    if irow.is_none() {
-                        let mut item = None;
-                        std::mem::swap(&mut item, &mut self.config.scroll_to_item);
-                        self.config.scroll_to_item_retry = item;
+    // Mark what a failed request was for, so that it can be retried. Failures
+    // of tiles that are no longer wanted (e.g., after zooming away) are
+    // dropped, like their responses would be
+    fn record_failure(&mut self, failure: FailedRequest) {
+        let (Some(entry_id), Some(tile_id)) = (&failure.entry_id, failure.tile_id) else {
+            return;
+        };
+        if let Some(breakdown) = &mut self.config.time_breakdown {
+            if breakdown.is_pending(failure.kind, entry_id, tile_id) {
+                breakdown.error = Some(format!("Unable to load the items: {}", failure.error));
+                return;
+            }
+        }
+        if self.discard_stale(failure.kind, entry_id, tile_id) {
+            return;
+        }
+        match failure.kind {
+            RequestKind::Info => {}
+            RequestKind::SummaryTile => {
+                if let Some(summary) = self.find_summary_mut(entry_id) {
+                    if let Some(None) = summary.tiles.get(&tile_id) {
+                        summary.failed.insert(tile_id, failure.error);
+                    }
+                }
+            }
+            RequestKind::SlotTile | RequestKind::SlotMetaTile => {
+                if let Some(slot) = self.find_slot_mut(entry_id) {
+                    let pending = if failure.kind == RequestKind::SlotTile {
+                        matches!(slot.tiles.get(&tile_id), Some(None))
+                    } else {
+                        matches!(slot.tile_metas.get(&tile_id), Some(None))
+                    };
+                    if pending {
+                        slot.failed.insert((tile_id, failure.kind), failure.error);
                     }
-                    self.config.scroll_to_item = None;
-                }
-
-                // If we're able to find the item, we do a second scroll to the item
-                let mut found_irow = None;
-                if let Some(ItemLocator {
-                    ref entry_id,
-                    irow,
-                    item_uid,
-                }) = self.config.scroll_to_item_retry
-                {
-                    assert!(irow.is_none());
-                    found_irow = self.find_item_irow(entry_id, item_uid);
                 }
-
-                if let Some(ItemLocator { ref entry_id, .. }) = self.config.scroll_to_item_retry {
-                    if let Some(irow) = found_irow {
-                        let prefix_height = self.panel.height(Some(entry_id), &self.config, cx);
-                        scroll_to(irow, prefix_height);
-                        self.config.scroll_to_item_retry = None;
+            }
+            RequestKind::SlotMetaItem => {
+                // Items are small, so they are simply requested again the
+                // next time they are needed (e.g., hovered)
+                let Some(item_uid) = failure.item_uid else {
+                    return;
+                };
+                if let Some(slot) = self.find_slot_mut(entry_id) {
+                    if let Some(None) = slot.item_metas.get(&(tile_id, item_uid)) {
+                        slot.item_metas.remove(&(tile_id, item_uid));
                     }
                 }
+            }
+        }
+    }
 
-                // Root panel has no label
-                self.panel.content(ui, rect, viewport, &mut self.config, cx);
-            });
+    // The profile changed while open (see DataSourceUpdate::Info), so rebuild
+    // the entries, keeping the same state as when saving the config. The
+    // field schema is assumed not to change
+    fn reload(&mut self, info: DataSourceInfo) {
+        if let EntryInfo::Panel { slots, .. } = &info.entry_info {
+            // Keep showing what we have if the profile can't be reached
+            if slots.is_empty() {
+                log::warn!("unable to reload profile: {:?}", info.warning_message);
+                return;
+            }
+        }
+
+        let state = self.save_config();
+        self.panel = Panel::new(&info.entry_info, EntryID::root());
+        self.config.nodes = info.entry_info.nodes();
+        self.config.kinds = info.entry_info.kinds();
+        self.config.selected_nodes = (0..self.config.nodes).collect();
+        self.config.interval = info.interval;
+        self.config.tile_set = info.tile_set;
+        self.config.search_state.remote_search = info.supports_search;
+        self.config.search_state.clear();
+        // Values seen are still good, but the tiles may have changed
+        let known = &mut self.config.search_state.known_values;
+        known.tiles.clear();
+        known.stale = true;
+        self.kind_aggregates.clear();
+        self.overlay_curves.clear();
+        self.load_config(&state);
     }
 
-    fn node_selection(&mut self, ui: &mut egui::Ui, cx: &Context) {
-        ui.subheading("Node Selection", cx);
-        let total = self.panel.slots.len().saturating_sub(1) as u64;
-        let min_node = &mut self.config.min_node;
-        let max_node = &mut self.config.max_node;
-        ui.add(Slider::new(min_node, 0..=total).text("First"));
-        if *min_node > *max_node {
-            *max_node = *min_node;
+    fn interval_stats(&self, interval: Interval, view: usize) -> IntervalStats {
+        let interval = interval.intersection(self.config.interval);
+        let mut stats = IntervalStats {
+            complete: true,
+            ..Default::default()
+        };
+        if interval.duration_ns() <= 0 {
+            return stats;
         }
-        ui.add(Slider::new(max_node, 0..=total).text("Last"));
-        if *min_node > *max_node {
-            *min_node = *max_node;
+
+        for slot in self.visible_slots() {
+            let (full, partial) = slot.count_items(interval, view);
+            stats.full_items += full;
+            stats.partial_items += partial;
+            let busy: i64 = slot
+                .busy_intervals(interval, view)
+                .iter()
+                .map(|i| i.duration_ns())
+                .sum();
+            let fraction = busy as f32 / interval.duration_ns() as f32;
+            stats.slots.push((slot.long_name.clone(), fraction));
+            stats.complete &= slot.covers(interval, view);
         }
+        stats
     }
 
-    fn filter_by_kind(&mut self, ui: &mut egui::Ui, cx: &Context) {
-        ui.subheading("Filter by Kind", cx);
-        ui.horizontal_wrapped(|ui| {
-            for kind in &self.config.kinds {
-                let initial = self.config.kind_filter.contains(kind);
-                let mut enabled = initial;
-                ui.toggle_value(&mut enabled, kind);
-                if initial != enabled {
-                    if enabled {
-                        self.config.kind_filter.insert(kind.clone());
-                    } else {
-                        self.config.kind_filter.remove(kind);
-                    }
-                }
-            }
+    // Write the visible slots, cropped to the interval, into a new archive
+    // at the path. The profile is opened again on a worker thread, so that
+    // the viewer stays responsive and its own requests aren't mixed up with
+    // those of the export. Requires a shareable_locator
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_archive(&mut self, interval: Interval, path: PathBuf, options: &SourceOptions) {
+        const LEVELS: u32 = 3;
+        const BRANCH_FACTOR: u64 = 4;
+        const ZSTD_COMPRESSION: i32 = 1;
+
+        let locator = self.shareable_locator().unwrap().to_owned();
+        let slots: BTreeSet<_> = self
+            .visible_slots()
+            .into_iter()
+            .map(|slot| slot.entry_id.clone())
+            .collect();
+        let options = options.clone();
+        let progress = Arc::new(ArchiveProgress::default());
+        let (sender, result) = mpsc::channel();
+        let thread_progress = progress.clone();
+        std::thread::spawn(move || {
+            let result = ProfApp::open_data_source(&locator, &options)
+                .map_err(std::io::Error::other)
+                .and_then(|data_source| {
+                    let data_source =
+                        CropDeferredDataSource::new(data_source, interval, Some(slots));
+                    DataSourceArchiveWriter::new(
+                        data_source,
+                        LEVELS,
+                        BRANCH_FACTOR,
+                        path,
+                        false,
+                        ZSTD_COMPRESSION,
+                    )
+                    .with_progress(thread_progress)
+                    .write()
+                });
+            // The viewer may have closed the profile in the meantime
+            let _ = sender.send(result);
         });
+        self.archive_export = Some(ArchiveExport { progress, result });
     }
 
-    fn expand_collapse(&mut self, ui: &mut egui::Ui, cx: &Context) {
-        let mut toggle_all = |label, toggle| {
-            for node in &mut self.panel.slots {
-                for kind in &mut node.slots {
-                    if kind.expanded == toggle && kind.label_text() == label {
-                        kind.toggle_expanded();
-                    }
-                }
-            }
+    // Report the result of the archive export, once it is done
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_archive_export(&mut self, cx: &mut Context) {
+        let Some(export) = &self.archive_export else {
+            return;
         };
-
-        ui.subheading("Expand/Collapse", cx);
-        ui.label("Expand by kind:");
-        ui.horizontal_wrapped(|ui| {
-            for kind in &self.config.kinds {
-                if ui.button(kind).clicked() {
-                    toggle_all(kind.to_lowercase(), false);
-                }
-            }
-        });
-        ui.label("Collapse by kind:");
-        ui.horizontal_wrapped(|ui| {
-            for kind in &self.config.kinds {
-                if ui.button(kind).clicked() {
-                    toggle_all(kind.to_lowercase(), true);
-                }
+        let status = match export.result.try_recv() {
+            Ok(Ok(path)) => format!("Wrote archive to {}", path.display()),
+            Ok(Err(e)) => format!("Unable to write archive: {e}"),
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                "Unable to write archive: the export stopped unexpectedly".to_owned()
             }
-        });
+        };
+        cx.export_status = Some(status);
+        self.archive_export = None;
     }
 
-    fn select_interval(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
-        ui.subheading("Interval", cx);
-        let start_res = ui
-            .horizontal(|ui| {
-                ui.label("Start:");
-                ui.text_edit_singleline(&mut cx.interval_select_state.start_buffer)
-            })
-            .inner;
-
-        if let Some(error) = cx.interval_select_state.start_error {
-            ui.label(RichText::new(error.to_string()).color(Color32::RED));
+    // Lists the slots with the most item time in the view interval, to find
+    // hotspots without scrolling through every processor
+    fn overview_window(&mut self, ctx: &egui::Context, cx: &mut Context) {
+        if !self.config.show_overview {
+            return;
         }
 
-        let stop_res = ui
-            .horizontal(|ui| {
-                ui.label("Stop:");
-                ui.text_edit_singleline(&mut cx.interval_select_state.stop_buffer)
+        let interval = cx.view.interval.intersection(self.config.interval);
+        let mut loaded = 0;
+        let mut busiest: Vec<_> = self
+            .all_slots()
+            .filter_map(|slot| {
+                let (loaded_tiles, duration) = slot.item_time(interval, cx.view.index);
+                loaded += loaded_tiles as usize;
+                (duration > 0).then_some((duration, &slot.entry_id, &slot.long_name))
             })
-            .inner;
+            .collect();
+        busiest.sort_by_key(|(duration, entry_id, _)| (std::cmp::Reverse(*duration), *entry_id));
+        busiest.truncate(self.config.overview_count);
+
+        let mut enabled = true;
+        let mut target = None;
+        egui::Window::new(format!("Overview: {}", self.config.title(self.index)))
+            .id(egui::Id::new(("overview", self.index)))
+            .open(&mut enabled)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(format!("Busiest slots {}", cx.display_units.interval(interval)));
+                ui.label(format!(
+                    "Based on the {loaded} slots with loaded tiles. Expand or scroll to others to include them."
+                ));
+
+                if busiest.is_empty() {
+                    ui.label("No items found.");
+                    return;
+                }
 
-        if let Some(error) = cx.interval_select_state.stop_error {
-            ui.label(RichText::new(error.to_string()).color(Color32::RED));
+                TableBuilder::new(ui)
+                    .striped(true)
+                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                    .column(Column::remainder())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .header(20.0, |mut header| {
+                        header.col(|ui| {
+                            ui.strong("Slot");
+                        });
+                        header.col(|ui| {
+                            ui.strong("Item Time");
+                        });
+                        header.col(|_| {});
+                    })
+                    .body(|mut body| {
+                        for (duration, entry_id, long_name) in &busiest {
+                            body.row(20.0, |mut row| {
+                                row.col(|ui| {
+                                    ui.label(*long_name);
+                                });
+                                row.col(|ui| {
+                                    let time = cx.display_units.timestamp(Timestamp(*duration));
+                                    ui.label(time.to_string()).on_hover_text(format!(
+                                        "{:.1}% of the interval, summed over all rows",
+                                        100.0 * *duration as f64 / interval.duration_ns().max(1) as f64
+                                    ));
+                                });
+                                row.col(|ui| {
+                                    if ui.button("Go").clicked() {
+                                        target = Some((*entry_id).clone());
+                                    }
+                                });
+                            });
+                        }
+                    });
+            });
+
+        if let Some(entry_id) = target {
+            self.expand_slot(&entry_id);
+            self.scroll_to_entry(&entry_id);
+        }
+        if !enabled {
+            self.config.show_overview = false;
+        }
+    }
+
+    fn request_log_window(&mut self, ctx: &egui::Context, cx: &Context) {
+        if !cx.debug || !self.show_request_log {
+            return;
         }
 
-        if start_res.lost_focus()
-            && cx.interval_select_state.start_buffer != cx.view_interval.start.to_string()
-        {
-            match Timestamp::parse(&cx.interval_select_state.start_buffer) {
-                Ok(start) => {
-                    // validate timestamp
-                    if start > cx.view_interval.stop {
-                        cx.interval_select_state.start_error =
-                            Some(IntervalSelectError::StartAfterStop);
-                        return;
+        let log = self.config.data_source.request_log();
+        let latencies: Vec<_> = log.iter().filter_map(|entry| entry.latency()).collect();
+        let waiting = log
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.status,
+                    RequestStatus::Queued | RequestStatus::InFlight
+                )
+            })
+            .count();
+
+        let mut enabled = true;
+        let mut clear = false;
+        egui::Window::new(format!("Request Log: {}", self.config.title(self.index)))
+            .id(egui::Id::new(("request_log", self.index)))
+            .open(&mut enabled)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} requests, {waiting} waiting", log.len()));
+                    if !latencies.is_empty() {
+                        let mean = latencies.iter().sum::<f64>() / latencies.len() as f64;
+                        let max = latencies.iter().copied().fold(0.0, f64::max);
+                        ui.label(format!(
+                            "Latency: {:.0} ms mean, {:.0} ms max",
+                            mean * 1e3,
+                            max * 1e3
+                        ));
                     }
-                    if start > cx.total_interval.stop {
-                        cx.interval_select_state.start_error =
-                            Some(IntervalSelectError::StartAfterEnd);
-                        return;
+                    if ui.button("Clear").clicked() {
+                        clear = true;
                     }
-                    let target = Interval::new(start, cx.view_interval.stop);
-                    ProfApp::zoom(cx, target);
-                }
-                Err(e) => {
-                    cx.interval_select_state.start_error = Some(e.into());
-                }
-            }
+                });
+
+                let now = ui.input(|i| i.time);
+                let row_height = ui.text_style_height(&TextStyle::Body);
+                TableBuilder::new(ui)
+                    .striped(true)
+                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::remainder())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .header(20.0, |mut header| {
+                        for name in ["Type", "Entry", "Tile", "Latency", "Size", "Status"] {
+                            header.col(|ui| {
+                                ui.strong(name);
+                            });
+                        }
+                    })
+                    .body(|body| {
+                        // Newest first
+                        body.rows(row_height, log.len(), |mut row| {
+                            let entry = &log[log.len() - row.index() - 1];
+                            row.col(|ui| {
+                                ui.label(entry.kind.label_text());
+                            });
+                            row.col(|ui| {
+                                if let Some(entry_id) = &entry.entry_id {
+                                    let label = ui.label(EntryIDSlug(entry_id).to_string());
+                                    if let Some(slot) = self.find_slot(entry_id) {
+                                        label.on_hover_text(&slot.long_name);
+                                    }
+                                }
+                            });
+                            row.col(|ui| {
+                                if let Some(tile_id) = entry.tile_id {
+                                    ui.label(TileIDSlug(tile_id).to_string()).on_hover_text(
+                                        cx.display_units.interval(tile_id.0).to_string(),
+                                    );
+                                }
+                            });
+                            row.col(|ui| {
+                                // Requests still waiting show how long so far
+                                let latency = match (entry.sent, entry.received) {
+                                    (Some(sent), Some(received)) => Some(received - sent),
+                                    (Some(sent), None) => Some(now - sent),
+                                    _ => None,
+                                };
+                                if let Some(latency) = latency {
+                                    ui.label(format!("{:.0} ms", latency * 1e3));
+                                }
+                            });
+                            row.col(|ui| {
+                                if entry.received.is_some() {
+                                    ui.label(format_bytes(entry.size as i128));
+                                }
+                            });
+                            row.col(|ui| {
+                                let response = ui.label(entry.status.label_text());
+                                if let Some(error) = &entry.error {
+                                    response.on_hover_text(error);
+                                }
+                            });
+                        });
+                    });
+            });
+
+        if clear {
+            self.config.data_source.clear_request_log();
         }
-        if stop_res.lost_focus()
-            && cx.interval_select_state.stop_buffer != cx.view_interval.stop.to_string()
-        {
-            match Timestamp::parse(&cx.interval_select_state.stop_buffer) {
-                Ok(stop) => {
-                    // validate timestamp
-                    if stop < cx.view_interval.start {
-                        cx.interval_select_state.stop_error =
-                            Some(IntervalSelectError::StopBeforeStart);
-                        return;
-                    }
-                    let target = Interval::new(cx.view_interval.start, stop);
-                    ProfApp::zoom(cx, target);
-                }
-                Err(e) => {
-                    cx.interval_select_state.stop_error = Some(e.into());
-                }
-            }
+        if !enabled {
+            self.show_request_log = false;
         }
     }
 
-    fn controls(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
-        const WIDGET_PADDING: f32 = 8.0;
-        ui.heading(format!("Profile {}: Controls", self.index));
-        ui.add_space(WIDGET_PADDING);
-        self.node_selection(ui, cx);
-        ui.add_space(WIDGET_PADDING);
-        self.filter_by_kind(ui, cx);
-        ui.add_space(WIDGET_PADDING);
-        self.expand_collapse(ui, cx);
-        ui.add_space(WIDGET_PADDING);
-        self.select_interval(ui, cx);
+    fn idle_gaps_window(&mut self, ctx: &egui::Context, cx: &mut Context) {
+        let Some(analysis) = &self.config.idle_gaps else {
+            return;
+        };
+
+        let mut enabled = true;
+        let mut refresh = false;
+        let mut zoom_target = None;
+        egui::Window::new(format!("Idle Gaps: {}", analysis.long_name))
+            .id(egui::Id::new(("idle_gaps", self.index)))
+            .open(&mut enabled)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(format!("Largest gaps {}", analysis.interval));
+                if !analysis.complete {
+                    ui.label(
+                        RichText::new("Some tiles were still loading. Results may be incomplete.")
+                            .color(Color32::RED),
+                    );
+                }
+                if ui.button("Refresh").clicked() {
+                    refresh = true;
+                }
+
+                if analysis.gaps.is_empty() {
+                    ui.label("No idle gaps found.");
+                    return;
+                }
+
+                TableBuilder::new(ui)
+                    .striped(true)
+                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                    .column(Column::auto())
+                    .column(Column::remainder())
+                    .column(Column::auto())
+                    .header(20.0, |mut header| {
+                        header.col(|ui| {
+                            ui.strong("Duration");
+                        });
+                        header.col(|ui| {
+                            ui.strong("Interval");
+                        });
+                        header.col(|_| {});
+                    })
+                    .body(|mut body| {
+                        for gap in &analysis.gaps {
+                            body.row(20.0, |mut row| {
+                                row.col(|ui| {
+                                    let duration = Timestamp(gap.duration_ns());
+                                    ui.label(cx.display_units.timestamp(duration).to_string());
+                                });
+                                row.col(|ui| {
+                                    ui.label(cx.display_units.interval(*gap).to_string());
+                                });
+                                row.col(|ui| {
+                                    if ui.button("Zoom to Gap").clicked() {
+                                        zoom_target = Some(*gap);
+                                    }
+                                });
+                            });
+                        }
+                    });
+            });
+
+        let entry_id = analysis.entry_id.clone();
+        if let Some(gap) = zoom_target {
+            ProfApp::zoom(cx, gap.saturating_grow(gap.duration_ns() / 20));
+            self.expand_slot(&entry_id);
+        }
+        if refresh {
+            let interval = cx.view.interval.intersection(self.config.interval);
+            self.config.idle_gaps = self
+                .find_slot(&entry_id)
+                .map(|s| s.idle_gaps(interval, cx.view.index));
+        }
+        if !enabled {
+            self.config.idle_gaps = None;
+        }
     }
 
     fn search(&mut self, cx: &mut Context) {
@@ -1725,8 +8759,32 @@ impl Window {
             return;
         }
 
-        // Expand meta tiles. (Including collapsed entries, if requested).
-        self.panel.inflate_meta(&mut self.config, cx);
+        // Let the data source search, if it can. Matches in the meta tiles
+        // that are already loaded are still found below
+        self.config
+            .search_state
+            .start_remote(&mut self.config.data_source, &cx.view);
+
+        // Expand meta tiles, visible entries first, then collapsed entries
+        // (if requested). Limit the number of requests in flight so that
+        // results for visible entries show up promptly.
+        const MAX_META_REQUESTS: u64 = 64;
+        let outstanding = self.config.data_source.outstanding_requests();
+        let search = &mut self.config.search_state;
+        search.meta_budget = MAX_META_REQUESTS.saturating_sub(outstanding) as usize;
+        search.searched_entries = 0;
+        search.total_entries = 0;
+        for visible in [true, false] {
+            if self.config.search_state.is_remote() {
+                break;
+            }
+            if !visible && !self.config.search_state.searches_collapsed() {
+                break;
+            }
+            self.config.search_state.meta_pass_visible = visible;
+            self.config.search_state.walk_visible = true;
+            self.panel.inflate_meta(&mut self.config, cx);
+        }
 
         // Search whatever data we have. Results are cached by entry/tile.
         self.panel.search(&mut self.config);
@@ -1734,6 +8792,77 @@ impl Window {
         // Cache is now full and we can highlight/render the entries.
     }
 
+    fn update_known_values(&mut self) {
+        let search = &mut self.config.search_state;
+        let field = search.search_field;
+        let known = &mut search.known_values;
+        if known.field != Some(field) {
+            *known = KnownValues {
+                field: Some(field),
+                stale: true,
+                ..Default::default()
+            };
+        }
+        if known.too_many || !known.stale {
+            return;
+        }
+        known.stale = false;
+        let slots = self.panel.slots.iter().flat_map(|node| &node.slots);
+        for slot in slots.flat_map(|kind| &kind.slots) {
+            for (tile_id, tile) in &slot.tile_metas {
+                let Some(tile) = tile else {
+                    continue;
+                };
+                let seen = known.tiles.get(&slot.entry_id);
+                if seen.is_some_and(|seen| seen.contains(tile_id)) {
+                    continue;
+                }
+                known
+                    .tiles
+                    .entry(slot.entry_id.clone())
+                    .or_default()
+                    .insert(*tile_id);
+                if field == search.title_field {
+                    known.insert(tile.titles().map(str::to_owned));
+                } else {
+                    known.insert(tile.string_values(field));
+                }
+                if known.too_many {
+                    return;
+                }
+            }
+        }
+    }
+
+    // Values of the search field in the loaded tiles that contain the query
+    // (ignoring case), to pick from instead of typing them out
+    fn known_values_menu(&mut self, ui: &mut egui::Ui) {
+        self.update_known_values();
+        let search = &mut self.config.search_state;
+        let query = search.query.to_lowercase();
+        let values: Vec<_> = search
+            .known_values
+            .values
+            .iter()
+            .filter(|value| value.to_lowercase().contains(&query) && **value != search.query)
+            .cloned()
+            .collect();
+        if values.is_empty() {
+            return;
+        }
+        egui::ComboBox::from_id_source("Known values")
+            .selected_text(format!("{} values", values.len()))
+            .show_ui(ui, |ui| {
+                for value in values {
+                    if ui.selectable_label(false, &value).clicked() {
+                        search.query = value;
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Values of this field in the tiles loaded so far that match the query");
+    }
+
     fn search_box(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
         ui.horizontal(|ui| {
             // Hack: need to estimate the button width or else the text box
@@ -1767,6 +8896,7 @@ impl Window {
                         ui.selectable_value(search_field, *field, name);
                     }
                 });
+            self.known_values_menu(ui);
         });
         ui.checkbox(
             &mut self.config.search_state.whole_word,
@@ -1776,6 +8906,43 @@ impl Window {
             &mut self.config.search_state.include_collapsed_entries,
             "Include collapsed processors",
         );
+        ui.horizontal(|ui| {
+            let search = &mut self.config.search_state;
+            let count = search.scope.len();
+            let binding = cx.mouse_bindings.context_menu.label_text();
+            let help = format!(
+                "Pick processors with Search This Slot ({binding} on their labels), or add \
+                 the ones passing the node and kind filters"
+            );
+            ui.add_enabled(
+                count > 0,
+                egui::Checkbox::new(
+                    &mut search.scope_only,
+                    format!("Only search selected processors ({count})"),
+                ),
+            )
+            .on_hover_text(&help)
+            .on_disabled_hover_text(&help);
+            if count > 0 && ui.small_button("Clear").clicked() {
+                search.scope.clear();
+                search.scope_only = false;
+            }
+        });
+        if ui
+            .small_button("Select Filtered Processors")
+            .on_hover_text(
+                "Restrict the search to the processors passing the node and kind filters",
+            )
+            .clicked()
+        {
+            let slots: Vec<_> = self
+                .filtered_slots()
+                .map(|slot| slot.entry_id.clone())
+                .collect();
+            let search = &mut self.config.search_state;
+            search.scope.extend(slots);
+            search.scope_only = !search.scope.is_empty();
+        }
 
         self.search(cx);
     }
@@ -1786,8 +8953,22 @@ impl Window {
             return;
         }
 
+        let search = &self.config.search_state;
+        let pending = search.is_remote_pending();
+        if pending {
+            ui.label("Searching...");
+        }
+        if search.searched_entries < search.total_entries {
+            ui.label(format!(
+                "Searched {}/{} processors...",
+                search.searched_entries, search.total_entries
+            ));
+        }
+
         if self.config.search_state.result_set.is_empty() {
-            ui.label("No results found. Expand search to include collapsed processors?");
+            if !pending {
+                ui.label("No results found. Expand search to include collapsed processors?");
+            }
 
             return;
         }
@@ -1803,6 +8984,54 @@ impl Window {
             ui.label(format!("Found {} results.", num_results));
         }
 
+        // Results keep coming in while meta tiles load, so these are updated
+        // as they do
+        self.config.search_state.update_stats();
+        if let Some(stats) = self.config.search_state.stats {
+            let show = |ns| cx.display_units.timestamp(Timestamp(ns)).to_string();
+            egui::Grid::new(("search_stats", self.index))
+                .num_columns(4)
+                .show(ui, |ui| {
+                    ui.label("Total:");
+                    ui.label(show(stats.total));
+                    ui.label("Mean:");
+                    ui.label(show(stats.mean));
+                    ui.end_row();
+                    ui.label("Median:");
+                    ui.label(show(stats.median));
+                    ui.label("90th %ile:");
+                    ui.label(show(stats.p90));
+                    ui.end_row();
+                    ui.label("99th %ile:");
+                    ui.label(show(stats.p99));
+                    ui.label("Max:");
+                    ui.label(show(stats.max));
+                    ui.end_row();
+                });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Show as:");
+            for view in [
+                SearchResultsView::Tree,
+                SearchResultsView::Table,
+                SearchResultsView::Grouped,
+            ] {
+                ui.selectable_value(&mut cx.search_results_view, view, view.label_text());
+            }
+        });
+        match cx.search_results_view {
+            SearchResultsView::Tree => {}
+            SearchResultsView::Table => {
+                self.search_results_table(ui, cx);
+                return;
+            }
+            SearchResultsView::Grouped => {
+                self.search_results_grouped(ui, cx);
+                return;
+            }
+        }
+
         self.config.search_state.build_entry_tree();
 
         let mut scroll_target = None;
@@ -1829,14 +9058,15 @@ impl Window {
                                                 let button =
                                                     egui::widgets::Button::new(&item.title).small();
                                                 if ui.add(button).clicked() {
-                                                    let interval = item
-                                                        .interval
-                                                        .grow(item.interval.duration_ns() / 20);
+                                                    let interval = item.interval.saturating_grow(
+                                                        item.interval.duration_ns() / 20,
+                                                    );
                                                     ProfApp::zoom(cx, interval);
                                                     scroll_target = Some(ItemLocator {
                                                         entry_id: level2_slot.entry_id.clone(),
                                                         irow: Some(item.irow),
                                                         item_uid: item.item_uid,
+                                                        profile: None,
                                                     });
                                                     level2_slot.expanded = true;
                                                     level1_slot.expanded = true;
@@ -1851,14 +9081,255 @@ impl Window {
                     });
                 }
             });
-        if let Some(target) = scroll_target {
-            self.config.scroll_to_item(target);
+        if let Some(target) = scroll_target {
+            self.config.scroll_to_item(target);
+        }
+    }
+
+    fn search_results_table(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
+        const ROW_HEIGHT: f32 = 18.0;
+
+        let search = &mut self.config.search_state;
+        search.sort_table_rows(cx.search_sort_column, cx.search_sort_descending);
+
+        // Hack: estimate size of bottom UI.
+        let max_height = ui.available_height() - 70.0;
+        let mut target = None;
+        let mut sort = None;
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .max_scroll_height(max_height)
+            .auto_shrink([false; 2])
+            .column(Column::remainder().clip(true))
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .header(20.0, |mut header| {
+                for column in [
+                    SearchColumn::Title,
+                    SearchColumn::Node,
+                    SearchColumn::Processor,
+                    SearchColumn::Start,
+                    SearchColumn::Duration,
+                ] {
+                    header.col(|ui| {
+                        let sorted = cx.search_sort_column == column;
+                        let label = match (sorted, cx.search_sort_descending) {
+                            (true, false) => format!("{} ⏶", column.label_text()),
+                            (true, true) => format!("{} ⏷", column.label_text()),
+                            (false, _) => column.label_text().to_owned(),
+                        };
+                        if ui
+                            .selectable_label(sorted, RichText::new(label).strong())
+                            .on_hover_text("Sort by this column")
+                            .clicked()
+                        {
+                            sort = Some(column);
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                let search = &self.config.search_state;
+                body.rows(ROW_HEIGHT, search.table_rows.len(), |mut row| {
+                    let (entry_id, tile_id, item_uid) = &search.table_rows[row.index()];
+                    let Some(item) = search
+                        .result_cache
+                        .get(entry_id)
+                        .and_then(|cache| cache.get(tile_id))
+                        .and_then(|cache| cache.get(item_uid))
+                    else {
+                        return;
+                    };
+                    row.col(|ui| {
+                        if ui.link(&item.title).on_hover_text(&item.title).clicked() {
+                            let loc = ItemLocator {
+                                entry_id: entry_id.clone(),
+                                irow: Some(item.irow),
+                                item_uid: item.item_uid,
+                                profile: None,
+                            };
+                            target = Some((loc, item.interval));
+                        }
+                    });
+                    row.col(|ui| {
+                        let node = entry_id
+                            .slot_index(0)
+                            .and_then(|index| self.panel.slots.get(index as usize));
+                        ui.label(node.map_or("", |node| node.long_name.as_str()));
+                    });
+                    row.col(|ui| {
+                        let slot = self.find_slot(entry_id);
+                        ui.label(slot.map_or("", |slot| slot.long_name.as_str()));
+                    });
+                    row.col(|ui| {
+                        ui.label(cx.display_units.timestamp(item.interval.start).to_string());
+                    });
+                    row.col(|ui| {
+                        let duration = Timestamp(item.interval.duration_ns());
+                        ui.label(cx.display_units.timestamp(duration).to_string());
+                    });
+                });
+            });
+
+        if let Some(column) = sort {
+            if cx.search_sort_column == column {
+                cx.search_sort_descending = !cx.search_sort_descending;
+            } else {
+                cx.search_sort_column = column;
+                cx.search_sort_descending = false;
+            }
+        }
+        if let Some((loc, interval)) = target {
+            self.zoom_to_search_result(cx, loc, interval);
+        }
+    }
+
+    fn search_results_grouped(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
+        // Instances listed per title, to keep huge groups manageable
+        const MAX_GROUP_ITEMS: usize = 200;
+
+        self.config.search_state.group_by_title();
+
+        let mut target = None;
+        ScrollArea::vertical()
+            // Hack: estimate size of bottom UI.
+            .max_height(ui.available_height() - 70.0)
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                let search = &self.config.search_state;
+                for group in &search.title_groups {
+                    let count = group.items.len();
+                    let total = Timestamp(group.total);
+                    let mean = Timestamp(group.total / count as i64);
+                    let header = format!(
+                        "{} ({} items, total {}, mean {})",
+                        group.title,
+                        count,
+                        cx.display_units.timestamp(total),
+                        cx.display_units.timestamp(mean),
+                    );
+                    egui::CollapsingHeader::new(header)
+                        .id_source(("search_group", &group.title))
+                        .show(ui, |ui| {
+                            for (entry_id, tile_id, item_uid) in
+                                group.items.iter().take(MAX_GROUP_ITEMS)
+                            {
+                                let Some(item) = search
+                                    .result_cache
+                                    .get(entry_id)
+                                    .and_then(|cache| cache.get(tile_id))
+                                    .and_then(|cache| cache.get(item_uid))
+                                else {
+                                    continue;
+                                };
+                                let slot = self.find_slot(entry_id);
+                                let label = format!(
+                                    "{} at {}",
+                                    slot.map_or("", |slot| slot.long_name.as_str()),
+                                    cx.display_units.timestamp(item.interval.start),
+                                );
+                                let button = egui::widgets::Button::new(label).small();
+                                if ui.add(button).clicked() {
+                                    let loc = ItemLocator {
+                                        entry_id: entry_id.clone(),
+                                        irow: Some(item.irow),
+                                        item_uid: item.item_uid,
+                                        profile: None,
+                                    };
+                                    target = Some((loc, item.interval));
+                                }
+                            }
+                            if count > MAX_GROUP_ITEMS {
+                                ui.label(format!(
+                                    "...and {} more. (Refine the search to see them.)",
+                                    count - MAX_GROUP_ITEMS
+                                ));
+                            }
+                        });
+                }
+            });
+
+        if let Some((loc, interval)) = target {
+            self.zoom_to_search_result(cx, loc, interval);
+        }
+    }
+
+    fn zoom_to_search_result(&mut self, cx: &mut Context, loc: ItemLocator, interval: Interval) {
+        let interval = interval.saturating_grow(interval.duration_ns() / 20);
+        ProfApp::zoom(cx, interval);
+        self.expand_slot(&loc.entry_id);
+        self.config.scroll_to_item(loc);
+    }
+
+    fn watch_list(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
+        const WIDGET_PADDING: f32 = 8.0;
+        ui.heading(format!("{}: Watch List", self.config.title(self.index)))
+            .on_hover_text(self.config.source_text());
+        ui.add_space(WIDGET_PADDING);
+
+        let mut target = None;
+        let mut remove = None;
+        egui::Grid::new(("watch_list", self.index))
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                for (item_uid, item) in &self.config.watched_items {
+                    let in_view = item.interval.overlaps(cx.view.interval);
+                    let (status, status_text) = if in_view {
+                        (RichText::new("●").color(Color32::GREEN), "In view")
+                    } else {
+                        (RichText::new("○"), "Out of view")
+                    };
+                    ui.label(status).on_hover_text(status_text);
+
+                    let entry_name = self
+                        .find_slot(&item.entry_id)
+                        .map_or("", |slot| slot.long_name.as_str());
+                    let title: String = item.title.chars().take(40).collect();
+                    if ui
+                        .link(title)
+                        .on_hover_text(format!(
+                            "{}\n{}\n{}",
+                            item.title,
+                            entry_name,
+                            cx.display_units.interval(item.interval)
+                        ))
+                        .clicked()
+                    {
+                        let loc = ItemLocator {
+                            entry_id: item.entry_id.clone(),
+                            irow: item.irow,
+                            item_uid: *item_uid,
+                            profile: None,
+                        };
+                        target = Some((loc, item.interval));
+                    }
+
+                    let duration = Timestamp(item.interval.duration_ns());
+                    ui.label(cx.display_units.timestamp(duration).to_string());
+
+                    if ui.small_button("✖").on_hover_text("Unwatch").clicked() {
+                        remove = Some(*item_uid);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(item_uid) = remove {
+            self.config.watched_items.remove(&item_uid);
+        }
+        if let Some((loc, interval)) = target {
+            ProfApp::navigate_to_item(cx, self, loc, interval);
         }
     }
 
     fn search_controls(&mut self, ui: &mut egui::Ui, cx: &mut Context) {
         const WIDGET_PADDING: f32 = 8.0;
-        ui.heading(format!("Profile {}: Search", self.index));
+        ui.heading(format!("{}: Search", self.config.title(self.index)))
+            .on_hover_text(self.config.source_text());
         ui.add_space(WIDGET_PADDING);
         self.search_box(ui, cx);
         ui.add_space(WIDGET_PADDING);
@@ -1876,19 +9347,45 @@ impl ProfApp {
     /// Called once before the first frame.
     pub fn new(
         cc: &eframe::CreationContext<'_>,
-        mut data_sources: Vec<Box<dyn DeferredDataSource>>,
+        data_sources: Vec<Box<dyn DeferredDataSource>>,
     ) -> Self {
         // This is also where you can customized the look at feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        let mut result: Self = if let Some(storage) = cc.storage {
+        let result: Self = if let Some(storage) = cc.storage {
             eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
         } else {
             Default::default()
         };
 
+        let mut result = result.init(&cc.egui_ctx, data_sources);
+
+        // Ctrl + Plus/Minus/0 zoom the timeline, not the UI
+        cc.egui_ctx
+            .options_mut(|options| options.zoom_with_keyboard = false);
+        Self::apply_ui_scale(&cc.egui_ctx, &mut result.cx);
+
+        result
+    }
+
+    /// Create the viewer for embedding in another egui application. The
+    /// host owns the zoom factor, fonts and style, so the UI scale settings
+    /// have no effect here, and the theme only applies to the viewer's own
+    /// UI. Keys are only handled once the viewer has been clicked.
+    pub fn with_context(
+        ctx: &egui::Context,
+        data_sources: Vec<Box<dyn DeferredDataSource>>,
+    ) -> Self {
+        let mut result = Self::default();
+        result.cx.embedded = true;
+        result.init(ctx, data_sources)
+    }
+
+    fn init(self, ctx: &egui::Context, mut data_sources: Vec<Box<dyn DeferredDataSource>>) -> Self {
+        let mut result = self;
+
         for data_source in &mut data_sources {
             data_source.fetch_info();
         }
@@ -1900,38 +9397,489 @@ impl ProfApp {
         result.cx.scale_factor = 1.0;
         result.cx.row_scroll_delta = 0;
 
+        // Older saved states don't have these
+        if !UI_SCALE_PRESETS.contains(&result.cx.ui_scale) {
+            result.cx.ui_scale = 1.0;
+        }
+        if !FONT_SCALE_RANGE.contains(&result.cx.font_scale) {
+            result.cx.font_scale = 1.0;
+        }
+        result.cx.applied_font_scale = 1.0;
+        if !MAX_REQUESTS_RANGE.contains(&result.cx.max_requests) {
+            result.cx.max_requests = DEFAULT_MAX_REQUESTS;
+        }
+        if !MEMORY_BUDGET_RANGE.contains(&result.cx.memory_budget) {
+            result.cx.memory_budget = DEFAULT_MEMORY_BUDGET;
+        }
+        if !MAX_SLOT_ROWS_RANGE.contains(&result.cx.max_slot_rows) {
+            result.cx.max_slot_rows = DEFAULT_MAX_SLOT_ROWS;
+        }
+
         #[cfg(not(target_arch = "wasm32"))]
         {
             result.last_update = Some(Instant::now());
         }
 
-        let theme = if result.cx.toggle_dark_mode {
+        if !result.cx.embedded {
+            ctx.style_mut(|style| Self::apply_style(style, &result.cx));
+        }
+
+        result
+    }
+
+    fn apply_style(style: &mut egui::Style, cx: &Context) {
+        style.visuals = if cx.toggle_dark_mode {
             egui::Visuals::dark()
         } else {
             egui::Visuals::light()
         };
-        cc.egui_ctx.set_visuals(theme);
 
         // Set solid scroll bar (default from egui pre-0.24)
         // The new default "thin" style isn't clickable with our canvas widget
-        cc.egui_ctx.style_mut(|style| {
-            style.spacing.scroll = egui::style::ScrollStyle::solid();
-        });
+        style.spacing.scroll = egui::style::ScrollStyle::solid();
+    }
 
-        result
+    /// Register a callback for changes to the selection, the view interval
+    /// and the search query.
+    pub fn subscribe(&mut self, callback: impl FnMut(&ViewerEvent) + 'static) {
+        self.subscribers.push(Box::new(callback));
+    }
+
+    fn emit_events(
+        windows: &[Window],
+        cx: &Context,
+        subscribers: &mut [EventCallback],
+        state: &mut EventState,
+    ) {
+        if windows.is_empty() {
+            return;
+        }
+
+        let mut events = Vec::new();
+        if state.interval.is_some_and(|i| i != cx.view.interval) {
+            events.push(ViewerEvent::IntervalChanged(cx.view.interval));
+        }
+        state.interval = Some(cx.view.interval);
+
+        state
+            .items_selected
+            .resize_with(windows.len(), BTreeSet::new);
+        state.queries.resize_with(windows.len(), String::new);
+        for (i, window) in windows.iter().enumerate() {
+            let config = &window.config;
+            let source_locator = &config.description.source_locator;
+            for (item_uid, item) in &config.items_selected {
+                if !state.items_selected[i].contains(item_uid) {
+                    events.push(ViewerEvent::ItemSelected {
+                        source_locator: source_locator.clone(),
+                        entry_id: item.loc.entry_id.clone(),
+                        item_uid: *item_uid,
+                    });
+                }
+            }
+            state.items_selected[i] = config.items_selected.keys().copied().collect();
+
+            // Only report searches once they actually run
+            let query = &config.search_state.last_query;
+            if *query != state.queries[i] {
+                if !query.is_empty() {
+                    events.push(ViewerEvent::Search {
+                        source_locator: source_locator.clone(),
+                        query: query.clone(),
+                    });
+                }
+                state.queries[i].clone_from(query);
+            }
+        }
+
+        for event in &events {
+            for subscriber in subscribers.iter_mut() {
+                subscriber(event);
+            }
+        }
+    }
+
+    // Open a profile given a URL (if built with the client feature) or the
+    // path of an archive (or of a tar file of one)
+    // The options only apply to URLs
+    #[cfg_attr(not(feature = "client"), allow(unused_variables))]
+    pub fn open_data_source(
+        locator: &str,
+        options: &SourceOptions,
+    ) -> Result<Box<dyn DeferredDataSource>, String> {
+        #[cfg(feature = "client")]
+        if let Ok(url) = url::Url::parse(locator) {
+            if url.scheme() == "http" || url.scheme() == "https" {
+                let timeouts =
+                    HTTPTimeouts::with_overrides(options.connect_timeout, options.read_timeout);
+                let data_source = HTTPClientDataSource::with_options(url, timeouts, &options.tls)?;
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(cache_dir) = &options.cache_dir {
+                    let data_source = CachingDeferredDataSource::new(data_source, cache_dir);
+                    return Ok(Box::new(data_source));
+                }
+                return Ok(Box::new(data_source));
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // Archives are directories, but may also be packed into a tar
+            // file (see MemoryDataSource)
+            if std::path::Path::new(locator).is_file() {
+                let bytes = std::fs::read(locator).map_err(|e| e.to_string())?;
+                let data_source = MemoryDataSource::new(locator, &bytes);
+                return Ok(Box::new(DeferredDataSourceWrapper::new(data_source)));
+            }
+            Ok(Box::new(DeferredDataSourceWrapper::new(
+                FileDataSource::new(locator),
+            )))
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            Err(format!("unable to open {locator}"))
+        }
+    }
+
+    // Open a profile archive dropped onto the viewer. In the browser, the
+    // archive must be packed into a tar file (see MemoryDataSource), since
+    // only the contents of files are available
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn open_dropped_file(
+        file: &egui::DroppedFile,
+        options: &SourceOptions,
+    ) -> Result<Box<dyn DeferredDataSource>, String> {
+        if let Some(bytes) = &file.bytes {
+            let data_source = MemoryDataSource::new(&file.name, bytes);
+            return Ok(Box::new(DeferredDataSourceWrapper::new(data_source)));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &file.path {
+            return Self::open_data_source(&path.to_string_lossy(), options);
+        }
+        Err("file contents not available".to_owned())
+    }
+
+    fn open_dropped_files(
+        ctx: &egui::Context,
+        pending_data_sources: &mut VecDeque<Box<dyn DeferredDataSource>>,
+        cx: &Context,
+    ) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        for file in &dropped {
+            match Self::open_dropped_file(file, &cx.sources) {
+                Ok(mut data_source) => {
+                    data_source.fetch_info();
+                    pending_data_sources.push_back(data_source);
+                }
+                Err(e) => log::warn!("unable to open {}: {}", file.name, e),
+            }
+        }
+
+        if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+            return;
+        }
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("file_drop_target"),
+        ));
+        let rect = ctx.screen_rect();
+        painter.rect_filled(rect, 0.0, Color32::from_black_alpha(192));
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "Drop a profile archive to open it",
+            egui::TextStyle::Heading.resolve(&ctx.style()),
+            Color32::WHITE,
+        );
+    }
+
+    // Open a profile in a new window, and remember it in the recent sources,
+    // if given. Only profiles opened by the user are remembered, not those
+    // opened by commands (e.g., from a script)
+    fn open_locator(
+        locator: &str,
+        pending_data_sources: &mut VecDeque<Box<dyn DeferredDataSource>>,
+        recent_sources: Option<&mut Vec<String>>,
+        cx: &Context,
+    ) -> Result<(), String> {
+        let mut data_source = Self::open_data_source(locator, &cx.sources)?;
+        data_source.fetch_info();
+        pending_data_sources.push_back(data_source);
+
+        if let Some(recent_sources) = recent_sources {
+            Self::remember_source(recent_sources, locator);
+        }
+        Ok(())
+    }
+
+    fn remember_source(recent_sources: &mut Vec<String>, locator: &str) {
+        // Keep relative paths valid in the recent sources (as in
+        // OpenDialog::validate). Anything else, e.g., a URL, is kept as is
+        #[cfg(not(target_arch = "wasm32"))]
+        let locator = &std::fs::canonicalize(locator).map_or_else(
+            |_| locator.to_owned(),
+            |path| path.to_string_lossy().into_owned(),
+        );
+        recent_sources.retain(|source| source != locator);
+        recent_sources.insert(0, locator.to_owned());
+        recent_sources.truncate(MAX_RECENT_SOURCES);
+    }
+
+    // Open the profiles in a link in new windows, and restore the rest of
+    // the view once they have loaded. The profiles are opened all or none,
+    // since items refer to them by index. A link without profiles applies
+    // to the profiles that are already open
+    fn open_view_link(
+        link: ViewLink,
+        pending_data_sources: &mut VecDeque<Box<dyn DeferredDataSource>>,
+        mut recent_sources: Option<&mut Vec<String>>,
+        pending_view: &mut Option<(ViewLink, usize)>,
+        num_windows: usize,
+        cx: &Context,
+    ) -> Result<(), String> {
+        let data_sources = link
+            .sources
+            .iter()
+            .map(|locator| {
+                Self::open_data_source(locator, &cx.sources)
+                    .map_err(|e| format!("unable to open {locator}: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let first = if data_sources.is_empty() {
+            0
+        } else {
+            num_windows + pending_data_sources.len()
+        };
+        for (locator, mut data_source) in link.sources.iter().zip(data_sources) {
+            data_source.fetch_info();
+            pending_data_sources.push_back(data_source);
+            if let Some(recent_sources) = recent_sources.as_mut() {
+                Self::remember_source(recent_sources, locator);
+            }
+        }
+        *pending_view = Some((link, first));
+        Ok(())
+    }
+
+    fn apply_view_link(link: &ViewLink, first: usize, windows: &mut [Window], cx: &mut Context) {
+        let windows = windows.get_mut(first..).unwrap_or_default();
+        for pattern in &link.expand {
+            for window in windows.iter_mut() {
+                window.expand_matching(pattern);
+            }
+        }
+        if let Some(interval) = link.interval {
+            ProfApp::zoom(cx, interval);
+        }
+        for item in &link.selected {
+            let Some(window) = windows.get_mut(item.profile) else {
+                log::warn!("linked item {:?} is in a missing profile", item.item_uid);
+                continue;
+            };
+            // The details are filled in once the item's tile has loaded
+            window.config.items_selected.insert(
+                item.item_uid,
+                ItemDetail {
+                    meta: None,
+                    loc: ItemLocator {
+                        entry_id: item.entry_id.clone(),
+                        irow: item.irow,
+                        item_uid: item.item_uid,
+                        profile: None,
+                    },
+                    field_filter: String::new(),
+                },
+            );
+        }
+    }
+
+    // Link to the current view of the profiles that can be reopened from
+    // their locators (see ViewLink)
+    fn view_link(windows: &[Window], cx: &Context) -> ViewLink {
+        let mut link = ViewLink {
+            interval: Some(cx.view.interval),
+            ..ViewLink::default()
+        };
+        for window in windows {
+            let Some(locator) = window.shareable_locator() else {
+                continue;
+            };
+            let profile = link.sources.len();
+            link.sources.push(locator.to_owned());
+            for pattern in window.expanded_patterns() {
+                if !link.expand.contains(&pattern) {
+                    link.expand.push(pattern);
+                }
+            }
+            for item in window.config.items_selected.values() {
+                link.selected.push(LinkedItem {
+                    profile,
+                    entry_id: item.loc.entry_id.clone(),
+                    item_uid: item.loc.item_uid,
+                    irow: item.loc.irow,
+                });
+            }
+        }
+        link
+    }
+
+    fn process_commands(
+        control: &ViewerControl,
+        pending_data_sources: &mut VecDeque<Box<dyn DeferredDataSource>>,
+        pending_view: &mut Option<(ViewLink, usize)>,
+        windows: &mut [Window],
+        cx: &mut Context,
+        subscribers: &mut Vec<EventCallback>,
+    ) {
+        for command in control.receiver.try_iter() {
+            let (command, reply) = match command {
+                ViewerCommand::Acknowledged(command, reply) => (*command, Some(reply)),
+                command => (command, None),
+            };
+            let open_link = matches!(command, ViewerCommand::OpenLink(_));
+            let result = Self::process_command(
+                command,
+                pending_data_sources,
+                pending_view,
+                windows,
+                cx,
+                subscribers,
+            );
+            if let Err(e) = &result {
+                log::warn!("{}", e);
+            }
+            let applied = result.is_ok();
+            if let Some(reply) = reply {
+                // The sender may have given up on the reply
+                let _ = reply.send(result);
+            }
+            // Hold the remaining commands until the view has been restored,
+            // so that it doesn't undo them
+            if open_link && applied {
+                break;
+            }
+        }
+    }
+
+    fn process_command(
+        command: ViewerCommand,
+        pending_data_sources: &mut VecDeque<Box<dyn DeferredDataSource>>,
+        pending_view: &mut Option<(ViewLink, usize)>,
+        windows: &mut [Window],
+        cx: &mut Context,
+        subscribers: &mut Vec<EventCallback>,
+    ) -> Result<(), String> {
+        match command {
+            ViewerCommand::SetInterval(interval) => ProfApp::zoom(cx, interval),
+            ViewerCommand::SelectItem(item_uid) => {
+                let target = windows.iter_mut().find_map(|window| {
+                    let target = window.find_item(item_uid)?;
+                    Some((window, target))
+                });
+                let Some((window, (loc, interval))) = target else {
+                    return Err(format!("item {} not found in loaded tiles", item_uid.0));
+                };
+                ProfApp::navigate_to_item(cx, window, loc, interval)
+            }
+            ViewerCommand::Search(query) => {
+                for window in windows.iter_mut() {
+                    window.config.search_state.query.clone_from(&query);
+                }
+            }
+            ViewerCommand::Snapshot(sender) => {
+                let profiles = windows
+                    .iter()
+                    .map(|window| ProfileSnapshot {
+                        source_locator: window.config.description.source_locator.clone(),
+                        items_selected: window.config.items_selected.keys().copied().collect(),
+                        search_query: window.config.search_state.query.clone(),
+                        search_results: window
+                            .config
+                            .search_state
+                            .result_set
+                            .iter()
+                            .copied()
+                            .collect(),
+                        loading: window.config.data_source.outstanding_requests() > 0,
+                    })
+                    .collect();
+                // The receiver may have given up on the snapshot
+                let _ = sender.send(ViewerSnapshot {
+                    interval: cx.view.interval,
+                    total_interval: cx.total_interval,
+                    profiles,
+                });
+            }
+            ViewerCommand::Open(locator) => {
+                Self::open_locator(&locator, pending_data_sources, None, cx)
+                    .map_err(|e| format!("unable to open {locator}: {e}"))?;
+            }
+            ViewerCommand::SetTlsOptions(tls) => cx.sources.tls = tls,
+            ViewerCommand::SetCacheDir(cache_dir) => cx.sources.cache_dir = cache_dir,
+            ViewerCommand::SetTimeouts(connect, read) => {
+                cx.sources.connect_timeout = connect;
+                cx.sources.read_timeout = read;
+            }
+            ViewerCommand::Expand(pattern) => {
+                let mut found = false;
+                for window in windows.iter_mut() {
+                    found |= window.expand_matching(&pattern);
+                }
+                if !found {
+                    return Err(format!("no entries match {pattern}"));
+                }
+            }
+            ViewerCommand::OpenLink(link) => {
+                Self::open_view_link(
+                    link,
+                    pending_data_sources,
+                    None,
+                    pending_view,
+                    windows.len(),
+                    cx,
+                )
+                .map_err(|e| format!("unable to open link: {e}"))?;
+            }
+            ViewerCommand::Subscribe(sender) => {
+                subscribers.push(Box::new(move |event| {
+                    // The receiver may have been dropped
+                    let _ = sender.send(event.clone());
+                }));
+            }
+            ViewerCommand::Acknowledged(..) => {
+                return Err("acknowledged commands can't be nested".to_owned())
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_ui_scale(ctx: &egui::Context, cx: &mut Context) {
+        ctx.set_zoom_factor(cx.ui_scale);
+        if cx.applied_font_scale != cx.font_scale {
+            let defaults = egui::Style::default().text_styles;
+            ctx.style_mut(|style| {
+                for (text_style, font_id) in style.text_styles.iter_mut() {
+                    if let Some(default) = defaults.get(text_style) {
+                        font_id.size = default.size * cx.font_scale;
+                    }
+                }
+            });
+            cx.applied_font_scale = cx.font_scale;
+        }
     }
 
     fn update_interval_select_state(cx: &mut Context) {
-        cx.interval_select_state.start_buffer = cx.view_interval.start.to_string();
-        cx.interval_select_state.stop_buffer = cx.view_interval.stop.to_string();
-        cx.interval_select_state.start_error = None;
-        cx.interval_select_state.stop_error = None;
+        cx.view.interval_select_state.start_buffer = cx.view.interval.start.to_string();
+        cx.view.interval_select_state.stop_buffer = cx.view.interval.stop.to_string();
+        cx.view.interval_select_state.start_error = None;
+        cx.view.interval_select_state.stop_error = None;
     }
 
     fn update_view_interval(cx: &mut Context, interval: Interval, origin: IntervalOrigin) {
-        cx.view_interval = interval;
+        cx.view.interval = interval;
 
-        let history = &mut cx.view_interval_history;
+        let history = &mut cx.view.history;
         let index = history.index;
 
         // Only keep at most one Pan origin in a row
@@ -1955,19 +9903,19 @@ impl ProfApp {
             return;
         }
 
-        let duration = percent.apply_to(cx.view_interval.duration_ns());
+        let duration = percent.apply_to(cx.view.interval.duration_ns());
         let sign = match dir {
             PanDirection::Left => -1,
             PanDirection::Right => 1,
         };
-        let interval = cx.view_interval.translate(duration * sign);
+        let interval = cx.view.interval.saturating_translate(duration * sign);
 
         ProfApp::update_view_interval(cx, interval, IntervalOrigin::Pan);
         ProfApp::update_interval_select_state(cx);
     }
 
     fn zoom(cx: &mut Context, interval: Interval) {
-        if cx.view_interval == interval {
+        if cx.view.interval == interval {
             return;
         }
 
@@ -1976,54 +9924,211 @@ impl ProfApp {
     }
 
     fn undo_pan_zoom(cx: &mut Context) {
-        if cx.view_interval_history.index == 0 {
+        if cx.view.history.index == 0 {
             return;
         }
-        cx.view_interval_history.index -= 1;
-        cx.view_interval = cx.view_interval_history.levels[cx.view_interval_history.index];
-        ProfApp::update_interval_select_state(cx);
+        cx.view.history.index -= 1;
+        cx.view.interval = cx.view.history.levels[cx.view.history.index];
+        ProfApp::update_interval_select_state(cx);
+    }
+
+    fn redo_pan_zoom(cx: &mut Context) {
+        if cx.view.history.index + 1 >= cx.view.history.levels.len() {
+            return;
+        }
+        cx.view.history.index += 1;
+        cx.view.interval = cx.view.history.levels[cx.view.history.index];
+        ProfApp::update_interval_select_state(cx);
+    }
+
+    fn zoom_in(cx: &mut Context) {
+        let quarter = -cx.view.interval.duration_ns() / 4;
+        Self::zoom(cx, cx.view.interval.saturating_grow(quarter));
+    }
+
+    fn zoom_out(cx: &mut Context) {
+        let half = cx.view.interval.duration_ns() / 2;
+        Self::zoom(
+            cx,
+            cx.view
+                .interval
+                .saturating_grow(half)
+                .intersection(cx.total_interval),
+        );
+    }
+
+    fn multiply_scale_factor(cx: &mut Context, factor: f32) {
+        cx.scale_factor = (cx.scale_factor * factor).clamp(0.25, 4.0);
+    }
+
+    fn reset_scale_factor(cx: &mut Context) {
+        cx.scale_factor = 1.0;
+    }
+
+    fn debounce_view(ctx: &egui::Context, view: &mut ViewState) {
+        const DEBOUNCE_SECONDS: f64 = 0.15;
+
+        let now = ctx.input(|i| i.time);
+        let elapsed = now - view.last_interval_change;
+        if view.interval != view.last_interval {
+            // The first change after a quiet period goes through immediately
+            if elapsed >= DEBOUNCE_SECONDS {
+                view.request_interval = view.interval;
+            }
+            view.last_interval = view.interval;
+            view.last_interval_change = now;
+        } else if elapsed >= DEBOUNCE_SECONDS {
+            view.request_interval = view.interval;
+        }
+
+        if view.request_interval != view.interval {
+            let remaining = DEBOUNCE_SECONDS - (now - view.last_interval_change);
+            ctx.request_repaint_after(Duration::from_secs_f64(remaining.max(0.0)));
+        }
+    }
+
+    fn toggle_split_view(cx: &mut Context) {
+        cx.split_view = match cx.split_view.take() {
+            Some(_) => None,
+            // Start the split view at the same position as the main view
+            None => Some(ViewState {
+                index: 1,
+                ..cx.view.clone()
+            }),
+        };
+    }
+
+    fn reset_ui(cx: &mut Context, windows: &mut [Window]) {
+        cx.show_controls = false;
+        cx.selected_interval = None;
+        Self::clear_selection(windows);
+        if cx.escape_clears_search {
+            Self::clear_search(windows);
+        }
+    }
+
+    fn clear_selection(windows: &mut [Window]) {
+        for window in windows.iter_mut() {
+            window.config.items_selected.clear();
+            window.config.scroll_to_item = None;
+            window.config.scroll_to_item_retry = None;
+        }
+    }
+
+    fn clear_search(windows: &mut [Window]) {
+        for window in windows.iter_mut() {
+            window.config.search_state.query.clear();
+        }
+    }
+
+    fn expand_all(windows: &mut [Window], expanded: bool) {
+        for window in windows.iter_mut() {
+            window.expand_all(expanded);
+        }
+    }
+
+    fn expand_nodes(windows: &mut [Window], expanded: bool) {
+        for window in windows.iter_mut() {
+            window.expand_nodes(expanded);
+        }
     }
 
-    fn redo_pan_zoom(cx: &mut Context) {
-        if cx.view_interval_history.index + 1 >= cx.view_interval_history.levels.len() {
-            return;
+    fn toggle_playback(cx: &mut Context) {
+        let playback = &mut cx.playback;
+        playback.playing = !playback.playing;
+        // Restart from the left edge if the cursor is out of view
+        if playback.playing
+            && !playback
+                .position
+                .is_some_and(|p| cx.view.interval.contains(p))
+        {
+            playback.position = Some(cx.view.interval.start);
         }
-        cx.view_interval_history.index += 1;
-        cx.view_interval = cx.view_interval_history.levels[cx.view_interval_history.index];
-        ProfApp::update_interval_select_state(cx);
     }
 
-    fn zoom_in(cx: &mut Context) {
-        let quarter = -cx.view_interval.duration_ns() / 4;
-        Self::zoom(cx, cx.view_interval.grow(quarter));
+    fn stop_playback(cx: &mut Context) {
+        cx.playback.playing = false;
+        cx.playback.position = None;
     }
 
-    fn zoom_out(cx: &mut Context) {
-        let half = cx.view_interval.duration_ns() / 2;
-        Self::zoom(
-            cx,
-            cx.view_interval.grow(half).intersection(cx.total_interval),
-        );
-    }
+    fn advance_playback(ctx: &egui::Context, cx: &mut Context) {
+        if !cx.playback.playing {
+            return;
+        }
+        let Some(position) = cx.playback.position else {
+            return;
+        };
 
-    fn multiply_scale_factor(cx: &mut Context, factor: f32) {
-        cx.scale_factor = (cx.scale_factor * factor).clamp(0.25, 4.0);
-    }
+        let dt = ctx.input(|i| i.stable_dt) as f64;
+        let delta = ((dt * 1e9 * cx.playback.speed).round() as i64).max(1);
+        let position = Timestamp(position.0 + delta);
+        if position >= cx.total_interval.stop {
+            cx.playback.playing = false;
+            return;
+        }
+        cx.playback.position = Some(position);
+
+        match cx.playback.mode {
+            PlaybackMode::Cursor => {
+                if !cx.view.interval.contains(position) {
+                    // Page forward so the cursor is back at the left edge
+                    let offset = position.0 - cx.view.interval.start.0;
+                    let interval = cx.view.interval.saturating_translate(offset);
+                    ProfApp::update_view_interval(cx, interval, IntervalOrigin::Pan);
+                    ProfApp::update_interval_select_state(cx);
+                }
+            }
+            PlaybackMode::SlideView => {
+                let interval = cx.view.interval.saturating_translate(delta);
+                ProfApp::update_view_interval(cx, interval, IntervalOrigin::Pan);
+                ProfApp::update_interval_select_state(cx);
+            }
+        }
 
-    fn reset_scale_factor(cx: &mut Context) {
-        cx.scale_factor = 1.0;
+        ctx.request_repaint();
     }
 
-    fn reset_ui(cx: &mut Context, windows: &mut [Window]) {
-        cx.show_controls = false;
-        for window in windows.iter_mut() {
-            window.config.items_selected.clear();
-        }
+    fn playback_controls(ui: &mut egui::Ui, cx: &mut Context) {
+        ui.horizontal(|ui| {
+            let label = if cx.playback.playing { "⏸" } else { "▶" };
+            if ui.button(label).on_hover_text("Play/Pause").clicked() {
+                Self::toggle_playback(cx);
+            }
+            if ui.button("⏹").on_hover_text("Stop").clicked() {
+                Self::stop_playback(cx);
+            }
+            ui.add(
+                egui::DragValue::new(&mut cx.playback.speed)
+                    .clamp_range(1e-6..=1e6)
+                    .speed(0.01)
+                    .suffix("×"),
+            )
+            .on_hover_text("Profile time per second of real time");
+            let mode = &mut cx.playback.mode;
+            egui::ComboBox::from_id_source("Playback Mode")
+                .selected_text(mode.label_text())
+                .show_ui(ui, |ui| {
+                    for value in [PlaybackMode::Cursor, PlaybackMode::SlideView] {
+                        ui.selectable_value(mode, value, value.label_text());
+                    }
+                });
+        });
     }
 
     fn keyboard(ctx: &egui::Context, cx: &mut Context, windows: &mut [Window]) {
+        // The keys belong to the host until the viewer is clicked
+        if cx.embedded && !cx.embedded_focus {
+            return;
+        }
         // Focus is elsewhere, don't check any keys
-        if ctx.memory(|m| m.focus().is_some()) {
+        let focused = ctx.memory(|m| m.focus().is_some());
+        let had_focus = std::mem::replace(&mut cx.had_focus, focused);
+        if focused {
+            return;
+        }
+        // Escape also takes focus away from a widget, in which case it was
+        // meant for that widget and not for us
+        if had_focus && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
             return;
         }
 
@@ -2038,8 +10143,17 @@ impl ProfApp {
             ExpandVertical,
             ShrinkVertical,
             ResetVertical,
+            ExpandAll,
+            CollapseAll,
+            ExpandNodes,
+            CollapseNodes,
+            ToggleSplitView,
+            ToggleMagnifier,
+            TogglePlayback,
             ToggleControls,
             ResetUI,
+            ClearSelection,
+            ClearSearch,
             NoAction,
         }
         let action = ctx.input(|i| {
@@ -2064,6 +10178,8 @@ impl ProfApp {
                     Actions::RedoZoom
                 } else if i.key_pressed(egui::Key::Num0) {
                     Actions::ResetZoom
+                } else if i.key_pressed(egui::Key::Escape) {
+                    Actions::ClearSearch
                 } else {
                     Actions::NoAction
                 }
@@ -2076,11 +10192,27 @@ impl ProfApp {
                     Actions::Scroll(1)
                 } else if i.key_pressed(egui::Key::ArrowDown) {
                     Actions::Scroll(-1)
+                } else if i.key_pressed(egui::Key::E) {
+                    Actions::ExpandNodes
+                } else if i.key_pressed(egui::Key::C) {
+                    Actions::CollapseNodes
+                } else if i.key_pressed(egui::Key::Escape) {
+                    Actions::ClearSelection
                 } else {
                     Actions::NoAction
                 }
             } else if i.key_pressed(egui::Key::H) {
                 Actions::ToggleControls
+            } else if i.key_pressed(egui::Key::E) {
+                Actions::ExpandAll
+            } else if i.key_pressed(egui::Key::C) {
+                Actions::CollapseAll
+            } else if i.key_pressed(egui::Key::S) {
+                Actions::ToggleSplitView
+            } else if i.key_pressed(egui::Key::M) {
+                Actions::ToggleMagnifier
+            } else if i.key_pressed(egui::Key::Space) {
+                Actions::TogglePlayback
             } else if i.key_pressed(egui::Key::Escape) {
                 Actions::ResetUI
             } else if i.key_pressed(egui::Key::ArrowLeft) {
@@ -2106,24 +10238,113 @@ impl ProfApp {
             Actions::ExpandVertical => ProfApp::multiply_scale_factor(cx, 2.0),
             Actions::ShrinkVertical => ProfApp::multiply_scale_factor(cx, 0.5),
             Actions::ResetVertical => ProfApp::reset_scale_factor(cx),
+            Actions::ExpandAll => ProfApp::expand_all(windows, true),
+            Actions::CollapseAll => ProfApp::expand_all(windows, false),
+            Actions::ExpandNodes => ProfApp::expand_nodes(windows, true),
+            Actions::CollapseNodes => ProfApp::expand_nodes(windows, false),
+            Actions::ToggleSplitView => ProfApp::toggle_split_view(cx),
+            Actions::ToggleMagnifier => cx.magnifier = !cx.magnifier,
+            Actions::TogglePlayback => ProfApp::toggle_playback(cx),
             Actions::ToggleControls => cx.show_controls = !cx.show_controls,
             Actions::ResetUI => ProfApp::reset_ui(cx, windows),
+            Actions::ClearSelection => ProfApp::clear_selection(windows),
+            Actions::ClearSearch => ProfApp::clear_search(windows),
             Actions::NoAction => {}
         }
     }
 
+    fn timeline(ui: &mut egui::Ui, windows: &mut [Window], cx: &mut Context, linked_scroll: bool) {
+        // Popped out profiles are rendered in their own OS windows
+        let mut remaining = windows.iter().filter(|w| w.detached.is_none()).count();
+        // Only wrap in a frame if more than one profile
+        if remaining > 1 {
+            for window in windows.iter_mut().filter(|w| w.detached.is_none()) {
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.push_id(window.index, |ui| {
+                        ui.set_height(ui.available_height() / (remaining as f32));
+                        ui.set_width(ui.available_width());
+                        window.content(ui, cx, linked_scroll);
+                        remaining -= 1;
+                    });
+                });
+            }
+        } else {
+            for window in windows.iter_mut().filter(|w| w.detached.is_none()) {
+                window.content(ui, cx, linked_scroll);
+            }
+        }
+
+        Self::cursor(ui, cx);
+    }
+
+    fn detached_window(ctx: &egui::Context, window: &mut Window, cx: &mut Context) {
+        const DEFAULT_SIZE: Vec2 = Vec2::new(1024.0, 768.0);
+
+        let Some(detached) = &mut window.detached else {
+            return;
+        };
+        // Take the view out so that it can stand in for the current view
+        // while rendering
+        let mut view = std::mem::take(&mut detached.view);
+        if detached.independent {
+            view.index = DETACHED_VIEW;
+            Self::debounce_view(ctx, &mut view);
+        } else {
+            view.index = 0;
+            view.follow(&cx.view);
+        }
+
+        let title = format!("{} - {APP_NAME}", window.config.title(window.index));
+        let viewport_id = egui::ViewportId::from_hash_of(("detached", window.index));
+        let builder = egui::ViewportBuilder::default()
+            .with_title(title.clone())
+            .with_inner_size(DEFAULT_SIZE);
+        std::mem::swap(&mut cx.view, &mut view);
+        ctx.show_viewport_immediate(viewport_id, builder, |ctx, class| {
+            let mut show = |ui: &mut egui::Ui| {
+                window.content(ui, cx, false);
+                Self::cursor(ui, cx);
+            };
+            if class == egui::ViewportClass::Embedded {
+                // Multiple native windows are not supported (e.g., on the
+                // web), so fall back to a window inside the main one
+                let mut open = true;
+                egui::Window::new(title)
+                    .id(viewport_id.0)
+                    .open(&mut open)
+                    .default_size(DEFAULT_SIZE)
+                    .show(ctx, |ui| show(ui));
+                window.toggle_detached |= !open;
+            } else {
+                egui::CentralPanel::default().show(ctx, |ui| show(ui));
+                window.toggle_detached |= ctx.input(|i| i.viewport().close_requested());
+            }
+        });
+        std::mem::swap(&mut cx.view, &mut view);
+
+        let Some(detached) = &mut window.detached else {
+            unreachable!();
+        };
+        if !detached.independent {
+            cx.view.follow(&view);
+        }
+        detached.view = view;
+    }
+
     fn cursor(ui: &mut egui::Ui, cx: &mut Context) {
+        let hover_slot = cx.view.hover_slot.take();
+
         // Hack: the UI rect we have at this point is not where the
         // timeline is being drawn. So fish out the coordinates we
         // need to draw the correct rect.
 
         // Sometimes slot_rect is None when initializing the UI
-        if cx.slot_rect.is_none() {
+        if cx.view.slot_rect.is_none() {
             return;
         }
 
         let ui_rect = ui.min_rect();
-        let slot_rect = cx.slot_rect.unwrap();
+        let slot_rect = cx.view.slot_rect.unwrap();
         let rect = Rect::from_min_max(
             Pos2::new(slot_rect.min.x, ui_rect.min.y),
             Pos2::new(slot_rect.max.x, ui_rect.max.y),
@@ -2131,26 +10352,95 @@ impl ProfApp {
 
         let response = ui.allocate_rect(rect, egui::Sense::drag());
 
+        // Draw the playback cursor, if any
+        if let Some(position) = cx.playback.position {
+            if cx.view.interval.contains(position) {
+                let x = rect
+                    .lerp_inside(Vec2::new(cx.view.interval.unlerp(position), 0.0))
+                    .x;
+                let stroke = Stroke::new(2.0, ui.visuals().warn_fg_color);
+                ui.painter()
+                    .line_segment([Pos2::new(x, rect.min.y), Pos2::new(x, rect.max.y)], stroke);
+            }
+        }
+
+        // Show the last interval measured or zoomed to, if it's still
+        // relevant in this view
+        if let Some((selected, view)) = cx.selected_interval {
+            if view == cx.view.index
+                && selected != cx.view.interval
+                && selected.overlaps(cx.view.interval)
+            {
+                let min = rect.lerp_inside(Vec2::new(cx.view.interval.unlerp(selected.start), 0.0));
+                let max = rect.lerp_inside(Vec2::new(cx.view.interval.unlerp(selected.stop), 1.0));
+                let selected_rect = Rect::from_min_max(min, max).intersect(rect);
+                let color = ui.visuals().selection.bg_fill.linear_multiply(0.2);
+                let stroke = ui.visuals().selection.stroke;
+                ui.painter().rect(selected_rect, 0.0, color, Stroke::NONE);
+                for x in [selected_rect.left(), selected_rect.right()] {
+                    ui.painter()
+                        .line_segment([Pos2::new(x, rect.min.y), Pos2::new(x, rect.max.y)], stroke);
+                }
+            }
+        }
+
         // Handle drag detection
         let mut drag_interval = None;
 
-        let is_active_drag = response.dragged_by(egui::PointerButton::Primary);
-        if is_active_drag && response.drag_started() {
-            // On the beginning of a drag, save our position so we can
-            // calculate the delta
-            cx.drag_origin = response.interact_pointer_pos();
+        // The drag's buttons decide what it does
+        let bindings = cx.mouse_bindings;
+        let modifiers = ui.input(|i| i.modifiers);
+        if response.drag_started() {
+            cx.view.drag_action = [
+                (DragAction::Zoom, bindings.zoom),
+                (DragAction::Measure, bindings.measure),
+                (DragAction::Pan, bindings.pan),
+            ]
+            .into_iter()
+            .find(|(_, binding)| binding.drag_started(&response, modifiers))
+            .map(|(action, _)| action);
+            if cx.view.drag_action.is_some() {
+                // On the beginning of a drag, save our position so we can
+                // calculate the delta
+                cx.view.drag_origin = response.interact_pointer_pos();
+            }
         }
 
-        if let Some(origin) = cx.drag_origin {
+        // Measuring shows the dragged interval without zooming to it
+        let measure = match cx.view.drag_action {
+            Some(action) => action == DragAction::Measure,
+            None => {
+                bindings.measure.modifier != bindings.zoom.modifier
+                    && bindings.measure.modifier.is_held(modifiers)
+            }
+        };
+        let is_active_drag = response.dragged();
+
+        if cx.view.drag_action == Some(DragAction::Pan) {
+            // Move the view along with the pointer
+            if is_active_drag {
+                let delta = response.drag_delta().x / rect.width();
+                let duration = cx.view.interval.duration_ns() as f32;
+                let offset = (-delta * duration) as i64;
+                if offset != 0 {
+                    let interval = cx.view.interval.saturating_translate(offset);
+                    ProfApp::update_view_interval(cx, interval, IntervalOrigin::Pan);
+                    ProfApp::update_interval_select_state(cx);
+                }
+            } else {
+                cx.view.drag_origin = None;
+                cx.view.drag_action = None;
+            }
+        } else if let Some(origin) = cx.view.drag_origin {
             // We're in a drag, calculate the drag inetrval
             let current = response.interact_pointer_pos().unwrap();
             let min = origin.x.min(current.x);
             let max = origin.x.max(current.x);
 
             let start = (min - rect.left()) / rect.width();
-            let start = cx.view_interval.lerp(start);
+            let start = cx.view.interval.lerp(start);
             let stop = (max - rect.left()) / rect.width();
-            let stop = cx.view_interval.lerp(stop);
+            let stop = cx.view.interval.lerp(stop);
 
             let interval = Interval::new(start, stop);
 
@@ -2158,7 +10448,11 @@ impl ProfApp {
                 // Still in drag, draw a rectangle to show the dragged region
                 let drag_rect =
                     Rect::from_min_max(Pos2::new(min, rect.min.y), Pos2::new(max, rect.max.y));
-                let color = Color32::DARK_GRAY.linear_multiply(0.5);
+                let color = if measure {
+                    ui.visuals().selection.bg_fill.linear_multiply(0.5)
+                } else {
+                    Color32::DARK_GRAY.linear_multiply(0.5)
+                };
                 ui.painter().rect(drag_rect, 0.0, color, Stroke::NONE);
 
                 drag_interval = Some(interval);
@@ -2166,10 +10460,14 @@ impl ProfApp {
                 // Only set view interval if the drag was a certain amount
                 const MIN_DRAG_DISTANCE: f32 = 4.0;
                 if max - min > MIN_DRAG_DISTANCE {
-                    ProfApp::zoom(cx, interval);
+                    cx.selected_interval = Some((interval, cx.view.index));
+                    if !measure {
+                        ProfApp::zoom(cx, interval);
+                    }
                 }
 
-                cx.drag_origin = None;
+                cx.view.drag_origin = None;
+                cx.view.drag_action = None;
             }
         }
 
@@ -2191,18 +10489,47 @@ impl ProfApp {
 
             const HOVER_PADDING: f32 = 8.0;
             let time = (hover.x - rect.left()) / rect.width();
-            let time = cx.view_interval.lerp(time);
+            let time = cx.view.interval.lerp(time);
+
+            let start = cx.view.interval.start;
+            let prefix = if measure { "Measure: " } else { "" };
+            let label_text = match drag_interval {
+                Some(drag) if cx.relative_time => {
+                    let drag = drag.translate(-start.0);
+                    format!("{prefix}{} (relative)", cx.display_units.interval(drag))
+                }
+                Some(drag) => format!("{prefix}{}", cx.display_units.interval(drag)),
+                None if cx.relative_time => {
+                    // Offset from the start of the view
+                    let offset = Timestamp(time.0 - start.0);
+                    let units = cx
+                        .display_units
+                        .resolve(cx.view.interval.translate(-start.0).into());
+                    let time_units = TimestampDisplay {
+                        timestamp: offset,
+                        units,
+                        include_units: true,
+                    };
+                    format!("t=start+{time_units}")
+                }
+                None => {
+                    let units = cx.display_units.resolve(cx.view.interval.into());
+                    let time_units = TimestampDisplay {
+                        timestamp: time,
+                        units,
+                        include_units: true,
+                    };
+                    format!("t={time_units}")
+                }
+            };
 
-            let label_text = if let Some(drag) = drag_interval {
-                format!("{drag}")
-            } else {
-                let units: TimestampUnits = cx.view_interval.into();
-                let time_units = TimestampDisplay {
-                    timestamp: time,
-                    units,
-                    include_units: true,
-                };
-                format!("t={time_units}")
+            let label_text = match hover_slot {
+                Some((name, Some(row), Some(row_name))) => {
+                    format!("{label_text}\n{name}, row {row} ({row_name})")
+                }
+                Some((name, Some(row), None)) => format!("{label_text}\n{name}, row {row}"),
+                Some((name, None, _)) => format!("{label_text}\n{name}"),
+                None => label_text,
             };
 
             let label_size = {
@@ -2241,7 +10568,115 @@ impl ProfApp {
         }
     }
 
-    fn display_controls(ui: &mut egui::Ui, mode: &mut ItemLinkNavigationMode) {
+    fn interval_stats_window(ctx: &egui::Context, windows: &mut [Window], cx: &mut Context) {
+        // Exports go on even while the window is closed
+        #[cfg(not(target_arch = "wasm32"))]
+        for window in windows.iter_mut() {
+            window.poll_archive_export(cx);
+            if window.archive_export.is_some() {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+
+        let Some((interval, view)) = cx.selected_interval else {
+            return;
+        };
+
+        let mut enabled = true;
+        egui::Window::new("Selected Interval")
+            .open(&mut enabled)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(format!("{}", cx.display_units.interval(interval)));
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.horizontal(|ui| {
+                    ui.label("Archive Name:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut cx.archive_name)
+                            .hint_text(DEFAULT_ARCHIVE_NAME),
+                    )
+                    .on_hover_text(
+                        "Name of the archive written by Export Archive, in the export \
+                         directory (see Settings). A number is added if it exists already",
+                    );
+                });
+                if let Some(status) = &cx.export_status {
+                    ui.label(status);
+                }
+                for window in windows.iter_mut() {
+                    let stats = window.interval_stats(interval, view);
+                    ui.separator();
+                    ui.strong(window.config.title(window.index));
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(export) = &window.archive_export {
+                        let written = export.progress.written.load(Ordering::Relaxed);
+                        let total = export.progress.total.load(Ordering::Relaxed);
+                        let fraction = written as f32 / total.max(1) as f32;
+                        ui.add(
+                            egui::ProgressBar::new(fraction)
+                                .text(format!("Exporting archive: {written}/{total} tiles")),
+                        );
+                    } else {
+                        let ready = window.shareable_locator().is_some() && !stats.slots.is_empty();
+                        let button = ui
+                            .add_enabled(ready, egui::Button::new("Export Archive"))
+                            .on_hover_text(
+                                "Write the visible slots, cropped to this interval, \
+                                 to a new archive in the export directory",
+                            )
+                            .on_disabled_hover_text(
+                                "Requires at least one visible slot, and a profile \
+                                 opened from a single file or URL",
+                            );
+                        if button.clicked() {
+                            let name = cx.archive_name.trim();
+                            let name = if name.is_empty() {
+                                DEFAULT_ARCHIVE_NAME
+                            } else {
+                                name
+                            };
+                            let path = cx.export_dir.clone().unwrap_or_default().join(name);
+                            cx.export_status = None;
+                            window.export_archive(interval, path, &cx.sources);
+                        }
+                    }
+                    if !stats.complete {
+                        ui.label(
+                            RichText::new("Some tiles are not loaded. Results may be incomplete.")
+                                .color(Color32::RED),
+                        );
+                    }
+                    ui.label(format!(
+                        "Items: {} fully inside, {} partially inside",
+                        stats.full_items, stats.partial_items
+                    ));
+                    if stats.slots.is_empty() {
+                        continue;
+                    }
+                    egui::CollapsingHeader::new("Busy Fraction by Slot")
+                        .id_source(("interval_stats", window.index))
+                        .show(ui, |ui| {
+                            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                                egui::Grid::new(("interval_stats_grid", window.index))
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for (name, fraction) in &stats.slots {
+                                            ui.label(name);
+                                            ui.label(format!("{:.1}%", fraction * 100.0));
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        });
+                }
+            });
+        if !enabled {
+            cx.selected_interval = None;
+            cx.export_status = None;
+        }
+    }
+
+    fn display_controls(ui: &mut egui::Ui, cx: &mut Context) {
         fn show_row_ui(
             body: &mut egui_extras::TableBody<'_>,
             label: &str,
@@ -2261,12 +10696,49 @@ impl ProfApp {
             .column(Column::auto())
             .column(Column::remainder())
             .body(|mut body| {
+                let bindings = &mut cx.mouse_bindings;
+                let drag_buttons = [
+                    MouseButton::Primary,
+                    MouseButton::Secondary,
+                    MouseButton::Middle,
+                ];
+                show_row_ui(&mut body, "Zoom to Interval (Drag)", |ui: &mut _| {
+                    bindings.zoom.edit(ui, "zoom_binding", &drag_buttons);
+                });
+                show_row_ui(&mut body, "Measure Interval (Drag)", |ui: &mut _| {
+                    bindings.measure.edit(ui, "measure_binding", &drag_buttons);
+                });
+                show_row_ui(&mut body, "Pan (Drag)", |ui: &mut _| {
+                    bindings.pan.edit(ui, "pan_binding", &drag_buttons);
+                });
+                show_row_ui(&mut body, "Context Menu (Click)", |ui: &mut _| {
+                    let buttons = [MouseButton::Secondary, MouseButton::Middle];
+                    bindings
+                        .context_menu
+                        .edit(ui, "context_menu_binding", &buttons);
+                });
+                if bindings.has_conflicts() {
+                    show_row_ui(&mut body, "", |ui: &mut _| {
+                        ui.label(
+                            RichText::new(
+                                "Drags bound to the same buttons: only the first applies",
+                            )
+                            .color(ui.visuals().warn_fg_color),
+                        );
+                    });
+                }
+                show_row_ui(&mut body, "Reset Mouse Bindings", |ui: &mut _| {
+                    if ui.button("Reset").clicked() {
+                        *bindings = MouseBindings::default();
+                    }
+                });
+                let slot_actions = format!("{} on Slot Label", bindings.context_menu.label_text());
+
                 let mut show_row = |a, b| {
                     show_row_ui(&mut body, a, |ui| {
                         ui.label(b);
                     });
                 };
-                show_row("Zoom to Interval", "Click and Drag");
                 show_row("Pan 5%", "Left/Right Arrow");
                 show_row("Pan 1%", "Shift + Left/Right Arrow");
                 show_row("Vertical Scroll", "Up/Down Arrow");
@@ -2279,8 +10751,24 @@ impl ProfApp {
                 show_row("Expand Vertical Spacing", "Ctrl + Alt + Plus/Equals");
                 show_row("Shrink Vertical Spacing", "Ctrl + Alt + Minus");
                 show_row("Reset Vertical Spacing", "Ctrl + Alt + 0");
+                show_row("Expand All", "E");
+                show_row("Collapse All", "C");
+                show_row("Expand All Nodes", "Shift + E");
+                show_row("Collapse All Nodes", "Shift + C");
+                show_row("Toggle Split View", "S");
+                show_row("Toggle Magnifier", "M");
+                show_row("Play/Pause Playback", "Space");
+                show_row("Slot Actions (e.g., Idle Gaps)", &slot_actions);
+                show_row("Focus Next/Previous Slot", "Tab / Shift + Tab");
+                show_row("Select Item in Focused Slot", "Arrow Keys");
+                show_row("Show Details of Selected Item", "Enter");
+                show_row("Leave Focused Slot", "Esc");
+                show_row("Clear Selection", "Esc");
+                show_row("Clear Selection Only", "Shift + Esc");
+                show_row("Clear Search Only", "Ctrl + Esc");
                 show_row("Toggle This Window", "H");
                 show_row_ui(&mut body, "Item Link Zoom or Pan", |ui: &mut _| {
+                    let mode = &mut cx.item_link_mode;
                     egui::ComboBox::from_id_source("Item Link Zoom or Pan")
                         .selected_text(format!("{:?}", mode))
                         .show_ui(ui, |ui| {
@@ -2288,6 +10776,209 @@ impl ProfApp {
                             ui.selectable_value(mode, ItemLinkNavigationMode::Pan, "Pan");
                         });
                 });
+                show_row_ui(&mut body, "Collapsed Slot Sparklines", |ui: &mut _| {
+                    ui.checkbox(&mut cx.collapsed_sparklines, "");
+                });
+                show_row_ui(&mut body, "Fit Slot Rows to View", |ui: &mut _| {
+                    ui.checkbox(&mut cx.fit_rows, "");
+                });
+                show_row_ui(&mut body, "Stable Row Layout", |ui: &mut _| {
+                    ui.checkbox(&mut cx.stable_rows, "").on_hover_text(
+                        "Pack items into rows in the viewer instead of using the rows \
+                         from the data source, so that items don't jump between rows \
+                         when zooming or panning",
+                    );
+                });
+                show_row_ui(&mut body, "Row Guides in Slots", |ui: &mut _| {
+                    let guides = &mut cx.row_guides;
+                    egui::ComboBox::from_id_source("Row Guides in Slots")
+                        .selected_text(guides.label_text())
+                        .show_ui(ui, |ui| {
+                            for value in
+                                [RowGuides::None, RowGuides::Separators, RowGuides::Stripes]
+                            {
+                                ui.selectable_value(guides, value, value.label_text());
+                            }
+                        });
+                });
+                show_row_ui(&mut body, "Heatmap Shading", |ui: &mut _| {
+                    let metric = &mut cx.heatmap_metric;
+                    egui::ComboBox::from_id_source("Heatmap Shading")
+                        .selected_text(metric.label_text())
+                        .show_ui(ui, |ui| {
+                            for value in [HeatmapMetric::Count, HeatmapMetric::BusyTime] {
+                                ui.selectable_value(metric, value, value.label_text());
+                            }
+                        });
+                });
+                show_row_ui(&mut body, "Max Rows per Slot", |ui: &mut _| {
+                    ui.checkbox(&mut cx.cap_slot_rows, "");
+                    ui.add_enabled(
+                        cx.cap_slot_rows,
+                        egui::DragValue::new(&mut cx.max_slot_rows)
+                            .clamp_range(MAX_SLOT_ROWS_RANGE),
+                    );
+                });
+                show_row_ui(&mut body, "Esc Also Clears Search", |ui: &mut _| {
+                    ui.checkbox(&mut cx.escape_clears_search, "");
+                });
+                show_row_ui(&mut body, "Dim Stale Tiles While Loading", |ui: &mut _| {
+                    ui.checkbox(&mut cx.dim_stale_tiles, "");
+                });
+                show_row_ui(&mut body, "Rasterize Dense Tiles", |ui: &mut _| {
+                    ui.checkbox(&mut cx.rasterize_dense_tiles, "")
+                        .on_hover_text(
+                            "Draw tiles with many items from images, for smoother panning",
+                        );
+                });
+                show_row_ui(&mut body, "Full Tiles (No Sampling)", |ui: &mut _| {
+                    ui.checkbox(&mut cx.full_tiles, "").on_hover_text(
+                        "Load every item in the view, including ones the profile would \
+                         otherwise sample or merge because they are too short to see. \
+                         Full tiles can be many times larger, so zoom in first on big \
+                         profiles",
+                    );
+                });
+                show_row_ui(&mut body, "Save Power While Unfocused", |ui: &mut _| {
+                    ui.checkbox(&mut cx.power_saving, "")
+                        .on_hover_text("Redraw less often while the window is in the background");
+                });
+                show_row_ui(
+                    &mut body,
+                    "Cursor Time Relative to View Start",
+                    |ui: &mut _| {
+                        ui.checkbox(&mut cx.relative_time, "");
+                    },
+                );
+                show_row_ui(&mut body, "Time Units", |ui: &mut _| {
+                    let units = &mut cx.display_units;
+                    egui::ComboBox::from_id_source("Time Units")
+                        .selected_text(units.to_string())
+                        .show_ui(ui, |ui| {
+                            for value in [
+                                DisplayUnits::Auto,
+                                DisplayUnits::Nanoseconds,
+                                DisplayUnits::Microseconds,
+                                DisplayUnits::Milliseconds,
+                                DisplayUnits::Seconds,
+                                DisplayUnits::SecondsFixed(6),
+                                DisplayUnits::SecondsFixed(9),
+                            ] {
+                                ui.selectable_value(units, value, value.to_string());
+                            }
+                        });
+                });
+                // Applied on the next frame, which would move a slider out
+                // from under the mouse, so offer fixed choices instead
+                show_row_ui(&mut body, "UI Scale", |ui: &mut _| {
+                    let scale = &mut cx.ui_scale;
+                    egui::ComboBox::from_id_source("UI Scale")
+                        .selected_text(format!("{:.0}%", *scale * 100.0))
+                        .show_ui(ui, |ui| {
+                            for value in UI_SCALE_PRESETS {
+                                ui.selectable_value(scale, value, format!("{:.0}%", value * 100.0));
+                            }
+                        });
+                });
+                show_row_ui(&mut body, "Font Size", |ui: &mut _| {
+                    ui.add(
+                        egui::Slider::new(&mut cx.font_scale, FONT_SCALE_RANGE)
+                            .step_by(0.05)
+                            .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                            .custom_parser(|text| {
+                                let text = text.trim().trim_end_matches('%');
+                                text.parse::<f64>().ok().map(|value| value / 100.0)
+                            }),
+                    );
+                    if ui.button("Reset").clicked() {
+                        cx.font_scale = 1.0;
+                    }
+                });
+                show_row_ui(&mut body, "Max Concurrent Requests", |ui: &mut _| {
+                    ui.add(
+                        egui::Slider::new(&mut cx.max_requests, MAX_REQUESTS_RANGE)
+                            .logarithmic(true),
+                    );
+                });
+                show_row_ui(&mut body, "Memory Budget per Profile", |ui: &mut _| {
+                    ui.add(
+                        egui::Slider::new(&mut cx.memory_budget, MEMORY_BUDGET_RANGE)
+                            .logarithmic(true)
+                            .suffix(" MiB"),
+                    );
+                });
+                // Browsers verify servers themselves
+                #[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+                {
+                    show_row_ui(&mut body, "Trusted CA Bundle", |ui: &mut _| {
+                        let mut path = cx
+                            .sources
+                            .tls
+                            .ca_bundle
+                            .as_ref()
+                            .map_or_else(String::new, |path| path.display().to_string());
+                        let response = ui
+                            .add(egui::TextEdit::singleline(&mut path).hint_text("PEM file"))
+                            .on_hover_text(
+                                "Extra certificates to trust, e.g., for servers with \
+                                 self-signed certificates. Applies to profiles opened from \
+                                 now on.",
+                            );
+                        if response.changed() {
+                            let path = path.trim();
+                            cx.sources.tls.ca_bundle = (!path.is_empty()).then(|| path.into());
+                        }
+                    });
+                    show_row_ui(&mut body, "Skip Certificate Verification", |ui: &mut _| {
+                        ui.checkbox(&mut cx.sources.tls.accept_invalid_certs, "")
+                            .on_hover_text(
+                                "Accept any certificate, without checking who the server is. \
+                                 Applies to profiles opened from now on, until the viewer \
+                                 restarts.",
+                            );
+                        if cx.sources.tls.accept_invalid_certs {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "⚠ Insecure: only use on trusted networks",
+                            );
+                        }
+                    });
+                    show_row_ui(&mut body, "Cache Directory", |ui: &mut _| {
+                        let mut path = cx
+                            .sources
+                            .cache_dir
+                            .as_ref()
+                            .map_or_else(String::new, |path| path.display().to_string());
+                        let response = ui
+                            .add(egui::TextEdit::singleline(&mut path).hint_text("None"))
+                            .on_hover_text(
+                                "Keep everything fetched from servers here, so that profiles \
+                                 can be reopened without refetching them, even when the \
+                                 server is no longer running. Applies to profiles opened \
+                                 from now on.",
+                            );
+                        if response.changed() {
+                            let path = path.trim();
+                            cx.sources.cache_dir = (!path.is_empty()).then(|| path.into());
+                        }
+                    });
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                show_row_ui(&mut body, "Export Directory", |ui: &mut _| {
+                    let mut path = cx
+                        .export_dir
+                        .as_ref()
+                        .map_or_else(String::new, |path| path.display().to_string());
+                    let response = ui
+                        .add(egui::TextEdit::singleline(&mut path).hint_text("Current directory"))
+                        .on_hover_text(
+                            "Where exported archives, items and utilization are written",
+                        );
+                    if response.changed() {
+                        let path = path.trim();
+                        cx.export_dir = (!path.is_empty()).then(|| path.into());
+                    }
+                });
             });
     }
 
@@ -2304,31 +10995,53 @@ impl ProfApp {
 
     fn render_field_as_text(
         field: &Field,
+        format: Option<FieldFormat>,
         mode: ItemLinkNavigationMode,
+        units: DisplayUnits,
     ) -> Vec<(String, Option<&'static str>)> {
+        if let Some(value) = format.and_then(|f| format_field_value(field, f, units)) {
+            return vec![(value, None)];
+        }
         match field {
             Field::I64(value) => vec![(format!("{value}"), None)],
             Field::U64(value) => vec![(format!("{value}"), None)],
             Field::String(value) => vec![(value.to_string(), None)],
-            Field::Interval(value) => vec![(format!("{value}"), None)],
+            Field::Interval(value) => vec![(format!("{}", units.interval(*value)), None)],
             Field::ItemLink(ItemLink { title, .. }) => {
                 vec![(title.to_string(), Some(mode.label_text()))]
             }
             Field::Vec(fields) => fields
                 .iter()
-                .flat_map(|f| Self::render_field_as_text(f, mode))
+                .flat_map(|f| Self::render_field_as_text(f, format, mode, units))
                 .collect(),
             Field::Empty => vec![("".to_string(), None)],
+            Field::Markdown(value) => vec![(value.to_string(), None)],
+            Field::Timestamp(value) => {
+                vec![(units.timestamp(*value).to_string(), Some("Go to time"))]
+            }
+            Field::Group(_, fields) => fields
+                .iter()
+                .flat_map(|(_, f)| Self::render_field_as_text(f, None, mode, units))
+                .collect(),
         }
     }
 
     fn compute_field_height(
         field: &Field,
+        format: Option<FieldFormat>,
         width: f32,
         mode: ItemLinkNavigationMode,
+        units: DisplayUnits,
         ui: &mut egui::Ui,
     ) -> f32 {
-        let text = Self::render_field_as_text(field, mode);
+        if let Field::Markdown(value) = field {
+            let mut job = markdown_layout_job(value, ui.style(), None);
+            job.wrap.max_width = width;
+            let galley = ui.fonts(|f| f.layout_job(job));
+            return galley.size().y + ui.spacing().item_spacing.y * 2.0;
+        }
+
+        let text = Self::render_field_as_text(field, format, mode, units);
         text.into_iter()
             .map(|(mut v, b)| {
                 // Hack: if we have button text, guess how much space it will need
@@ -2344,10 +11057,12 @@ impl ProfApp {
 
     fn render_field_as_ui(
         field: &Field,
+        format: Option<FieldFormat>,
         color: Option<Color32>,
         mode: ItemLinkNavigationMode,
+        units: DisplayUnits,
         ui: &mut egui::Ui,
-    ) -> Option<(ItemLocator, Interval)> {
+    ) -> Option<FieldTarget> {
         let mut result = None;
         let label = |ui: &mut egui::Ui, v| {
             if let Some(color) = color {
@@ -2364,23 +11079,34 @@ impl ProfApp {
                 ui.button(b).clicked()
             }
         };
+        if let Some(value) = format.and_then(|f| format_field_value(field, f, units)) {
+            label(ui, &value);
+            return None;
+        }
         match field {
             Field::I64(value) => label(ui, &format!("{value}")),
             Field::U64(value) => label(ui, &format!("{value}")),
             Field::String(value) => label(ui, value),
-            Field::Interval(value) => label(ui, &format!("{value}")),
+            Field::Interval(value) => label(ui, &format!("{}", units.interval(*value))),
             Field::ItemLink(ItemLink {
                 title,
                 item_uid,
                 interval,
                 entry_id,
+                profile,
             }) => {
-                if label_button(ui, title, mode.label_text()) {
-                    result = Some((
+                let clicked = label_button(ui, title, mode.label_text());
+                if let Some(profile) = profile {
+                    ui.label("↗")
+                        .on_hover_text(format!("In profile {}", profile.join(" ")));
+                }
+                if clicked {
+                    result = Some(FieldTarget::Item(
                         ItemLocator {
                             entry_id: entry_id.clone(),
                             irow: None,
                             item_uid: *item_uid,
+                            profile: profile.clone(),
                         },
                         *interval,
                     ));
@@ -2390,7 +11116,9 @@ impl ProfApp {
                 ui.vertical(|ui| {
                     for f in fields {
                         ui.horizontal(|ui| {
-                            if let Some(x) = Self::render_field_as_ui(f, color, mode, ui) {
+                            if let Some(x) =
+                                Self::render_field_as_ui(f, format, color, mode, units, ui)
+                            {
                                 result = Some(x);
                             }
                         });
@@ -2398,100 +11126,551 @@ impl ProfApp {
                 });
             }
             Field::Empty => {}
+            Field::Timestamp(value) => {
+                let text = units.timestamp(*value).to_string();
+                if label_button(ui, &text, "Go to time") {
+                    result = Some(FieldTarget::Time(*value));
+                }
+            }
+            Field::Markdown(value) => {
+                let job = markdown_layout_job(value, ui.style(), color);
+                ui.add(egui::Label::new(job).wrap(true));
+            }
+            // Groups are normally shown as sections by display_fields, this
+            // only happens for groups nested in other fields
+            Field::Group(_, fields) => {
+                ui.vertical(|ui| {
+                    for (_, f) in fields {
+                        ui.horizontal(|ui| {
+                            if let Some(x) =
+                                Self::render_field_as_ui(f, None, color, mode, units, ui)
+                            {
+                                result = Some(x);
+                            }
+                        });
+                    }
+                });
+            }
+        }
+        result
+    }
+
+    fn navigate_to_item(
+        cx: &mut Context,
+        window: &mut Window,
+        item_loc: ItemLocator,
+        interval: Interval,
+    ) {
+        let interval = match cx.item_link_mode {
+            // In Zoom mode, put the item in the center of the view
+            // interval with a small amount of padding on either side.
+            ItemLinkNavigationMode::Zoom => interval.saturating_grow(interval.duration_ns() / 20),
+            // In Pan mode, maintain the current window size but shift
+            // the center to place the item in the middle of it.
+            ItemLinkNavigationMode::Pan => cx
+                .view
+                .interval
+                .saturating_translate(interval.center().0 - cx.view.interval.center().0),
+        };
+        ProfApp::zoom(cx, interval);
+        window.expand_slot(&item_loc.entry_id);
+        window.config.scroll_to_item(item_loc);
+    }
+
+    fn breadcrumb(ui: &mut egui::Ui, path: &[(EntryID, String, String)]) -> Option<EntryID> {
+        let mut result = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            for (i, (entry_id, short_name, long_name)) in path.iter().enumerate() {
+                if i > 0 {
+                    ui.label("→");
+                }
+                if ui
+                    .link(long_name)
+                    .on_hover_text(format!("Scroll to {short_name}"))
+                    .clicked()
+                {
+                    result = Some(entry_id.clone());
+                }
+            }
+        });
+        result
+    }
+
+    fn display_fields(
+        ui: &mut egui::Ui,
+        rows: Vec<FieldRow<'_>>,
+        field_schema: &FieldSchema,
+        cx: &Context,
+    ) -> Option<FieldTarget> {
+        // Groups go in collapsible sections after the ungrouped fields
+        let (groups, rows): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .partition(|(_, field, _, _)| matches!(field, Field::Group(..)));
+
+        let font_id = TextStyle::Body.resolve(ui.style());
+        let row_height = ui.fonts(|f| f.row_height(&font_id));
+
+        let mut result: Option<FieldTarget> = None;
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::remainder())
+            .body(|mut body| {
+                let mut show_row =
+                    |k: &str,
+                     field: &Field,
+                     color: Option<Color32>,
+                     format: Option<FieldFormat>| {
+                        // We need to manually work out the height of the labels
+                        // so that the table knows how large to make each row.
+                        let width = body.widths()[1];
+
+                        let ui = body.ui_mut();
+                        let height = Self::compute_field_height(
+                            field,
+                            format,
+                            width,
+                            cx.item_link_mode,
+                            cx.display_units,
+                            ui,
+                        )
+                        .max(row_height);
+
+                        body.row(height, |mut row| {
+                            row.col(|ui| {
+                                if let Some(color) = color {
+                                    ui.label(RichText::new(k).color(color).strong());
+                                } else {
+                                    ui.strong(k);
+                                }
+                            });
+                            row.col(|ui| {
+                                if let Some(x) = Self::render_field_as_ui(
+                                    field,
+                                    format,
+                                    color,
+                                    cx.item_link_mode,
+                                    cx.display_units,
+                                    ui,
+                                ) {
+                                    result = Some(x);
+                                }
+                            });
+                        });
+                    };
+
+                for (k, field, color, format) in rows {
+                    show_row(k, field, color, format);
+                }
+            });
+
+        for (_, field, color, _) in groups {
+            let Field::Group(name, fields) = field else {
+                unreachable!();
+            };
+            let rows = fields
+                .iter()
+                .map(|(field_id, field)| {
+                    let name = field_schema.get_name(*field_id).unwrap();
+                    (name, field, color, field_schema.get_format(*field_id))
+                })
+                .collect();
+            egui::CollapsingHeader::new(name).show(ui, |ui| {
+                if let Some(x) = Self::display_fields(ui, rows, field_schema, cx) {
+                    result = Some(x);
+                }
+            });
         }
         result
     }
 
     fn display_item_details(
         ui: &mut egui::Ui,
-        item: &ItemDetail,
+        item: &mut ItemDetail,
         field_schema: &FieldSchema,
-        cx: &Context,
-    ) -> Option<(ItemLocator, Interval)> {
-        let Some(ref item_meta) = item.meta else {
+        watched_items: &mut BTreeMap<ItemUID, WatchedItem>,
+        origin_trace: &mut Option<OriginTrace>,
+        cx: &mut Context,
+    ) -> Option<FieldTarget> {
+        let Some(item_meta) = item.meta.as_ref() else {
             ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
                 ui.label("Item will be displayed once data is available.");
             });
             return None;
         };
 
-        let font_id = TextStyle::Body.resolve(ui.style());
-        let row_height = ui.fonts(|f| f.row_height(&font_id));
+        let title = Field::String(item_meta.title.to_string());
+        let item_uid = Field::U64(item_meta.item_uid.0);
+        let mut rows = vec![("Title", &title, None, None)];
+        if cx.debug {
+            rows.push(("Item UID", &item_uid, None, None));
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut item.field_filter)
+                    .hint_text("Filter fields")
+                    .desired_width(120.0),
+            );
+            let order = &mut cx.field_sort_order;
+            egui::ComboBox::from_id_source(("field_sort_order", item.loc.item_uid.0))
+                .selected_text(order.label_text())
+                .show_ui(ui, |ui| {
+                    for value in [
+                        FieldSortOrder::Item,
+                        FieldSortOrder::Schema,
+                        FieldSortOrder::Alphabetical,
+                    ] {
+                        ui.selectable_value(order, value, value.label_text());
+                    }
+                });
+        });
 
-        let mut result: Option<(ItemLocator, Interval)> = None;
-        TableBuilder::new(ui)
-            .striped(true)
-            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-            .column(Column::auto())
-            .column(Column::remainder())
-            .body(|mut body| {
-                let mut show_row = |k: &str, field: &Field, color: Option<Color32>| {
-                    // We need to manually work out the height of the labels
-                    // so that the table knows how large to make each row.
-                    let width = body.widths()[1];
+        let filter = item.field_filter.to_lowercase();
+        let matches_filter = |name: &str, field: &Field| {
+            filter.is_empty()
+                || name.to_lowercase().contains(&filter)
+                || field.to_string().to_lowercase().contains(&filter)
+        };
+        let mut fields: Vec<_> = item_meta
+            .fields
+            .iter()
+            .map(|(field_id, field, color)| {
+                let name = field_schema.get_name(*field_id).unwrap();
+                (*field_id, name, field, *color)
+            })
+            .filter(|(_, name, field, _)| matches_filter(name, field))
+            .collect();
+        match cx.field_sort_order {
+            FieldSortOrder::Item => {}
+            FieldSortOrder::Schema => fields.sort_by_key(|(field_id, ..)| *field_id),
+            FieldSortOrder::Alphabetical => {
+                fields.sort_by_cached_key(|(_, name, ..)| name.to_lowercase())
+            }
+        }
+        for (field_id, name, field, color) in fields {
+            rows.push((name, field, color, field_schema.get_format(field_id)));
+        }
 
-                    let ui = body.ui_mut();
-                    let height = Self::compute_field_height(field, width, cx.item_link_mode, ui)
-                        .max(row_height);
+        let mut result = Self::display_fields(ui, rows, field_schema, cx);
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            if ui.button(cx.item_link_mode.label_text()).clicked() {
+                result = Some(FieldTarget::Item(
+                    item.loc.clone(),
+                    item_meta.original_interval,
+                ));
+            }
+            let mut watched = watched_items.contains_key(&item_meta.item_uid);
+            if ui.toggle_value(&mut watched, "★ Watch").changed() {
+                if watched {
+                    watched_items.insert(
+                        item_meta.item_uid,
+                        WatchedItem {
+                            entry_id: item.loc.entry_id.clone(),
+                            irow: item.loc.irow,
+                            title: item_meta.title.clone(),
+                            interval: item_meta.original_interval,
+                        },
+                    );
+                } else {
+                    watched_items.remove(&item_meta.item_uid);
+                }
+            }
 
-                    body.row(height, |mut row| {
-                        row.col(|ui| {
-                            if let Some(color) = color {
-                                ui.label(RichText::new(k).color(color).strong());
-                            } else {
-                                ui.strong(k);
-                            }
-                        });
-                        row.col(|ui| {
-                            if let Some(x) =
-                                Self::render_field_as_ui(field, color, cx.item_link_mode, ui)
-                            {
-                                result = Some(x);
+            // Link fields the item's origin can be traced through. Default
+            // to the one traced last
+            let links: Vec<_> = item_meta
+                .fields
+                .iter()
+                .filter(|(_, field, _)| matches!(field, Field::ItemLink(_)))
+                .filter_map(|(field_id, ..)| Some((*field_id, field_schema.get_name(*field_id)?)))
+                .collect();
+            if links.is_empty() {
+                return;
+            }
+            let (mut field_id, mut name) = *links
+                .iter()
+                .find(|(_, name)| *name == cx.trace_field)
+                .unwrap_or(&links[0]);
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source(("trace_field", item.loc.item_uid.0))
+                    .selected_text(name)
+                    .show_ui(ui, |ui| {
+                        for (id, n) in &links {
+                            if ui.selectable_label(*id == field_id, *n).clicked() {
+                                (field_id, name) = (*id, n);
+                                cx.trace_field = n.to_string();
                             }
-                        });
+                        }
                     });
-                };
+                if ui
+                    .button("Trace to Origin")
+                    .on_hover_text("Follow this link from item to item, as far back as it goes")
+                    .clicked()
+                {
+                    cx.trace_field = name.to_owned();
+                    *origin_trace = Some(OriginTrace::new(
+                        field_id,
+                        name,
+                        item.loc.clone(),
+                        item_meta,
+                    ));
+                }
+            });
+        });
+        result
+    }
+}
 
-                show_row("Title", &Field::String(item_meta.title.to_string()), None);
-                if cx.debug {
-                    show_row("Item UID", &Field::U64(item_meta.item_uid.0), None);
+impl ProfApp {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_open_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.open_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut submitted = false;
+        let mut cancelled = false;
+        egui::Window::new(dialog.title())
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let hint = match dialog.target {
+                    OpenTarget::File => "Path of a profile archive (or a tar file of one)",
+                    #[cfg(feature = "client")]
+                    OpenTarget::Url => "http://host:port/",
+                    OpenTarget::Link => "legion-prof:url=… (or a link to the web viewer)",
+                };
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut dialog.input)
+                        .hint_text(hint)
+                        .desired_width(400.0),
+                );
+                if response.changed() {
+                    dialog.error = None;
                 }
-                for (field_id, field, color) in &item_meta.fields {
-                    let name = field_schema.get_name(*field_id).unwrap();
-                    show_row(name, field, *color);
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    submitted = true;
                 }
+                if dialog.target == OpenTarget::File {
+                    dialog.browse(ui);
+                }
+                if let Some(error) = &dialog.error {
+                    ui.label(RichText::new(error).color(ui.visuals().error_fg_color));
+                }
+                ui.horizontal(|ui| {
+                    submitted |= ui.button("Open").clicked();
+                    cancelled = ui.button("Cancel").clicked();
+                });
             });
-        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
-            if ui.button(cx.item_link_mode.label_text()).clicked() {
-                result = Some((item.loc.clone(), item_meta.original_interval));
+
+        if submitted {
+            let result = dialog.validate().and_then(|input| match dialog.target {
+                OpenTarget::Link => Self::open_view_link(
+                    ViewLink::parse_token(&input)?,
+                    &mut self.pending_data_sources,
+                    Some(&mut self.recent_sources),
+                    &mut self.pending_view,
+                    self.windows.len(),
+                    &self.cx,
+                ),
+                _ => Self::open_locator(
+                    &input,
+                    &mut self.pending_data_sources,
+                    Some(&mut self.recent_sources),
+                    &self.cx,
+                ),
+            });
+            match result {
+                Ok(()) => open = false,
+                Err(e) => dialog.error = Some(e),
             }
-        });
-        result
+        }
+        if !open || cancelled {
+            self.open_dialog = None;
+        }
     }
 }
 
 impl eframe::App for ProfApp {
     /// Called to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        for window in &self.windows {
+            self.saved_configs
+                .insert(window.config.profile_key(), window.save_config());
+        }
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
     /// Called each time the UI needs repainting.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        Self::apply_ui_scale(ctx, &mut self.cx);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("Open File…").clicked() {
+                        self.open_dialog = Some(OpenDialog::new(OpenTarget::File));
+                        ui.close_menu();
+                    }
+                    #[cfg(feature = "client")]
+                    if ui.button("Open URL…").clicked() {
+                        self.open_dialog = Some(OpenDialog::new(OpenTarget::Url));
+                        ui.close_menu();
+                    }
+                    if ui.button("Open View Link…").clicked() {
+                        self.open_dialog = Some(OpenDialog::new(OpenTarget::Link));
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Open Recent", |ui| {
+                        if self.recent_sources.is_empty() {
+                            ui.add_enabled(false, egui::Label::new("No recent profiles"));
+                        }
+                        let mut opened = None;
+                        for locator in &self.recent_sources {
+                            if ui.button(locator).clicked() {
+                                opened = Some(locator.clone());
+                                ui.close_menu();
+                            }
+                        }
+                        if let Some(locator) = opened {
+                            if let Err(e) = Self::open_locator(
+                                &locator,
+                                &mut self.pending_data_sources,
+                                Some(&mut self.recent_sources),
+                                &self.cx,
+                            ) {
+                                log::warn!("unable to open {}: {}", locator, e);
+                            }
+                        }
+                        if !self.recent_sources.is_empty() {
+                            ui.separator();
+                            if ui.button("Clear Recent").clicked() {
+                                self.recent_sources.clear();
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.separator();
+                    if ui.button("Quit").clicked() {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+            });
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.show_open_dialog(ctx);
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none())
+            .show(ctx, |ui| self.update_ui(ui));
+
+        // Keep the native window title in sync with the profile names
+        let windows = &self.windows;
+        if !windows.is_empty() {
+            let titles = windows.iter().map(|w| w.config.title(w.index)).join(", ");
+            let title = format!("{titles} - {APP_NAME}");
+            if self.cx.native_title != title {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+                self.cx.native_title = title;
+            }
+        }
+    }
+}
+
+impl ProfApp {
+    /// Show the viewer in the given Ui. Item details and other auxiliary
+    /// windows float above the rest of the application.
+    pub fn update_ui(&mut self, ui: &mut egui::Ui) {
+        if !self.cx.embedded {
+            self.show(ui);
+            return;
+        }
+
+        let rect = ui.max_rect();
+        if let Some(pos) = ui.input(|i| i.pointer.any_pressed().then(|| i.pointer.interact_pos())) {
+            self.cx.embedded_focus = pos.is_some_and(|pos| rect.contains(pos));
+        }
+        ui.scope(|ui| {
+            Self::apply_style(ui.style_mut(), &self.cx);
+            self.show(ui);
+        });
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui) {
+        let ctx = &ui.ctx().clone();
         let Self {
             pending_data_sources,
+            #[cfg(target_arch = "wasm32")]
+            picked_directories,
             windows,
             cx,
+            saved_configs,
+            pending_view,
             #[cfg(not(target_arch = "wasm32"))]
             last_update,
+            control,
+            subscribers,
+            event_state,
             ..
         } = self;
 
+        cx.frame += 1;
+        cx.frame_time = ui.input(|i| i.time);
+
+        if pending_data_sources.is_empty() {
+            if let Some((link, first)) = pending_view.take() {
+                Self::apply_view_link(&link, first, windows, cx);
+            }
+        }
+
+        // Hold commands until every profile has loaded, so that they apply
+        // to all of them (and aren't undone by the initial zoom to fit), but
+        // not indefinitely, in case a profile never does
+        if let Some(control) = control {
+            let ready = if pending_data_sources.is_empty() {
+                cx.commands_held_since = None;
+                true
+            } else {
+                let now = ui.input(|i| i.time);
+                now - *cx.commands_held_since.get_or_insert(now) >= COMMAND_HOLD_SECONDS
+            };
+            if ready {
+                Self::process_commands(
+                    control,
+                    pending_data_sources,
+                    pending_view,
+                    windows,
+                    cx,
+                    subscribers,
+                );
+            }
+        }
+
+        Self::open_dropped_files(ctx, pending_data_sources, cx);
+        #[cfg(target_arch = "wasm32")]
+        for mut data_source in picked_directories.borrow_mut().drain(..) {
+            data_source.fetch_info();
+            pending_data_sources.push_back(Box::new(data_source));
+        }
+
         if let Some(mut source) = pending_data_sources.pop_front() {
             // We made one request, so we know there is always zero or one
             // elements in this list.
             if let Some(info) = source.get_infos().pop() {
-                let window = Window::new(source, info, windows.len() as u64);
+                let mut window = Window::new(source, info, windows.len() as u64);
+                let waker_ctx = ui.ctx().clone();
+                window.config.data_source.set_waker(Arc::new(move |delay| {
+                    waker_ctx.request_repaint_after(delay)
+                }));
+                if let Some(state) = saved_configs.get(&window.config.profile_key()) {
+                    window.load_config(state);
+                }
                 if windows.is_empty() {
                     cx.total_interval = window.config.interval;
                 } else {
@@ -2499,16 +11678,62 @@ impl eframe::App for ProfApp {
                 }
                 ProfApp::zoom(cx, cx.total_interval);
                 windows.push(window);
+            } else if let Some(failure) = source
+                .get_failures()
+                .into_iter()
+                .find(|failure| failure.kind == RequestKind::Info)
+            {
+                // Don't wait for a profile that will never load
+                log::warn!(
+                    "unable to load {}: {}",
+                    source.fetch_description().source_locator.join(", "),
+                    failure.error
+                );
             } else {
                 pending_data_sources.push_front(source);
             }
         }
 
+        let now = ui.input(|i| i.time);
         for window in windows.iter_mut() {
+            window
+                .config
+                .data_source
+                .set_max_in_flight(Some(cx.max_requests));
+            window.config.data_source.set_time(now);
+            window.update_active_entries();
+            window.set_full_tiles(cx.full_tiles);
+            window
+                .config
+                .set_view_width(cx.view.slot_rect.map_or(0.0, |rect| rect.width()));
+
+            window.config.data_source.fetch_updates();
+            for update in window.config.data_source.get_updates() {
+                match update {
+                    DataSourceUpdate::Info => window.config.data_source.fetch_info(),
+                    DataSourceUpdate::Tiles { entry_id, interval } => {
+                        window.invalidate_tiles(&entry_id, interval)
+                    }
+                    DataSourceUpdate::Items { tile, meta } => {
+                        if let Some(slot) = window.find_slot_mut(&tile.entry_id) {
+                            slot.merge_items(tile, meta);
+                        }
+                    }
+                }
+            }
+            for info in window.config.data_source.get_infos() {
+                window.reload(info);
+                cx.total_interval = cx.total_interval.union(window.config.interval);
+            }
+
             for tile in window.config.data_source.get_summary_tiles() {
+                if window.discard_stale(RequestKind::SummaryTile, &tile.entry_id, tile.tile_id) {
+                    continue;
+                }
                 if let Some(entry) = window.find_summary_mut(&tile.entry_id) {
                     // If the entry doesn't exist, we already zoomed away and
                     // are no longer interested in this tile.
+                    entry.memory = None;
                     entry
                         .tiles
                         .entry(tile.tile_id)
@@ -2517,9 +11742,13 @@ impl eframe::App for ProfApp {
             }
 
             for tile in window.config.data_source.get_slot_tiles() {
+                if window.discard_stale(RequestKind::SlotTile, &tile.entry_id, tile.tile_id) {
+                    continue;
+                }
                 if let Some(entry) = window.find_slot_mut(&tile.entry_id) {
                     // If the entry doesn't exist, we already zoomed away and
                     // are no longer interested in this tile.
+                    entry.memory = None;
                     entry
                         .tiles
                         .entry(tile.tile_id)
@@ -2528,15 +11757,50 @@ impl eframe::App for ProfApp {
             }
 
             for tile in window.config.data_source.get_slot_meta_tiles() {
+                let breakdown = window.config.time_breakdown.as_mut();
+                if breakdown.is_some_and(|breakdown| breakdown.receive(&tile)) {
+                    continue;
+                }
+                if window.discard_stale(RequestKind::SlotMetaTile, &tile.entry_id, tile.tile_id) {
+                    continue;
+                }
+                let mut numeric_fields = BTreeSet::new();
                 if let Some(entry) = window.find_slot_mut(&tile.entry_id) {
                     // If the entry doesn't exist, we already zoomed away and
                     // are no longer interested in this tile.
+                    entry.memory = None;
+                    entry.tile_metas.entry(tile.tile_id).and_modify(|t| {
+                        let data = CompactSlotMetaTileData::new(&tile.data);
+                        numeric_fields = data.numeric_field_ids();
+                        *t = Some(data);
+                    });
+                }
+                window.numeric_fields.extend(numeric_fields);
+                window.config.search_state.known_values.stale = true;
+            }
+
+            for item in window.config.data_source.get_slot_meta_items() {
+                if let Some(entry) = window.find_slot_mut(&item.entry_id) {
+                    entry.memory = None;
                     entry
-                        .tile_metas
-                        .entry(tile.tile_id)
-                        .and_modify(|t| *t = Some(tile.data));
+                        .item_metas
+                        .entry((item.tile_id, item.item_uid))
+                        .and_modify(|m| *m = item.data);
                 }
             }
+
+            for failure in window.config.data_source.get_failures() {
+                window.record_failure(failure);
+            }
+
+            for results in window.config.data_source.get_search_results() {
+                window.config.search_state.receive_remote(results);
+            }
+
+            window.advance_origin_trace();
+            window.release_hidden_tiles(cx);
+            window.update_memory_usage();
+            window.evict_tiles((cx.memory_budget as usize) << 20, cx.frame);
         }
 
         let mut _fps = 0.0;
@@ -2549,18 +11813,7 @@ impl eframe::App for ProfApp {
             *last_update = Some(now);
         }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Quit").clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    }
-                });
-            });
-        });
-
-        egui::SidePanel::left("side_panel").show(ctx, |ui| {
+        egui::SidePanel::left("side_panel").show_inside(ui, |ui| {
             let body = TextStyle::Body.resolve(ui.style()).size;
             let heading = TextStyle::Heading.resolve(ui.style()).size;
             // Just set this on every frame for now
@@ -2576,6 +11829,16 @@ impl eframe::App for ProfApp {
                 });
             }
 
+            for window in windows.iter_mut() {
+                if window.config.watched_items.is_empty() {
+                    continue;
+                }
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    window.watch_list(ui, cx);
+                });
+            }
+
             for window in windows.iter_mut() {
                 egui::Frame::group(ui.style()).show(ui, |ui| {
                     ui.set_width(ui.available_width());
@@ -2606,7 +11869,10 @@ impl eframe::App for ProfApp {
                     current_theme.light_dark_radio_buttons(ui);
                     if current_theme.dark_mode != cx.toggle_dark_mode {
                         cx.toggle_dark_mode = current_theme.dark_mode;
-                        ctx.set_visuals(current_theme);
+                        // Embedded viewers restyle their own UI every frame
+                        if !cx.embedded {
+                            ctx.set_visuals(current_theme);
+                        }
                     }
 
                     ui.toggle_value(&mut cx.debug, "🛠 Debug");
@@ -2617,6 +11883,57 @@ impl eframe::App for ProfApp {
                         cx.show_controls = true;
                     }
 
+                    let mut split_view = cx.split_view.is_some();
+                    if ui.toggle_value(&mut split_view, "Split View").changed() {
+                        Self::toggle_split_view(cx);
+                    }
+
+                    ui.toggle_value(&mut cx.magnifier, "🔍 Magnifier");
+
+                    let button = ui
+                        .add_enabled(!windows.is_empty(), egui::Button::new("🔗 Copy Link"))
+                        .on_hover_text(
+                            "Copy a link that reopens the profiles with the current interval, \
+                             expanded entries and selected items",
+                        );
+                    if button.clicked() {
+                        let link = Self::view_link(windows, cx);
+                        #[cfg(target_arch = "wasm32")]
+                        let text = {
+                            let location = web_sys::window().unwrap().location();
+                            format!(
+                                "{}{}?{}",
+                                location.origin().unwrap_or_default(),
+                                location.pathname().unwrap_or_default(),
+                                link.to_query()
+                            )
+                        };
+                        // Opened with File > Open View Link
+                        #[cfg(not(target_arch = "wasm32"))]
+                        let text = link.to_token();
+                        ui.output_mut(|o| o.copied_text = text);
+                    }
+
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        let button = ui
+                            .add_enabled(
+                                directory_data::is_supported(),
+                                egui::Button::new("📂 Open Directory"),
+                            )
+                            .on_hover_text("Open an archive directory on this computer")
+                            .on_disabled_hover_text(
+                                "This browser can't open local directories. Instead, drag \
+                                 a tar file of the archive onto the viewer.",
+                            );
+                        if button.clicked() {
+                            let picked = picked_directories.clone();
+                            directory_data::pick_directory(move |data_source| {
+                                picked.borrow_mut().push(data_source)
+                            });
+                        }
+                    }
+
                     #[cfg(not(target_arch = "wasm32"))]
                     {
                         if cx.debug {
@@ -2625,12 +11942,19 @@ impl eframe::App for ProfApp {
                     }
                 });
 
+                Self::playback_controls(ui, cx);
+
                 ui.separator();
                 egui::warn_if_debug_build(ui);
             });
         });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
+        Self::debounce_view(ctx, &mut cx.view);
+        if let Some(split_view) = &mut cx.split_view {
+            Self::debounce_view(ctx, split_view);
+        }
+
+        egui::CentralPanel::default().show_inside(ui, |ui| {
             // Use body font to figure out how tall to draw rectangles.
             let font_id = TextStyle::Body.resolve(ui.style());
             let row_height = ui.fonts(|f| f.row_height(&font_id));
@@ -2641,35 +11965,51 @@ impl eframe::App for ProfApp {
             ui.scroll_with_delta(Vec2::new(0.0, y_scroll_delta));
             cx.row_scroll_delta = 0;
 
-            let mut remaining = windows.len();
-            // Only wrap in a frame if more than one profile
-            if remaining > 1 {
-                for window in windows.iter_mut() {
-                    egui::Frame::group(ui.style()).show(ui, |ui| {
-                        ui.push_id(window.index, |ui| {
-                            ui.set_height(ui.available_height() / (remaining as f32));
-                            ui.set_width(ui.available_width());
-                            window.content(ui, cx);
-                            remaining -= 1;
-                        });
-                    });
-                }
+            if let Some(mut split_view) = cx.split_view.take() {
+                const SEPARATOR_HEIGHT: f32 = 8.0;
+                let size = Vec2::new(
+                    ui.available_width(),
+                    (ui.available_height() - SEPARATOR_HEIGHT) / 2.0,
+                );
+                ui.allocate_ui(size, |ui| Self::timeline(ui, windows, cx, true));
+                ui.separator();
+
+                // Render the split view by temporarily making it the current view
+                std::mem::swap(&mut cx.view, &mut split_view);
+                ui.push_id("split_view", |ui| Self::timeline(ui, windows, cx, true));
+                std::mem::swap(&mut cx.view, &mut split_view);
+                cx.split_view = Some(split_view);
             } else {
-                for window in windows.iter_mut() {
-                    window.content(ui, cx);
-                }
+                Self::timeline(ui, windows, cx, false);
             }
-
-            Self::cursor(ui, cx);
         });
 
+        for window in windows.iter_mut() {
+            Self::detached_window(ctx, window, cx);
+            if std::mem::take(&mut window.toggle_detached) {
+                window.detached = match window.detached.take() {
+                    Some(_) => None,
+                    // Start out following the main view
+                    None => Some(Detached {
+                        view: cx.view.clone(),
+                        independent: false,
+                    }),
+                };
+            }
+        }
+
+        // Hack: work around mutability conflict
+        let mut show_controls = cx.show_controls;
         egui::Window::new("Controls")
-            .open(&mut cx.show_controls)
+            .open(&mut show_controls)
             .resizable(false)
-            .show(ctx, |ui| Self::display_controls(ui, &mut cx.item_link_mode));
+            .show(ctx, |ui| Self::display_controls(ui, cx));
+        cx.show_controls = show_controls;
 
+        let mut link_target = None;
         for window in windows.iter_mut() {
             let mut zoom_target = None;
+            let mut entry_target = None;
 
             // Hack: work around mutability conflict
             let mut items_selected = BTreeMap::new();
@@ -2680,7 +12020,7 @@ impl eframe::App for ProfApp {
                     window.inflate_meta(&item.loc.entry_id, cx);
                     if let Some(meta) = window.find_item_meta(&item.loc.entry_id, item.loc.item_uid)
                     {
-                        item.meta = Some(meta.clone());
+                        item.meta = Some(meta);
                     }
                 }
 
@@ -2689,14 +12029,25 @@ impl eframe::App for ProfApp {
                     None => format!("Item <Item UID: {}>", item.loc.item_uid.0),
                 };
 
+                let path = window.entry_path(&item.loc.entry_id);
+
                 let mut enabled = true;
                 egui::Window::new(short_title)
                     .id(egui::Id::new(item.loc.item_uid.0))
                     .open(&mut enabled)
                     .resizable(true)
                     .show(ctx, |ui| {
-                        let target =
-                            Self::display_item_details(ui, item, &window.config.field_schema, cx);
+                        if let Some(entry_id) = Self::breadcrumb(ui, &path) {
+                            entry_target = Some(entry_id);
+                        }
+                        let target = Self::display_item_details(
+                            ui,
+                            item,
+                            &window.config.field_schema,
+                            &mut window.config.watched_items,
+                            &mut window.config.origin_trace,
+                            cx,
+                        );
                         if target.is_some() {
                             zoom_target = target;
                         }
@@ -2705,33 +12056,71 @@ impl eframe::App for ProfApp {
             });
             std::mem::swap(&mut items_selected, &mut window.config.items_selected);
 
-            if let Some((item_loc, interval)) = zoom_target {
-                let interval = match cx.item_link_mode {
-                    // In Zoom mode, put the item in the center of the view
-                    // interval with a small amount of padding on either side.
-                    ItemLinkNavigationMode::Zoom => interval.grow(interval.duration_ns() / 20),
-                    // In Pan mode, maintain the current window size but shift
-                    // the center to place the item in the middle of it.
-                    ItemLinkNavigationMode::Pan => cx
-                        .view_interval
-                        .translate(interval.center().0 - cx.view_interval.center().0),
-                };
-                ProfApp::zoom(cx, interval);
-                window.expand_slot(&item_loc.entry_id);
-                window.config.scroll_to_item(item_loc);
+            if let Some(entry_id) = entry_target {
+                window.scroll_to_entry(&entry_id);
+            }
+
+            match zoom_target {
+                // Links into other profiles are resolved once we are done
+                // with this window
+                Some(FieldTarget::Item(item_loc, interval)) => match &item_loc.profile {
+                    Some(profile) if *profile != window.config.description.source_locator => {
+                        link_target = Some((item_loc, interval));
+                    }
+                    _ => Self::navigate_to_item(cx, window, item_loc, interval),
+                },
+                // Keep the current window size, centered on the given time
+                Some(FieldTarget::Time(time)) => {
+                    let offset = time.0.saturating_sub(cx.view.interval.center().0);
+                    let interval = cx.view.interval.saturating_translate(offset);
+                    ProfApp::zoom(cx, interval);
+                }
+                None => {}
+            }
+
+            window.idle_gaps_window(ctx, cx);
+            window.time_breakdown_window(ctx, cx);
+            window.origin_trace_window(ctx, cx);
+            window.overview_window(ctx, cx);
+            window.request_log_window(ctx, cx);
+        }
+
+        Self::interval_stats_window(ctx, windows, cx);
+
+        if let Some((item_loc, interval)) = link_target {
+            let target = windows.iter_mut().find(|window| {
+                Some(&window.config.description.source_locator) == item_loc.profile.as_ref()
+            });
+            if let Some(window) = target {
+                Self::navigate_to_item(cx, window, item_loc, interval);
             }
         }
 
         Self::keyboard(ctx, cx, windows);
+        Self::advance_playback(ctx, cx);
+
+        Self::emit_events(windows, cx, subscribers, event_state);
 
         // Keep repainting as long as we have outstanding requests.
-        if !pending_data_sources.is_empty()
+        let loading = !pending_data_sources.is_empty()
             || windows
                 .iter()
-                .any(|w| w.config.data_source.outstanding_requests() > 0)
-        {
-            ctx.request_repaint_after(Duration::from_millis(50));
+                .any(|w| w.config.data_source.outstanding_requests() > 0);
+        if !loading {
+            cx.loading_since = None;
+            return;
+        }
+        let (now, focused) = ctx.input(|i| (i.time, i.raw.focused));
+        let since = *cx.loading_since.get_or_insert(now);
+        let mut repaint = if now - since >= LONG_FETCH_SECONDS {
+            LONG_FETCH_REPAINT
+        } else {
+            LOADING_REPAINT
+        };
+        if cx.power_saving && !focused {
+            repaint = repaint.max(UNFOCUSED_LOADING_REPAINT);
         }
+        ctx.request_repaint_after(repaint);
     }
 }
 
@@ -2788,6 +12177,8 @@ impl UiExtra for egui::Ui {
     }
 }
 
+const APP_NAME: &str = "Legion Prof";
+
 #[cfg(not(target_arch = "wasm32"))]
 fn get_locator(data_sources: &[Box<dyn DeferredDataSource>]) -> String {
     let all_locators = data_sources
@@ -2804,15 +12195,43 @@ fn get_locator(data_sources: &[Box<dyn DeferredDataSource>]) -> String {
     }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+fn create_app(
+    cc: &eframe::CreationContext<'_>,
+    data_sources: Vec<Box<dyn DeferredDataSource>>,
+    control: Option<ViewerControl>,
+) -> Box<dyn eframe::App> {
+    let mut app = ProfApp::new(cc, data_sources);
+    if let Some(control) = &control {
+        *control.ctx.lock().unwrap() = Some(cc.egui_ctx.clone());
+    }
+    app.control = control;
+    Box::new(app)
+}
+
 pub fn start(data_sources: Vec<Box<dyn DeferredDataSource>>) {
+    run(data_sources, None);
+}
+
+// Like start, but first hands a ViewerHandle to on_start, e.g., to spawn a
+// thread that drives the viewer
+pub fn start_with_handle(
+    data_sources: Vec<Box<dyn DeferredDataSource>>,
+    on_start: impl FnOnce(ViewerHandle),
+) {
+    let (handle, control) = ViewerHandle::new();
+    on_start(handle);
+    run(data_sources, Some(control));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run(data_sources: Vec<Box<dyn DeferredDataSource>>, control: Option<ViewerControl>) {
     env_logger::try_init().unwrap_or(()); // Log to stderr (if you run with `RUST_LOG=debug`).
 
     // IMPORTANT: This will be used as the directory name for the storage
     // location for the persisted app.ron configuration. eframe is not good
     // about sanitizing these directory names, so it is VERY IMPORTANT that
     // this be a short, predictable name without weird characters in it.
-    let app_name = "Legion Prof";
+    let app_name = APP_NAME;
 
     // This is what will be displayed as the window's actual title.
     let locator = format!("{} - {}", get_locator(&data_sources), app_name);
@@ -2824,13 +12243,13 @@ pub fn start(data_sources: Vec<Box<dyn DeferredDataSource>>) {
     eframe::run_native(
         app_name,
         native_options,
-        Box::new(|cc| Box::new(ProfApp::new(cc, data_sources))),
+        Box::new(|cc| create_app(cc, data_sources, control)),
     )
     .expect("failed to start eframe");
 }
 
 #[cfg(target_arch = "wasm32")]
-pub fn start(data_sources: Vec<Box<dyn DeferredDataSource>>) {
+fn run(data_sources: Vec<Box<dyn DeferredDataSource>>, control: Option<ViewerControl>) {
     // Redirect `log` message to `console.log` and friends:
     eframe::WebLogger::init(log::LevelFilter::Debug).ok();
 
@@ -2841,9 +12260,203 @@ pub fn start(data_sources: Vec<Box<dyn DeferredDataSource>>) {
             .start(
                 "the_canvas_id", // hardcode it
                 web_options,
-                Box::new(|cc| Box::new(ProfApp::new(cc, data_sources))),
+                Box::new(|cc| create_app(cc, data_sources, control)),
             )
             .await
             .expect("failed to start eframe");
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::data::{DataSource, TileLevelMeta};
+    use crate::deferred_data::DeferredDataSourceWrapper;
+    use crate::test_data::{panel, slot, TestDataSource};
+
+    fn span(start: i64, stop: i64) -> Interval {
+        Interval::new(Timestamp(start), Timestamp(stop))
+    }
+
+    // A static profile of 1000 ns with 1, 2 and 4 tiles at its levels
+    fn static_config(level_meta: Vec<TileLevelMeta>) -> Config {
+        let data_source = TestDataSource::default();
+        let mut info = data_source.fetch_info();
+        info.entry_info = panel(
+            "root",
+            vec![panel("0", vec![panel("CPU", vec![slot("S")])])],
+        );
+        info.tile_set.tiles = (0..3)
+            .map(|level| {
+                let tiles = 1 << level;
+                (0..tiles)
+                    .map(|i| TileID(span(1000 * i / tiles, 1000 * (i + 1) / tiles), level as u32))
+                    .collect()
+            })
+            .collect();
+        info.tile_set.level_meta = level_meta;
+        Config::new(Box::new(DeferredDataSourceWrapper::new(data_source)), info)
+    }
+
+    fn level_meta(items_per_tile: u64, bytes_per_tile: u64) -> TileLevelMeta {
+        TileLevelMeta {
+            items_per_tile,
+            bytes_per_tile,
+        }
+    }
+
+    #[test]
+    fn test_fit_static_level() {
+        let small = vec![level_meta(1, 1); 3];
+        let full = span(0, 1000);
+
+        // Without estimates, or a width, there is nothing to fit
+        let mut config = static_config(Vec::new());
+        config.set_view_width(1000.0);
+        assert_eq!(config.fit_static_level(full), None);
+        let mut config = static_config(small.clone());
+        assert_eq!(config.fit_static_level(full), None);
+
+        // Wide enough for the finest level
+        config.set_view_width(1000.0);
+        assert_eq!(config.fit_static_level(full), Some(2));
+
+        // Tiles of the finer levels would be too narrow on screen
+        config.set_view_width(200.0);
+        assert_eq!(config.fit_static_level(full), Some(1));
+        config.set_view_width(100.0);
+        assert_eq!(config.fit_static_level(full), Some(0));
+        // ... unless the view is zoomed in
+        assert_eq!(config.fit_static_level(span(0, 200)), Some(2));
+
+        // Tiles with more items than pixels to show them in
+        let mut config =
+            static_config(vec![level_meta(1, 1), level_meta(1, 1), level_meta(600, 1)]);
+        config.set_view_width(1000.0);
+        assert_eq!(config.fit_static_level(full), Some(1));
+
+        // The tiles for the view must fit in the budget together, but a
+        // single tile of the same level may
+        let bytes = TILE_PAYLOAD_BUDGET / 2;
+        let mut config = static_config(vec![
+            level_meta(1, 1),
+            level_meta(1, bytes),
+            level_meta(1, bytes),
+        ]);
+        config.set_view_width(1000.0);
+        assert_eq!(config.fit_static_level(full), Some(1));
+        assert_eq!(config.fit_static_level(span(0, 200)), Some(2));
+
+        // The coarsest level is used when nothing fits
+        let mut config = static_config(vec![level_meta(1, TILE_PAYLOAD_BUDGET + 1); 3]);
+        config.set_view_width(1000.0);
+        assert_eq!(config.fit_static_level(full), Some(0));
+    }
+
+    #[test]
+    fn test_set_view_width() {
+        let mut config = static_config(vec![level_meta(1, 1); 3]);
+        config.set_view_width(1000.0);
+        assert_eq!(config.request_tiles(span(0, 1000)).len(), 4);
+
+        // Small changes keep the tiles already chosen
+        config.set_view_width(1050.0);
+        assert!(config.last_request_interval.is_some());
+        assert_eq!(config.view_width, 1000.0);
+
+        // Larger ones choose again
+        config.set_view_width(200.0);
+        assert!(config.last_request_interval.is_none());
+        assert_eq!(config.request_tiles(span(0, 1000)).len(), 2);
+    }
+
+    #[test]
+    fn test_busy_fraction() {
+        let config = static_config(Vec::new());
+        let mut cx = Context::default();
+        cx.view.interval = span(0, 1000);
+        let mut slot = Slot::new(&slot("S"), EntryID::root().child(0));
+        let first = TileID(span(0, 500), 1);
+        let second = TileID(span(500, 1000), 1);
+        slot.views[0].tile_ids = vec![first, second];
+        let items = vec![vec![Item {
+            item_uid: ItemUID(0),
+            interval: span(0, 500),
+            color: Color32::RED,
+        }]];
+        slot.tiles.insert(first, Some(SlotTileData { items }));
+        slot.tiles.insert(second, None);
+
+        // Nothing is shown until all of the view's tiles have loaded
+        slot.update_busy_fraction(&config, &cx);
+        assert_eq!(slot.label_badge(&cx), None);
+
+        slot.tiles
+            .insert(second, Some(SlotTileData { items: Vec::new() }));
+        slot.update_busy_fraction(&config, &cx);
+        assert_eq!(slot.label_badge(&cx).as_deref(), Some("50%"));
+    }
+
+    #[test]
+    fn test_remote_search() {
+        let title_field = FieldSchema::new().insert("Title".to_owned(), false);
+        let mut search = SearchState::new(title_field, true);
+        search.query = "It".to_owned();
+        let mut data_source = DeferredDataSourceWrapper::new(TestDataSource {
+            search: true,
+            ..Default::default()
+        });
+
+        // Nothing is requested while the view is moving, but the meta tiles
+        // aren't fetched in the meantime either
+        let mut view = ViewState {
+            interval: span(0, 1000),
+            request_interval: span(0, 500),
+            ..Default::default()
+        };
+        search.start_remote(&mut data_source, &view);
+        assert!(data_source.get_search_results().is_empty());
+        assert!(search.is_remote() && search.is_remote_pending());
+
+        // Once the view settles, the view is searched once
+        view.request_interval = view.interval;
+        search.start_remote(&mut data_source, &view);
+        search.start_remote(&mut data_source, &view);
+        let mut results = data_source.get_search_results();
+        assert_eq!(results.len(), 1);
+
+        // Results of other searches are dropped
+        let mut stale = results[0].clone();
+        stale.interval = span(0, 500);
+        search.receive_remote(stale);
+        assert!(search.remote_matches.is_empty());
+        assert!(search.is_remote_pending());
+
+        search.receive_remote(results.pop().unwrap());
+        assert_eq!(search.remote_status, RemoteSearch::Done);
+        assert!(search.is_remote() && !search.is_remote_pending());
+
+        // Matches wait until the walk reaches their slot
+        let slot = Slot::new(&slot("S"), EntryID::root().child(0));
+        search.start_entry(&slot);
+        search.insert_remote(&slot, 3);
+        assert!(search.remote_matches.is_empty());
+        assert_eq!(search.result_set, [ItemUID(0)].into());
+        let item = &search.result_cache[slot.entry_id()][&TileID(span(0, 1000), 0)][&ItemUID(0)];
+        assert_eq!(item.title, "Item");
+        assert_eq!(item.interval, span(0, 1000));
+        // Rows are reversed, as on screen
+        assert_eq!(item.irow, 2);
+        assert!(search.cache_bytes > 0);
+
+        // If the data source fails, the meta tiles are searched instead
+        search.clear();
+        search.start_remote(&mut data_source, &view);
+        let mut failed = data_source.get_search_results().pop().unwrap();
+        failed.error = Some("unavailable".to_owned());
+        search.receive_remote(failed);
+        assert_eq!(search.remote_status, RemoteSearch::Incomplete);
+        assert!(!search.is_remote() && !search.is_remote_pending());
+    }
+}
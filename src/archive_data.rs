@@ -1,10 +1,14 @@
 use std::fs::{create_dir, remove_dir_all, File};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use serde::Serialize;
 
-use crate::data::{DataSourceInfo, EntryID, EntryIDSlug, EntryIndex, EntryInfo, TileID, TileSet};
+use crate::data::{
+    DataSourceInfo, EntryID, EntryIDSlug, EntryIndex, EntryInfo, TileID, TileLevelMeta, TileSet,
+};
 use crate::deferred_data::{CountingDeferredDataSource, DeferredDataSource};
 use crate::http::schema::TileRequestRef;
 use crate::timestamp::{Interval, Timestamp};
@@ -16,6 +20,41 @@ pub struct DataSourceArchiveWriter<T: DeferredDataSource> {
     path: PathBuf,
     force: bool,
     zstd_compression: i32,
+    // What has been written so far at each level, for TileSet::level_meta
+    level_stats: Vec<LevelStats>,
+    // Levels are fetched one at a time, so every tile in flight is at this
+    // level. Dynamic data sources are fetched with level 0 tiles, so their
+    // tiles get the level put back in when writing
+    dynamic: bool,
+    level: u32,
+    // Reported to instead of printing, if given
+    progress: Option<Arc<ArchiveProgress>>,
+}
+
+// How far along a write is, for callers that watch it from another thread
+#[derive(Debug, Default)]
+pub struct ArchiveProgress {
+    // Tiles written so far, and in total (zero until the info is known)
+    pub written: AtomicU64,
+    pub total: AtomicU64,
+}
+
+#[derive(Default)]
+struct LevelStats {
+    slot_tiles: u64,
+    items: u64,
+    // Added to by the writes, once the compressed size is known
+    bytes: Arc<AtomicU64>,
+}
+
+impl LevelStats {
+    fn meta(&self) -> TileLevelMeta {
+        let tiles = self.slot_tiles.max(1);
+        TileLevelMeta {
+            items_per_tile: self.items / tiles,
+            bytes_per_tile: self.bytes.load(Ordering::Relaxed) / tiles,
+        }
+    }
 }
 
 fn create_unique_dir<P: AsRef<Path>>(path: P, force: bool) -> io::Result<PathBuf> {
@@ -45,23 +84,33 @@ fn create_unique_dir<P: AsRef<Path>>(path: P, force: bool) -> io::Result<PathBuf
     Ok(path)
 }
 
-fn write_data<T>(path: PathBuf, data: T, zstd_compression: i32) -> io::Result<()>
+// Returns the size of the file written
+fn write_data<T>(path: PathBuf, data: T, zstd_compression: i32) -> io::Result<u64>
 where
     T: Serialize,
 {
     let mut f = zstd::Encoder::new(File::create(path)?, zstd_compression)?;
     ciborium::into_writer(&data, &mut f).expect("ciborium encoding failed");
-    f.finish()?;
-    Ok(())
+    let f = f.finish()?;
+    Ok(f.metadata()?.len())
 }
 
-fn spawn_write<T>(path: PathBuf, data: T, zstd_compression: i32, scope: &rayon::Scope<'_>)
-where
+// If given, the size of the file is added to bytes
+fn spawn_write<T>(
+    path: PathBuf,
+    data: T,
+    zstd_compression: i32,
+    bytes: Option<Arc<AtomicU64>>,
+    scope: &rayon::Scope<'_>,
+) where
     T: Serialize + Send + Sync + 'static,
 {
     scope.spawn(move |_| {
         // FIXME (Elliott): is there a better way to handle I/O failure?
-        write_data(path, data, zstd_compression).unwrap();
+        let size = write_data(path, data, zstd_compression).unwrap();
+        if let Some(bytes) = bytes {
+            bytes.fetch_add(size, Ordering::Relaxed);
+        }
     });
 }
 
@@ -107,6 +156,29 @@ impl<T: DeferredDataSource> DataSourceArchiveWriter<T> {
             path: path.as_ref().to_owned(),
             force,
             zstd_compression,
+            level_stats: Vec::new(),
+            dynamic: false,
+            level: 0,
+            progress: None,
+        }
+    }
+
+    pub fn with_progress(self, progress: Arc<ArchiveProgress>) -> Self {
+        Self {
+            progress: Some(progress),
+            ..self
+        }
+    }
+
+    fn log(&self, message: impl FnOnce() -> String) {
+        if self.progress.is_none() {
+            println!("{}", message());
+        }
+    }
+
+    fn tile_written(&self) {
+        if let Some(progress) = &self.progress {
+            progress.written.fetch_add(1, Ordering::Relaxed);
         }
     }
 
@@ -115,60 +187,105 @@ impl<T: DeferredDataSource> DataSourceArchiveWriter<T> {
         self.data_source.get_infos().pop()
     }
 
-    fn write_info(&mut self, info: DataSourceInfo, scope: &rayon::Scope<'_>) {
+    // A failed request would leave a hole in the archive, so give up instead
+    fn check_failures(&mut self) -> io::Result<()> {
+        match self.data_source.get_failures().pop() {
+            Some(failure) => Err(io::Error::other(format!(
+                "{} request failed: {}",
+                failure.kind.label_text(),
+                failure.error
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn leveled(&self, tile_id: TileID) -> TileID {
+        if self.dynamic {
+            TileID(tile_id.0, self.level)
+        } else {
+            tile_id
+        }
+    }
+
+    fn write_info(&mut self, mut info: DataSourceInfo, scope: &rayon::Scope<'_>) {
+        // The archive is served from files, which can't be searched
+        info.supports_search = false;
+        info.tile_set.level_meta = self.level_stats.iter().map(LevelStats::meta).collect();
         let path = self.path.join("info");
-        spawn_write(path, info, self.zstd_compression, scope);
+        spawn_write(path, info, self.zstd_compression, None, scope);
     }
 
     fn write_summary_tiles(&mut self, scope: &rayon::Scope<'_>) {
-        for tile in self.data_source.get_summary_tiles() {
+        for mut tile in self.data_source.get_summary_tiles() {
+            tile.tile_id = self.leveled(tile.tile_id);
             let mut path = self.path.join("summary_tile");
             let req = TileRequestRef {
                 entry_id: &tile.entry_id,
                 tile_id: tile.tile_id,
             };
             path.push(req.to_slug());
-            spawn_write(path, tile, self.zstd_compression, scope);
+            spawn_write(path, tile, self.zstd_compression, None, scope);
+            self.tile_written();
         }
     }
 
     fn write_slot_tiles(&mut self, scope: &rayon::Scope<'_>) {
-        for tile in self.data_source.get_slot_tiles() {
+        for mut tile in self.data_source.get_slot_tiles() {
+            tile.tile_id = self.leveled(tile.tile_id);
             let mut path = self.path.join("slot_tile");
             let req = TileRequestRef {
                 entry_id: &tile.entry_id,
                 tile_id: tile.tile_id,
             };
             path.push(req.to_slug());
-            spawn_write(path, tile, self.zstd_compression, scope);
+            let stats = &mut self.level_stats[self.level as usize];
+            stats.slot_tiles += 1;
+            stats.items += tile
+                .data
+                .items
+                .iter()
+                .map(|row| row.len() as u64)
+                .sum::<u64>();
+            let bytes = Some(stats.bytes.clone());
+            spawn_write(path, tile, self.zstd_compression, bytes, scope);
+            self.tile_written();
         }
     }
 
     fn write_slot_meta_tiles(&mut self, scope: &rayon::Scope<'_>) {
-        for tile in self.data_source.get_slot_meta_tiles() {
+        for mut tile in self.data_source.get_slot_meta_tiles() {
+            tile.tile_id = self.leveled(tile.tile_id);
             let mut path = self.path.join("slot_meta_tile");
             let req = TileRequestRef {
                 entry_id: &tile.entry_id,
                 tile_id: tile.tile_id,
             };
             path.push(req.to_slug());
-            spawn_write(path, tile, self.zstd_compression, scope);
+            spawn_write(path, tile, self.zstd_compression, None, scope);
+            self.tile_written();
         }
     }
 
-    pub fn write(mut self) -> io::Result<()> {
-        self.path = create_unique_dir(&self.path, self.force)?;
-        println!("Created output directory {:?}", &self.path);
-        create_dir(self.path.join("summary_tile"))?;
-        create_dir(self.path.join("slot_tile"))?;
-        create_dir(self.path.join("slot_meta_tile"))?;
-
+    pub fn write(mut self) -> io::Result<PathBuf> {
         self.data_source.fetch_info();
         let mut info = None;
         while info.is_none() {
             info = self.check_info();
+            self.check_failures()?;
         }
         let mut info = info.unwrap();
+        // Don't write an archive of the placeholder for a missing profile
+        if info.is_unavailable() {
+            let message = info.warning_message.unwrap_or_default();
+            return Err(io::Error::other(message));
+        }
+
+        self.path = create_unique_dir(&self.path, self.force)?;
+        let path = self.path.clone();
+        self.log(|| format!("Created output directory {:?}", &path));
+        create_dir(self.path.join("summary_tile"))?;
+        create_dir(self.path.join("slot_tile"))?;
+        create_dir(self.path.join("slot_meta_tile"))?;
 
         let entry_ids = walk_entry_list(&info.entry_info);
         for entry_id in &entry_ids {
@@ -184,50 +301,77 @@ impl<T: DeferredDataSource> DataSourceArchiveWriter<T> {
             }
         }
 
-        // For now, this only works on dynamic data sources
-        assert!(info.tile_set.tiles.is_empty());
-
-        let mut tile_set = Vec::new();
-
-        for level in 0..self.levels {
-            let num_tiles = self.branch_factor.pow(level) as i64;
-            let duration = info.interval.duration_ns();
-            let tile_ids: Vec<_> = (0..num_tiles)
-                .map(|i| {
-                    let start = Timestamp(duration * i / num_tiles);
-                    let stop = Timestamp(duration * (i + 1) / num_tiles);
-                    TileID(Interval::new(start, stop))
+        // Static data sources can only be fetched with their own tiles
+        self.dynamic = info.tile_set.tiles.is_empty();
+        let tile_set = if self.dynamic {
+            let mut tile_set = Vec::new();
+            for level in 0..self.levels {
+                let num_tiles = self.branch_factor.pow(level) as i64;
+                let start = info.interval.start.0;
+                let duration = info.interval.duration_ns();
+                let tile_ids: Vec<_> = (0..num_tiles)
+                    .map(|i| {
+                        let stop = Timestamp(start + duration * (i + 1) / num_tiles);
+                        let start = Timestamp(start + duration * i / num_tiles);
+                        TileID(Interval::new(start, stop), level)
+                    })
+                    .collect();
+                tile_set.push(tile_ids);
+            }
+            info.tile_set = TileSet {
+                tiles: tile_set.clone(),
+                level_meta: Vec::new(),
+            };
+            tile_set
+        } else {
+            info.tile_set.tiles.clone()
+        };
+        let levels = tile_set.len() as u32;
+        self.level_stats
+            .resize_with(tile_set.len(), LevelStats::default);
+        if let Some(progress) = &self.progress {
+            // Slots are written as pairs of slot and meta tiles
+            let tiles_per_level: u64 = entry_ids
+                .iter()
+                .map(|entry_id| match entry_id.last_index().unwrap() {
+                    EntryIndex::Summary => 1,
+                    EntryIndex::Slot(..) => 2,
                 })
-                .collect();
-            tile_set.push(tile_ids);
+                .sum();
+            let tiles: u64 = tile_set.iter().map(|level| level.len() as u64).sum();
+            progress
+                .total
+                .store(tiles * tiles_per_level, Ordering::Relaxed);
         }
 
-        info.tile_set = TileSet {
-            tiles: tile_set.clone(),
-        };
-
-        rayon::in_place_scope(|s| {
-            self.write_info(info, s);
-        });
-
-        for level in 0..self.levels {
-            let tile_ids = &tile_set[level as usize];
-            let full = level == self.levels - 1;
+        for level in 0..levels {
+            let tile_ids: Vec<_> = tile_set[level as usize]
+                .iter()
+                .map(|tile_id| {
+                    if self.dynamic {
+                        TileID(tile_id.0, 0)
+                    } else {
+                        *tile_id
+                    }
+                })
+                .collect();
+            let full = level == levels - 1;
+            self.level = level;
 
-            println!("Writing level {} with {} tiles", level, tile_ids.len());
+            self.log(|| format!("Writing level {} with {} tiles", level, tile_ids.len()));
 
             const MAX_IN_FLIGHT_REQUESTS: u64 = 100;
 
             for entry_id in &entry_ids {
                 match entry_id.last_index().unwrap() {
                     EntryIndex::Summary => {
-                        for tile_id in tile_ids {
+                        for tile_id in &tile_ids {
                             self.data_source
                                 .fetch_summary_tile(entry_id, *tile_id, full);
                         }
                     }
                     EntryIndex::Slot(..) => {
-                        for tile_id in tile_ids {
+                        for tile_id in &tile_ids {
                             self.data_source.fetch_slot_tile(entry_id, *tile_id, full);
                             self.data_source
                                 .fetch_slot_meta_tile(entry_id, *tile_id, full);
@@ -241,17 +385,27 @@ impl<T: DeferredDataSource> DataSourceArchiveWriter<T> {
                         self.write_summary_tiles(s);
                         self.write_slot_tiles(s);
                         self.write_slot_meta_tiles(s);
+                        self.check_failures()?;
                     }
-                });
+                    Ok::<_, io::Error>(())
+                })?;
             }
+
+            // Finish the level, so that every tile in flight is at self.level
+            rayon::in_place_scope(|s| {
+                while self.data_source.outstanding_requests() > 0 {
+                    self.write_summary_tiles(s);
+                    self.write_slot_tiles(s);
+                    self.write_slot_meta_tiles(s);
+                    self.check_failures()?;
+                }
+                Ok::<_, io::Error>(())
+            })?;
         }
 
+        // The info goes last, once the sizes of the tiles are known
         rayon::in_place_scope(|s| {
-            while self.data_source.outstanding_requests() > 0 {
-                self.write_summary_tiles(s);
-                self.write_slot_tiles(s);
-                self.write_slot_meta_tiles(s);
-            }
+            self.write_info(info, s);
         });
 
         std::fs::write(
@@ -270,6 +424,49 @@ window.onload = function() {
 ",
         )?;
 
-        Ok(())
+        Ok(self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::data::DataSource;
+    use crate::file_data::FileDataSource;
+    use crate::test_data::TestDeferredDataSource;
+
+    #[test]
+    fn test_write_dynamic_levels() {
+        let path = std::env::temp_dir().join(format!("archive-levels-{}", std::process::id()));
+        let progress = Arc::new(ArchiveProgress::default());
+        let path =
+            DataSourceArchiveWriter::new(TestDeferredDataSource::default(), 2, 2, &path, true, 1)
+                .with_progress(progress.clone())
+                .write()
+                .unwrap();
+        assert_eq!(
+            progress.written.load(Ordering::Relaxed),
+            progress.total.load(Ordering::Relaxed)
+        );
+
+        let archive = FileDataSource::new(&path);
+        let info = archive.fetch_info();
+        assert_eq!(info.tile_set.tiles.len(), 2);
+        assert_eq!(info.tile_set.level_meta.len(), 2);
+        let entry_id = EntryID::root().child(0);
+        for (level, tile_ids) in info.tile_set.tiles.iter().enumerate() {
+            assert_eq!(info.tile_set.level_meta[level].items_per_tile, 1);
+            for tile_id in tile_ids {
+                assert_eq!(tile_id.1, level as u32);
+                // The level is in the file name, and in the tile
+                let tile = archive.fetch_slot_tile(&entry_id, *tile_id, false);
+                assert_eq!(tile.tile_id, *tile_id);
+                assert_eq!(tile.data.items[0][0].interval, tile_id.0);
+                let tile = archive.fetch_slot_meta_tile(&entry_id, *tile_id, false);
+                assert_eq!(tile.tile_id, *tile_id);
+            }
+        }
+        remove_dir_all(&path).unwrap();
     }
 }
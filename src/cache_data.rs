@@ -0,0 +1,570 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{create_dir_all, remove_dir_all, rename, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::warn;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{
+    DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID, ItemUID, SearchQuery,
+    SearchResults, SlotMetaItem, SlotMetaTile, SlotMetaTileData, SlotTile, SlotTileData,
+    SummaryTile, SummaryTileData, TileID,
+};
+use crate::deferred_data::{DeferredDataSource, FailedRequest, RequestKind};
+use crate::http::schema::{ItemRequestRef, TileRequestRef};
+use crate::timestamp::Interval;
+
+// Caches everything fetched from another data source on disk, so that
+// reopening a profile does not refetch it, and works even when the data
+// source cannot be reached. The cache for a profile is dropped whenever the
+// data source reports a different info, as the tiles are then stale.
+pub struct CachingDeferredDataSource<T: DeferredDataSource> {
+    data_source: T,
+    path: PathBuf,
+    // Set when the data source could not be reached, in which case tiles
+    // missing from the cache are returned empty
+    offline: bool,
+    // Outstanding requests, by kind, so that the results for full tiles can
+    // be kept apart from the regular tiles. Results don't say which they
+    // are, so when both are outstanding for the same tile (marked by None),
+    // nothing is stored until all of them are answered
+    outstanding: BTreeMap<(&'static str, EntryID, TileID), (usize, Option<bool>)>,
+    summary_tiles: Vec<SummaryTile>,
+    slot_tiles: Vec<SlotTile>,
+    slot_meta_tiles: Vec<SlotMetaTile>,
+    slot_meta_items: Vec<SlotMetaItem>,
+    search_results: Vec<SearchResults>,
+}
+
+const SUMMARY_TILE: &str = "summary_tile";
+const SLOT_TILE: &str = "slot_tile";
+const SLOT_META_TILE: &str = "slot_meta_tile";
+const SLOT_META_ITEM: &str = "slot_meta_item";
+
+// Directory name for a profile within the cache: a readable prefix of the
+// locators, made safe for file names, and a hash of all of them (each with
+// its length, so that they can't run together), so that locators that only
+// differ where the prefix is lossy are kept apart. The hash (64-bit FNV-1a)
+// must be the same in every build sharing a cache
+fn cache_key(description: &DataSourceDescription) -> String {
+    const PREFIX_LEN: usize = 64;
+    let locator = description.source_locator.join("+");
+    let prefix: String = locator
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(PREFIX_LEN)
+        .collect();
+    let bytes = description
+        .source_locator
+        .iter()
+        .flat_map(|l| (l.len() as u64).to_le_bytes().into_iter().chain(l.bytes()));
+    let hash = bytes.fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{prefix}-{hash:016x}")
+}
+
+fn kind_name(kind: RequestKind) -> Option<&'static str> {
+    match kind {
+        RequestKind::SummaryTile => Some(SUMMARY_TILE),
+        RequestKind::SlotTile => Some(SLOT_TILE),
+        RequestKind::SlotMetaTile => Some(SLOT_META_TILE),
+        RequestKind::Info | RequestKind::SlotMetaItem => None,
+    }
+}
+
+fn encode<T: Serialize>(data: &T) -> Vec<u8> {
+    let mut result = Vec::new();
+    ciborium::into_writer(data, &mut result).expect("ciborium encoding failed");
+    result
+}
+
+fn read_file<T>(path: &Path) -> Option<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    // Anything we can't read is treated as a cache miss
+    let f = File::open(path).ok()?;
+    let f = zstd::Decoder::new(f).ok()?;
+    ciborium::from_reader(f).ok()
+}
+
+fn write_file<T: Serialize>(path: &Path, data: &T) -> io::Result<()> {
+    create_dir_all(path.parent().unwrap())?;
+    // Write to a temporary file first, so that a partially written file is
+    // never mistaken for a cached one. The name is unique, as other threads
+    // and processes may be writing the same file into a shared cache
+    static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut f = zstd::Encoder::new(File::create(&temp)?, 1)?;
+    ciborium::into_writer(data, &mut f).expect("ciborium encoding failed");
+    f.finish()?;
+    rename(temp, path)
+}
+
+impl<T: DeferredDataSource> CachingDeferredDataSource<T> {
+    pub fn new(data_source: T, cache_dir: impl AsRef<Path>) -> Self {
+        let path = cache_dir
+            .as_ref()
+            .join(cache_key(&data_source.fetch_description()));
+        Self {
+            data_source,
+            path,
+            offline: false,
+            outstanding: BTreeMap::new(),
+            summary_tiles: Vec::new(),
+            slot_tiles: Vec::new(),
+            slot_meta_tiles: Vec::new(),
+            slot_meta_items: Vec::new(),
+            search_results: Vec::new(),
+        }
+    }
+
+    fn tile_path(&self, kind: &str, entry_id: &EntryID, tile_id: TileID, full: bool) -> PathBuf {
+        let kind = if full {
+            format!("{kind}_full")
+        } else {
+            kind.to_owned()
+        };
+        let req = TileRequestRef { entry_id, tile_id };
+        self.path.join(kind).join(req.to_slug())
+    }
+
+    fn item_path(&self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) -> PathBuf {
+        let req = ItemRequestRef {
+            entry_id,
+            tile_id,
+            item_uid,
+        };
+        self.path.join(SLOT_META_ITEM).join(req.to_slug())
+    }
+
+    fn store<D: Serialize>(&self, path: &Path, data: &D) {
+        if let Err(e) = write_file(path, data) {
+            warn!("unable to write cache file {}: {}", path.display(), e);
+        }
+    }
+
+    // Look up a tile in the cache, and otherwise decide whether it needs to
+    // be fetched. Returns Err(true) if the tile should be fetched
+    fn lookup<D>(
+        &mut self,
+        kind: &'static str,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<D, bool>
+    where
+        D: for<'a> Deserialize<'a>,
+    {
+        if let Some(tile) = read_file(&self.tile_path(kind, entry_id, tile_id, full)) {
+            return Ok(tile);
+        }
+        if self.offline {
+            return Err(false);
+        }
+        self.outstanding
+            .entry((kind, entry_id.clone(), tile_id))
+            .and_modify(|(count, kind)| {
+                *count += 1;
+                if *kind != Some(full) {
+                    *kind = None;
+                }
+            })
+            .or_insert((1, Some(full)));
+        Err(true)
+    }
+
+    // Marks a request for the tile as answered. Returns whether it was for
+    // a full tile, if that can be told
+    fn answered(
+        &mut self,
+        kind: &'static str,
+        entry_id: &EntryID,
+        tile_id: TileID,
+    ) -> Option<bool> {
+        let key = (kind, entry_id.clone(), tile_id);
+        let (count, full) = self.outstanding.get_mut(&key)?;
+        let full = *full;
+        *count -= 1;
+        if *count == 0 {
+            self.outstanding.remove(&key);
+        }
+        full
+    }
+
+    fn store_tile<D: Serialize>(
+        &mut self,
+        kind: &'static str,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        tile: &D,
+    ) {
+        if let Some(full) = self.answered(kind, entry_id, tile_id) {
+            let path = self.tile_path(kind, entry_id, tile_id, full);
+            self.store(&path, tile);
+        }
+    }
+
+    // Working out which cached tiles are stale isn't worth it for profiles
+    // that are still changing, so drop all of them (but keep the info)
+    fn clear_tiles(&self) {
+        for kind in [SUMMARY_TILE, SLOT_TILE, SLOT_META_TILE] {
+            let _ = remove_dir_all(self.path.join(kind));
+            let _ = remove_dir_all(self.path.join(format!("{kind}_full")));
+        }
+        let _ = remove_dir_all(self.path.join(SLOT_META_ITEM));
+    }
+
+    fn check_info(&mut self, info: DataSourceInfo) -> DataSourceInfo {
+        let info_path = self.path.join("info");
+        let cached: Option<DataSourceInfo> = read_file(&info_path);
+
+        if info.is_unavailable() {
+            let Some(mut cached) = cached else {
+                return info;
+            };
+            self.offline = true;
+            let message = format!(
+                "Showing cached data. Tiles that were never loaded will appear empty. ({})",
+                info.warning_message.unwrap_or_default()
+            );
+            cached.warning_message = Some(match cached.warning_message {
+                Some(warning) => format!("{warning}\n\n{message}"),
+                None => message,
+            });
+            return cached;
+        }
+
+        self.offline = false;
+        if cached.map_or(true, |cached| encode(&cached) != encode(&info)) {
+            // The profile changed, so everything cached for it is stale
+            let _ = remove_dir_all(&self.path);
+            self.store(&info_path, &info);
+        }
+        info
+    }
+}
+
+impl<T: DeferredDataSource> DeferredDataSource for CachingDeferredDataSource<T> {
+    fn fetch_description(&self) -> DataSourceDescription {
+        self.data_source.fetch_description()
+    }
+
+    fn fetch_info(&mut self) {
+        self.data_source.fetch_info()
+    }
+
+    fn get_infos(&mut self) -> Vec<DataSourceInfo> {
+        let infos = self.data_source.get_infos();
+        infos
+            .into_iter()
+            .map(|info| self.check_info(info))
+            .collect()
+    }
+
+    fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        match self.lookup(SUMMARY_TILE, entry_id, tile_id, full) {
+            Ok(tile) => self.summary_tiles.push(tile),
+            Err(true) => self.data_source.fetch_summary_tile(entry_id, tile_id, full),
+            Err(false) => self.summary_tiles.push(SummaryTile {
+                entry_id: entry_id.clone(),
+                tile_id,
+                data: SummaryTileData {
+                    utilization: Vec::new(),
+                },
+            }),
+        }
+    }
+
+    fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
+        let mut result = std::mem::take(&mut self.summary_tiles);
+        for tile in self.data_source.get_summary_tiles() {
+            self.store_tile(SUMMARY_TILE, &tile.entry_id, tile.tile_id, &tile);
+            result.push(tile);
+        }
+        result
+    }
+
+    fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        match self.lookup(SLOT_TILE, entry_id, tile_id, full) {
+            Ok(tile) => self.slot_tiles.push(tile),
+            Err(true) => self.data_source.fetch_slot_tile(entry_id, tile_id, full),
+            Err(false) => self.slot_tiles.push(SlotTile {
+                entry_id: entry_id.clone(),
+                tile_id,
+                data: SlotTileData { items: Vec::new() },
+            }),
+        }
+    }
+
+    fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
+        let mut result = std::mem::take(&mut self.slot_tiles);
+        for tile in self.data_source.get_slot_tiles() {
+            self.store_tile(SLOT_TILE, &tile.entry_id, tile.tile_id, &tile);
+            result.push(tile);
+        }
+        result
+    }
+
+    fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        match self.lookup(SLOT_META_TILE, entry_id, tile_id, full) {
+            Ok(tile) => self.slot_meta_tiles.push(tile),
+            Err(true) => self
+                .data_source
+                .fetch_slot_meta_tile(entry_id, tile_id, full),
+            Err(false) => self.slot_meta_tiles.push(SlotMetaTile {
+                entry_id: entry_id.clone(),
+                tile_id,
+                data: SlotMetaTileData { items: Vec::new() },
+            }),
+        }
+    }
+
+    fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
+        let mut result = std::mem::take(&mut self.slot_meta_tiles);
+        for tile in self.data_source.get_slot_meta_tiles() {
+            self.store_tile(SLOT_META_TILE, &tile.entry_id, tile.tile_id, &tile);
+            result.push(tile);
+        }
+        result
+    }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        if let Some(item) = read_file(&self.item_path(entry_id, tile_id, item_uid)) {
+            self.slot_meta_items.push(item);
+        } else if self.offline {
+            self.slot_meta_items.push(SlotMetaItem {
+                entry_id: entry_id.clone(),
+                tile_id,
+                item_uid,
+                data: None,
+            });
+        } else {
+            self.data_source
+                .fetch_slot_meta_item(entry_id, tile_id, item_uid);
+        }
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        let mut result = std::mem::take(&mut self.slot_meta_items);
+        for item in self.data_source.get_slot_meta_items() {
+            let path = self.item_path(&item.entry_id, item.tile_id, item.item_uid);
+            self.store(&path, &item);
+            result.push(item);
+        }
+        result
+    }
+
+    fn fetch_updates(&mut self) {
+        if !self.offline {
+            self.data_source.fetch_updates()
+        }
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        let updates = self.data_source.get_updates();
+        if !updates.is_empty() {
+            self.clear_tiles();
+        }
+        updates
+    }
+
+    // Nothing is stored, so the request goes upstream again when retried
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        let failures = self.data_source.get_failures();
+        for failure in &failures {
+            if let (Some(kind), Some(entry_id), Some(tile_id)) =
+                (kind_name(failure.kind), &failure.entry_id, failure.tile_id)
+            {
+                self.answered(kind, entry_id, tile_id);
+            }
+        }
+        failures
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.data_source.set_active_entries(entries)
+    }
+
+    // Searches aren't cached, so they fail when offline, and the viewer
+    // searches whatever tiles are cached instead
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        if self.offline {
+            self.search_results.push(SearchResults::failed(
+                query.clone(),
+                interval,
+                "data source is offline".to_owned(),
+            ));
+        } else {
+            self.data_source.fetch_search(query, interval, limit)
+        }
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        let mut result = std::mem::take(&mut self.search_results);
+        result.extend(self.data_source.get_search_results());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_data::TestDeferredDataSource;
+    use crate::timestamp::Timestamp;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("legion_prof_cache_{name}_{}", std::process::id()));
+            let _ = remove_dir_all(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = remove_dir_all(&self.0);
+        }
+    }
+
+    fn open(
+        data_source: TestDeferredDataSource,
+        dir: &TempDir,
+    ) -> (
+        CachingDeferredDataSource<TestDeferredDataSource>,
+        DataSourceInfo,
+    ) {
+        let mut cache = CachingDeferredDataSource::new(data_source, &dir.0);
+        cache.fetch_info();
+        cache.data_source.release();
+        let info = cache.get_infos().pop().unwrap();
+        (cache, info)
+    }
+
+    fn tile_id(start: i64) -> TileID {
+        TileID(Interval::new(Timestamp(start), Timestamp(start + 100)), 0)
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let key = |locators: &[&str]| {
+            cache_key(&DataSourceDescription {
+                source_locator: locators.iter().map(|l| l.to_string()).collect(),
+            })
+        };
+        assert_eq!(key(&["a"]), key(&["a"]));
+        assert!(key(&["http://h/a"]).starts_with("http___h_a-"));
+        assert_ne!(key(&["http://h/a?x"]), key(&["http://h/a_x"]));
+        assert_ne!(key(&["a+b"]), key(&["a_b"]));
+        assert_ne!(key(&["a+b"]), key(&["a", "b"]));
+        let long = "x".repeat(300);
+        assert_ne!(key(&[&format!("{long}1")]), key(&[&format!("{long}2")]));
+        assert!(key(&[&long]).len() < 100);
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = TempDir::new("round_trip");
+        let slot = EntryID::root().child(0);
+
+        let (mut cache, info) = open(TestDeferredDataSource::default(), &dir);
+        assert_eq!(info.warning_message, None);
+        cache.fetch_slot_tile(&slot, tile_id(0), false);
+        cache.fetch_slot_meta_tile(&slot, tile_id(0), true);
+        assert_eq!(cache.get_slot_tiles().len(), 1);
+        assert_eq!(cache.get_slot_meta_tiles().len(), 1);
+
+        // Cached tiles are served without asking the data source again
+        let (mut cache, _) = open(TestDeferredDataSource::held(), &dir);
+        cache.fetch_slot_tile(&slot, tile_id(0), false);
+        assert_eq!(cache.data_source.pending(), 0);
+        let tiles = cache.get_slot_tiles();
+        assert_eq!(tiles[0].data.items[0][0].item_uid, ItemUID(0));
+
+        // Full and regular tiles are kept apart
+        cache.fetch_slot_meta_tile(&slot, tile_id(0), false);
+        assert_eq!(cache.data_source.pending(), 1);
+        cache.fetch_slot_meta_tile(&slot, tile_id(0), true);
+        assert_eq!(cache.data_source.pending(), 1);
+    }
+
+    #[test]
+    fn test_cache_mixed_requests() {
+        let dir = TempDir::new("mixed");
+        let slot = EntryID::root().child(0);
+
+        // With both kinds outstanding, neither result can be told apart, so
+        // neither is stored
+        let (mut cache, _) = open(TestDeferredDataSource::held(), &dir);
+        cache.fetch_slot_tile(&slot, tile_id(0), true);
+        cache.fetch_slot_tile(&slot, tile_id(0), false);
+        cache.data_source.release();
+        assert_eq!(cache.get_slot_tiles().len(), 2);
+        assert!(cache.outstanding.is_empty());
+        assert!(!cache.tile_path(SLOT_TILE, &slot, tile_id(0), true).exists());
+        assert!(!cache
+            .tile_path(SLOT_TILE, &slot, tile_id(0), false)
+            .exists());
+    }
+
+    #[test]
+    fn test_cache_offline() {
+        let dir = TempDir::new("offline");
+        let slot = EntryID::root().child(0);
+
+        let (mut cache, info) = open(TestDeferredDataSource::default(), &dir);
+        cache.fetch_slot_tile(&slot, tile_id(0), false);
+        cache.get_slot_tiles();
+
+        // A data source that can't be reached is replaced by the cache
+        let offline = TestDeferredDataSource::held()
+            .with_info(DataSourceInfo::unavailable("unreachable".to_owned()));
+        let (mut cache, cached) = open(offline, &dir);
+        assert_eq!(cached.interval, info.interval);
+        assert!(cached.warning_message.unwrap().contains("unreachable"));
+
+        cache.fetch_slot_tile(&slot, tile_id(0), false);
+        cache.fetch_slot_tile(&slot, tile_id(100), false);
+        assert_eq!(cache.data_source.pending(), 0);
+        let tiles = cache.get_slot_tiles();
+        assert_eq!(tiles[0].data.items.len(), 1);
+        assert!(tiles[1].data.items.is_empty());
+    }
+
+    #[test]
+    fn test_cache_empty_profile() {
+        let dir = TempDir::new("empty");
+        // Shaped just like the placeholder, but not one
+        let info = DataSourceInfo {
+            unavailable: false,
+            ..DataSourceInfo::unavailable("not actually".to_owned())
+        };
+        let (_, result) = open(TestDeferredDataSource::default().with_info(info), &dir);
+
+        // An empty profile is still a profile, not a reason to go offline
+        let offline = TestDeferredDataSource::default()
+            .with_info(DataSourceInfo::unavailable("unreachable".to_owned()));
+        assert!(!result.is_unavailable());
+        let (cache, _) = open(offline, &dir);
+        assert!(cache.offline);
+    }
+}
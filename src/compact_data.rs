@@ -0,0 +1,510 @@
+use std::collections::{BTreeSet, HashMap};
+
+use egui::Color32;
+
+use crate::data::{Field, FieldID, ItemMeta, ItemUID, SlotMetaTileData};
+use crate::timestamp::{Interval, Timestamp};
+
+// Compact in-memory form of SlotMetaTileData, for the viewer to hold on to
+// meta tiles (e.g., for search) without keeping millions of ItemMetas
+// around. Items are stored column by column: item UIDs and intervals as
+// delta-encoded varints, titles and fields interned per tile (titles and
+// most field values repeat heavily). Items are decoded on access. Only meta
+// tiles are compacted; SlotTileData is kept as is (see Slot::tiles).
+#[derive(Debug, Clone, Default)]
+pub struct CompactSlotMetaTileData {
+    // Row -> index of the row's first item (plus one past the last item)
+    row_items: Vec<u32>,
+    // Row -> offset of the row's first item in varints
+    row_offsets: Vec<u32>,
+    // Per item: UID, start and duration, each relative to the previous
+    // item in the row
+    varints: Vec<u8>,
+    // Item -> index into strings
+    titles: Vec<u32>,
+    strings: Vec<Box<str>>,
+    // Item -> index of the item's first field in field_refs (plus one past
+    // the last item)
+    item_fields: Vec<u32>,
+    // Index into field_offsets for each field of each item
+    field_refs: Vec<u32>,
+    // Distinct fields, encoded as CBOR back to back
+    field_offsets: Vec<u32>,
+    field_bytes: Vec<u8>,
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+// Map signed deltas to unsigned, so that small negative deltas stay small
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+// Running state for decoding the varints of one row
+#[derive(Default)]
+struct RowCursor {
+    pos: usize,
+    item_uid: u64,
+    start: i64,
+}
+
+impl RowCursor {
+    fn next(&mut self, varints: &[u8]) -> (ItemUID, Interval) {
+        self.item_uid = self
+            .item_uid
+            .wrapping_add(unzigzag(read_varint(varints, &mut self.pos)) as u64);
+        self.start = self
+            .start
+            .wrapping_add(unzigzag(read_varint(varints, &mut self.pos)));
+        let duration = unzigzag(read_varint(varints, &mut self.pos));
+        let start = Timestamp(self.start);
+        let stop = Timestamp(self.start.wrapping_add(duration));
+        (ItemUID(self.item_uid), Interval::new(start, stop))
+    }
+}
+
+impl CompactSlotMetaTileData {
+    pub fn new(data: &SlotMetaTileData) -> Self {
+        let mut result = Self::default();
+        let mut strings = HashMap::new();
+        let mut fields = HashMap::new();
+        let mut encoded = Vec::new();
+
+        result.row_items.push(0);
+        for row in &data.items {
+            result.row_offsets.push(result.varints.len() as u32);
+            let mut cursor = RowCursor::default();
+            for item in row {
+                let item_uid = item.item_uid.0;
+                let start = item.original_interval.start.0;
+                let duration = item.original_interval.stop.0.wrapping_sub(start);
+                let uid_delta = item_uid.wrapping_sub(cursor.item_uid) as i64;
+                write_varint(&mut result.varints, zigzag(uid_delta));
+                write_varint(
+                    &mut result.varints,
+                    zigzag(start.wrapping_sub(cursor.start)),
+                );
+                write_varint(&mut result.varints, zigzag(duration));
+                cursor.item_uid = item_uid;
+                cursor.start = start;
+
+                let title = *strings.entry(item.title.as_str()).or_insert_with(|| {
+                    result.strings.push(item.title.as_str().into());
+                    result.strings.len() as u32 - 1
+                });
+                result.titles.push(title);
+
+                result.item_fields.push(result.field_refs.len() as u32);
+                for field in &item.fields {
+                    encoded.clear();
+                    ciborium::into_writer(field, &mut encoded).expect("ciborium encoding failed");
+                    let index = match fields.get(&encoded) {
+                        Some(index) => *index,
+                        None => {
+                            let index = result.field_offsets.len() as u32;
+                            result.field_offsets.push(result.field_bytes.len() as u32);
+                            result.field_bytes.extend_from_slice(&encoded);
+                            fields.insert(encoded.clone(), index);
+                            index
+                        }
+                    };
+                    result.field_refs.push(index);
+                }
+            }
+            result.row_items.push(result.titles.len() as u32);
+        }
+        result.item_fields.push(result.field_refs.len() as u32);
+        result.field_offsets.push(result.field_bytes.len() as u32);
+        result.shrink_to_fit();
+        result
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.row_items.shrink_to_fit();
+        self.row_offsets.shrink_to_fit();
+        self.varints.shrink_to_fit();
+        self.titles.shrink_to_fit();
+        self.strings.shrink_to_fit();
+        self.item_fields.shrink_to_fit();
+        self.field_refs.shrink_to_fit();
+        self.field_offsets.shrink_to_fit();
+        self.field_bytes.shrink_to_fit();
+    }
+
+    // Approximate number of bytes held, for memory accounting
+    pub fn heap_size(&self) -> usize {
+        let strings: usize = self.strings.iter().map(|s| s.len()).sum();
+        (self.row_items.len()
+            + self.row_offsets.len()
+            + self.titles.len()
+            + self.item_fields.len()
+            + self.field_refs.len()
+            + self.field_offsets.len())
+            * std::mem::size_of::<u32>()
+            + self.strings.len() * std::mem::size_of::<Box<str>>()
+            + strings
+            + self.varints.len()
+            + self.field_bytes.len()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.row_offsets.len()
+    }
+
+    pub fn row_len(&self, row: usize) -> usize {
+        (self.row_items[row + 1] - self.row_items[row]) as usize
+    }
+
+    fn field(&self, index: u32) -> (FieldID, Field, Option<Color32>) {
+        let index = index as usize;
+        let start = self.field_offsets[index] as usize;
+        let stop = self.field_offsets[index + 1] as usize;
+        ciborium::from_reader(&self.field_bytes[start..stop]).expect("cbor decoding failed")
+    }
+
+    fn item_meta(&self, item: usize, item_uid: ItemUID, interval: Interval) -> ItemMeta {
+        let fields = self.item_fields[item] as usize..self.item_fields[item + 1] as usize;
+        ItemMeta {
+            item_uid,
+            original_interval: interval,
+            title: self.strings[self.titles[item] as usize].to_string(),
+            fields: self.field_refs[fields]
+                .iter()
+                .map(|index| self.field(*index))
+                .collect(),
+        }
+    }
+
+    // Items of one row, in order, as (index in row, UID, interval), without
+    // decoding their titles or fields
+    fn row_headers(&self, row: usize) -> impl Iterator<Item = (usize, ItemUID, Interval)> + '_ {
+        let mut cursor = RowCursor {
+            pos: self.row_offsets[row] as usize,
+            ..Default::default()
+        };
+        (0..self.row_len(row)).map(move |idx| {
+            let (item_uid, interval) = cursor.next(&self.varints);
+            (idx, item_uid, interval)
+        })
+    }
+
+    pub fn get(&self, row: usize, idx: usize) -> Option<ItemMeta> {
+        if row >= self.rows() || idx >= self.row_len(row) {
+            return None;
+        }
+        let (_, item_uid, interval) = self.row_headers(row).nth(idx)?;
+        let item = self.row_items[row] as usize + idx;
+        Some(self.item_meta(item, item_uid, interval))
+    }
+
+    pub fn find(&self, item_uid: ItemUID) -> Option<ItemMeta> {
+        (0..self.rows()).find_map(|row| {
+            let (idx, _, interval) = self.row_headers(row).find(|(_, uid, _)| *uid == item_uid)?;
+            let item = self.row_items[row] as usize + idx;
+            Some(self.item_meta(item, item_uid, interval))
+        })
+    }
+
+    // Every item, decoded, as (row, item)
+    pub fn iter(&self) -> impl Iterator<Item = (usize, ItemMeta)> + '_ {
+        (0..self.rows()).flat_map(move |row| {
+            let first = self.row_items[row] as usize;
+            self.row_headers(row).map(move |(idx, item_uid, interval)| {
+                (row, self.item_meta(first + idx, item_uid, interval))
+            })
+        })
+    }
+
+    // Value of a numeric field for every item, by row, or None for items
+    // without the field (or where it isn't numeric)
+    pub fn numeric_field(&self, field_id: FieldID) -> Vec<Vec<Option<f64>>> {
+        // Including inside groups, as in string_values
+        fn numeric(id: FieldID, field: Field, field_id: FieldID) -> Option<f64> {
+            match field {
+                Field::I64(v) if id == field_id => Some(v as f64),
+                Field::U64(v) if id == field_id => Some(v as f64),
+                Field::Group(_, fields) => fields
+                    .into_iter()
+                    .find_map(|(id, field)| numeric(id, field, field_id)),
+                _ => None,
+            }
+        }
+
+        // Items share distinct fields, so each is decoded at most once
+        let mut decoded: Vec<Option<Option<f64>>> =
+            vec![None; self.field_offsets.len().saturating_sub(1)];
+        let mut result = Vec::with_capacity(self.rows());
+        for row in 0..self.rows() {
+            let first = self.row_items[row] as usize;
+            let mut values = Vec::with_capacity(self.row_len(row));
+            for item in first..first + self.row_len(row) {
+                let fields = self.item_fields[item] as usize..self.item_fields[item + 1] as usize;
+                let mut value = None;
+                for index in &self.field_refs[fields] {
+                    let field_value = *decoded[*index as usize].get_or_insert_with(|| {
+                        let (id, field, _) = self.field(*index);
+                        numeric(id, field, field_id)
+                    });
+                    if field_value.is_some() {
+                        value = field_value;
+                        break;
+                    }
+                }
+                values.push(value);
+            }
+            result.push(values);
+        }
+        result
+    }
+
+    // Fields with numeric values (as in numeric_field) in the tile. Only the
+    // distinct fields are decoded
+    pub fn numeric_field_ids(&self) -> BTreeSet<FieldID> {
+        fn collect(id: FieldID, field: Field, result: &mut BTreeSet<FieldID>) {
+            match field {
+                Field::I64(_) | Field::U64(_) => {
+                    result.insert(id);
+                }
+                Field::Group(_, fields) => {
+                    for (id, field) in fields {
+                        collect(id, field, result);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut result = BTreeSet::new();
+        for index in 0..self.field_offsets.len().saturating_sub(1) {
+            let (id, field, _) = self.field(index as u32);
+            collect(id, field, &mut result);
+        }
+        result
+    }
+
+    // Distinct values of a string field (including inside groups) in the
+    // tile. Only the distinct fields are decoded
+    pub fn string_values(&self, field_id: FieldID) -> Vec<String> {
+        fn collect(id: FieldID, field: Field, field_id: FieldID, result: &mut Vec<String>) {
+            match field {
+                Field::String(value) if id == field_id => result.push(value),
+                Field::Group(_, fields) => {
+                    for (id, field) in fields {
+                        collect(id, field, field_id, result);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut result = Vec::new();
+        for index in 0..self.field_offsets.len().saturating_sub(1) {
+            let (id, field, _) = self.field(index as u32);
+            collect(id, field, field_id, &mut result);
+        }
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    // Distinct titles of the items in the tile
+    pub fn titles(&self) -> impl Iterator<Item = &str> {
+        self.strings.iter().map(|title| &**title)
+    }
+
+    pub fn decode(&self) -> SlotMetaTileData {
+        let mut items: Vec<Vec<ItemMeta>> = vec![Vec::new(); self.rows()];
+        for (row, item) in self.iter() {
+            items[row].push(item);
+        }
+        SlotMetaTileData { items }
+    }
+}
+
+impl From<&SlotMetaTileData> for CompactSlotMetaTileData {
+    fn from(data: &SlotMetaTileData) -> Self {
+        Self::new(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::data::FieldSchema;
+
+    fn item(item_uid: u64, start: i64, stop: i64, title: &str, fields: Vec<Field>) -> ItemMeta {
+        let field_id = FieldSchema::new().insert("field".to_owned(), false);
+        ItemMeta {
+            item_uid: ItemUID(item_uid),
+            original_interval: Interval::new(Timestamp(start), Timestamp(stop)),
+            title: title.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|field| (field_id, field, None))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_compact_meta_tile() {
+        let data = SlotMetaTileData {
+            items: vec![
+                vec![
+                    item(7, 100, 200, "task", vec![Field::U64(1)]),
+                    item(3, -50, i64::MAX, "task", vec![Field::U64(1), Field::Empty]),
+                ],
+                Vec::new(),
+                vec![item(u64::MAX, i64::MIN, 0, "other", Vec::new())],
+            ],
+        };
+        let compact = CompactSlotMetaTileData::new(&data);
+        assert_eq!(compact.rows(), 3);
+        assert_eq!(compact.row_len(1), 0);
+        assert_eq!(compact.strings.len(), 2);
+        assert_eq!(compact.field_offsets.len() - 1, 2);
+
+        // Round trips exactly
+        let encode = |data: &SlotMetaTileData| {
+            let mut result = Vec::new();
+            ciborium::into_writer(data, &mut result).unwrap();
+            result
+        };
+        assert_eq!(encode(&compact.decode()), encode(&data));
+
+        let second = compact.get(0, 1).unwrap();
+        assert_eq!(second.item_uid, ItemUID(3));
+        assert_eq!(second.original_interval.stop, Timestamp(i64::MAX));
+        assert_eq!(second.fields.len(), 2);
+        assert!(compact.get(1, 0).is_none());
+        assert!(compact.get(3, 0).is_none());
+        assert_eq!(compact.find(ItemUID(u64::MAX)).unwrap().title, "other");
+        assert!(compact.find(ItemUID(4)).is_none());
+    }
+
+    #[test]
+    fn test_numeric_field() {
+        let mut schema = FieldSchema::new();
+        let size = schema.insert("size".to_owned(), false);
+        let name = schema.insert("name".to_owned(), false);
+        let group = schema.insert("group".to_owned(), false);
+        let depth = schema.insert("depth".to_owned(), false);
+        let meta = |item_uid, fields: Vec<(FieldID, Field)>| ItemMeta {
+            item_uid: ItemUID(item_uid),
+            original_interval: Interval::new(Timestamp(0), Timestamp(1)),
+            title: "task".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(field_id, field)| (field_id, field, None))
+                .collect(),
+        };
+        let data = SlotMetaTileData {
+            items: vec![
+                vec![
+                    meta(
+                        1,
+                        vec![(name, Field::String("a".to_owned())), (size, Field::U64(8))],
+                    ),
+                    meta(2, vec![(size, Field::I64(-3))]),
+                ],
+                vec![
+                    meta(3, vec![(name, Field::U64(5))]),
+                    meta(4, vec![(size, Field::String("big".to_owned()))]),
+                    meta(5, vec![(size, Field::U64(8))]),
+                    meta(
+                        6,
+                        vec![(
+                            group,
+                            Field::Group(
+                                "g".to_owned(),
+                                vec![(depth, Field::I64(2)), (size, Field::U64(16))],
+                            ),
+                        )],
+                    ),
+                ],
+            ],
+        };
+        let compact = CompactSlotMetaTileData::new(&data);
+        assert_eq!(
+            compact.numeric_field(size),
+            vec![
+                vec![Some(8.0), Some(-3.0)],
+                vec![None, None, Some(8.0), Some(16.0)]
+            ]
+        );
+        assert_eq!(
+            compact.numeric_field(depth),
+            vec![vec![None, None], vec![None, None, None, Some(2.0)]]
+        );
+        assert_eq!(
+            compact.numeric_field_ids(),
+            BTreeSet::from([size, name, depth])
+        );
+
+        // Grouped fields round trip along with the rest
+        let decoded = compact.decode();
+        let grouped = &decoded.items[1][3].fields[0];
+        assert_eq!(grouped.0, group);
+        assert!(matches!(
+            &grouped.1,
+            Field::Group(_, fields) if matches!(fields[1], (id, Field::U64(16)) if id == size)
+        ));
+    }
+
+    #[test]
+    fn test_string_values() {
+        let mut schema = FieldSchema::new();
+        let kind = schema.insert("kind".to_owned(), true);
+        let other = schema.insert("other".to_owned(), true);
+        let meta = |title: &str, fields: Vec<(FieldID, Field)>| ItemMeta {
+            item_uid: ItemUID(1),
+            original_interval: Interval::new(Timestamp(0), Timestamp(1)),
+            title: title.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(field_id, field)| (field_id, field, None))
+                .collect(),
+        };
+        let string = |value: &str| Field::String(value.to_owned());
+        let data = SlotMetaTileData {
+            items: vec![vec![
+                meta("a", vec![(kind, string("copy")), (other, string("x"))]),
+                meta("b", vec![(kind, string("fill"))]),
+                meta("a", vec![(kind, string("copy")), (kind, Field::U64(3))]),
+                meta(
+                    "c",
+                    vec![(
+                        other,
+                        Field::Group("g".to_owned(), vec![(kind, string("dma"))]),
+                    )],
+                ),
+            ]],
+        };
+        let compact = CompactSlotMetaTileData::new(&data);
+        assert_eq!(compact.string_values(kind), vec!["copy", "dma", "fill"]);
+        assert_eq!(compact.string_values(other), vec!["x"]);
+        assert_eq!(compact.titles().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::data::{
+    DataSource, DataSourceInfo, EntryID, EntryInfo, Field, ItemMeta, SlotMetaTile, SlotTile,
+    SummaryTile, TileID, PROTOCOL_VERSION,
+};
+use crate::deferred_data::{
+    CountingDeferredDataSource, DeferredDataSource, DeferredDataSourceWrapper, RequestStatus,
+};
+use crate::timestamp::{Interval, Timestamp};
+
+// Checks that a data source returns what the viewer expects, so that authors
+// of data sources can validate them without having to chase down the
+// resulting misbehavior in the UI. Violations are collected rather than
+// reported at the first one, as they tend to come in groups.
+
+#[derive(Debug, Clone)]
+pub struct ConformanceError {
+    pub entry_id: Option<EntryID>,
+    pub tile_id: Option<TileID>,
+    pub message: String,
+}
+
+impl fmt::Display for ConformanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(entry_id) = &self.entry_id {
+            write!(f, "entry {:?}: ", entry_id)?;
+        }
+        if let Some(tile_id) = &self.tile_id {
+            write!(f, "tile {}: ", tile_id.0)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+pub struct ConformanceChecker<T: DeferredDataSource> {
+    data_source: CountingDeferredDataSource<T>,
+    // Number of tiles to split the interval into, for data sources that
+    // don't have a static tile set
+    dynamic_tiles: u64,
+    // How long to wait for each batch of requests to be answered. Once a
+    // batch times out, the data source is assumed to have stopped answering,
+    // and the check ends there
+    timeout: Duration,
+    timed_out: bool,
+    errors: Vec<ConformanceError>,
+}
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+// How often to check for responses while waiting on the data source
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+pub fn check_data_source<T: DataSource>(data_source: T) -> Vec<ConformanceError> {
+    ConformanceChecker::new(DeferredDataSourceWrapper::new(data_source), 4).check()
+}
+
+fn check_entry_info(
+    entry: &EntryInfo,
+    entry_id: EntryID,
+    slots: &mut BTreeMap<EntryID, u64>,
+    summaries: &mut Vec<EntryID>,
+    errors: &mut Vec<ConformanceError>,
+) {
+    match entry {
+        EntryInfo::Panel {
+            summary,
+            slots: children,
+            ..
+        } => {
+            if let Some(summary) = summary {
+                if let EntryInfo::Summary { .. } = **summary {
+                    summaries.push(entry_id.summary());
+                } else {
+                    errors.push(ConformanceError {
+                        entry_id: Some(entry_id.clone()),
+                        tile_id: None,
+                        message: "panel summary is not a Summary entry".to_owned(),
+                    });
+                }
+            }
+            for (i, child) in children.iter().enumerate() {
+                check_entry_info(child, entry_id.child(i as u64), slots, summaries, errors);
+            }
+        }
+        EntryInfo::Slot {
+            max_rows,
+            row_names,
+            ..
+        } => {
+            if row_names.len() as u64 > *max_rows {
+                errors.push(ConformanceError {
+                    entry_id: Some(entry_id.clone()),
+                    tile_id: None,
+                    message: format!(
+                        "slot has {} row names, but declares max_rows = {max_rows}",
+                        row_names.len()
+                    ),
+                });
+            }
+            slots.insert(entry_id, *max_rows);
+        }
+        EntryInfo::Summary { .. } => {
+            errors.push(ConformanceError {
+                entry_id: Some(entry_id),
+                tile_id: None,
+                message: "Summary entry outside of a panel summary".to_owned(),
+            });
+        }
+    }
+}
+
+fn check_fields(item: &ItemMeta, info: &DataSourceInfo) -> Option<String> {
+    fn check(field: &Field, info: &DataSourceInfo) -> bool {
+        match field {
+            Field::Vec(elts) => elts.iter().all(|elt| check(elt, info)),
+            Field::Group(_, elts) => elts
+                .iter()
+                .all(|(id, elt)| info.field_schema.contains_id(*id) && check(elt, info)),
+            _ => true,
+        }
+    }
+    for (id, field, _) in &item.fields {
+        if !info.field_schema.contains_id(*id) || !check(field, info) {
+            return Some(format!(
+                "item {:?} uses a field that is not in the field schema",
+                item.item_uid
+            ));
+        }
+    }
+    None
+}
+
+impl<T: DeferredDataSource> ConformanceChecker<T> {
+    pub fn new(data_source: T, dynamic_tiles: u64) -> Self {
+        assert!(dynamic_tiles >= 1);
+        Self {
+            data_source: CountingDeferredDataSource::new(data_source),
+            dynamic_tiles,
+            timeout: DEFAULT_TIMEOUT,
+            timed_out: false,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    // True while there are requests left to wait for, and the deadline
+    // hasn't passed. Otherwise, the requests still outstanding are reported
+    fn waiting(&mut self, deadline: Instant) -> bool {
+        if self.timed_out || self.data_source.outstanding_requests() == 0 {
+            return false;
+        }
+        if Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+            return true;
+        }
+        self.timed_out = true;
+        let unanswered: Vec<_> = self
+            .data_source
+            .request_log()
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.status,
+                    RequestStatus::Queued | RequestStatus::InFlight
+                )
+            })
+            .map(|entry| (entry.kind, entry.entry_id.clone(), entry.tile_id))
+            .collect();
+        for (kind, entry_id, tile_id) in unanswered {
+            self.error(
+                entry_id.as_ref(),
+                tile_id,
+                format!(
+                    "{} request was not answered within {:?}",
+                    kind.label_text(),
+                    self.timeout
+                ),
+            );
+        }
+        false
+    }
+
+    fn error(&mut self, entry_id: Option<&EntryID>, tile_id: Option<TileID>, message: String) {
+        self.errors.push(ConformanceError {
+            entry_id: entry_id.cloned(),
+            tile_id,
+            message,
+        });
+    }
+
+    fn check_info(&mut self, info: &DataSourceInfo) {
+        if info.protocol_version != PROTOCOL_VERSION {
+            self.error(
+                None,
+                None,
+                format!(
+                    "protocol version is {} (expected {PROTOCOL_VERSION})",
+                    info.protocol_version
+                ),
+            );
+        }
+        if info.interval.duration_ns() <= 0 {
+            self.error(None, None, format!("interval {} is empty", info.interval));
+        }
+        if !matches!(info.entry_info, EntryInfo::Panel { .. }) {
+            self.error(None, None, "root entry is not a panel".to_owned());
+        }
+    }
+
+    // Tiles to request at each level, along with whether they are full
+    fn tile_levels(&mut self, info: &DataSourceInfo) -> Vec<(Vec<TileID>, bool)> {
+        if info.tile_set.tiles.is_empty() {
+            let interval = info.interval;
+            let n = self.dynamic_tiles as i64;
+            let split = (0..n)
+                .map(|i| {
+                    let start = Timestamp(interval.start.0 + interval.duration_ns() * i / n);
+                    let stop = Timestamp(interval.start.0 + interval.duration_ns() * (i + 1) / n);
+                    TileID(Interval::new(start, stop), 0)
+                })
+                .collect();
+            return vec![(vec![TileID(interval, 0)], false), (split, true)];
+        }
+
+        // Each level of a static tile set must cover the interval exactly,
+        // without gaps or overlaps. Tiles are labeled with their level, or
+        // with 0 by data sources that predate levels
+        let levels = info.tile_set.tiles.clone();
+        let level_meta = info.tile_set.level_meta.len();
+        if level_meta != 0 && level_meta != levels.len() {
+            self.error(
+                None,
+                None,
+                format!(
+                    "tile set has metadata for {level_meta} levels (expected {})",
+                    levels.len()
+                ),
+            );
+        }
+        for (level, tiles) in levels.iter().enumerate() {
+            let mut expected_start = info.interval.start;
+            for tile_id in tiles {
+                if tile_id.1 != 0 && tile_id.1 as usize != level {
+                    self.error(
+                        None,
+                        Some(*tile_id),
+                        format!("tile in level {level} is labeled with level {}", tile_id.1),
+                    );
+                }
+                if tile_id.0.start != expected_start {
+                    self.error(
+                        None,
+                        Some(*tile_id),
+                        format!("tile in level {level} does not start at {expected_start}"),
+                    );
+                }
+                expected_start = tile_id.0.stop;
+            }
+            if expected_start != info.interval.stop {
+                self.error(
+                    None,
+                    None,
+                    format!(
+                        "tiles in level {level} do not cover the interval {}",
+                        info.interval
+                    ),
+                );
+            }
+        }
+        let last = levels.len() - 1;
+        levels
+            .into_iter()
+            .enumerate()
+            .map(|(level, tiles)| (tiles, level == last))
+            .collect()
+    }
+
+    fn check_summary_tile(&mut self, tile: &SummaryTile) {
+        let mut last = None;
+        for point in &tile.data.utilization {
+            if !(0.0..=1.0).contains(&point.util) {
+                self.error(
+                    Some(&tile.entry_id),
+                    Some(tile.tile_id),
+                    format!(
+                        "utilization {} at {} is outside of [0, 1]",
+                        point.util, point.time
+                    ),
+                );
+            }
+            if last.is_some_and(|last| point.time < last) {
+                self.error(
+                    Some(&tile.entry_id),
+                    Some(tile.tile_id),
+                    "utilization points are not sorted by time".to_owned(),
+                );
+            }
+            last = Some(point.time);
+        }
+    }
+
+    fn check_slot_tiles(
+        &mut self,
+        tile: &SlotTile,
+        meta: &SlotMetaTile,
+        max_rows: u64,
+        info: &DataSourceInfo,
+    ) {
+        let entry_id = Some(&tile.entry_id);
+        let tile_id = Some(tile.tile_id);
+
+        if tile.data.items.len() as u64 > max_rows {
+            self.error(
+                entry_id,
+                tile_id,
+                format!(
+                    "tile has {} rows, but the slot declares max_rows = {max_rows}",
+                    tile.data.items.len()
+                ),
+            );
+        }
+        if tile.data.items.len() != meta.data.items.len() {
+            self.error(
+                entry_id,
+                tile_id,
+                format!(
+                    "tile has {} rows, but the meta tile has {}",
+                    tile.data.items.len(),
+                    meta.data.items.len()
+                ),
+            );
+            return;
+        }
+
+        for (row, (items, metas)) in tile.data.items.iter().zip(&meta.data.items).enumerate() {
+            if items.len() != metas.len() {
+                self.error(
+                    entry_id,
+                    tile_id,
+                    format!(
+                        "row {row} has {} items, but {} in the meta tile",
+                        items.len(),
+                        metas.len()
+                    ),
+                );
+                continue;
+            }
+            let mut last_stop = None;
+            for (item, meta) in items.iter().zip(metas) {
+                if item.item_uid != meta.item_uid {
+                    self.error(
+                        entry_id,
+                        tile_id,
+                        format!(
+                            "row {row} has item {:?} where the meta tile has {:?}",
+                            item.item_uid, meta.item_uid
+                        ),
+                    );
+                }
+                if !item.interval.overlaps(tile.tile_id.0) {
+                    self.error(
+                        entry_id,
+                        tile_id,
+                        format!(
+                            "item {:?} at {} does not overlap the tile",
+                            item.item_uid, item.interval
+                        ),
+                    );
+                }
+                if last_stop.is_some_and(|stop| item.interval.start < stop) {
+                    self.error(
+                        entry_id,
+                        tile_id,
+                        format!(
+                            "item {:?} in row {row} is out of order or overlaps the previous item",
+                            item.item_uid
+                        ),
+                    );
+                }
+                last_stop = Some(item.interval.stop);
+                if let Some(message) = check_fields(meta, info) {
+                    self.error(entry_id, tile_id, message);
+                }
+            }
+        }
+    }
+
+    // Failed requests are reported rather than waited on, so that the
+    // checker finishes even when the data source can't answer
+    fn check_failures(&mut self) {
+        for failure in self.data_source.get_failures() {
+            self.error(
+                failure.entry_id.as_ref(),
+                failure.tile_id,
+                format!(
+                    "{} request failed: {}",
+                    failure.kind.label_text(),
+                    failure.error
+                ),
+            );
+        }
+    }
+
+    fn check_tile_id(&mut self, entry_id: &EntryID, tile_id: TileID, expected: &[TileID]) {
+        if !expected.contains(&tile_id) {
+            self.error(
+                Some(entry_id),
+                Some(tile_id),
+                "returned a tile that was not requested".to_owned(),
+            );
+        }
+    }
+
+    fn check_level(
+        &mut self,
+        info: &DataSourceInfo,
+        slots: &BTreeMap<EntryID, u64>,
+        summaries: &[EntryID],
+        tile_ids: &[TileID],
+        full: bool,
+    ) {
+        for entry_id in summaries {
+            for tile_id in tile_ids {
+                self.data_source
+                    .fetch_summary_tile(entry_id, *tile_id, full);
+            }
+        }
+        for entry_id in slots.keys() {
+            for tile_id in tile_ids {
+                self.data_source.fetch_slot_tile(entry_id, *tile_id, full);
+                self.data_source
+                    .fetch_slot_meta_tile(entry_id, *tile_id, full);
+            }
+        }
+
+        let mut tiles = BTreeMap::new();
+        let mut metas = BTreeMap::new();
+        let deadline = Instant::now() + self.timeout;
+        while self.waiting(deadline) {
+            for tile in self.data_source.get_summary_tiles() {
+                self.check_tile_id(&tile.entry_id, tile.tile_id, tile_ids);
+                self.check_summary_tile(&tile);
+            }
+            for tile in self.data_source.get_slot_tiles() {
+                self.check_tile_id(&tile.entry_id, tile.tile_id, tile_ids);
+                tiles.insert((tile.entry_id.clone(), tile.tile_id), tile);
+            }
+            for tile in self.data_source.get_slot_meta_tiles() {
+                self.check_tile_id(&tile.entry_id, tile.tile_id, tile_ids);
+                metas.insert((tile.entry_id.clone(), tile.tile_id), tile);
+            }
+            self.check_failures();
+        }
+        if self.timed_out {
+            return;
+        }
+
+        for (key, tile) in &tiles {
+            let Some(&max_rows) = slots.get(&key.0) else {
+                self.error(
+                    Some(&key.0),
+                    Some(key.1),
+                    "returned a tile for an entry that is not a slot".to_owned(),
+                );
+                continue;
+            };
+            let Some(meta) = metas.get(key) else {
+                self.error(Some(&key.0), Some(key.1), "meta tile is missing".to_owned());
+                continue;
+            };
+            self.check_slot_tiles(tile, meta, max_rows, info);
+        }
+
+        // Individually fetched items must agree with the meta tiles
+        for (key, meta) in &metas {
+            let Some(item) = meta.data.items.iter().flatten().next() else {
+                continue;
+            };
+            self.data_source
+                .fetch_slot_meta_item(&key.0, key.1, item.item_uid);
+            let mut result = Vec::new();
+            let deadline = Instant::now() + self.timeout;
+            while self.waiting(deadline) {
+                result.extend(self.data_source.get_slot_meta_items());
+                self.check_failures();
+            }
+            if self.timed_out {
+                return;
+            }
+            let found = result.pop().and_then(|result| result.data);
+            if found.as_ref().map(|found| (found.item_uid, &found.title))
+                != Some((item.item_uid, &item.title))
+            {
+                self.error(
+                    Some(&key.0),
+                    Some(key.1),
+                    format!(
+                        "fetching item {:?} individually does not match the meta tile",
+                        item.item_uid
+                    ),
+                );
+            }
+        }
+    }
+
+    pub fn check(mut self) -> Vec<ConformanceError> {
+        self.data_source.fetch_info();
+        let mut infos = Vec::new();
+        let deadline = Instant::now() + self.timeout;
+        while infos.is_empty() && self.waiting(deadline) {
+            infos = self.data_source.get_infos();
+            self.check_failures();
+        }
+        let Some(info) = infos.pop() else {
+            return self.errors;
+        };
+        self.check_info(&info);
+
+        let mut slots = BTreeMap::new();
+        let mut summaries = Vec::new();
+        check_entry_info(
+            &info.entry_info,
+            EntryID::root(),
+            &mut slots,
+            &mut summaries,
+            &mut self.errors,
+        );
+
+        for (tile_ids, full) in self.tile_levels(&info) {
+            self.check_level(&info, &slots, &summaries, &tile_ids, full);
+            if self.timed_out {
+                break;
+            }
+        }
+
+        self.errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_data::{TestDataSource, TestDeferredDataSource};
+
+    #[test]
+    fn test_check_data_source() {
+        assert!(check_data_source(TestDataSource::default()).is_empty());
+
+        let errors = check_data_source(TestDataSource {
+            broken: true,
+            ..Default::default()
+        });
+        assert!(!errors.is_empty());
+        assert!(errors.iter().all(|e| e.message.contains("meta tile has 0")));
+    }
+
+    #[test]
+    fn test_check_failed_requests() {
+        let mut data_source = TestDeferredDataSource::default();
+        data_source.fail_tiles = true;
+        let errors = ConformanceChecker::new(data_source, 4).check();
+        assert!(!errors.is_empty());
+        assert!(errors
+            .iter()
+            .all(|e| e.message.contains("request failed: tile unavailable")));
+    }
+
+    #[test]
+    fn test_check_dropped_requests() {
+        let mut data_source = TestDeferredDataSource::default();
+        data_source.drop_tiles = true;
+        let errors = ConformanceChecker::new(data_source, 4)
+            .with_timeout(Duration::from_millis(10))
+            .check();
+        // The first level has a tile and a meta tile for the only slot
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|e| e.message.contains("request was not answered within 10ms")));
+    }
+}
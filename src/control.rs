@@ -0,0 +1,300 @@
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::app::{ViewerCommand, ViewerHandle};
+use crate::data::ItemUID;
+use crate::http::tls::TlsOptions;
+use crate::timestamp::{Interval, Timestamp};
+use crate::view_link::ViewLink;
+
+// Line-based protocol for driving a running viewer from scripts, one command
+// per line on stdin:
+//
+//     zoom <start> <stop>      e.g., zoom 1.5ms 3ms
+//     select-item <uid>
+//     search <query>
+//     open <url or path>
+//     expand <pattern>         e.g., expand node3/cpu/*
+//     snapshot
+//
+// Each command is answered with a line on stdout once it has been applied:
+// "ok", "error: <message>", or for snapshots, the snapshot itself as JSON.
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Zoom(Interval),
+    SelectItem(ItemUID),
+    Search(String),
+    Open(String),
+    Expand(String),
+    Snapshot,
+}
+
+// Initial view requested on the command line, so that a view can be
+// reproduced exactly (e.g., from a bug report):
+//
+//     --interval <start>:<stop>    e.g., --interval 1.2s:1.5s
+//     --expand <pattern>           e.g., --expand node3/cpu/* (repeatable)
+//     --link <token>               as copied with the Copy Link button
+//     --cache-dir <dir>            cache profiles opened by URL here
+//     --connect-timeout <seconds>  give up connecting to a server after this
+//     --read-timeout <seconds>     give up on a response after this
+//
+// along with the TLS options for profiles opened later (see TlsOptions).
+// Other arguments are ignored, so that they can be handled by the caller
+#[derive(Debug, Default, PartialEq)]
+pub struct ViewOptions {
+    pub interval: Option<Interval>,
+    pub expand: Vec<String>,
+    pub link: Option<ViewLink>,
+    pub tls: TlsOptions,
+    pub cache_dir: Option<PathBuf>,
+    pub connect_timeout: Option<Duration>,
+    pub read_timeout: Option<Duration>,
+}
+
+// A whole number of seconds, as given on the command line
+pub fn parse_timeout(value: &str) -> Result<Duration, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("invalid timeout {value}"))
+}
+
+impl ViewOptions {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args: Vec<_> = args.into_iter().collect();
+        let mut result = Self {
+            tls: TlsOptions::extract_args(&mut args)?,
+            ..Self::default()
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_owned(), Some(value.to_owned())),
+                None => (arg, None),
+            };
+            const NAMES: [&str; 6] = [
+                "--interval",
+                "--expand",
+                "--link",
+                "--cache-dir",
+                "--connect-timeout",
+                "--read-timeout",
+            ];
+            if !NAMES.contains(&name.as_str()) {
+                continue;
+            }
+            let Some(value) = value.or_else(|| args.next()) else {
+                return Err(format!("missing value for {name}"));
+            };
+            match name.as_str() {
+                "--interval" => result.interval = Some(Interval::parse(&value)?),
+                "--link" => result.link = Some(ViewLink::parse_token(&value)?),
+                "--cache-dir" => result.cache_dir = Some(PathBuf::from(value)),
+                "--connect-timeout" => result.connect_timeout = Some(parse_timeout(&value)?),
+                "--read-timeout" => result.read_timeout = Some(parse_timeout(&value)?),
+                _ => result.expand.push(value),
+            }
+        }
+        Ok(result)
+    }
+
+    // Commands are held until the profiles have loaded, so this can be
+    // called as soon as the viewer starts
+    pub fn apply(&self, handle: &ViewerHandle) {
+        if self.tls != TlsOptions::default() {
+            handle.set_tls_options(self.tls.clone());
+        }
+        if self.cache_dir.is_some() {
+            handle.set_cache_dir(self.cache_dir.clone());
+        }
+        if self.connect_timeout.is_some() || self.read_timeout.is_some() {
+            handle.set_timeouts(self.connect_timeout, self.read_timeout);
+        }
+        // The link's view is restored before the options below, so that
+        // they take precedence
+        if let Some(link) = &self.link {
+            handle.open_link(link.clone());
+        }
+        for pattern in &self.expand {
+            handle.expand(pattern.clone());
+        }
+        if let Some(interval) = self.interval {
+            handle.set_interval(interval);
+        }
+    }
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let args = args.trim();
+    match name {
+        "zoom" => {
+            let [start, stop] = args.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err("usage: zoom <start> <stop>".to_owned());
+            };
+            let start = Timestamp::parse(start).map_err(|e| format!("invalid start: {e:?}"))?;
+            let stop = Timestamp::parse(stop).map_err(|e| format!("invalid stop: {e:?}"))?;
+            if start >= stop {
+                return Err("start must be before stop".to_owned());
+            }
+            Ok(Command::Zoom(Interval::new(start, stop)))
+        }
+        "select-item" => {
+            let uid = args
+                .parse()
+                .map_err(|_| "usage: select-item <uid>".to_owned())?;
+            Ok(Command::SelectItem(ItemUID(uid)))
+        }
+        "search" => Ok(Command::Search(args.to_owned())),
+        "open" if !args.is_empty() => Ok(Command::Open(args.to_owned())),
+        "open" => Err("usage: open <url or path>".to_owned()),
+        "expand" if !args.is_empty() => Ok(Command::Expand(args.to_owned())),
+        "expand" => Err("usage: expand <pattern>".to_owned()),
+        "snapshot" => Ok(Command::Snapshot),
+        _ => Err(format!("unknown command: {name}")),
+    }
+}
+
+// Reads commands from stdin on a separate thread until stdin is closed or
+// the viewer exits
+pub fn spawn_stdin_control(handle: ViewerHandle) {
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = match parse_command(&line) {
+                Ok(command) => command,
+                Err(e) => {
+                    println!("error: {e}");
+                    continue;
+                }
+            };
+            let command = match command {
+                Command::Zoom(interval) => ViewerCommand::SetInterval(interval),
+                Command::SelectItem(item_uid) => ViewerCommand::SelectItem(item_uid),
+                Command::Search(query) => ViewerCommand::Search(query),
+                Command::Open(locator) => ViewerCommand::Open(locator),
+                Command::Expand(pattern) => ViewerCommand::Expand(pattern),
+                // Fails if the viewer exits before taking the snapshot
+                Command::Snapshot => match handle.snapshot().recv() {
+                    Ok(snapshot) => {
+                        let json = serde_json::to_string(&snapshot).expect("snapshot encoding");
+                        println!("{json}");
+                        continue;
+                    }
+                    Err(_) => break,
+                },
+            };
+            // Fails if the viewer exits before applying the command
+            match handle.send_acknowledged(command).recv() {
+                Ok(Ok(())) => println!("ok"),
+                Ok(Err(e)) => println!("error: {e}"),
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("zoom 1ms  2ms"),
+            Ok(Command::Zoom(Interval::new(
+                Timestamp(1_000_000),
+                Timestamp(2_000_000)
+            )))
+        );
+        assert!(parse_command("zoom 2ms 1ms").is_err());
+        assert!(parse_command("zoom 1ms").is_err());
+        assert_eq!(
+            parse_command("select-item 42"),
+            Ok(Command::SelectItem(ItemUID(42)))
+        );
+        assert!(parse_command("select-item x").is_err());
+        assert_eq!(
+            parse_command("search  task 1 "),
+            Ok(Command::Search("task 1".to_owned()))
+        );
+        assert_eq!(
+            parse_command("open http://localhost:8080/"),
+            Ok(Command::Open("http://localhost:8080/".to_owned()))
+        );
+        assert!(parse_command("open").is_err());
+        assert_eq!(
+            parse_command("expand node3/cpu/*"),
+            Ok(Command::Expand("node3/cpu/*".to_owned()))
+        );
+        assert_eq!(parse_command("snapshot"), Ok(Command::Snapshot));
+        assert!(parse_command("quit").is_err());
+    }
+
+    #[test]
+    fn test_view_options() {
+        let args = |s: &str| s.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+        assert_eq!(
+            ViewOptions::parse(args(
+                "--control-stdin --interval 1.2s:1.5s --expand node3/cpu/* --expand=n0"
+            )),
+            Ok(ViewOptions {
+                interval: Some(Interval::new(
+                    Timestamp(1_200_000_000),
+                    Timestamp(1_500_000_000)
+                )),
+                expand: vec!["node3/cpu/*".to_owned(), "n0".to_owned()],
+                link: None,
+                tls: TlsOptions::default(),
+                cache_dir: None,
+                connect_timeout: None,
+                read_timeout: None,
+            })
+        );
+        assert_eq!(
+            ViewOptions::parse(args("--connect-timeout 5 --read-timeout=300"))
+                .map(|o| (o.connect_timeout, o.read_timeout)),
+            Ok((Some(Duration::from_secs(5)), Some(Duration::from_secs(300))))
+        );
+        assert!(ViewOptions::parse(args("--read-timeout 0")).is_err());
+        assert_eq!(
+            ViewOptions::parse(args("--cache-dir /tmp/cache")).map(|o| o.cache_dir),
+            Ok(Some(PathBuf::from("/tmp/cache")))
+        );
+        assert!(
+            ViewOptions::parse(args("--insecure --expand n0"))
+                .unwrap()
+                .tls
+                .accept_invalid_certs
+        );
+        assert_eq!(
+            ViewOptions::parse(args("--interval=01:02:1:03")).map(|o| o.interval),
+            Ok(Some(Interval::new(
+                Timestamp(62_000_000_000),
+                Timestamp(63_000_000_000)
+            )))
+        );
+        assert!(ViewOptions::parse(args("--interval 2s:1s")).is_err());
+        assert!(ViewOptions::parse(args("--interval 1s")).is_err());
+        assert!(ViewOptions::parse(args("--expand")).is_err());
+        assert_eq!(
+            ViewOptions::parse(args("--link legion-prof:url=/tmp/prof&expand=n0/cpu"))
+                .unwrap()
+                .link
+                .map(|link| (link.sources, link.expand)),
+            Some((vec!["/tmp/prof".to_owned()], vec!["n0/cpu".to_owned()]))
+        );
+        assert!(ViewOptions::parse(args("--link /tmp/prof")).is_err());
+    }
+}
@@ -0,0 +1,811 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::data::{
+    DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID, EntryInfo, Field, ItemUID,
+    SearchMatch, SearchQuery, SearchResults, SlotMetaItem, SlotMetaTile, SlotTile, SummaryTile,
+    TileID, TileSet,
+};
+use crate::deferred_data::{DeferredDataSource, FailedRequest, RequestError, RequestKind, Waker};
+use crate::filter_data::{matching_slots, EntryPattern};
+use crate::timestamp::Interval;
+
+// Restricts a data source to a subset of its slots and to an interval, e.g.,
+// to write a small archive out of a much larger profile. Items crossing the
+// boundary of the interval are clipped to it. Panels left without any slots
+// are dropped, and the remaining entries are renumbered accordingly. Panels
+// that lose only some of their slots lose their summary too, since it would
+// still count the slots that are gone.
+pub struct CropDeferredDataSource<T: DeferredDataSource> {
+    data_source: T,
+    // None to keep the entire profile
+    interval: Option<Interval>,
+    // Slots to keep (in the numbering of the original data source), or None
+    // to keep all of them
+    slots: Option<BTreeSet<EntryID>>,
+    // If not empty, the slots are chosen again by these patterns whenever
+    // the info is fetched, since the entries may change between runs
+    patterns: Vec<EntryPattern>,
+    src_to_dst_entry: BTreeMap<EntryID, EntryID>,
+    dst_to_src_entry: BTreeMap<EntryID, EntryID>,
+    // Static tiles crossing the boundary of the interval are clipped to it,
+    // but can only be fetched by their original IDs
+    src_to_dst_tile: BTreeMap<TileID, TileID>,
+    dst_to_src_tile: BTreeMap<TileID, TileID>,
+    // Requests for entries that aren't mapped (e.g., made before the info
+    // arrived), which fail rather than go to the data source
+    failures: Vec<FailedRequest>,
+}
+
+fn count_slots(entry: &EntryInfo) -> usize {
+    match entry {
+        EntryInfo::Panel { slots, .. } => slots.iter().map(count_slots).sum(),
+        EntryInfo::Slot { .. } => 1,
+        EntryInfo::Summary { .. } => 0,
+    }
+}
+
+impl<T: DeferredDataSource> CropDeferredDataSource<T> {
+    pub fn new(data_source: T, interval: Interval, slots: Option<BTreeSet<EntryID>>) -> Self {
+        Self {
+            data_source,
+            interval: Some(interval),
+            slots,
+            patterns: Vec::new(),
+            src_to_dst_entry: BTreeMap::new(),
+            dst_to_src_entry: BTreeMap::new(),
+            src_to_dst_tile: BTreeMap::new(),
+            dst_to_src_tile: BTreeMap::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    // See FilteredDeferredDataSource
+    pub(crate) fn with_patterns(
+        data_source: T,
+        interval: Option<Interval>,
+        patterns: Vec<EntryPattern>,
+    ) -> Self {
+        Self {
+            data_source,
+            interval,
+            slots: None,
+            patterns,
+            src_to_dst_entry: BTreeMap::new(),
+            dst_to_src_entry: BTreeMap::new(),
+            src_to_dst_tile: BTreeMap::new(),
+            dst_to_src_tile: BTreeMap::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    fn insert_entry(&mut self, src_entry: EntryID, dst_entry: EntryID) {
+        self.src_to_dst_entry
+            .insert(src_entry.clone(), dst_entry.clone());
+        self.dst_to_src_entry.insert(dst_entry, src_entry);
+    }
+
+    fn crop_entry(
+        &mut self,
+        entry: &EntryInfo,
+        src_entry: EntryID,
+        dst_entry: EntryID,
+    ) -> Option<EntryInfo> {
+        match entry {
+            EntryInfo::Panel {
+                short_name,
+                long_name,
+                summary,
+                slots,
+            } => {
+                let mut dst_slots = Vec::new();
+                for (i, slot) in slots.iter().enumerate() {
+                    let src_child = src_entry.child(i as u64);
+                    let dst_child = dst_entry.child(dst_slots.len() as u64);
+                    if let Some(slot) = self.crop_entry(slot, src_child, dst_child) {
+                        dst_slots.push(slot);
+                    }
+                }
+
+                // Always keep the root so that the result is well-formed
+                if dst_slots.is_empty() && src_entry != EntryID::root() {
+                    return None;
+                }
+
+                let complete =
+                    dst_slots.iter().map(count_slots).sum::<usize>() == count_slots(entry);
+                let summary = summary.clone().filter(|_| complete);
+                if summary.is_some() {
+                    self.insert_entry(src_entry.summary(), dst_entry.summary());
+                }
+
+                Some(EntryInfo::Panel {
+                    short_name: short_name.clone(),
+                    long_name: long_name.clone(),
+                    summary,
+                    slots: dst_slots,
+                })
+            }
+            EntryInfo::Slot { .. } => {
+                if let Some(keep) = &self.slots {
+                    if !keep.contains(&src_entry) {
+                        return None;
+                    }
+                }
+                self.insert_entry(src_entry, dst_entry);
+                Some(entry.clone())
+            }
+            EntryInfo::Summary { .. } => unreachable!(),
+        }
+    }
+
+    fn crop_tile_set(&mut self, tile_set: &TileSet) -> TileSet {
+        let Some(interval) = self.interval else {
+            return tile_set.clone();
+        };
+        let mut tiles = Vec::new();
+        for level in &tile_set.tiles {
+            let mut dst_level = Vec::new();
+            for src_tile in level.iter().filter(|tile_id| tile_id.0.overlaps(interval)) {
+                let dst_tile = TileID(src_tile.0.intersection(interval), src_tile.1);
+                if dst_tile != *src_tile {
+                    self.src_to_dst_tile.insert(*src_tile, dst_tile);
+                    self.dst_to_src_tile.insert(dst_tile, *src_tile);
+                }
+                dst_level.push(dst_tile);
+            }
+            tiles.push(dst_level);
+        }
+        // Clipped tiles only get smaller, so the estimates still bound them
+        TileSet {
+            tiles,
+            level_meta: tile_set.level_meta.clone(),
+        }
+    }
+
+    fn crop_info(&mut self, info: DataSourceInfo) -> DataSourceInfo {
+        self.src_to_dst_entry.clear();
+        self.dst_to_src_entry.clear();
+        self.src_to_dst_tile.clear();
+        self.dst_to_src_tile.clear();
+
+        if !self.patterns.is_empty() {
+            self.slots = Some(matching_slots(&info.entry_info, &self.patterns));
+        }
+
+        self.interval = self
+            .interval
+            .map(|interval| interval.intersection(info.interval));
+        let entry_info = self
+            .crop_entry(&info.entry_info, EntryID::root(), EntryID::root())
+            .unwrap();
+        let tile_set = self.crop_tile_set(&info.tile_set);
+
+        DataSourceInfo {
+            entry_info,
+            interval: self.interval.unwrap_or(info.interval),
+            tile_set,
+            ..info
+        }
+    }
+
+    fn map_dst_to_src_entry(&self, dst_entry: &EntryID) -> Option<EntryID> {
+        self.dst_to_src_entry.get(dst_entry).cloned()
+    }
+
+    // The entry and tile to request from the data source, or None if the
+    // request failed
+    fn map_dst_to_src_request(
+        &mut self,
+        kind: RequestKind,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: Option<ItemUID>,
+    ) -> Option<(EntryID, TileID)> {
+        let Some(src_entry) = self.map_dst_to_src_entry(entry_id) else {
+            self.failures.push(FailedRequest {
+                kind,
+                entry_id: Some(entry_id.clone()),
+                tile_id: Some(tile_id),
+                item_uid,
+                error: RequestError::Other(format!("unknown entry {entry_id:?}")),
+            });
+            return None;
+        };
+        Some((src_entry, self.map_dst_to_src_tile(tile_id)))
+    }
+
+    fn map_src_to_dst_entry(&self, src_entry: &EntryID) -> EntryID {
+        self.src_to_dst_entry[src_entry].clone()
+    }
+
+    fn map_dst_to_src_tile(&self, dst_tile: TileID) -> TileID {
+        self.dst_to_src_tile
+            .get(&dst_tile)
+            .copied()
+            .unwrap_or(dst_tile)
+    }
+
+    fn map_src_to_dst_tile(&self, src_tile: TileID) -> TileID {
+        self.src_to_dst_tile
+            .get(&src_tile)
+            .copied()
+            .unwrap_or(src_tile)
+    }
+
+    // Only slots and summaries are mapped, and panels may be numbered
+    // differently once cropped, so the entries below the given ones are
+    // named individually
+    fn map_dst_to_src_entries(&self, dst_entries: &BTreeSet<EntryID>) -> BTreeSet<EntryID> {
+        self.dst_to_src_entry
+            .iter()
+            .filter(|(dst_entry, _)| dst_entry.is_within(dst_entries))
+            .map(|(_, src_entry)| src_entry.clone())
+            .collect()
+    }
+
+    fn map_src_to_dst_field(&self, field: &mut Field) {
+        match field {
+            Field::ItemLink(link) => {
+                // Links into other profiles are left alone
+                if link.profile.is_some() {
+                    return;
+                }
+                // Links into entries that were cropped away can't be
+                // followed any more, so just keep their text
+                match self.src_to_dst_entry.get(&link.entry_id) {
+                    Some(entry_id) => link.entry_id = entry_id.clone(),
+                    None => *field = Field::String(link.title.clone()),
+                }
+            }
+            Field::Vec(elts) => {
+                for elt in elts {
+                    self.map_src_to_dst_field(elt);
+                }
+            }
+            Field::Group(_, elts) => {
+                for (_, elt) in elts {
+                    self.map_src_to_dst_field(elt);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn map_src_to_dst_summary(&self, tile: SummaryTile) -> SummaryTile {
+        SummaryTile {
+            entry_id: self.map_src_to_dst_entry(&tile.entry_id),
+            tile_id: self.map_src_to_dst_tile(tile.tile_id),
+            data: tile.data,
+        }
+    }
+
+    fn map_src_to_dst_slot(&self, mut tile: SlotTile) -> SlotTile {
+        // Items can't be dropped, because they must stay in agreement with
+        // the meta tile. Items entirely outside of the interval can only
+        // occur in static tiles crossing the boundary, and are shrunk to
+        // nothing at the nearest end of it, so that they are never shown
+        if let Some(interval) = self.interval {
+            for items in &mut tile.data.items {
+                for item in items {
+                    item.interval = if item.interval.overlaps(interval) {
+                        item.interval.intersection(interval)
+                    } else if item.interval.stop <= interval.start {
+                        Interval::new(interval.start, interval.start)
+                    } else {
+                        Interval::new(interval.stop, interval.stop)
+                    };
+                }
+            }
+        }
+
+        SlotTile {
+            entry_id: self.map_src_to_dst_entry(&tile.entry_id),
+            tile_id: self.map_src_to_dst_tile(tile.tile_id),
+            data: tile.data,
+        }
+    }
+
+    fn map_src_to_dst_slot_meta(&self, mut tile: SlotMetaTile) -> SlotMetaTile {
+        for items in &mut tile.data.items {
+            for item in items {
+                for (_, field, _) in &mut item.fields {
+                    self.map_src_to_dst_field(field);
+                }
+            }
+        }
+
+        SlotMetaTile {
+            entry_id: self.map_src_to_dst_entry(&tile.entry_id),
+            tile_id: self.map_src_to_dst_tile(tile.tile_id),
+            data: tile.data,
+        }
+    }
+
+    // Matches in entries or outside of the interval that were cropped away
+    // are dropped. The interval of the search is left alone, so that the
+    // caller can recognize the results
+    fn map_src_to_dst_search_results(&self, mut results: SearchResults) -> SearchResults {
+        results.matches = results
+            .matches
+            .into_iter()
+            .filter(|m| {
+                self.interval
+                    .map_or(true, |interval| m.original_interval.overlaps(interval))
+            })
+            .filter_map(|m| {
+                Some(SearchMatch {
+                    entry_id: self.src_to_dst_entry.get(&m.entry_id)?.clone(),
+                    ..m
+                })
+            })
+            .collect();
+        results
+    }
+
+    // Updates to entries or intervals that were cropped away are dropped.
+    // Panels may be numbered differently once cropped, so updates to them
+    // name the entries below them individually
+    fn map_src_to_dst_update(&self, update: DataSourceUpdate) -> Vec<DataSourceUpdate> {
+        match update {
+            DataSourceUpdate::Info => vec![DataSourceUpdate::Info],
+            DataSourceUpdate::Tiles { entry_id, interval } => {
+                let interval = match self.interval {
+                    Some(crop) if !interval.overlaps(crop) => return Vec::new(),
+                    Some(crop) => interval.intersection(crop),
+                    None => interval,
+                };
+                if entry_id == EntryID::root() {
+                    return vec![DataSourceUpdate::Tiles { entry_id, interval }];
+                }
+                self.src_to_dst_entry
+                    .iter()
+                    .filter(|(src_entry, _)| src_entry.has_prefix(&entry_id))
+                    .map(|(_, dst_entry)| DataSourceUpdate::Tiles {
+                        entry_id: dst_entry.clone(),
+                        interval,
+                    })
+                    .collect()
+            }
+            DataSourceUpdate::Items { tile, meta } => {
+                if !self.src_to_dst_entry.contains_key(&tile.entry_id) {
+                    return Vec::new();
+                }
+                let mut tile_id = tile.tile_id;
+                if let Some(crop) = self.interval {
+                    if !tile_id.0.overlaps(crop) {
+                        return Vec::new();
+                    }
+                    tile_id.0 = tile_id.0.intersection(crop);
+                }
+                let mut tile = self.map_src_to_dst_slot(tile);
+                let mut meta = self.map_src_to_dst_slot_meta(meta);
+                tile.tile_id = tile_id;
+                meta.tile_id = tile_id;
+                vec![DataSourceUpdate::Items { tile, meta }]
+            }
+        }
+    }
+
+    fn map_src_to_dst_slot_meta_item(&self, mut item: SlotMetaItem) -> SlotMetaItem {
+        if let Some(ref mut data) = item.data {
+            for (_, field, _) in &mut data.fields {
+                self.map_src_to_dst_field(field);
+            }
+        }
+
+        SlotMetaItem {
+            entry_id: self.map_src_to_dst_entry(&item.entry_id),
+            tile_id: self.map_src_to_dst_tile(item.tile_id),
+            item_uid: item.item_uid,
+            data: item.data,
+        }
+    }
+}
+
+impl<T: DeferredDataSource> DeferredDataSource for CropDeferredDataSource<T> {
+    fn fetch_description(&self) -> DataSourceDescription {
+        self.data_source.fetch_description()
+    }
+
+    fn fetch_info(&mut self) {
+        self.data_source.fetch_info()
+    }
+
+    fn get_infos(&mut self) -> Vec<DataSourceInfo> {
+        let infos = self.data_source.get_infos();
+        infos.into_iter().map(|info| self.crop_info(info)).collect()
+    }
+
+    fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        let Some((src_entry, src_tile)) =
+            self.map_dst_to_src_request(RequestKind::SummaryTile, entry_id, tile_id, None)
+        else {
+            return;
+        };
+        self.data_source
+            .fetch_summary_tile(&src_entry, src_tile, full);
+    }
+
+    fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
+        let tiles = self.data_source.get_summary_tiles();
+        tiles
+            .into_iter()
+            .map(|tile| self.map_src_to_dst_summary(tile))
+            .collect()
+    }
+
+    fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        let Some((src_entry, src_tile)) =
+            self.map_dst_to_src_request(RequestKind::SlotTile, entry_id, tile_id, None)
+        else {
+            return;
+        };
+        self.data_source.fetch_slot_tile(&src_entry, src_tile, full);
+    }
+
+    fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
+        let tiles = self.data_source.get_slot_tiles();
+        tiles
+            .into_iter()
+            .map(|tile| self.map_src_to_dst_slot(tile))
+            .collect()
+    }
+
+    fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        let Some((src_entry, src_tile)) =
+            self.map_dst_to_src_request(RequestKind::SlotMetaTile, entry_id, tile_id, None)
+        else {
+            return;
+        };
+        self.data_source
+            .fetch_slot_meta_tile(&src_entry, src_tile, full);
+    }
+
+    fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
+        let tiles = self.data_source.get_slot_meta_tiles();
+        tiles
+            .into_iter()
+            .map(|tile| self.map_src_to_dst_slot_meta(tile))
+            .collect()
+    }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        let Some((src_entry, src_tile)) = self.map_dst_to_src_request(
+            RequestKind::SlotMetaItem,
+            entry_id,
+            tile_id,
+            Some(item_uid),
+        ) else {
+            return;
+        };
+        self.data_source
+            .fetch_slot_meta_item(&src_entry, src_tile, item_uid);
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        let items = self.data_source.get_slot_meta_items();
+        items
+            .into_iter()
+            .map(|item| self.map_src_to_dst_slot_meta_item(item))
+            .collect()
+    }
+
+    fn fetch_updates(&mut self) {
+        self.data_source.fetch_updates()
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        let updates = self.data_source.get_updates();
+        updates
+            .into_iter()
+            .flat_map(|update| self.map_src_to_dst_update(update))
+            .collect()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.data_source.set_waker(waker)
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        let mut result = std::mem::take(&mut self.failures);
+        let failures = self.data_source.get_failures();
+        result.extend(failures.into_iter().map(|failure| {
+            FailedRequest {
+                entry_id: failure
+                    .entry_id
+                    .map(|entry_id| self.map_src_to_dst_entry(&entry_id)),
+                tile_id: failure
+                    .tile_id
+                    .map(|tile_id| self.map_src_to_dst_tile(tile_id)),
+                ..failure
+            }
+        }));
+        result
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        let entries = entries.map(|entries| self.map_dst_to_src_entries(entries));
+        self.data_source.set_active_entries(entries.as_ref())
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        self.data_source.fetch_search(query, interval, limit)
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        let results = self.data_source.get_search_results();
+        results
+            .into_iter()
+            .map(|results| self.map_src_to_dst_search_results(results))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::data::{Color32, DataSource, FieldSchema, PROTOCOL_VERSION};
+    use crate::deferred_data::DeferredDataSourceWrapper;
+    use crate::file_data::FileDataSource;
+    use crate::test_data::{panel, slot, TestDataSource, TestDeferredDataSource};
+    use crate::timestamp::Timestamp;
+
+    #[test]
+    fn test_crop_info() {
+        let info = DataSourceInfo {
+            entry_info: panel(
+                "root",
+                vec![
+                    panel("N0", vec![slot("S00"), slot("S01")]),
+                    panel("N1", vec![slot("S10"), slot("S11")]),
+                ],
+            ),
+            interval: Interval::new(Timestamp(0), Timestamp(1000)),
+            tile_set: TileSet::default(),
+            field_schema: FieldSchema::new(),
+            warning_message: None,
+            protocol_version: PROTOCOL_VERSION,
+            supports_search: false,
+            unavailable: false,
+        };
+
+        // Keep only the second slot of the second node
+        let keep = EntryID::root().child(1).child(1);
+        let mut crop = CropDeferredDataSource::new(
+            DeferredDataSourceWrapper::new(FileDataSource::new("")),
+            Interval::new(Timestamp(500), Timestamp(2000)),
+            Some([keep.clone()].into_iter().collect()),
+        );
+        let result = crop.crop_info(info);
+
+        assert_eq!(
+            result.interval,
+            Interval::new(Timestamp(500), Timestamp(1000))
+        );
+
+        let EntryInfo::Panel { slots, .. } = &result.entry_info else {
+            panic!("unexpected variant result in root");
+        };
+        assert_eq!(slots.len(), 1);
+        let EntryInfo::Panel {
+            short_name, slots, ..
+        } = &slots[0]
+        else {
+            panic!("unexpected variant result in node");
+        };
+        assert_eq!(short_name, "N1");
+        assert_eq!(slots.len(), 1);
+
+        let dst = EntryID::root().child(0).child(0);
+        assert_eq!(crop.map_src_to_dst_entry(&keep), dst);
+        assert_eq!(crop.map_dst_to_src_entry(&dst), Some(keep.clone()));
+
+        // Search matches outside of what is kept are dropped
+        let search_match = |entry_id: &EntryID, start, stop| SearchMatch {
+            entry_id: entry_id.clone(),
+            tile_id: TileID(result.interval, 0),
+            item_uid: ItemUID(start as u64),
+            row: 0,
+            title: String::new(),
+            original_interval: Interval::new(Timestamp(start), Timestamp(stop)),
+        };
+        let results = SearchResults {
+            query: SearchQuery {
+                text: String::new(),
+                field: None,
+                whole_word: false,
+            },
+            interval: result.interval,
+            matches: vec![
+                search_match(&keep, 100, 200),
+                search_match(&keep, 400, 600),
+                search_match(&EntryID::root().child(0).child(0), 600, 700),
+            ],
+            truncated: false,
+            error: None,
+        };
+        let results = crop.map_src_to_dst_search_results(results);
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(results.matches[0].entry_id, dst);
+        assert_eq!(results.matches[0].item_uid, ItemUID(400));
+
+        // Active entries name the slots below them in the original numbering
+        let active = |entries: &[EntryID]| {
+            crop.map_dst_to_src_entries(&entries.iter().cloned().collect())
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(active(&[EntryID::root().child(0)]), vec![keep.clone()]);
+        assert_eq!(active(&[dst]), vec![keep]);
+        assert!(active(&[EntryID::root().child(1)]).is_empty());
+    }
+
+    #[test]
+    fn test_crop_before_info() {
+        let span = Interval::new(Timestamp(0), Timestamp(1000));
+        let mut crop = CropDeferredDataSource::new(TestDeferredDataSource::default(), span, None);
+
+        // Nothing is mapped until the info arrives, so requests fail
+        let entry_id = EntryID::root().child(0).child(0);
+        crop.fetch_slot_tile(&entry_id, TileID(span, 0), false);
+        crop.fetch_slot_meta_item(&entry_id, TileID(span, 0), ItemUID(0));
+        assert!(crop.get_slot_tiles().is_empty());
+        let failures = crop.get_failures();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].kind, RequestKind::SlotTile);
+        assert_eq!(failures[0].entry_id, Some(entry_id.clone()));
+        assert_eq!(failures[1].item_uid, Some(ItemUID(0)));
+        assert!(crop.get_failures().is_empty());
+    }
+
+    #[test]
+    fn test_crop_static_tiles() {
+        let span = |start, stop| Interval::new(Timestamp(start), Timestamp(stop));
+        let info = DataSourceInfo {
+            tile_set: TileSet {
+                tiles: vec![
+                    vec![TileID(span(0, 1000), 0)],
+                    vec![TileID(span(0, 500), 1), TileID(span(500, 1000), 1)],
+                ],
+                level_meta: Vec::new(),
+            },
+            ..TestDataSource::default().fetch_info()
+        };
+        let mut crop = CropDeferredDataSource::new(
+            TestDeferredDataSource::default().with_info(info),
+            span(250, 600),
+            None,
+        );
+        crop.fetch_info();
+        let info = crop.get_infos().pop().unwrap();
+
+        // Tiles crossing the boundary are clipped to it
+        assert_eq!(
+            info.tile_set.tiles,
+            vec![
+                vec![TileID(span(250, 600), 0)],
+                vec![TileID(span(250, 500), 1), TileID(span(500, 600), 1)],
+            ]
+        );
+
+        // But are still fetched by their original IDs, and their items are
+        // clipped along with them
+        let slot = EntryID::root().child(0);
+        crop.fetch_slot_tile(&slot, TileID(span(250, 500), 1), false);
+        let tiles = crop.get_slot_tiles();
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].tile_id, TileID(span(250, 500), 1));
+        assert_eq!(tiles[0].data.items[0][0].interval, span(250, 500));
+        assert_eq!(tiles[0].data.items[0][0].item_uid, ItemUID(0));
+
+        crop.fetch_slot_meta_tile(&slot, TileID(span(500, 600), 1), false);
+        let tiles = crop.get_slot_meta_tiles();
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].tile_id, TileID(span(500, 600), 1));
+    }
+
+    #[test]
+    fn test_crop_updates() {
+        let span = |start, stop| Interval::new(Timestamp(start), Timestamp(stop));
+        let with_summary = |entry: EntryInfo| match entry {
+            EntryInfo::Panel {
+                short_name,
+                long_name,
+                slots,
+                ..
+            } => EntryInfo::Panel {
+                short_name,
+                long_name,
+                summary: Some(Box::new(EntryInfo::Summary {
+                    color: Color32::RED,
+                })),
+                slots,
+            },
+            _ => unreachable!(),
+        };
+        let info = DataSourceInfo {
+            entry_info: panel(
+                "root",
+                vec![
+                    with_summary(panel("N0", vec![slot("S00"), slot("S01")])),
+                    with_summary(panel("N1", vec![slot("S10"), slot("S11")])),
+                ],
+            ),
+            ..TestDataSource::default().fetch_info()
+        };
+        let n0 = EntryID::root().child(0);
+        let n1 = EntryID::root().child(1);
+        let mut crop = CropDeferredDataSource::new(
+            TestDeferredDataSource::default().with_info(info),
+            span(250, 600),
+            Some([n0.child(0), n0.child(1), n1.child(1)].into()),
+        );
+        crop.fetch_info();
+        let info = crop.get_infos().pop().unwrap();
+
+        // Only the panel that kept all of its slots keeps its summary
+        let EntryInfo::Panel { slots, .. } = &info.entry_info else {
+            panic!("unexpected variant result in root");
+        };
+        let has_summary = |entry: &EntryInfo| {
+            let EntryInfo::Panel { summary, .. } = entry else {
+                panic!("unexpected variant result in node");
+            };
+            summary.is_some()
+        };
+        assert!(has_summary(&slots[0]));
+        assert!(!has_summary(&slots[1]));
+
+        let tiles = |entry_id: &EntryID, interval| DataSourceUpdate::Tiles {
+            entry_id: entry_id.clone(),
+            interval,
+        };
+        let items = |entry_id: &EntryID, interval| {
+            let source = TestDataSource::default();
+            let tile_id = TileID(interval, 0);
+            DataSourceUpdate::Items {
+                tile: source.fetch_slot_tile(entry_id, tile_id, false),
+                meta: source.fetch_slot_meta_tile(entry_id, tile_id, false),
+            }
+        };
+        crop.data_source.updates = vec![
+            tiles(&n0, span(0, 1000)),
+            tiles(&n1, span(0, 1000)),
+            tiles(&n1.child(0), span(0, 1000)),
+            tiles(&n1.child(1), span(700, 800)),
+            items(&n1.child(1), span(500, 800)),
+            items(&n1.child(0), span(500, 800)),
+            DataSourceUpdate::Info,
+        ];
+        let updates: Vec<_> = crop
+            .get_updates()
+            .into_iter()
+            .map(|update| match update {
+                DataSourceUpdate::Info => (None, None),
+                DataSourceUpdate::Tiles { entry_id, interval } => (Some(entry_id), Some(interval)),
+                DataSourceUpdate::Items { tile, meta } => {
+                    assert_eq!(tile.entry_id, meta.entry_id);
+                    assert_eq!(tile.tile_id, meta.tile_id);
+                    assert_eq!(tile.data.items[0][0].interval, span(500, 600));
+                    (Some(tile.entry_id), Some(tile.tile_id.0))
+                }
+            })
+            .collect();
+
+        let dst_n1 = EntryID::root().child(1);
+        assert_eq!(
+            updates,
+            vec![
+                // The panel's entries are named individually
+                (Some(n0.summary()), Some(span(250, 600))),
+                (Some(n0.child(0)), Some(span(250, 600))),
+                (Some(n0.child(1)), Some(span(250, 600))),
+                (Some(dst_n1.child(0)), Some(span(250, 600))),
+                // Updates to what was cropped away are dropped
+                (Some(dst_n1.child(0)), Some(span(500, 600))),
+                (None, None),
+            ]
+        );
+    }
+}
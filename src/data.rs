@@ -2,7 +2,8 @@ use std::collections::BTreeMap;
 use std::fmt;
 
 pub use egui::{Color32, Rgba};
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeTupleStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeSet;
 
 use crate::timestamp::{Interval, Timestamp};
@@ -19,6 +20,14 @@ pub enum EntryIndex {
     Slot(u64),
 }
 
+// Version of the data exchanged between data sources and the viewer (over
+// HTTP or in archives). Bump this on any incompatible change.
+//
+//     1: initial version
+//     2: single item and search endpoints, active entry hints, Items
+//        updates, and tile IDs with levels
+pub const PROTOCOL_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DataSourceInfo {
     pub entry_info: EntryInfo,
@@ -26,6 +35,45 @@ pub struct DataSourceInfo {
     pub tile_set: TileSet,
     pub field_schema: FieldSchema,
     pub warning_message: Option<String>,
+    // PROTOCOL_VERSION of the data source, or 0 if it predates versioning
+    #[serde(default)]
+    pub protocol_version: u32,
+    // True if the data source can search its items itself (see
+    // DataSource::fetch_search), so the viewer need not fetch every meta tile
+    #[serde(default)]
+    pub supports_search: bool,
+    // Only set by DataSourceInfo::unavailable, so that a placeholder is never
+    // mistaken for a profile that happens to be empty (or vice versa)
+    #[serde(default)]
+    pub unavailable: bool,
+}
+
+impl DataSourceInfo {
+    // Empty profile standing in for one that could not be loaded, so the
+    // viewer can explain why instead of failing
+    pub fn unavailable(message: String) -> Self {
+        Self {
+            entry_info: EntryInfo::Panel {
+                short_name: "unavailable".to_owned(),
+                long_name: "Unavailable".to_owned(),
+                summary: None,
+                slots: Vec::new(),
+            },
+            interval: Interval::new(Timestamp(0), Timestamp(1)),
+            tile_set: TileSet::default(),
+            field_schema: FieldSchema::new(),
+            warning_message: Some(message),
+            protocol_version: PROTOCOL_VERSION,
+            supports_search: false,
+            unavailable: true,
+        }
+    }
+
+    // True for the placeholder from unavailable, rather than a profile that
+    // happens to be empty
+    pub fn is_unavailable(&self) -> bool {
+        self.unavailable
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -40,6 +88,10 @@ pub enum EntryInfo {
         short_name: String,
         long_name: String,
         max_rows: u64,
+        // Names of the rows (e.g., instance names or stream IDs), indexed
+        // like the rows of the slot's tiles. Rows past the end are unnamed
+        #[serde(default)]
+        row_names: Vec<String>,
     },
     Summary {
         color: Color32,
@@ -62,11 +114,26 @@ pub struct ItemLink {
     // Required to enable zoom/scroll-to-item
     pub interval: Interval,
     pub entry_id: EntryID,
+
+    // Source locator (as in DataSourceDescription) of the profile containing
+    // the item, when linking to an item in another attached profile
+    #[serde(default)]
+    pub profile: Option<Vec<String>>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct FieldID(usize);
 
+// How the viewer should display integer values of a field, so that data
+// sources can send raw numbers rather than pre-formatted strings
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum FieldFormat {
+    Bytes,
+    Percentage,
+    Nanoseconds,
+    Hex,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct FieldSchema {
     // Field names that may potentially exist on a given item. They are not
@@ -74,6 +141,8 @@ pub struct FieldSchema {
     field_ids: BTreeMap<String, FieldID>,
     field_names: BTreeMap<FieldID, String>,
     searchable: BTreeSet<FieldID>,
+    #[serde(default)]
+    formats: BTreeMap<FieldID, FieldFormat>,
 }
 
 impl FieldSchema {
@@ -82,10 +151,20 @@ impl FieldSchema {
             field_ids: BTreeMap::new(),
             field_names: BTreeMap::new(),
             searchable: BTreeSet::new(),
+            formats: BTreeMap::new(),
         }
     }
 
     pub fn insert(&mut self, field_name: String, searchable: bool) -> FieldID {
+        self.insert_with_format(field_name, searchable, None)
+    }
+
+    pub fn insert_with_format(
+        &mut self,
+        field_name: String,
+        searchable: bool,
+        format: Option<FieldFormat>,
+    ) -> FieldID {
         if let Some(field_id) = self.field_ids.get(&field_name) {
             return *field_id;
         }
@@ -101,6 +180,9 @@ impl FieldSchema {
         if searchable {
             self.searchable.insert(next_id);
         }
+        if let Some(format) = format {
+            self.formats.insert(next_id, format);
+        }
         next_id
     }
 
@@ -112,6 +194,10 @@ impl FieldSchema {
         self.field_names.get(&field_id).map(|x| x.as_str())
     }
 
+    pub fn get_format(&self, field_id: FieldID) -> Option<FieldFormat> {
+        self.formats.get(&field_id).copied()
+    }
+
     pub fn contains_id(&self, field_id: FieldID) -> bool {
         self.field_names.contains_key(&field_id)
     }
@@ -123,6 +209,13 @@ impl FieldSchema {
     pub fn searchable(&self) -> &BTreeSet<FieldID> {
         &self.searchable
     }
+
+    // Every field, in order of ID
+    pub fn fields(&self) -> impl Iterator<Item = (FieldID, &str)> {
+        self.field_names
+            .iter()
+            .map(|(id, name)| (*id, name.as_str()))
+    }
 }
 
 impl Default for FieldSchema {
@@ -140,11 +233,23 @@ pub enum Field {
     ItemLink(ItemLink),
     Vec(Vec<Field>),
     Empty,
+    // Named section of related fields, shown collapsible in item details
+    Group(String, Vec<(FieldID, Field)>),
+    // Text with a small subset of Markdown: headings, bullet lists, fenced
+    // code blocks, and `code`, **bold** and *italic* spans
+    Markdown(String),
+    // Point in time, rendered with a button that pans the view to it
+    Timestamp(Timestamp),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct ItemUID(pub u64);
 
+// One of the clients sharing a data source (e.g., through the server), so
+// that their hints can be told apart
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct ClientID(pub u64);
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Item {
     pub item_uid: ItemUID,
@@ -163,12 +268,61 @@ pub struct ItemMeta {
     pub fields: Vec<(FieldID, Field, Option<Color32>)>,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
-pub struct TileID(pub Interval);
+// A tile is identified by its interval and the level of the tile set it
+// belongs to, as tiles at different levels may have the same interval (e.g.,
+// when a level has a single tile). Dynamic tiles are always at level 0.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TileID(pub Interval, pub u32);
+
+// Tiles at level 0 are written as a bare interval, as they were before tiles
+// had levels, and tiles at other levels are written as a pair. This only works
+// one way: new readers understand old data sources and archives, but old
+// readers understand only level 0 tiles, so they can't read archives with
+// more than one level written by this version
+impl Serialize for TileID {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.1 == 0 {
+            return serializer.serialize_newtype_struct("TileID", &self.0);
+        }
+        let mut state = serializer.serialize_tuple_struct("TileID", 2)?;
+        state.serialize_field(&self.0)?;
+        state.serialize_field(&self.1)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TileID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Leveled(Interval, u32),
+            Plain(Interval),
+        }
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Leveled(interval, level) => Self(interval, level),
+            Repr::Plain(interval) => Self(interval, 0),
+        })
+    }
+}
+
+// Estimates for the tiles of one level of a TileSet, so that the viewer can
+// weigh the levels against each other before fetching anything. Both are
+// averages over the slot tiles of the level
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct TileLevelMeta {
+    pub items_per_tile: u64,
+    // As transferred, i.e., after compression
+    pub bytes_per_tile: u64,
+}
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct TileSet {
+    // Levels go from coarsest to finest
     pub tiles: Vec<Vec<TileID>>,
+    // One per level, or empty if the data source can't tell
+    #[serde(default)]
+    pub level_meta: Vec<TileLevelMeta>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -185,6 +339,7 @@ pub struct SummaryTile {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SlotTileData {
+    // Items in each row must be sorted by start time and must not overlap
     pub items: Vec<Vec<Item>>, // row -> [item]
 }
 
@@ -207,6 +362,97 @@ pub struct SlotMetaTile {
     pub data: SlotMetaTileData,
 }
 
+// Metadata for a single item, for when fetching the entire SlotMetaTile (e.g.,
+// to show a tooltip) would be wasteful. The data is None if the item is not
+// found in the tile
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SlotMetaItem {
+    pub entry_id: EntryID,
+    pub tile_id: TileID,
+    pub item_uid: ItemUID,
+    pub data: Option<ItemMeta>,
+}
+
+// Items match if the text occurs in their title (or the name of their row),
+// or if the field is given, in that field (looking inside groups, vectors
+// and links). With whole_word, the text must start and end at word
+// boundaries
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SearchQuery {
+    pub text: String,
+    pub field: Option<FieldID>,
+    pub whole_word: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SearchMatch {
+    pub entry_id: EntryID,
+    // A tile containing the item, through which it can be fetched
+    pub tile_id: TileID,
+    pub item_uid: ItemUID,
+    // Row of the item within the slot, as in the tiles
+    pub row: u64,
+    pub title: String,
+    pub original_interval: Interval,
+}
+
+// The query and interval are those of the request, so that the caller can
+// tell which search the results are for
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SearchResults {
+    pub query: SearchQuery,
+    pub interval: Interval,
+    pub matches: Vec<SearchMatch>,
+    // True if there were more matches than the limit
+    pub truncated: bool,
+    // Set if the search could not be done, in which case the caller must
+    // search on its own
+    pub error: Option<String>,
+}
+
+impl SearchResults {
+    pub fn failed(query: SearchQuery, interval: Interval, error: String) -> Self {
+        Self {
+            query,
+            interval,
+            matches: Vec::new(),
+            truncated: false,
+            error: Some(error),
+        }
+    }
+}
+
+// Changes to a profile that is still being written (e.g., by an application
+// that is still running), reported to the viewer while it is open
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum DataSourceUpdate {
+    // The interval or the entries changed, so the info must be fetched again,
+    // and every tile is stale
+    Info,
+    // Tiles of the entry (and everything under it) that overlap the interval
+    // are stale
+    Tiles {
+        entry_id: EntryID,
+        interval: Interval,
+    },
+    // Items were added to the rows of a slot. Rather than invalidating whole
+    // tiles, only the new items are sent, to be merged into the tiles that
+    // are already loaded. The tile ID covers the new items, which (unlike in
+    // regular tiles) are not clipped to it. Summaries are not updated, so
+    // they should be invalidated separately
+    Items {
+        tile: SlotTile,
+        meta: SlotMetaTile,
+    },
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DataSourceUpdates {
+    // Pass this back to fetch_updates to get only the updates made since
+    pub version: u64,
+    pub updates: Vec<DataSourceUpdate>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DataSourceDescription {
     pub source_locator: Vec<String>,
@@ -219,6 +465,61 @@ pub trait DataSource {
     fn fetch_slot_tile(&self, entry_id: &EntryID, tile_id: TileID, full: bool) -> SlotTile;
     fn fetch_slot_meta_tile(&self, entry_id: &EntryID, tile_id: TileID, full: bool)
         -> SlotMetaTile;
+
+    // Data sources that can look up individual items should override this;
+    // by default it extracts the item from the full meta tile, which at
+    // least saves the bandwidth of sending the tile
+    fn fetch_slot_meta_item(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: ItemUID,
+    ) -> SlotMetaItem {
+        let tile = self.fetch_slot_meta_tile(entry_id, tile_id, false);
+        let data = tile
+            .data
+            .items
+            .into_iter()
+            .flatten()
+            .find(|item| item.item_uid == item_uid);
+        SlotMetaItem {
+            entry_id: entry_id.clone(),
+            tile_id,
+            item_uid,
+            data,
+        }
+    }
+
+    // Data sources for profiles that change while being viewed should
+    // override this to return the updates made after the given version (or
+    // just DataSourceUpdate::Info, if that is too far back). Version 0 asks
+    // for the current version only. By default, the profile never changes
+    fn fetch_updates(&self, version: u64) -> DataSourceUpdates {
+        DataSourceUpdates {
+            version,
+            updates: Vec::new(),
+        }
+    }
+
+    // See DeferredDataSource::set_active_entries. Each client has its own
+    // hint, replacing its previous one. By default, the hint is ignored
+    fn set_active_entries(&self, _client: ClientID, _entries: Option<&BTreeSet<EntryID>>) {}
+
+    // Data sources that set DataSourceInfo::supports_search must override
+    // this to return (up to limit of) the items overlapping the interval that
+    // match the query. By default, searches fail
+    fn fetch_search(
+        &self,
+        query: &SearchQuery,
+        interval: Interval,
+        _limit: usize,
+    ) -> SearchResults {
+        SearchResults::failed(
+            query.clone(),
+            interval,
+            "search is not supported".to_owned(),
+        )
+    }
 }
 
 impl EntryID {
@@ -272,6 +573,11 @@ impl EntryID {
         )
     }
 
+    // True if this is one of the entries, or is below one of them
+    pub fn is_within(&self, entries: &BTreeSet<EntryID>) -> bool {
+        (0..=self.0.len()).any(|level| entries.contains(&Self(self.0[..level].to_vec())))
+    }
+
     pub fn has_prefix(&self, prefix: &EntryID) -> bool {
         if prefix.0.len() > self.0.len() {
             return false;
@@ -374,10 +680,18 @@ impl From<std::num::ParseIntError> for SlugParseError {
 }
 
 impl TileID {
+    // The level is omitted at level 0, as in TileIDSlug
     pub fn from_slug(s: &str) -> Result<Self, SlugParseError> {
-        let elts: Result<Vec<i64>, _> = s.split('_').map(|x| x.parse::<i64>()).collect();
-        match elts?.as_slice() {
-            [start, stop] => Ok(Self(Interval::new(Timestamp(*start), Timestamp(*stop)))),
+        let interval = |start: &str, stop: &str| -> Result<_, std::num::ParseIntError> {
+            Ok(Interval::new(
+                Timestamp(start.parse()?),
+                Timestamp(stop.parse()?),
+            ))
+        };
+        let elts: Vec<_> = s.split('_').collect();
+        match elts.as_slice() {
+            [start, stop] => Ok(Self(interval(start, stop)?, 0)),
+            [start, stop, level] => Ok(Self(interval(start, stop)?, level.parse()?)),
             [_] => Err(SlugParseError::TooFewValues),
             [] => Err(SlugParseError::TooFewValues),
             _ => Err(SlugParseError::TooManyValues),
@@ -405,7 +719,11 @@ impl fmt::Display for TileIDSlug {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0 .0.start.0)?;
         write!(f, "_")?;
-        write!(f, "{}", self.0 .0.stop.0)
+        write!(f, "{}", self.0 .0.stop.0)?;
+        if self.0 .1 != 0 {
+            write!(f, "_{}", self.0 .1)?;
+        }
+        Ok(())
     }
 }
 
@@ -420,3 +738,40 @@ impl EntryID {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Serialize + for<'a> Deserialize<'a>>(value: &impl Serialize) -> T {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).unwrap();
+        ciborium::from_reader(&bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn test_tile_id_levels() {
+        let interval = Interval::new(Timestamp(10), Timestamp(20));
+        let level0 = TileID(interval, 0);
+        let level2 = TileID(interval, 2);
+        assert_ne!(level0, level2);
+
+        // Tiles written before tiles had levels are at level 0, and tiles
+        // at level 0 can still be read as just an interval
+        assert_eq!(round_trip::<TileID>(&interval), level0);
+        assert_eq!(round_trip::<Interval>(&level0), interval);
+        assert_eq!(round_trip::<TileID>(&level2), level2);
+        let tile_set = TileSet {
+            tiles: vec![vec![level0], vec![level2]],
+            level_meta: Vec::new(),
+        };
+        assert_eq!(round_trip::<TileSet>(&tile_set), tile_set);
+
+        assert_eq!(TileIDSlug(level0).to_string(), "10_20");
+        assert_eq!(TileIDSlug(level2).to_string(), "10_20_2");
+        assert_eq!(TileID::from_slug("10_20").unwrap(), level0);
+        assert_eq!(TileID::from_slug("10_20_2").unwrap(), level2);
+        assert!(TileID::from_slug("10_20_-1").is_err());
+        assert!(TileID::from_slug("10_20_2_3").is_err());
+    }
+}
@@ -1,7 +1,14 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::data::{
-    DataSource, DataSourceDescription, DataSourceInfo, EntryID, SlotMetaTile, SlotTile,
-    SummaryTile, TileID,
+    ClientID, DataSource, DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID, Item,
+    ItemMeta, ItemUID, SearchQuery, SearchResults, SlotMetaItem, SlotMetaTile, SlotTile,
+    SummaryTile, TileID, UtilPoint,
 };
+use crate::timestamp::Interval;
 
 pub trait DeferredDataSource {
     fn fetch_description(&self) -> DataSourceDescription;
@@ -13,14 +20,59 @@ pub trait DeferredDataSource {
     fn get_slot_tiles(&mut self) -> Vec<SlotTile>;
     fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool);
     fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile>;
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID);
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem>;
+
+    // Watch for updates to the profile. This is called repeatedly, and it is
+    // up to the data source to avoid watching more than once at a time. By
+    // default, there are never any updates
+    fn fetch_updates(&mut self) {}
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        Vec::new()
+    }
+
+    // Lets a data source that watches for updates in the background ask to
+    // be polled again (i.e., for fetch_updates and get_updates to be called)
+    // after at most the given delay, e.g., because updates arrived or it is
+    // time to retry a failed watch. The waker may be called from any thread.
+    // By default, it is never called
+    fn set_waker(&mut self, _waker: Waker) {}
+
+    // Requests that will never be answered, e.g., because the server timed
+    // out. The caller may make them again. By default, requests never fail
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        Vec::new()
+    }
+
+    // Hint that only these entries (and everything below them) are being
+    // shown, or all of them if None, e.g., because the rest are hidden by
+    // the node selection or kind filter. The data source may then avoid
+    // computing tiles for the others, but must still answer requests for
+    // them. By default, the hint is ignored
+    fn set_active_entries(&mut self, _entries: Option<&BTreeSet<EntryID>>) {}
+
+    // Search the items overlapping the interval, if the data source sets
+    // DataSourceInfo::supports_search (see DataSource::fetch_search). Each
+    // search is answered once, with an error if it fails. By default,
+    // searches are never answered
+    fn fetch_search(&mut self, _query: &SearchQuery, _interval: Interval, _limit: usize) {}
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        Vec::new()
+    }
 }
 
+pub type Waker = Arc<dyn Fn(Duration) + Send + Sync>;
+
 pub struct DeferredDataSourceWrapper<T: DataSource> {
     data_source: T,
     infos: Vec<DataSourceInfo>,
     summary_tiles: Vec<SummaryTile>,
     slot_tiles: Vec<SlotTile>,
     slot_meta_tiles: Vec<SlotMetaTile>,
+    slot_meta_items: Vec<SlotMetaItem>,
+    update_version: u64,
+    updates: Vec<DataSourceUpdate>,
+    search_results: Vec<SearchResults>,
 }
 
 impl<T: DataSource> DeferredDataSourceWrapper<T> {
@@ -31,6 +83,10 @@ impl<T: DataSource> DeferredDataSourceWrapper<T> {
             summary_tiles: Vec::new(),
             slot_tiles: Vec::new(),
             slot_meta_tiles: Vec::new(),
+            slot_meta_items: Vec::new(),
+            update_version: 0,
+            updates: Vec::new(),
+            search_results: Vec::new(),
         }
     }
 }
@@ -76,33 +132,481 @@ impl<T: DataSource> DeferredDataSource for DeferredDataSourceWrapper<T> {
     fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
         std::mem::take(&mut self.slot_meta_tiles)
     }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        self.slot_meta_items.push(
+            self.data_source
+                .fetch_slot_meta_item(entry_id, tile_id, item_uid),
+        );
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        std::mem::take(&mut self.slot_meta_items)
+    }
+
+    fn fetch_updates(&mut self) {
+        let result = self.data_source.fetch_updates(self.update_version);
+        self.update_version = result.version;
+        self.updates.extend(result.updates);
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        std::mem::take(&mut self.updates)
+    }
+
+    // The wrapper is the data source's only client
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.data_source
+            .set_active_entries(ClientID::default(), entries)
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        self.search_results
+            .push(self.data_source.fetch_search(query, interval, limit));
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        std::mem::take(&mut self.search_results)
+    }
+}
+
+#[derive(Clone)]
+enum Request {
+    Info,
+    SummaryTile(EntryID, TileID, bool),
+    SlotTile(EntryID, TileID, bool),
+    SlotMetaTile(EntryID, TileID, bool),
+    SlotMetaItem(EntryID, TileID, ItemUID),
+}
+
+impl Request {
+    fn kind(&self) -> RequestKind {
+        match self {
+            Request::Info => RequestKind::Info,
+            Request::SummaryTile(..) => RequestKind::SummaryTile,
+            Request::SlotTile(..) => RequestKind::SlotTile,
+            Request::SlotMetaTile(..) => RequestKind::SlotMetaTile,
+            Request::SlotMetaItem(..) => RequestKind::SlotMetaItem,
+        }
+    }
+
+    fn key(&self) -> RequestKey<'_> {
+        match self {
+            Request::Info => (None, None, None),
+            Request::SummaryTile(entry_id, tile_id, _)
+            | Request::SlotTile(entry_id, tile_id, _)
+            | Request::SlotMetaTile(entry_id, tile_id, _) => (Some(entry_id), Some(*tile_id), None),
+            Request::SlotMetaItem(entry_id, tile_id, item_uid) => {
+                (Some(entry_id), Some(*tile_id), Some(*item_uid))
+            }
+        }
+    }
+}
+
+// Entry, tile and item a request is for, as applicable
+pub(crate) type RequestKey<'a> = (Option<&'a EntryID>, Option<TileID>, Option<ItemUID>);
+
+// A RequestKey along with the kind of request, for keeping track of requests
+type OwnedRequestKey = (
+    RequestKind,
+    Option<EntryID>,
+    Option<TileID>,
+    Option<ItemUID>,
+);
+
+fn owned_key(kind: RequestKind, key: RequestKey<'_>) -> OwnedRequestKey {
+    let (entry_id, tile_id, item_uid) = key;
+    (kind, entry_id.cloned(), tile_id, item_uid)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RequestKind {
+    Info,
+    SummaryTile,
+    SlotTile,
+    SlotMetaTile,
+    SlotMetaItem,
+}
+
+impl RequestKind {
+    pub fn label_text(&self) -> &'static str {
+        match *self {
+            RequestKind::Info => "Info",
+            RequestKind::SummaryTile => "Summary Tile",
+            RequestKind::SlotTile => "Slot Tile",
+            RequestKind::SlotMetaTile => "Slot Meta Tile",
+            RequestKind::SlotMetaItem => "Slot Meta Item",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    // The data source took too long to connect or respond
+    Timeout,
+    // The data source responded, but with an error
+    Server(String),
+    // Anything else, e.g., the connection dropped or the response could not
+    // be decoded
+    Other(String),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Timeout => write!(f, "timed out"),
+            RequestError::Server(e) => write!(f, "server error: {e}"),
+            RequestError::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedRequest {
+    pub kind: RequestKind,
+    pub entry_id: Option<EntryID>,
+    pub tile_id: Option<TileID>,
+    pub item_uid: Option<ItemUID>,
+    pub error: RequestError,
+}
+
+impl FailedRequest {
+    fn key(&self) -> RequestKey<'_> {
+        (self.entry_id.as_ref(), self.tile_id, self.item_uid)
+    }
+
+    pub fn is_for(
+        &self,
+        kind: RequestKind,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: Option<ItemUID>,
+    ) -> bool {
+        self.kind == kind && self.key() == (Some(entry_id), Some(tile_id), item_uid)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RequestStatus {
+    // Waiting for other requests to finish (see set_max_in_flight)
+    Queued,
+    InFlight,
+    Done,
+    // Answered without any data, e.g., an empty tile or a missing item
+    Empty,
+    TimedOut,
+    Failed,
+}
+
+impl RequestStatus {
+    pub fn label_text(&self) -> &'static str {
+        match *self {
+            RequestStatus::Queued => "Queued",
+            RequestStatus::InFlight => "In Flight",
+            RequestStatus::Done => "Done",
+            RequestStatus::Empty => "Empty",
+            RequestStatus::TimedOut => "Timed Out",
+            RequestStatus::Failed => "Failed",
+        }
+    }
+}
+
+// A recent request to the data source, for diagnosing slow or failing data
+// sources. Times are in seconds, as given to set_time
+#[derive(Debug, Clone)]
+pub struct LoggedRequest {
+    id: u64,
+    pub kind: RequestKind,
+    pub entry_id: Option<EntryID>,
+    pub tile_id: Option<TileID>,
+    pub item_uid: Option<ItemUID>,
+    pub requested: f64,
+    pub sent: Option<f64>,
+    pub received: Option<f64>,
+    // Approximate size of the response in memory, in bytes
+    pub size: usize,
+    pub status: RequestStatus,
+    pub error: Option<String>,
+}
+
+impl LoggedRequest {
+    // Excludes the time spent queued
+    pub fn latency(&self) -> Option<f64> {
+        Some(self.received? - self.sent?)
+    }
+}
+
+// What the request log needs to know about each kind of response
+pub(crate) trait LoggedResponse {
+    const KIND: RequestKind;
+    fn key(&self) -> RequestKey<'_>;
+    // Approximate size in memory, in bytes, or None if there is no data
+    fn size(&self) -> Option<usize>;
+}
+
+fn item_meta_size(item: &ItemMeta) -> usize {
+    std::mem::size_of::<ItemMeta>() + item.title.len() + std::mem::size_of_val(&item.fields[..])
+}
+
+impl LoggedResponse for DataSourceInfo {
+    const KIND: RequestKind = RequestKind::Info;
+    fn key(&self) -> RequestKey<'_> {
+        (None, None, None)
+    }
+    fn size(&self) -> Option<usize> {
+        Some(std::mem::size_of::<DataSourceInfo>())
+    }
+}
+
+impl LoggedResponse for SummaryTile {
+    const KIND: RequestKind = RequestKind::SummaryTile;
+    fn key(&self) -> RequestKey<'_> {
+        (Some(&self.entry_id), Some(self.tile_id), None)
+    }
+    fn size(&self) -> Option<usize> {
+        let points = self.data.utilization.len();
+        (points > 0).then(|| points * std::mem::size_of::<UtilPoint>())
+    }
+}
+
+impl LoggedResponse for SlotTile {
+    const KIND: RequestKind = RequestKind::SlotTile;
+    fn key(&self) -> RequestKey<'_> {
+        (Some(&self.entry_id), Some(self.tile_id), None)
+    }
+    fn size(&self) -> Option<usize> {
+        let items: usize = self.data.items.iter().map(Vec::len).sum();
+        (items > 0).then(|| items * std::mem::size_of::<Item>())
+    }
+}
+
+impl LoggedResponse for SlotMetaTile {
+    const KIND: RequestKind = RequestKind::SlotMetaTile;
+    fn key(&self) -> RequestKey<'_> {
+        (Some(&self.entry_id), Some(self.tile_id), None)
+    }
+    fn size(&self) -> Option<usize> {
+        let items = self.data.items.iter().flatten();
+        let size: usize = items.map(item_meta_size).sum();
+        (size > 0).then_some(size)
+    }
+}
+
+impl LoggedResponse for SlotMetaItem {
+    const KIND: RequestKind = RequestKind::SlotMetaItem;
+    fn key(&self) -> RequestKey<'_> {
+        (
+            Some(&self.entry_id),
+            Some(self.tile_id),
+            Some(self.item_uid),
+        )
+    }
+    fn size(&self) -> Option<usize> {
+        self.data.as_ref().map(item_meta_size)
+    }
 }
 
+// Counts requests that have not been answered yet. Optionally, caps the
+// number of requests sent to the data source at once: the rest wait in a
+// queue (in the order they were made) and are sent as responses come in, so
+// that e.g. expanding a large panel doesn't flood a server with requests.
+// The most recent requests are kept in a log, along with their latency
 pub struct CountingDeferredDataSource<T: DeferredDataSource> {
     data_source: T,
-    outstanding_requests: u64,
+    in_flight: u64,
+    // Number of requests sent for each key. Only responses matching one of
+    // them are counted, since data sources may deliver duplicate responses
+    // (e.g., on retries)
+    in_flight_keys: BTreeMap<OwnedRequestKey, u64>,
+    max_in_flight: Option<u64>,
+    // Along with the ID of their log entries
+    pending: VecDeque<(u64, Request)>,
+    log: VecDeque<LoggedRequest>,
+    next_id: u64,
+    now: f64,
 }
 
 impl<T: DeferredDataSource> CountingDeferredDataSource<T> {
+    const MAX_LOGGED_REQUESTS: usize = 1000;
+
     pub fn new(data_source: T) -> Self {
         Self {
             data_source,
-            outstanding_requests: 0,
+            in_flight: 0,
+            in_flight_keys: BTreeMap::new(),
+            max_in_flight: None,
+            pending: VecDeque::new(),
+            log: VecDeque::new(),
+            next_id: 0,
+            now: 0.0,
         }
     }
 
+    // Time (in seconds) to record for requests and responses from now on.
+    // Set by the caller since the data source has no clock of its own on
+    // every platform
+    pub fn set_time(&mut self, now: f64) {
+        self.now = now;
+    }
+
+    // Oldest first
+    pub fn request_log(&self) -> &VecDeque<LoggedRequest> {
+        &self.log
+    }
+
+    pub fn clear_request_log(&mut self) {
+        self.log.clear();
+    }
+
+    fn log_entry_mut(&mut self, id: u64) -> Option<&mut LoggedRequest> {
+        let index = self.log.binary_search_by_key(&id, |entry| entry.id).ok()?;
+        self.log.get_mut(index)
+    }
+
+    // Includes requests still waiting in the queue
     pub fn outstanding_requests(&self) -> u64 {
-        self.outstanding_requests
+        self.in_flight + self.pending.len() as u64
+    }
+
+    pub fn set_max_in_flight(&mut self, max_in_flight: Option<u64>) {
+        assert_ne!(max_in_flight, Some(0));
+        self.max_in_flight = max_in_flight;
+        self.send_pending();
+    }
+
+    fn has_capacity(&self) -> bool {
+        self.max_in_flight
+            .map_or(true, |max_in_flight| self.in_flight < max_in_flight)
+    }
+
+    fn send(&mut self, id: u64, request: Request) {
+        self.in_flight += 1;
+        *self
+            .in_flight_keys
+            .entry(owned_key(request.kind(), request.key()))
+            .or_default() += 1;
+        let now = self.now;
+        if let Some(entry) = self.log_entry_mut(id) {
+            entry.sent = Some(now);
+            entry.status = RequestStatus::InFlight;
+        }
+        match request {
+            Request::Info => self.data_source.fetch_info(),
+            Request::SummaryTile(entry_id, tile_id, full) => self
+                .data_source
+                .fetch_summary_tile(&entry_id, tile_id, full),
+            Request::SlotTile(entry_id, tile_id, full) => {
+                self.data_source.fetch_slot_tile(&entry_id, tile_id, full)
+            }
+            Request::SlotMetaTile(entry_id, tile_id, full) => self
+                .data_source
+                .fetch_slot_meta_tile(&entry_id, tile_id, full),
+            Request::SlotMetaItem(entry_id, tile_id, item_uid) => self
+                .data_source
+                .fetch_slot_meta_item(&entry_id, tile_id, item_uid),
+        }
+    }
+
+    fn send_pending(&mut self) {
+        while self.has_capacity() {
+            let Some((id, request)) = self.pending.pop_front() else {
+                break;
+            };
+            self.send(id, request);
+        }
+    }
+
+    fn start_request(&mut self, request: Request) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let (entry_id, tile_id, item_uid) = request.key();
+        self.log.push_back(LoggedRequest {
+            id,
+            kind: request.kind(),
+            entry_id: entry_id.cloned(),
+            tile_id,
+            item_uid,
+            requested: self.now,
+            sent: None,
+            received: None,
+            size: 0,
+            status: RequestStatus::Queued,
+            error: None,
+        });
+        if self.log.len() > Self::MAX_LOGGED_REQUESTS {
+            self.log.pop_front();
+        }
+
+        // Requests can't skip the queue, to keep them in order
+        if self.pending.is_empty() && self.has_capacity() {
+            self.send(id, request);
+        } else {
+            self.pending.push_back((id, request));
+        }
     }
 
-    fn start_request(&mut self) {
-        self.outstanding_requests += 1;
+    // Responses are matched to the oldest request for the same thing
+    fn log_responses<E: LoggedResponse>(&mut self, result: &[E]) {
+        for response in result {
+            let key = response.key();
+            let entry = self.log.iter_mut().find(|entry| {
+                entry.status == RequestStatus::InFlight
+                    && entry.kind == E::KIND
+                    && (entry.entry_id.as_ref(), entry.tile_id, entry.item_uid) == key
+            });
+            if let Some(entry) = entry {
+                entry.received = Some(self.now);
+                let size = response.size();
+                entry.size = size.unwrap_or(0);
+                entry.status = if size.is_some() {
+                    RequestStatus::Done
+                } else {
+                    RequestStatus::Empty
+                };
+            }
+        }
+    }
+
+    fn log_failures(&mut self, failures: &[FailedRequest]) {
+        for failure in failures {
+            let entry = self.log.iter_mut().find(|entry| {
+                entry.status == RequestStatus::InFlight
+                    && entry.kind == failure.kind
+                    && (entry.entry_id.as_ref(), entry.tile_id, entry.item_uid) == failure.key()
+            });
+            if let Some(entry) = entry {
+                entry.received = Some(self.now);
+                entry.status = if failure.error == RequestError::Timeout {
+                    RequestStatus::TimedOut
+                } else {
+                    RequestStatus::Failed
+                };
+                entry.error = Some(failure.error.to_string());
+            }
+        }
+    }
+
+    // Returns false for responses to requests that were never sent, or have
+    // been answered already
+    fn finish_key(&mut self, kind: RequestKind, key: RequestKey<'_>) -> bool {
+        let key = owned_key(kind, key);
+        let Some(count) = self.in_flight_keys.get_mut(&key) else {
+            return false;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.in_flight_keys.remove(&key);
+        }
+        self.in_flight -= 1;
+        true
     }
 
-    fn finish_request<E>(&mut self, result: Vec<E>) -> Vec<E> {
-        let count = result.len() as u64;
-        assert!(self.outstanding_requests >= count);
-        self.outstanding_requests -= count;
+    fn finish_request<E: LoggedResponse>(&mut self, result: Vec<E>) -> Vec<E> {
+        self.log_responses(&result);
+        for response in &result {
+            self.finish_key(E::KIND, response.key());
+        }
+        self.send_pending();
         result
     }
 }
@@ -113,8 +617,7 @@ impl<T: DeferredDataSource> DeferredDataSource for CountingDeferredDataSource<T>
     }
 
     fn fetch_info(&mut self) {
-        self.start_request();
-        self.data_source.fetch_info()
+        self.start_request(Request::Info);
     }
 
     fn get_infos(&mut self) -> Vec<DataSourceInfo> {
@@ -123,8 +626,7 @@ impl<T: DeferredDataSource> DeferredDataSource for CountingDeferredDataSource<T>
     }
 
     fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
-        self.start_request();
-        self.data_source.fetch_summary_tile(entry_id, tile_id, full)
+        self.start_request(Request::SummaryTile(entry_id.clone(), tile_id, full));
     }
 
     fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
@@ -133,8 +635,7 @@ impl<T: DeferredDataSource> DeferredDataSource for CountingDeferredDataSource<T>
     }
 
     fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
-        self.start_request();
-        self.data_source.fetch_slot_tile(entry_id, tile_id, full)
+        self.start_request(Request::SlotTile(entry_id.clone(), tile_id, full));
     }
 
     fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
@@ -143,15 +644,59 @@ impl<T: DeferredDataSource> DeferredDataSource for CountingDeferredDataSource<T>
     }
 
     fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
-        self.start_request();
-        self.data_source
-            .fetch_slot_meta_tile(entry_id, tile_id, full)
+        self.start_request(Request::SlotMetaTile(entry_id.clone(), tile_id, full));
     }
 
     fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
         let result = self.data_source.get_slot_meta_tiles();
         self.finish_request(result)
     }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        self.start_request(Request::SlotMetaItem(entry_id.clone(), tile_id, item_uid));
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        let result = self.data_source.get_slot_meta_items();
+        self.finish_request(result)
+    }
+
+    // Not counted, since a watch may stay outstanding indefinitely
+    fn fetch_updates(&mut self) {
+        self.data_source.fetch_updates()
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        self.data_source.get_updates()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.data_source.set_waker(waker)
+    }
+
+    // Failed requests are finished too, so they make room for queued ones
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        let result = self.data_source.get_failures();
+        self.log_failures(&result);
+        for failure in &result {
+            self.finish_key(failure.kind, failure.key());
+        }
+        self.send_pending();
+        result
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.data_source.set_active_entries(entries)
+    }
+
+    // Not counted either, since a search may take much longer than a tile
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        self.data_source.fetch_search(query, interval, limit)
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        self.data_source.get_search_results()
+    }
 }
 
 impl DeferredDataSource for Box<dyn DeferredDataSource> {
@@ -190,4 +735,234 @@ impl DeferredDataSource for Box<dyn DeferredDataSource> {
     fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
         self.as_mut().get_slot_meta_tiles()
     }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        self.as_mut()
+            .fetch_slot_meta_item(entry_id, tile_id, item_uid)
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        self.as_mut().get_slot_meta_items()
+    }
+
+    fn fetch_updates(&mut self) {
+        self.as_mut().fetch_updates()
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        self.as_mut().get_updates()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.as_mut().set_waker(waker)
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        self.as_mut().get_failures()
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.as_mut().set_active_entries(entries)
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        self.as_mut().fetch_search(query, interval, limit)
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        self.as_mut().get_search_results()
+    }
+}
+
+impl<T: DeferredDataSource + ?Sized> DeferredDataSource for &mut T {
+    fn fetch_description(&self) -> DataSourceDescription {
+        (**self).fetch_description()
+    }
+
+    fn fetch_info(&mut self) {
+        (**self).fetch_info()
+    }
+
+    fn get_infos(&mut self) -> Vec<DataSourceInfo> {
+        (**self).get_infos()
+    }
+
+    fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        (**self).fetch_summary_tile(entry_id, tile_id, full)
+    }
+
+    fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
+        (**self).get_summary_tiles()
+    }
+
+    fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        (**self).fetch_slot_tile(entry_id, tile_id, full)
+    }
+
+    fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
+        (**self).get_slot_tiles()
+    }
+
+    fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        (**self).fetch_slot_meta_tile(entry_id, tile_id, full)
+    }
+
+    fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
+        (**self).get_slot_meta_tiles()
+    }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        (**self).fetch_slot_meta_item(entry_id, tile_id, item_uid)
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        (**self).get_slot_meta_items()
+    }
+
+    fn fetch_updates(&mut self) {
+        (**self).fetch_updates()
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        (**self).get_updates()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        (**self).set_waker(waker)
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        (**self).get_failures()
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        (**self).set_active_entries(entries)
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        (**self).fetch_search(query, interval, limit)
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        (**self).get_search_results()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_data::TestDeferredDataSource;
+    use crate::timestamp::{Interval, Timestamp};
+
+    #[test]
+    fn test_max_in_flight() {
+        let mut data_source = CountingDeferredDataSource::new(TestDeferredDataSource::held());
+        data_source.set_max_in_flight(Some(3));
+        for i in 0..10 {
+            let tile_id = TileID(Interval::new(Timestamp(i), Timestamp(i + 1)), 0);
+            data_source.fetch_summary_tile(&EntryID::root(), tile_id, false);
+        }
+        assert_eq!(data_source.outstanding_requests(), 10);
+        assert_eq!(data_source.data_source.pending(), 3);
+
+        // Each batch of responses lets the next requests through, in order
+        let mut received = Vec::new();
+        while data_source.outstanding_requests() > 0 {
+            assert!(data_source.data_source.pending() <= 3);
+            data_source.data_source.release();
+            received.extend(
+                data_source
+                    .get_summary_tiles()
+                    .into_iter()
+                    .map(|tile| tile.tile_id.0.start.0),
+            );
+        }
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+
+        // Raising the cap sends the queued requests immediately
+        for i in 0..10 {
+            let tile_id = TileID(Interval::new(Timestamp(i), Timestamp(i + 1)), 0);
+            data_source.fetch_summary_tile(&EntryID::root(), tile_id, false);
+        }
+        data_source.set_max_in_flight(None);
+        assert_eq!(data_source.data_source.pending(), 10);
+    }
+
+    #[test]
+    fn test_request_log() {
+        let mut data_source = CountingDeferredDataSource::new(TestDeferredDataSource::held());
+        data_source.set_max_in_flight(Some(1));
+        data_source.set_time(1.0);
+        for i in 0..2 {
+            let tile_id = TileID(Interval::new(Timestamp(i), Timestamp(i + 1)), 0);
+            data_source.fetch_summary_tile(&EntryID::root(), tile_id, false);
+        }
+        let statuses = |data_source: &CountingDeferredDataSource<TestDeferredDataSource>| {
+            let log = data_source.request_log();
+            log.iter().map(|entry| entry.status).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            statuses(&data_source),
+            [RequestStatus::InFlight, RequestStatus::Queued]
+        );
+
+        // The response to the first request sends the second
+        data_source.set_time(1.5);
+        data_source.data_source.release();
+        data_source.get_summary_tiles();
+        assert_eq!(
+            statuses(&data_source),
+            [RequestStatus::Empty, RequestStatus::InFlight]
+        );
+        let log = data_source.request_log();
+        assert_eq!(log[0].latency(), Some(0.5));
+        assert_eq!(log[1].sent, Some(1.5));
+        assert_eq!(log[1].latency(), None);
+    }
+
+    #[test]
+    fn test_request_failures() {
+        let mut data_source = CountingDeferredDataSource::new(TestDeferredDataSource::held());
+        data_source.set_max_in_flight(Some(1));
+        for i in 0..3 {
+            let tile_id = TileID(Interval::new(Timestamp(i), Timestamp(i + 1)), 0);
+            data_source.fetch_summary_tile(&EntryID::root(), tile_id, false);
+        }
+
+        // Failures make room for queued requests, like responses do
+        data_source.data_source.fail(RequestError::Timeout);
+        assert_eq!(data_source.get_failures().len(), 1);
+        assert_eq!(data_source.outstanding_requests(), 2);
+        data_source
+            .data_source
+            .fail(RequestError::Server("HTTP 500".to_owned()));
+        assert_eq!(data_source.get_failures().len(), 1);
+        assert_eq!(data_source.outstanding_requests(), 1);
+
+        let log = data_source.request_log();
+        assert_eq!(log[0].status, RequestStatus::TimedOut);
+        assert_eq!(log[1].status, RequestStatus::Failed);
+        assert_eq!(log[1].error.as_deref(), Some("server error: HTTP 500"));
+        assert_eq!(log[2].status, RequestStatus::InFlight);
+    }
+
+    #[test]
+    fn test_duplicate_responses() {
+        let mut data_source = CountingDeferredDataSource::new(TestDeferredDataSource::held());
+        data_source.data_source.duplicate = true;
+        for i in 0..2 {
+            let tile_id = TileID(Interval::new(Timestamp(i), Timestamp(i + 1)), 0);
+            data_source.fetch_summary_tile(&EntryID::root(), tile_id, false);
+        }
+
+        // A duplicate doesn't count as the response to another request
+        data_source.data_source.release_one();
+        assert_eq!(data_source.get_summary_tiles().len(), 2);
+        assert_eq!(data_source.outstanding_requests(), 1);
+
+        data_source.data_source.release_one();
+        assert_eq!(data_source.get_summary_tiles().len(), 2);
+        assert_eq!(data_source.outstanding_requests(), 0);
+    }
 }
@@ -0,0 +1,270 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Array, Function, Promise, Reflect, Uint8Array};
+use log::warn;
+use serde::Deserialize;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+use crate::data::{
+    DataSourceDescription, DataSourceInfo, EntryID, ItemUID, SlotMetaItem, SlotMetaTile, SlotTile,
+    SummaryTile, TileID, PROTOCOL_VERSION,
+};
+use crate::deferred_data::{DeferredDataSource, FailedRequest, RequestError, RequestKind};
+use crate::http::schema::TileRequestRef;
+
+// Serves an archive directory on the local disk in the browser, through the
+// File System Access API. Files are read as they are needed, so nothing has
+// to be uploaded (or even loaded into memory up front), however large the
+// archive. The API is only available in some browsers (see is_supported);
+// elsewhere, an archive can be dropped as a tar file (see MemoryDataSource).
+pub struct DirectoryDataSource {
+    name: String,
+    // FileSystemDirectoryHandle of the root of the archive
+    handle: JsValue,
+    infos: Rc<RefCell<Vec<DataSourceInfo>>>,
+    summary_tiles: Rc<RefCell<Vec<SummaryTile>>>,
+    slot_tiles: Rc<RefCell<Vec<SlotTile>>>,
+    slot_meta_tiles: Rc<RefCell<Vec<SlotMetaTile>>>,
+    slot_meta_items: Rc<RefCell<Vec<SlotMetaItem>>>,
+    failures: Rc<RefCell<Vec<FailedRequest>>>,
+}
+
+// The API isn't covered by web-sys (without unstable features), so methods
+// are looked up by name. All of the ones used here return promises
+async fn call_method(target: &JsValue, name: &str, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let method: Function = Reflect::get(target, &name.into())?.dyn_into()?;
+    let args: Array = args.iter().collect();
+    let promise: Promise = method.apply(target, &args)?.dyn_into()?;
+    JsFuture::from(promise).await
+}
+
+fn error_name(e: &JsValue) -> Option<String> {
+    Reflect::get(e, &"name".into()).ok()?.as_string()
+}
+
+fn error_message(e: &JsValue) -> String {
+    Reflect::get(e, &"message".into())
+        .ok()
+        .and_then(|message| message.as_string())
+        .or_else(|| e.as_string())
+        .unwrap_or_else(|| format!("{e:?}"))
+}
+
+async fn read_file(root: &JsValue, path: &str) -> Result<Vec<u8>, String> {
+    let read = async {
+        let mut dir = root.clone();
+        let mut components: Vec<_> = path.split('/').collect();
+        let name = components.pop().unwrap();
+        for component in components {
+            dir = call_method(&dir, "getDirectoryHandle", &[component.into()]).await?;
+        }
+        let file = call_method(&dir, "getFileHandle", &[name.into()]).await?;
+        let file = call_method(&file, "getFile", &[]).await?;
+        let buffer = call_method(&file, "arrayBuffer", &[]).await?;
+        Ok::<_, JsValue>(Uint8Array::new(&buffer).to_vec())
+    };
+    read.await
+        .map_err(|e| format!("unable to read {path}: {}", error_message(&e)))
+}
+
+// Directory of the archive holding the tiles for this kind of request
+fn archive_dir(kind: RequestKind) -> &'static str {
+    match kind {
+        RequestKind::SummaryTile => "summary_tile",
+        RequestKind::SlotTile => "slot_tile",
+        // Archives have no files for individual items, so those are
+        // extracted from the meta tile
+        RequestKind::SlotMetaTile | RequestKind::SlotMetaItem => "slot_meta_tile",
+        RequestKind::Info => unreachable!(),
+    }
+}
+
+fn decode<T: for<'a> Deserialize<'a>>(data: &[u8]) -> Result<T, String> {
+    let f = zstd::Decoder::new(data).map_err(|e| format!("zstd decompression failed: {e}"))?;
+    ciborium::from_reader(f).map_err(|e| format!("cbor decoding failed: {e}"))
+}
+
+// True if the browser can open local directories
+pub fn is_supported() -> bool {
+    web_sys::window()
+        .is_some_and(|window| Reflect::has(&window, &"showDirectoryPicker".into()).unwrap_or(false))
+}
+
+// Ask the user for an archive directory. This must be called in response to
+// user input (e.g., a click), or the browser refuses to show the picker.
+// Nothing happens if the user cancels
+pub fn pick_directory(on_done: impl FnOnce(DirectoryDataSource) + 'static) {
+    spawn_local(async move {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        match call_method(&window, "showDirectoryPicker", &[]).await {
+            Ok(handle) => on_done(DirectoryDataSource::new(handle)),
+            Err(e) if error_name(&e).as_deref() == Some("AbortError") => {}
+            Err(e) => warn!("unable to open directory: {}", error_message(&e)),
+        }
+    });
+}
+
+impl DirectoryDataSource {
+    fn new(handle: JsValue) -> Self {
+        let name = Reflect::get(&handle, &"name".into())
+            .ok()
+            .and_then(|name| name.as_string())
+            .unwrap_or_default();
+        Self {
+            name,
+            handle,
+            infos: Rc::new(RefCell::new(Vec::new())),
+            summary_tiles: Rc::new(RefCell::new(Vec::new())),
+            slot_tiles: Rc::new(RefCell::new(Vec::new())),
+            slot_meta_tiles: Rc::new(RefCell::new(Vec::new())),
+            slot_meta_items: Rc::new(RefCell::new(Vec::new())),
+            failures: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    fn request<T, D>(
+        &self,
+        kind: RequestKind,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: Option<ItemUID>,
+        container: Rc<RefCell<Vec<T>>>,
+        finish: impl FnOnce(D) -> T + 'static,
+    ) where
+        T: 'static,
+        D: for<'a> Deserialize<'a>,
+    {
+        let req = TileRequestRef { entry_id, tile_id };
+        let path = format!("{}/{}", archive_dir(kind), req.to_slug());
+        let root = self.handle.clone();
+        let entry_id = entry_id.clone();
+        let failures = self.failures.clone();
+        spawn_local(async move {
+            match read_file(&root, &path).await.and_then(|data| decode(&data)) {
+                Ok(result) => container.borrow_mut().push(finish(result)),
+                Err(error) => {
+                    warn!("request failed: {}", error);
+                    failures.borrow_mut().push(FailedRequest {
+                        kind,
+                        entry_id: Some(entry_id),
+                        tile_id: Some(tile_id),
+                        item_uid,
+                        error: RequestError::Other(error),
+                    });
+                }
+            }
+        });
+    }
+}
+
+impl DeferredDataSource for DirectoryDataSource {
+    fn fetch_description(&self) -> DataSourceDescription {
+        DataSourceDescription {
+            source_locator: vec![self.name.clone()],
+        }
+    }
+
+    fn fetch_info(&mut self) {
+        let root = self.handle.clone();
+        let name = self.name.clone();
+        let container = self.infos.clone();
+        spawn_local(async move {
+            let info = read_file(&root, "info")
+                .await
+                .and_then(|data| decode(&data))
+                .unwrap_or_else(|e| {
+                    DataSourceInfo::unavailable(format!(
+                        "Unable to load profile from {name}: {e}. The directory must be an \
+                         archive written by this viewer (protocol version {PROTOCOL_VERSION})."
+                    ))
+                });
+            container.borrow_mut().push(info);
+        });
+    }
+
+    fn get_infos(&mut self) -> Vec<DataSourceInfo> {
+        std::mem::take(&mut self.infos.borrow_mut())
+    }
+
+    fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, _full: bool) {
+        let container = self.summary_tiles.clone();
+        self.request(
+            RequestKind::SummaryTile,
+            entry_id,
+            tile_id,
+            None,
+            container,
+            |tile: SummaryTile| tile,
+        );
+    }
+
+    fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
+        std::mem::take(&mut self.summary_tiles.borrow_mut())
+    }
+
+    fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, _full: bool) {
+        let container = self.slot_tiles.clone();
+        self.request(
+            RequestKind::SlotTile,
+            entry_id,
+            tile_id,
+            None,
+            container,
+            |tile: SlotTile| tile,
+        );
+    }
+
+    fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
+        std::mem::take(&mut self.slot_tiles.borrow_mut())
+    }
+
+    fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, _full: bool) {
+        let container = self.slot_meta_tiles.clone();
+        self.request(
+            RequestKind::SlotMetaTile,
+            entry_id,
+            tile_id,
+            None,
+            container,
+            |tile: SlotMetaTile| tile,
+        );
+    }
+
+    fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
+        std::mem::take(&mut self.slot_meta_tiles.borrow_mut())
+    }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        let container = self.slot_meta_items.clone();
+        self.request(
+            RequestKind::SlotMetaItem,
+            entry_id,
+            tile_id,
+            Some(item_uid),
+            container,
+            move |tile: SlotMetaTile| SlotMetaItem {
+                entry_id: tile.entry_id,
+                tile_id,
+                item_uid,
+                data: tile
+                    .data
+                    .items
+                    .into_iter()
+                    .flatten()
+                    .find(|item| item.item_uid == item_uid),
+            },
+        );
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        std::mem::take(&mut self.slot_meta_items.borrow_mut())
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        std::mem::take(&mut self.failures.borrow_mut())
+    }
+}
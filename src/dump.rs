@@ -0,0 +1,212 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::app::{ProfApp, SourceOptions};
+use crate::data::{EntryID, ItemUID, TileID};
+use crate::deferred_data::DeferredDataSource;
+use crate::http::tls::TlsOptions;
+
+// Prints the contents of a data source as JSON, for debugging data sources
+// without the UI. Entries and tiles are given as slugs, the same as in the
+// HTTP API (e.g., entry 0_1_2 and tile 0_1000000):
+//
+//     dump <locator> info
+//     dump <locator> tile-set
+//     dump <locator> summary-tile <entry> <tile> [--full]
+//     dump <locator> slot-tile <entry> <tile> [--full]
+//     dump <locator> slot-meta-tile <entry> <tile> [--full]
+//     dump <locator> slot-meta-item <entry> <tile> <item uid>
+//
+// where the locator is a URL or the path of an archive. The TLS options
+// (--ca-bundle <path> and --insecure) may be given anywhere.
+
+#[derive(Debug, PartialEq)]
+pub enum DumpRequest {
+    Info,
+    TileSet,
+    SummaryTile(EntryID, TileID, bool),
+    SlotTile(EntryID, TileID, bool),
+    SlotMetaTile(EntryID, TileID, bool),
+    SlotMetaItem(EntryID, TileID, ItemUID),
+}
+
+const USAGE: &str = "usage: dump <locator> (info | tile-set | \
+                     (summary-tile | slot-tile | slot-meta-tile) <entry> <tile> [--full] | \
+                     slot-meta-item <entry> <tile> <item uid>)";
+
+impl DumpRequest {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let args: Vec<_> = args.iter().map(String::as_str).collect();
+        let (kind, args) = args.split_first().ok_or(USAGE)?;
+        if let "info" | "tile-set" = *kind {
+            if !args.is_empty() {
+                return Err(USAGE.to_owned());
+            }
+            return Ok(if *kind == "info" {
+                Self::Info
+            } else {
+                Self::TileSet
+            });
+        }
+
+        let [entry_id, tile_id, rest @ ..] = args else {
+            return Err(USAGE.to_owned());
+        };
+        let entry_id =
+            EntryID::from_slug(entry_id).map_err(|e| format!("invalid entry {entry_id}: {e}"))?;
+        let tile_id =
+            TileID::from_slug(tile_id).map_err(|e| format!("invalid tile {tile_id}: {e}"))?;
+
+        if *kind == "slot-meta-item" {
+            let [item_uid] = rest else {
+                return Err(USAGE.to_owned());
+            };
+            let item_uid = item_uid
+                .parse()
+                .map_err(|_| format!("invalid item uid {item_uid}"))?;
+            return Ok(Self::SlotMetaItem(entry_id, tile_id, ItemUID(item_uid)));
+        }
+
+        let full = match rest {
+            [] => false,
+            ["--full"] => true,
+            _ => return Err(USAGE.to_owned()),
+        };
+        match *kind {
+            "summary-tile" => Ok(Self::SummaryTile(entry_id, tile_id, full)),
+            "slot-tile" => Ok(Self::SlotTile(entry_id, tile_id, full)),
+            "slot-meta-tile" => Ok(Self::SlotMetaTile(entry_id, tile_id, full)),
+            _ => Err(USAGE.to_owned()),
+        }
+    }
+}
+
+// Block until the data source responds, or fails to
+fn wait<T>(
+    data_source: &mut dyn DeferredDataSource,
+    mut get: impl FnMut(&mut dyn DeferredDataSource) -> Vec<T>,
+) -> io::Result<T> {
+    loop {
+        if let Some(result) = get(data_source).pop() {
+            return Ok(result);
+        }
+        if let Some(failure) = data_source.get_failures().pop() {
+            return Err(io::Error::other(format!(
+                "{} request failed: {}",
+                failure.kind.label_text(),
+                failure.error
+            )));
+        }
+        std::thread::yield_now();
+    }
+}
+
+fn write_json(out: impl Write, data: &impl Serialize) -> io::Result<()> {
+    let mut out = io::BufWriter::new(out);
+    serde_json::to_writer_pretty(&mut out, data)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+pub fn dump(
+    data_source: &mut dyn DeferredDataSource,
+    request: &DumpRequest,
+    out: impl Write,
+) -> io::Result<()> {
+    match request {
+        DumpRequest::Info | DumpRequest::TileSet => {
+            data_source.fetch_info();
+            let info = wait(data_source, |d| d.get_infos())?;
+            if *request == DumpRequest::Info {
+                write_json(out, &info)
+            } else {
+                write_json(out, &info.tile_set)
+            }
+        }
+        DumpRequest::SummaryTile(entry_id, tile_id, full) => {
+            data_source.fetch_summary_tile(entry_id, *tile_id, *full);
+            write_json(out, &wait(data_source, |d| d.get_summary_tiles())?)
+        }
+        DumpRequest::SlotTile(entry_id, tile_id, full) => {
+            data_source.fetch_slot_tile(entry_id, *tile_id, *full);
+            write_json(out, &wait(data_source, |d| d.get_slot_tiles())?)
+        }
+        DumpRequest::SlotMetaTile(entry_id, tile_id, full) => {
+            data_source.fetch_slot_meta_tile(entry_id, *tile_id, *full);
+            write_json(out, &wait(data_source, |d| d.get_slot_meta_tiles())?)
+        }
+        DumpRequest::SlotMetaItem(entry_id, tile_id, item_uid) => {
+            data_source.fetch_slot_meta_item(entry_id, *tile_id, *item_uid);
+            write_json(out, &wait(data_source, |d| d.get_slot_meta_items())?)
+        }
+    }
+}
+
+// Entry point for the dump subcommand, given the arguments following it
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut args = args.to_vec();
+    let tls = TlsOptions::extract_args(&mut args)?;
+    let (locator, args) = args.split_first().ok_or(USAGE)?;
+    let request = DumpRequest::parse(args)?;
+    let options = SourceOptions {
+        tls,
+        ..Default::default()
+    };
+    let mut data_source = ProfApp::open_data_source(locator, &options)?;
+    dump(data_source.as_mut(), &request, io::stdout().lock()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_data::TestDeferredDataSource;
+    use crate::timestamp::{Interval, Timestamp};
+
+    fn parse(s: &str) -> Result<DumpRequest, String> {
+        let args: Vec<_> = s.split_whitespace().map(str::to_owned).collect();
+        DumpRequest::parse(&args)
+    }
+
+    #[test]
+    fn test_parse_dump_request() {
+        assert_eq!(parse("info"), Ok(DumpRequest::Info));
+        assert_eq!(parse("tile-set"), Ok(DumpRequest::TileSet));
+        assert!(parse("info 0").is_err());
+
+        let entry_id = EntryID::root().child(0).child(1);
+        let tile_id = TileID(Interval::new(Timestamp(0), Timestamp(1000)), 0);
+        assert_eq!(
+            parse("slot-tile 0_1 0_1000 --full"),
+            Ok(DumpRequest::SlotTile(entry_id.clone(), tile_id, true))
+        );
+        assert_eq!(
+            parse("summary-tile 0_1 0_1000"),
+            Ok(DumpRequest::SummaryTile(entry_id.clone(), tile_id, false))
+        );
+        assert_eq!(
+            parse("slot-meta-item 0_1 0_1000 42"),
+            Ok(DumpRequest::SlotMetaItem(entry_id, tile_id, ItemUID(42)))
+        );
+        assert!(parse("slot-tile 0_1").is_err());
+        assert!(parse("slot-tile 0_1 0 --full").is_err());
+        assert!(parse("slot-meta-item 0_1 0_1000").is_err());
+        assert!(parse("bogus 0_1 0_1000").is_err());
+    }
+
+    #[test]
+    fn test_dump_failed_request() {
+        let mut data_source = TestDeferredDataSource::default();
+        data_source.fail_tiles = true;
+        let mut out = Vec::new();
+        dump(&mut data_source, &DumpRequest::Info, &mut out).unwrap();
+        assert!(!out.is_empty());
+
+        let entry_id = EntryID::root().child(0);
+        let tile_id = TileID(Interval::new(Timestamp(0), Timestamp(1000)), 0);
+        let request = DumpRequest::SlotTile(entry_id, tile_id, false);
+        let error = dump(&mut data_source, &request, Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("tile unavailable"));
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::BTreeSet;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::data::{
+    DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID, ItemUID, SearchQuery,
+    SearchResults, SlotMetaItem, SlotMetaTile, SlotTile, SummaryTile, TileID,
+};
+use crate::deferred_data::{
+    DeferredDataSource, FailedRequest, LoggedResponse, RequestError, Waker,
+};
+use crate::timestamp::Interval;
+
+// Faults to inject into the responses of a data source. Latency is measured
+// in calls to the get_* methods (i.e., roughly in frames in the viewer),
+// rather than in wall-clock time, so that a given seed always produces the
+// same sequence of responses.
+#[derive(Debug, Clone)]
+pub struct FaultConfig {
+    pub seed: u64,
+    pub min_latency: u32,
+    pub max_latency: u32,
+    // Deliver responses that are ready in random order, rather than in the
+    // order they were received
+    pub reorder: bool,
+    // Probability of delivering a response twice
+    pub duplicate_rate: f64,
+    // Probability of replacing a response with an error, reported through
+    // get_failures. The info can't be retried, so it is replaced by an
+    // unavailable one instead
+    pub error_rate: f64,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            min_latency: 0,
+            max_latency: 0,
+            reorder: false,
+            duplicate_rate: 0.0,
+            error_rate: 0.0,
+        }
+    }
+}
+
+struct Delayed<T> {
+    ready_at: u64,
+    data: T,
+}
+
+struct DelayQueue<T> {
+    polls: u64,
+    pending: Vec<Delayed<T>>,
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        Self {
+            polls: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+pub struct FaultInjectingDeferredDataSource<T: DeferredDataSource> {
+    data_source: T,
+    config: FaultConfig,
+    rng: StdRng,
+    infos: DelayQueue<DataSourceInfo>,
+    summary_tiles: DelayQueue<SummaryTile>,
+    slot_tiles: DelayQueue<SlotTile>,
+    slot_meta_tiles: DelayQueue<SlotMetaTile>,
+    slot_meta_items: DelayQueue<SlotMetaItem>,
+    failures: Vec<FailedRequest>,
+}
+
+impl<T: DeferredDataSource> FaultInjectingDeferredDataSource<T> {
+    pub fn new(data_source: T, config: FaultConfig) -> Self {
+        assert!(config.min_latency <= config.max_latency);
+        assert!((0.0..=1.0).contains(&config.duplicate_rate));
+        assert!((0.0..=1.0).contains(&config.error_rate));
+        Self {
+            data_source,
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            infos: DelayQueue::default(),
+            summary_tiles: DelayQueue::default(),
+            slot_tiles: DelayQueue::default(),
+            slot_meta_tiles: DelayQueue::default(),
+            slot_meta_items: DelayQueue::default(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+fn injected_failure<D: LoggedResponse>(data: D) -> Result<D, FailedRequest> {
+    let (entry_id, tile_id, item_uid) = data.key();
+    Err(FailedRequest {
+        kind: D::KIND,
+        entry_id: entry_id.cloned(),
+        tile_id,
+        item_uid,
+        error: RequestError::Other("injected fault".to_owned()),
+    })
+}
+
+// Hack: this is a free function to avoid a mutability conflict between the
+// queue and the rest of the data source
+fn inject<D: Clone>(
+    queue: &mut DelayQueue<D>,
+    failures: &mut Vec<FailedRequest>,
+    results: Vec<D>,
+    config: &FaultConfig,
+    rng: &mut StdRng,
+    error: impl Fn(D) -> Result<D, FailedRequest>,
+) -> Vec<D> {
+    queue.polls += 1;
+
+    for data in results {
+        let data = if rng.gen_bool(config.error_rate) {
+            match error(data) {
+                Ok(data) => data,
+                Err(failure) => {
+                    failures.push(failure);
+                    continue;
+                }
+            }
+        } else {
+            data
+        };
+        let copies = if rng.gen_bool(config.duplicate_rate) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let latency = rng.gen_range(config.min_latency..=config.max_latency);
+            queue.pending.push(Delayed {
+                ready_at: queue.polls + latency as u64,
+                data: data.clone(),
+            });
+        }
+    }
+
+    let mut ready = Vec::new();
+    if config.reorder {
+        let mut i = 0;
+        while i < queue.pending.len() {
+            if queue.pending[i].ready_at <= queue.polls {
+                ready.push(queue.pending.remove(i).data);
+            } else {
+                i += 1;
+            }
+        }
+        ready.shuffle(rng);
+    } else {
+        // Preserve the order of responses, so a response that takes longer
+        // holds back the ones after it
+        let count = queue
+            .pending
+            .iter()
+            .take_while(|delayed| delayed.ready_at <= queue.polls)
+            .count();
+        ready.extend(queue.pending.drain(..count).map(|delayed| delayed.data));
+    }
+    ready
+}
+
+impl<T: DeferredDataSource> DeferredDataSource for FaultInjectingDeferredDataSource<T> {
+    fn fetch_description(&self) -> DataSourceDescription {
+        self.data_source.fetch_description()
+    }
+
+    fn fetch_info(&mut self) {
+        self.data_source.fetch_info()
+    }
+
+    fn get_infos(&mut self) -> Vec<DataSourceInfo> {
+        let results = self.data_source.get_infos();
+        inject(
+            &mut self.infos,
+            &mut self.failures,
+            results,
+            &self.config,
+            &mut self.rng,
+            |_| Ok(DataSourceInfo::unavailable("Injected fault".to_owned())),
+        )
+    }
+
+    fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.data_source.fetch_summary_tile(entry_id, tile_id, full)
+    }
+
+    fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
+        let results = self.data_source.get_summary_tiles();
+        inject(
+            &mut self.summary_tiles,
+            &mut self.failures,
+            results,
+            &self.config,
+            &mut self.rng,
+            injected_failure,
+        )
+    }
+
+    fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.data_source.fetch_slot_tile(entry_id, tile_id, full)
+    }
+
+    fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
+        let results = self.data_source.get_slot_tiles();
+        inject(
+            &mut self.slot_tiles,
+            &mut self.failures,
+            results,
+            &self.config,
+            &mut self.rng,
+            injected_failure,
+        )
+    }
+
+    fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.data_source
+            .fetch_slot_meta_tile(entry_id, tile_id, full)
+    }
+
+    fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
+        let results = self.data_source.get_slot_meta_tiles();
+        inject(
+            &mut self.slot_meta_tiles,
+            &mut self.failures,
+            results,
+            &self.config,
+            &mut self.rng,
+            injected_failure,
+        )
+    }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        self.data_source
+            .fetch_slot_meta_item(entry_id, tile_id, item_uid)
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        let results = self.data_source.get_slot_meta_items();
+        inject(
+            &mut self.slot_meta_items,
+            &mut self.failures,
+            results,
+            &self.config,
+            &mut self.rng,
+            injected_failure,
+        )
+    }
+
+    // Updates are passed along untouched, since they don't answer requests
+    fn fetch_updates(&mut self) {
+        self.data_source.fetch_updates()
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        self.data_source.get_updates()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.data_source.set_waker(waker)
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        let mut failures = std::mem::take(&mut self.failures);
+        failures.extend(self.data_source.get_failures());
+        failures
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.data_source.set_active_entries(entries)
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        self.data_source.fetch_search(query, interval, limit)
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        self.data_source.get_search_results()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::deferred_data::RequestKind;
+    use crate::test_data::TestDeferredDataSource;
+    use crate::timestamp::{Interval, Timestamp};
+
+    // Order in which the tiles are delivered, as (poll, start of tile)
+    fn deliveries(config: FaultConfig) -> Vec<(u32, i64)> {
+        let mut data_source =
+            FaultInjectingDeferredDataSource::new(TestDeferredDataSource::default(), config);
+        for i in 0..20 {
+            let tile_id = TileID(Interval::new(Timestamp(i), Timestamp(i + 1)), 0);
+            data_source.fetch_summary_tile(&EntryID::root(), tile_id, false);
+        }
+        let mut result = Vec::new();
+        for poll in 0..20 {
+            for tile in data_source.get_summary_tiles() {
+                result.push((poll, tile.tile_id.0.start.0));
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        let config = FaultConfig {
+            seed: 1,
+            min_latency: 2,
+            max_latency: 5,
+            reorder: true,
+            duplicate_rate: 0.5,
+            ..Default::default()
+        };
+        let result = deliveries(config.clone());
+
+        // Same seed, same responses
+        assert_eq!(result, deliveries(config));
+
+        // Every tile is delivered, and not before the minimum latency
+        assert!(result.len() > 20);
+        assert!(result.iter().all(|(poll, _)| *poll >= 2));
+        for i in 0..20 {
+            assert!(result.iter().any(|(_, start)| *start == i));
+        }
+    }
+
+    #[test]
+    fn test_injected_failures() {
+        let config = FaultConfig {
+            error_rate: 1.0,
+            ..Default::default()
+        };
+        let mut data_source =
+            FaultInjectingDeferredDataSource::new(TestDeferredDataSource::default(), config);
+        let tile_id = TileID(Interval::new(Timestamp(0), Timestamp(1)), 0);
+        data_source.fetch_slot_tile(&EntryID::root(), tile_id, false);
+
+        // Reported as failures, rather than as empty tiles
+        assert!(data_source.get_slot_tiles().is_empty());
+        let failures = data_source.get_failures();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].is_for(RequestKind::SlotTile, &EntryID::root(), tile_id, None));
+    }
+}
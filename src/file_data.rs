@@ -5,7 +5,7 @@ use serde::Deserialize;
 
 use crate::data::{
     DataSource, DataSourceDescription, DataSourceInfo, EntryID, SlotMetaTile, SlotTile,
-    SummaryTile, TileID,
+    SummaryTile, TileID, PROTOCOL_VERSION,
 };
 use crate::http::schema::TileRequestRef;
 
@@ -37,21 +37,33 @@ impl DataSource for FileDataSource {
         }
     }
     fn fetch_info(&self) -> DataSourceInfo {
+        // Archives may have been written by a different version, so explain
+        // decoding failures rather than panicking
         let path = self.basedir.join("info");
-        self.read_file::<DataSourceInfo>(&path)
+        let decoded = File::open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|f| zstd::Decoder::new(f).map_err(|e| e.to_string()))
+            .and_then(|f| ciborium::from_reader(f).map_err(|e| e.to_string()));
+        decoded.unwrap_or_else(|e| {
+            DataSourceInfo::unavailable(format!(
+                "Unable to load profile from {}: {e}. The archive may have been written by \
+                 an incompatible version (this viewer uses protocol version {PROTOCOL_VERSION}).",
+                self.basedir.display()
+            ))
+        })
     }
 
     fn fetch_summary_tile(&self, entry_id: &EntryID, tile_id: TileID, _full: bool) -> SummaryTile {
         let req = TileRequestRef { entry_id, tile_id };
         let mut path = self.basedir.join("summary_tile");
-        path.push(&req.to_slug());
+        path.push(req.to_slug());
         self.read_file::<SummaryTile>(&path)
     }
 
     fn fetch_slot_tile(&self, entry_id: &EntryID, tile_id: TileID, _full: bool) -> SlotTile {
         let req = TileRequestRef { entry_id, tile_id };
         let mut path = self.basedir.join("slot_tile");
-        path.push(&req.to_slug());
+        path.push(req.to_slug());
         self.read_file::<SlotTile>(&path)
     }
 
@@ -63,7 +75,7 @@ impl DataSource for FileDataSource {
     ) -> SlotMetaTile {
         let req = TileRequestRef { entry_id, tile_id };
         let mut path = self.basedir.join("slot_meta_tile");
-        path.push(&req.to_slug());
+        path.push(req.to_slug());
         self.read_file::<SlotMetaTile>(&path)
     }
 }
@@ -0,0 +1,241 @@
+use std::collections::BTreeSet;
+
+use regex::{escape, Regex};
+
+use crate::crop_data::CropDeferredDataSource;
+use crate::data::{
+    DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID, EntryInfo, ItemUID,
+    SearchQuery, SearchResults, SlotMetaItem, SlotMetaTile, SlotTile, SummaryTile, TileID,
+};
+use crate::deferred_data::{DeferredDataSource, FailedRequest, Waker};
+use crate::timestamp::Interval;
+
+// A pattern of the form node[/kind[/slot]] naming a set of entries, e.g.,
+// node3/cpu/* or */gpu. Each component is matched (ignoring case and
+// whitespace) against either the short or long name of the entry, and may
+// contain "*" wildcards.
+#[derive(Debug, Clone)]
+pub struct EntryPattern {
+    components: Vec<Regex>,
+}
+
+impl EntryPattern {
+    pub fn parse(pattern: &str) -> Self {
+        let components = pattern
+            .split('/')
+            .map(|component| {
+                let component: String = component.split_whitespace().collect();
+                let glob = escape(&component).replace("\\*", ".*");
+                Regex::new(&format!("(?i)^{glob}$")).unwrap()
+            })
+            .collect();
+        Self { components }
+    }
+
+    // Number of components, i.e., the depth of the entries named
+    pub fn depth(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn matches(&self, level: usize, short_name: &str, long_name: &str) -> bool {
+        let long_name: String = long_name.split_whitespace().collect();
+        let component = &self.components[level];
+        component.is_match(short_name) || component.is_match(&long_name)
+    }
+}
+
+fn select_slots(
+    entry: &EntryInfo,
+    entry_id: EntryID,
+    patterns: &[&EntryPattern],
+    result: &mut BTreeSet<EntryID>,
+) {
+    // Patterns that are fully matched by this entry or its ancestors select
+    // everything below it
+    let level = entry_id.level() as usize;
+    match entry {
+        EntryInfo::Panel { slots, .. } => {
+            for (i, slot) in slots.iter().enumerate() {
+                let (EntryInfo::Panel {
+                    short_name,
+                    long_name,
+                    ..
+                }
+                | EntryInfo::Slot {
+                    short_name,
+                    long_name,
+                    ..
+                }) = slot
+                else {
+                    unreachable!();
+                };
+                let patterns: Vec<_> = patterns
+                    .iter()
+                    .copied()
+                    .filter(|p| p.depth() <= level || p.matches(level, short_name, long_name))
+                    .collect();
+                if !patterns.is_empty() {
+                    select_slots(slot, entry_id.child(i as u64), &patterns, result);
+                }
+            }
+        }
+        EntryInfo::Slot { .. } => {
+            if patterns.iter().any(|p| p.depth() <= level) {
+                result.insert(entry_id);
+            }
+        }
+        EntryInfo::Summary { .. } => unreachable!(),
+    }
+}
+
+// Slots under the entries matching any of the patterns
+pub fn matching_slots(entry_info: &EntryInfo, patterns: &[EntryPattern]) -> BTreeSet<EntryID> {
+    let patterns: Vec<_> = patterns.iter().collect();
+    let mut result = BTreeSet::new();
+    select_slots(entry_info, EntryID::root(), &patterns, &mut result);
+    result
+}
+
+// Restricts a data source to the entries matching a set of patterns (or all
+// of them, if there are none) and optionally to an interval, so that a
+// narrowed view of a profile can be served on its own, e.g., by the proxy.
+// Unlike CropDeferredDataSource, entries are chosen by name, so the same
+// filter can be applied to other runs or to a profile that is still growing.
+pub struct FilteredDeferredDataSource<T: DeferredDataSource> {
+    data_source: CropDeferredDataSource<T>,
+}
+
+impl<T: DeferredDataSource> FilteredDeferredDataSource<T> {
+    pub fn new(data_source: T, patterns: Vec<EntryPattern>, interval: Option<Interval>) -> Self {
+        Self {
+            data_source: CropDeferredDataSource::with_patterns(data_source, interval, patterns),
+        }
+    }
+}
+
+impl<T: DeferredDataSource> DeferredDataSource for FilteredDeferredDataSource<T> {
+    fn fetch_description(&self) -> DataSourceDescription {
+        self.data_source.fetch_description()
+    }
+
+    fn fetch_info(&mut self) {
+        self.data_source.fetch_info()
+    }
+
+    fn get_infos(&mut self) -> Vec<DataSourceInfo> {
+        self.data_source.get_infos()
+    }
+
+    fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.data_source.fetch_summary_tile(entry_id, tile_id, full)
+    }
+
+    fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
+        self.data_source.get_summary_tiles()
+    }
+
+    fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.data_source.fetch_slot_tile(entry_id, tile_id, full)
+    }
+
+    fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
+        self.data_source.get_slot_tiles()
+    }
+
+    fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.data_source
+            .fetch_slot_meta_tile(entry_id, tile_id, full)
+    }
+
+    fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
+        self.data_source.get_slot_meta_tiles()
+    }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        self.data_source
+            .fetch_slot_meta_item(entry_id, tile_id, item_uid)
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        self.data_source.get_slot_meta_items()
+    }
+
+    fn fetch_updates(&mut self) {
+        self.data_source.fetch_updates()
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        self.data_source.get_updates()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.data_source.set_waker(waker)
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        self.data_source.get_failures()
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.data_source.set_active_entries(entries)
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        self.data_source.fetch_search(query, interval, limit)
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        self.data_source.get_search_results()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_data::{panel, slot};
+
+    fn matching(entry_info: &EntryInfo, patterns: &[&str]) -> Vec<EntryID> {
+        let patterns: Vec<_> = patterns.iter().map(|p| EntryPattern::parse(p)).collect();
+        matching_slots(entry_info, &patterns).into_iter().collect()
+    }
+
+    #[test]
+    fn test_matching_slots() {
+        let entry_info = panel(
+            "root",
+            vec![
+                panel(
+                    "n0",
+                    vec![
+                        panel("cpu", vec![slot("c0"), slot("c1")]),
+                        panel("gpu", vec![slot("g0")]),
+                    ],
+                ),
+                panel("n1", vec![panel("cpu", vec![slot("c0")])]),
+            ],
+        );
+        let n0 = EntryID::root().child(0);
+        let n1 = EntryID::root().child(1);
+
+        assert_eq!(
+            matching(&entry_info, &["n0"]),
+            vec![
+                n0.child(0).child(0),
+                n0.child(0).child(1),
+                n0.child(1).child(0)
+            ]
+        );
+        assert_eq!(
+            matching(&entry_info, &["*/CPU/c0"]),
+            vec![n0.child(0).child(0), n1.child(0).child(0)]
+        );
+        // Long names match too, ignoring whitespace
+        assert_eq!(
+            matching(&entry_info, &["longn1", "n0/Long GPU"]),
+            vec![n0.child(1).child(0), n1.child(0).child(0)]
+        );
+        assert!(matching(&entry_info, &["n2"]).is_empty());
+        assert!(matching(&entry_info, &["n0/cpu/c0/x"]).is_empty());
+    }
+}
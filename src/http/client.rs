@@ -1,25 +1,33 @@
+use std::collections::BTreeSet;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use bytes::Buf;
 
-use log::info;
+use log::{info, warn};
 
 #[cfg(not(target_arch = "wasm32"))]
-use reqwest::blocking::{Client, ClientBuilder};
-#[cfg(target_arch = "wasm32")]
-use reqwest::{Client, ClientBuilder};
+use reqwest::Certificate;
+use reqwest::{Client, ClientBuilder, RequestBuilder};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use url::Url;
 
 use crate::data::{
-    DataSourceDescription, DataSourceInfo, EntryID, SlotMetaTile, SlotTile, SummaryTile, TileID,
+    ClientID, DataSourceDescription, DataSourceInfo, DataSourceUpdate, DataSourceUpdates, EntryID,
+    ItemUID, SearchQuery, SearchResults, SlotMetaItem, SlotMetaTile, SlotTile, SummaryTile, TileID,
+    PROTOCOL_VERSION,
 };
-use crate::deferred_data::DeferredDataSource;
+use crate::deferred_data::{DeferredDataSource, FailedRequest, RequestError, RequestKind, Waker};
 use crate::http::fetch::{fetch, DataSourceResponse};
-use crate::http::schema::TileRequestRef;
+use crate::http::schema::{ActiveEntriesRequest, ItemRequestRef, SearchRequest, TileRequestRef};
+use crate::http::tls::TlsOptions;
+use crate::timestamp::Interval;
 
+// Every request goes through one client, and so one pool of connections:
+// with HTTP/2 (negotiated over HTTPS), requests are multiplexed over a single
+// connection, and otherwise, idle connections are kept alive and reused
 pub struct HTTPClientDataSource {
     pub baseurl: Url,
     pub client: Client,
@@ -27,22 +35,187 @@ pub struct HTTPClientDataSource {
     summary_tiles: Arc<Mutex<Vec<SummaryTile>>>,
     slot_tiles: Arc<Mutex<Vec<SlotTile>>>,
     slot_meta_tiles: Arc<Mutex<Vec<SlotMetaTile>>>,
+    slot_meta_items: Arc<Mutex<Vec<SlotMetaItem>>>,
+    failures: Arc<Mutex<Vec<FailedRequest>>>,
+    watch: Arc<Mutex<WatchState>>,
+    search_results: Arc<Mutex<Vec<SearchResults>>>,
+    pub timeouts: HTTPTimeouts,
+    // Identifies the hints of this client (see ActiveEntriesRequest)
+    client_id: ClientID,
+    active_sequence: u64,
+}
+
+// None means no limit. The read timeout covers each request from start to
+// finish, except for the watch for updates, which the server holds open on
+// purpose. Browsers manage timeouts themselves, so these are ignored on the
+// web
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HTTPTimeouts {
+    pub connect: Option<Duration>,
+    pub read: Option<Duration>,
+}
+
+impl Default for HTTPTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: Some(Duration::from_secs(10)),
+            read: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl HTTPTimeouts {
+    // Override the defaults where given
+    pub fn with_overrides(connect: Option<Duration>, read: Option<Duration>) -> Self {
+        let defaults = Self::default();
+        Self {
+            connect: connect.or(defaults.connect),
+            read: read.or(defaults.read),
+        }
+    }
+}
+
+// The server holds requests for updates until there are any (i.e., long
+// polling), so there is at most one such request outstanding at a time
+#[derive(Default)]
+struct WatchState {
+    version: u64,
+    watching: bool,
+    // After a failed watch (e.g., because the server is restarting, or
+    // predates updates), wait before trying again, backing off while the
+    // failures continue
+    failures: u32,
+    retry_at: Option<f64>,
+    updates: Vec<DataSourceUpdate>,
+    waker: Option<Waker>,
+}
+
+const WATCH_RETRY_MIN: Duration = Duration::from_secs(1);
+const WATCH_RETRY_MAX: Duration = Duration::from_secs(60);
+
+impl WatchState {
+    fn wake(&self, delay: Duration) {
+        if let Some(waker) = &self.waker {
+            waker(delay);
+        }
+    }
+
+    // Even without updates (i.e., the server gave up holding the request),
+    // the caller must come back to watch again
+    fn succeeded(&mut self, updates: DataSourceUpdates) {
+        self.failures = 0;
+        self.retry_at = None;
+        self.version = updates.version;
+        self.updates.extend(updates.updates);
+        self.wake(Duration::ZERO);
+    }
+
+    fn failed(&mut self) -> Duration {
+        let delay = WATCH_RETRY_MIN
+            .saturating_mul(1 << self.failures.min(16))
+            .min(WATCH_RETRY_MAX);
+        self.failures += 1;
+        self.retry_at = Some(now() + delay.as_secs_f64());
+        self.wake(delay);
+        delay
+    }
+}
+
+// Seconds since some fixed point in the past (std::time::Instant is not
+// available on the web)
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> f64 {
+    js_sys::Date::now() / 1000.0
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn build_client(timeouts: HTTPTimeouts, tls: &TlsOptions) -> Result<Client, String> {
+    // Tiles vary widely in size, so let HTTP/2 size its flow control windows
+    // to the connection
+    let mut builder = ClientBuilder::new().http2_adaptive_window(true);
+    if let Some(connect_timeout) = timeouts.connect {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    if let Some(path) = &tls.ca_bundle {
+        let name = path.display();
+        let pem =
+            std::fs::read(path).map_err(|e| format!("unable to read CA bundle {name}: {e}"))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("invalid CA bundle {name}: {e}"))?;
+        if certs.is_empty() {
+            return Err(format!("no certificates in CA bundle {name}"));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if tls.accept_invalid_certs {
+        warn!("TLS certificate verification is disabled; the server's identity is not checked");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn build_client(_timeouts: HTTPTimeouts, _tls: &TlsOptions) -> Result<Client, String> {
+    ClientBuilder::new().build().map_err(|e| e.to_string())
 }
 
 impl HTTPClientDataSource {
     pub fn new(baseurl: Url) -> Self {
-        Self {
+        Self::with_options(baseurl, HTTPTimeouts::default(), &TlsOptions::default())
+            .expect("unable to create HTTP client")
+    }
+
+    // Fails if the CA bundle can't be loaded
+    pub fn with_options(
+        baseurl: Url,
+        timeouts: HTTPTimeouts,
+        tls: &TlsOptions,
+    ) -> Result<Self, String> {
+        Ok(Self {
             baseurl,
-            client: ClientBuilder::new().build().unwrap(),
+            client: build_client(timeouts, tls)?,
             infos: Arc::new(Mutex::new(Vec::new())),
             summary_tiles: Arc::new(Mutex::new(Vec::new())),
             slot_tiles: Arc::new(Mutex::new(Vec::new())),
             slot_meta_tiles: Arc::new(Mutex::new(Vec::new())),
+            slot_meta_items: Arc::new(Mutex::new(Vec::new())),
+            failures: Arc::new(Mutex::new(Vec::new())),
+            watch: Arc::new(Mutex::new(WatchState::default())),
+            search_results: Arc::new(Mutex::new(Vec::new())),
+            timeouts,
+            client_id: ClientID(rand::random()),
+            active_sequence: 0,
+        })
+    }
+
+    // See HTTPTimeouts
+    fn with_read_timeout(&self, request: RequestBuilder) -> RequestBuilder {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(read_timeout) = self.timeouts.read {
+            return request.timeout(read_timeout);
         }
+        request
     }
 
-    fn request<T>(&mut self, url: Url, container: Arc<Mutex<Vec<T>>>)
-    where
+    fn request<T>(
+        &mut self,
+        url: Url,
+        container: Arc<Mutex<Vec<T>>>,
+        kind: RequestKind,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: Option<ItemUID>,
+    ) where
         T: 'static + Sync + Send + for<'a> Deserialize<'a>,
     {
         info!("fetch: {}", url);
@@ -51,18 +224,67 @@ impl HTTPClientDataSource {
             .get(url)
             .header("Accept", "*/*")
             .header("Content-Type", "application/octet-stream;");
+        let request = self.with_read_timeout(request);
+        let entry_id = entry_id.clone();
+        let failures = self.failures.clone();
         fetch(
             request,
-            move |response: Result<DataSourceResponse, String>| {
-                let f = response.unwrap().body.reader();
-                let f = zstd::Decoder::new(f).expect("zstd decompression failed");
-                let result = ciborium::from_reader(f).expect("cbor decoding failed");
-                container.lock().unwrap().push(result);
+            move |response: Result<DataSourceResponse, RequestError>| match response
+                .and_then(decode)
+            {
+                Ok(result) => container.lock().unwrap().push(result),
+                Err(error) => {
+                    warn!("request failed: {}", error);
+                    failures.lock().unwrap().push(FailedRequest {
+                        kind,
+                        entry_id: Some(entry_id),
+                        tile_id: Some(tile_id),
+                        item_uid,
+                        error,
+                    });
+                }
             },
         );
     }
 }
 
+fn encode<T: Serialize>(data: &T) -> Vec<u8> {
+    let mut f = zstd::Encoder::new(Vec::new(), 1).expect("zstd compression failed");
+    ciborium::into_writer(data, &mut f).expect("ciborium encoding failed");
+    f.finish().expect("zstd compression failed")
+}
+
+fn decode<T: for<'a> Deserialize<'a>>(response: DataSourceResponse) -> Result<T, RequestError> {
+    let f = zstd::Decoder::new(response.body.reader())
+        .map_err(|e| RequestError::Other(format!("zstd decompression failed: {e}")))?;
+    ciborium::from_reader(f).map_err(|e| RequestError::Other(format!("cbor decoding failed: {e}")))
+}
+
+// Unlike tiles, the info is decoded carefully: it is the first thing fetched
+// from a server, so this is where an incompatible server shows up
+fn decode_info(response: DataSourceResponse) -> DataSourceInfo {
+    let version = response.protocol_version.unwrap_or(0);
+    let decoded = zstd::Decoder::new(response.body.reader())
+        .map_err(|e| e.to_string())
+        .and_then(|f| ciborium::from_reader::<DataSourceInfo, _>(f).map_err(|e| e.to_string()));
+    match decoded {
+        Ok(mut info) => {
+            // Trust the header over the body, in case the data source did
+            // not fill in the version
+            if response.protocol_version.is_some() {
+                info.protocol_version = version;
+            }
+            info
+        }
+        Err(e) if version != PROTOCOL_VERSION => DataSourceInfo::unavailable(format!(
+            "Unable to load profile: the server uses protocol version {version} but this \
+             viewer uses version {PROTOCOL_VERSION}. Use matching versions of the server \
+             and viewer. ({e})"
+        )),
+        Err(e) => DataSourceInfo::unavailable(format!("Unable to decode profile: {e}")),
+    }
+}
+
 impl DeferredDataSource for HTTPClientDataSource {
     fn fetch_description(&self) -> DataSourceDescription {
         DataSourceDescription {
@@ -72,7 +294,24 @@ impl DeferredDataSource for HTTPClientDataSource {
 
     fn fetch_info(&mut self) {
         let url = self.baseurl.join("info").expect("invalid baseurl");
-        self.request::<DataSourceInfo>(url, self.infos.clone());
+        info!("fetch: {}", url);
+        let request = self
+            .client
+            .get(url)
+            .header("Accept", "*/*")
+            .header("Content-Type", "application/octet-stream;");
+        let request = self.with_read_timeout(request);
+        let container = self.infos.clone();
+        fetch(
+            request,
+            move |response: Result<DataSourceResponse, RequestError>| {
+                let info = match response {
+                    Ok(response) => decode_info(response),
+                    Err(e) => DataSourceInfo::unavailable(format!("Unable to fetch profile: {e}")),
+                };
+                container.lock().unwrap().push(info);
+            },
+        );
     }
 
     fn get_infos(&mut self) -> Vec<DataSourceInfo> {
@@ -87,7 +326,14 @@ impl DeferredDataSource for HTTPClientDataSource {
             .and_then(|u| u.join(&req.to_slug()))
             .expect("invalid baseurl");
         url.set_query(Some(&format!("full={}", full)));
-        self.request::<SummaryTile>(url, self.summary_tiles.clone());
+        self.request::<SummaryTile>(
+            url,
+            self.summary_tiles.clone(),
+            RequestKind::SummaryTile,
+            entry_id,
+            tile_id,
+            None,
+        );
     }
 
     fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
@@ -102,7 +348,14 @@ impl DeferredDataSource for HTTPClientDataSource {
             .and_then(|u| u.join(&req.to_slug()))
             .expect("invalid baseurl");
         url.set_query(Some(&format!("full={}", full)));
-        self.request::<SlotTile>(url, self.slot_tiles.clone());
+        self.request::<SlotTile>(
+            url,
+            self.slot_tiles.clone(),
+            RequestKind::SlotTile,
+            entry_id,
+            tile_id,
+            None,
+        );
     }
 
     fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
@@ -117,10 +370,194 @@ impl DeferredDataSource for HTTPClientDataSource {
             .and_then(|u| u.join(&req.to_slug()))
             .expect("invalid baseurl");
         url.set_query(Some(&format!("full={}", full)));
-        self.request::<SlotMetaTile>(url, self.slot_meta_tiles.clone());
+        self.request::<SlotMetaTile>(
+            url,
+            self.slot_meta_tiles.clone(),
+            RequestKind::SlotMetaTile,
+            entry_id,
+            tile_id,
+            None,
+        );
     }
 
     fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
         std::mem::take(&mut self.slot_meta_tiles.lock().unwrap())
     }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        let req = ItemRequestRef {
+            entry_id,
+            tile_id,
+            item_uid,
+        };
+        let url = self
+            .baseurl
+            .join("slot_meta_item/")
+            .and_then(|u| u.join(&req.to_slug()))
+            .expect("invalid baseurl");
+        self.request::<SlotMetaItem>(
+            url,
+            self.slot_meta_items.clone(),
+            RequestKind::SlotMetaItem,
+            entry_id,
+            tile_id,
+            Some(item_uid),
+        );
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        std::mem::take(&mut self.slot_meta_items.lock().unwrap())
+    }
+
+    fn fetch_updates(&mut self) {
+        let mut watch = self.watch.lock().unwrap();
+        if watch.watching || watch.retry_at.is_some_and(|t| now() < t) {
+            return;
+        }
+        watch.watching = true;
+
+        let mut url = self.baseurl.join("updates").expect("invalid baseurl");
+        url.set_query(Some(&format!("version={}", watch.version)));
+        info!("fetch: {}", url);
+        let request = self
+            .client
+            .get(url)
+            .header("Accept", "*/*")
+            .header("Content-Type", "application/octet-stream;");
+        let container = self.watch.clone();
+        fetch(
+            request,
+            move |response: Result<DataSourceResponse, RequestError>| {
+                let mut watch = container.lock().unwrap();
+                watch.watching = false;
+                match response.and_then(decode::<DataSourceUpdates>) {
+                    Ok(result) => watch.succeeded(result),
+                    Err(e) => {
+                        let delay = watch.failed();
+                        warn!(
+                            "unable to watch for updates, retrying in {}s: {}",
+                            delay.as_secs(),
+                            e
+                        );
+                    }
+                }
+            },
+        );
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        std::mem::take(&mut self.watch.lock().unwrap().updates)
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.watch.lock().unwrap().waker = Some(waker);
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        std::mem::take(&mut self.failures.lock().unwrap())
+    }
+
+    // Nothing waits for the reply, since this is only a hint. Servers that
+    // predate hints reject it, which is harmless
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        let url = self
+            .baseurl
+            .join("active_entries")
+            .expect("invalid baseurl");
+        info!("fetch: {}", url);
+        self.active_sequence += 1;
+        let req = ActiveEntriesRequest {
+            client: self.client_id,
+            sequence: self.active_sequence,
+            entries: entries.cloned(),
+        };
+        let request = self
+            .client
+            .post(url)
+            .header("Accept", "*/*")
+            .header("Content-Type", "application/octet-stream;")
+            .body(encode(&req));
+        let request = self.with_read_timeout(request);
+        fetch(
+            request,
+            |response: Result<DataSourceResponse, RequestError>| {
+                if let Err(e) = response {
+                    info!("server did not take active entries: {}", e);
+                }
+            },
+        );
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        let url = self.baseurl.join("search").expect("invalid baseurl");
+        info!("fetch: {}", url);
+        let req = SearchRequest {
+            query: query.clone(),
+            interval,
+            limit,
+        };
+        let request = self
+            .client
+            .post(url)
+            .header("Accept", "*/*")
+            .header("Content-Type", "application/octet-stream;")
+            .body(encode(&req));
+        let request = self.with_read_timeout(request);
+        let container = self.search_results.clone();
+        fetch(
+            request,
+            move |response: Result<DataSourceResponse, RequestError>| {
+                let result = response
+                    .and_then(decode::<SearchResults>)
+                    .unwrap_or_else(|e| {
+                        warn!("search failed: {}", e);
+                        SearchResults::failed(req.query, req.interval, e.to_string())
+                    });
+                container.lock().unwrap().push(result);
+            },
+        );
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        std::mem::take(&mut self.search_results.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn test_info_timeout() {
+        // A server that accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let connections: Vec<_> = listener.incoming().collect();
+            drop(connections);
+        });
+
+        let url = Url::parse(&format!("http://{addr}/")).unwrap();
+        let timeouts = HTTPTimeouts {
+            connect: Some(Duration::from_secs(1)),
+            read: Some(Duration::from_millis(100)),
+        };
+        let mut data_source =
+            HTTPClientDataSource::with_options(url, timeouts, &TlsOptions::default()).unwrap();
+        data_source.fetch_info();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let info = loop {
+            if let Some(info) = data_source.get_infos().pop() {
+                break info;
+            }
+            assert!(Instant::now() < deadline, "info request never timed out");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert!(info.is_unavailable());
+        let message = info.warning_message.unwrap();
+        assert!(message.contains("Unable to fetch profile"), "{message}");
+    }
 }
@@ -1,17 +1,53 @@
 use bytes::Bytes;
 
-#[cfg(not(target_arch = "wasm32"))]
-use reqwest::blocking::RequestBuilder;
-#[cfg(target_arch = "wasm32")]
-use reqwest::RequestBuilder;
+use reqwest::{RequestBuilder, Response};
+
+use crate::deferred_data::RequestError;
+use crate::http::schema::PROTOCOL_VERSION_HEADER;
 
 pub struct DataSourceResponse {
     pub body: Bytes,
+    // From PROTOCOL_VERSION_HEADER, if the server sent it
+    pub protocol_version: Option<u32>,
+}
+
+pub fn parse_protocol_version(value: Option<&reqwest::header::HeaderValue>) -> Option<u32> {
+    value?.to_str().ok()?.parse().ok()
+}
+
+// Timeouts (including those of a proxy waiting on its upstream) are told
+// apart from other errors, since they are usually worth retrying as they are
+pub fn classify_error(error: reqwest::Error) -> RequestError {
+    if error.is_timeout() || error.status() == Some(reqwest::StatusCode::GATEWAY_TIMEOUT) {
+        RequestError::Timeout
+    } else if let Some(status) = error.status() {
+        RequestError::Server(format!("HTTP {status}"))
+    } else {
+        RequestError::Other(error.to_string())
+    }
+}
+
+pub async fn send(request: RequestBuilder) -> Result<DataSourceResponse, RequestError> {
+    async fn receive(response: Response) -> Result<DataSourceResponse, reqwest::Error> {
+        let response = response.error_for_status()?;
+        let protocol_version =
+            parse_protocol_version(response.headers().get(PROTOCOL_VERSION_HEADER));
+        Ok(DataSourceResponse {
+            body: response.bytes().await?,
+            protocol_version,
+        })
+    }
+
+    let result = match request.send().await {
+        Ok(response) => receive(response).await,
+        Err(e) => Err(e),
+    };
+    result.map_err(classify_error)
 }
 
 pub fn fetch(
     request: RequestBuilder,
-    on_done: impl 'static + Send + FnOnce(Result<DataSourceResponse, String>),
+    on_done: impl 'static + Send + FnOnce(Result<DataSourceResponse, RequestError>),
 ) {
     #[cfg(not(target_arch = "wasm32"))]
     crate::http::fetch_native::fetch(request, Box::new(on_done));
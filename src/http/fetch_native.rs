@@ -1,18 +1,36 @@
-use reqwest::blocking::RequestBuilder;
+use std::sync::OnceLock;
 
-use crate::http::fetch::DataSourceResponse;
+use reqwest::RequestBuilder;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::deferred_data::RequestError;
+use crate::http::fetch::{send, DataSourceResponse};
+
+// Requests are sent from a small runtime shared by every client, so that
+// hundreds of them (e.g., when expanding a large panel) can be outstanding at
+// once without tying up a thread each, and so that they share each client's
+// pool of connections
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("http-client")
+            .enable_all()
+            .build()
+            .expect("unable to start HTTP client runtime")
+    })
+}
 
 pub fn fetch(
     request: RequestBuilder,
-    on_done: Box<dyn FnOnce(Result<DataSourceResponse, String>) + Send>,
+    on_done: Box<dyn FnOnce(Result<DataSourceResponse, RequestError>) + Send>,
 ) {
-    rayon::spawn(move || {
-        let result = request
-            .send()
-            .expect("request failed")
-            .bytes()
-            .expect("unable to get bytes");
-
-        on_done(Ok(DataSourceResponse { body: result }))
+    runtime().spawn(async move {
+        let result = send(request).await;
+        // Decoding large tiles would hold up other requests, so it is done
+        // elsewhere
+        rayon::spawn(move || on_done(result));
     });
 }
@@ -1,6 +1,7 @@
 use reqwest::RequestBuilder;
 
-use crate::http::fetch::DataSourceResponse;
+use crate::deferred_data::RequestError;
+use crate::http::fetch::{send, DataSourceResponse};
 
 /// Spawn an async task.
 ///
@@ -15,19 +16,7 @@ where
 
 pub fn fetch(
     request: RequestBuilder,
-    on_done: Box<dyn FnOnce(Result<DataSourceResponse, String>) + Send>,
+    on_done: Box<dyn FnOnce(Result<DataSourceResponse, RequestError>) + Send>,
 ) {
-    spawn_future(async move {
-        let result = request
-            .send()
-            .await
-            .expect("request failed")
-            .bytes()
-            .await
-            .expect("unable to get bytes");
-
-        let res = Ok(DataSourceResponse { body: result });
-
-        on_done(res)
-    });
+    spawn_future(async move { on_done(send(request).await) });
 }
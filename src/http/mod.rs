@@ -1,8 +1,11 @@
 pub mod schema;
+pub mod tls;
 
 #[cfg(feature = "client")]
 pub mod client;
 
+#[cfg(feature = "server")]
+pub mod proxy;
 #[cfg(feature = "server")]
 pub mod server;
 
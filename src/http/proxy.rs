@@ -0,0 +1,585 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use crate::cache_data::CachingDeferredDataSource;
+use crate::control::parse_timeout;
+use crate::data::{
+    ClientID, DataSourceDescription, DataSourceInfo, DataSourceUpdate, DataSourceUpdates, EntryID,
+    ItemUID, SearchQuery, SearchResults, SlotMetaItem, SlotMetaTile, SlotTile, SummaryTile, TileID,
+};
+use crate::deferred_data::{
+    DeferredDataSource, DeferredDataSourceWrapper, FailedRequest, RequestError, RequestKind,
+};
+use crate::file_data::FileDataSource;
+use crate::filter_data::{EntryPattern, FilteredDeferredDataSource};
+#[cfg(feature = "client")]
+use crate::http::client::{HTTPClientDataSource, HTTPTimeouts};
+use crate::http::server::{DataSourceHTTPServer, FallibleDataSource};
+use crate::http::tls::TlsOptions;
+use crate::timestamp::Interval;
+
+// Serves the HTTP data source API on behalf of an upstream data source (a
+// URL or the path of an archive), caching everything fetched on disk. This
+// lets a team share one warm cache for a huge remote profile:
+//
+//     proxy <upstream> [--cache-dir <dir>] [--host <host>] [--port <port>]
+//           [--connect-timeout <seconds>] [--read-timeout <seconds>]
+//           [--ca-bundle <path>] [--insecure]
+//           [--entries <pattern>] [--interval <start>:<stop>]
+//
+// The timeouts and TLS options (see TlsOptions) apply to requests to an
+// upstream server. With --entries (repeatable, see EntryPattern) or
+// --interval, only that part of the profile is served, e.g., to hand out
+// a small view of a huge profile. The cache still holds the unfiltered
+// data, so it can be shared with proxies serving other views.
+
+const USAGE: &str = "usage: proxy <url or path> [--cache-dir <dir>] [--host <host>] \
+                     [--port <port>] [--connect-timeout <seconds>] [--read-timeout <seconds>] \
+                     [--ca-bundle <path>] [--insecure] [--entries <pattern>] \
+                     [--interval <start>:<stop>]";
+
+// Give up on a request after this long (or the read timeout, if longer),
+// e.g., if the upstream server went away. Nothing is cached in that case, so
+// the request can be retried
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+// How often requests waiting on upstream check for responses
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// Responses received from upstream, until the requests waiting for them
+// pick them up. Each is kept along with when it was received, so that late
+// responses to requests that timed out can be dropped eventually
+struct Received<D>(Vec<(Instant, D)>);
+
+impl<D> Default for Received<D> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<D> Received<D> {
+    fn extend(&mut self, now: Instant, results: Vec<D>) {
+        self.0
+            .extend(results.into_iter().map(|result| (now, result)));
+    }
+
+    fn take(&mut self, matches: impl Fn(&D) -> bool) -> Option<D> {
+        let index = self.0.iter().position(|(_, result)| matches(result))?;
+        Some(self.0.swap_remove(index).1)
+    }
+
+    fn expire(&mut self, now: Instant, timeout: Duration) {
+        self.0
+            .retain(|(received, _)| now.duration_since(*received) < timeout);
+    }
+}
+
+struct BlockingState<T: DeferredDataSource> {
+    data_source: T,
+    infos: Received<DataSourceInfo>,
+    summary_tiles: Received<SummaryTile>,
+    slot_tiles: Received<SlotTile>,
+    slot_meta_tiles: Received<SlotMetaTile>,
+    slot_meta_items: Received<SlotMetaItem>,
+    search_results: Received<SearchResults>,
+    failures: Received<FailedRequest>,
+}
+
+impl<T: DeferredDataSource> BlockingState<T> {
+    // Returns true if anything arrived
+    fn poll(&mut self, timeout: Duration) -> bool {
+        let now = Instant::now();
+        let data_source = &mut self.data_source;
+        let infos = data_source.get_infos();
+        let summary_tiles = data_source.get_summary_tiles();
+        let slot_tiles = data_source.get_slot_tiles();
+        let slot_meta_tiles = data_source.get_slot_meta_tiles();
+        let slot_meta_items = data_source.get_slot_meta_items();
+        let search_results = data_source.get_search_results();
+        let failures = data_source.get_failures();
+        let arrived = !infos.is_empty()
+            || !summary_tiles.is_empty()
+            || !slot_tiles.is_empty()
+            || !slot_meta_tiles.is_empty()
+            || !slot_meta_items.is_empty()
+            || !search_results.is_empty()
+            || !failures.is_empty();
+        self.infos.extend(now, infos);
+        self.summary_tiles.extend(now, summary_tiles);
+        self.slot_tiles.extend(now, slot_tiles);
+        self.slot_meta_tiles.extend(now, slot_meta_tiles);
+        self.slot_meta_items.extend(now, slot_meta_items);
+        self.search_results.extend(now, search_results);
+        self.failures.extend(now, failures);
+
+        self.infos.expire(now, timeout);
+        self.summary_tiles.expire(now, timeout);
+        self.slot_tiles.expire(now, timeout);
+        self.slot_meta_tiles.expire(now, timeout);
+        self.slot_meta_items.expire(now, timeout);
+        self.search_results.expire(now, timeout);
+        self.failures.expire(now, timeout);
+        arrived
+    }
+}
+
+// Turns a deferred data source back into a blocking one, so that it can be
+// served. Requests from any number of clients are forwarded upstream as
+// they come in, and each waits for its own response
+struct BlockingDataSource<T: DeferredDataSource + Send> {
+    description: DataSourceDescription,
+    state: Mutex<BlockingState<T>>,
+    // Signaled whenever responses arrive, for the requests waiting on them
+    arrived: Condvar,
+    // Every update received from upstream, so that each client can be sent
+    // the ones it hasn't seen yet. The version is one past the length of the
+    // log, since version 0 is reserved
+    updates: Mutex<Vec<DataSourceUpdate>>,
+    // The latest hint of each client (see set_active_entries)
+    hints: Mutex<BTreeMap<ClientID, Option<BTreeSet<EntryID>>>>,
+    timeout: Duration,
+}
+
+impl<T: DeferredDataSource + Send> BlockingDataSource<T> {
+    fn new(data_source: T) -> Self {
+        Self {
+            description: data_source.fetch_description(),
+            state: Mutex::new(BlockingState {
+                data_source,
+                infos: Received::default(),
+                summary_tiles: Received::default(),
+                slot_tiles: Received::default(),
+                slot_meta_tiles: Received::default(),
+                slot_meta_items: Received::default(),
+                search_results: Received::default(),
+                failures: Received::default(),
+            }),
+            arrived: Condvar::new(),
+            updates: Mutex::new(Vec::new()),
+            hints: Mutex::new(BTreeMap::new()),
+            timeout: TIMEOUT,
+        }
+    }
+
+    // Wait for the response matching the request, or for the request to
+    // fail. The lock is only held while polling, so that other requests can
+    // be made in the meantime
+    fn wait<D>(
+        &self,
+        fetch: impl FnOnce(&mut T),
+        received: impl Fn(&mut BlockingState<T>) -> &mut Received<D>,
+        matches: impl Fn(&D) -> bool,
+        failed: impl Fn(&FailedRequest) -> bool,
+    ) -> Result<D, RequestError> {
+        let mut state = self.state.lock().unwrap();
+        fetch(&mut state.data_source);
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if state.poll(self.timeout) {
+                self.arrived.notify_all();
+            }
+            if let Some(result) = received(&mut state).take(&matches) {
+                return Ok(result);
+            }
+            if let Some(failure) = state.failures.take(&failed) {
+                warn!(
+                    "request to {:?} failed: {}",
+                    self.description.source_locator, failure.error
+                );
+                return Err(failure.error);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                warn!("request to {:?} timed out", self.description.source_locator);
+                return Err(RequestError::Timeout);
+            }
+            let wait = POLL_INTERVAL.min(deadline - now);
+            state = self.arrived.wait_timeout(state, wait).unwrap().0;
+        }
+    }
+}
+
+impl<T: DeferredDataSource + Send> FallibleDataSource for BlockingDataSource<T> {
+    fn fetch_info(&self) -> Result<DataSourceInfo, RequestError> {
+        self.wait(
+            |d| d.fetch_info(),
+            |s| &mut s.infos,
+            |_| true,
+            |f| f.kind == RequestKind::Info,
+        )
+    }
+
+    fn fetch_summary_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SummaryTile, RequestError> {
+        self.wait(
+            |d| d.fetch_summary_tile(entry_id, tile_id, full),
+            |s| &mut s.summary_tiles,
+            |t| t.entry_id == *entry_id && t.tile_id == tile_id,
+            |f| f.is_for(RequestKind::SummaryTile, entry_id, tile_id, None),
+        )
+    }
+
+    fn fetch_slot_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SlotTile, RequestError> {
+        self.wait(
+            |d| d.fetch_slot_tile(entry_id, tile_id, full),
+            |s| &mut s.slot_tiles,
+            |t| t.entry_id == *entry_id && t.tile_id == tile_id,
+            |f| f.is_for(RequestKind::SlotTile, entry_id, tile_id, None),
+        )
+    }
+
+    fn fetch_slot_meta_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SlotMetaTile, RequestError> {
+        self.wait(
+            |d| d.fetch_slot_meta_tile(entry_id, tile_id, full),
+            |s| &mut s.slot_meta_tiles,
+            |t| t.entry_id == *entry_id && t.tile_id == tile_id,
+            |f| f.is_for(RequestKind::SlotMetaTile, entry_id, tile_id, None),
+        )
+    }
+
+    fn fetch_slot_meta_item(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: ItemUID,
+    ) -> Result<SlotMetaItem, RequestError> {
+        self.wait(
+            |d| d.fetch_slot_meta_item(entry_id, tile_id, item_uid),
+            |s| &mut s.slot_meta_items,
+            |i| i.entry_id == *entry_id && i.tile_id == tile_id && i.item_uid == item_uid,
+            |f| f.is_for(RequestKind::SlotMetaItem, entry_id, tile_id, Some(item_uid)),
+        )
+    }
+
+    fn fetch_updates(&self, version: u64) -> DataSourceUpdates {
+        let received = {
+            let mut state = self.state.lock().unwrap();
+            state.data_source.fetch_updates();
+            state.data_source.get_updates()
+        };
+        let mut updates = self.updates.lock().unwrap();
+        updates.extend(received);
+
+        let current = updates.len() as u64 + 1;
+        DataSourceUpdates {
+            version: current,
+            updates: if version == 0 {
+                Vec::new()
+            } else if version > current {
+                // The client watched an earlier run of the proxy
+                vec![DataSourceUpdate::Info]
+            } else {
+                updates[version as usize - 1..].to_vec()
+            },
+        }
+    }
+
+    // Clients share the upstream, so it is told about the entries active in
+    // any of them. Clients that went away are never forgotten, which at worst
+    // makes the hint less useful
+    fn set_active_entries(&self, client: ClientID, entries: Option<&BTreeSet<EntryID>>) {
+        let mut hints = self.hints.lock().unwrap();
+        hints.insert(client, entries.cloned());
+        let mut active = Some(BTreeSet::new());
+        for entries in hints.values() {
+            match (&mut active, entries) {
+                (Some(active), Some(entries)) => active.extend(entries.iter().cloned()),
+                _ => active = None,
+            }
+        }
+        let mut state = self.state.lock().unwrap();
+        state.data_source.set_active_entries(active.as_ref())
+    }
+
+    fn fetch_search(&self, query: &SearchQuery, interval: Interval, limit: usize) -> SearchResults {
+        self.wait(
+            |d| d.fetch_search(query, interval, limit),
+            |s| &mut s.search_results,
+            |r| r.query == *query && r.interval == interval,
+            |_| false,
+        )
+        .unwrap_or_else(|e| SearchResults::failed(query.clone(), interval, format!("upstream {e}")))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ProxyOptions {
+    upstream: String,
+    cache_dir: PathBuf,
+    host: String,
+    port: u16,
+    // Override the defaults of the HTTP client
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    tls: TlsOptions,
+    // Narrow down what is served (see FilteredDeferredDataSource)
+    entries: Vec<String>,
+    interval: Option<Interval>,
+}
+
+impl ProxyOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.to_vec();
+        let tls = TlsOptions::extract_args(&mut args)?;
+        let mut upstream = None;
+        let mut result = Self {
+            upstream: String::new(),
+            cache_dir: PathBuf::from("legion_prof_cache"),
+            host: "127.0.0.1".to_owned(),
+            port: 8080,
+            connect_timeout: None,
+            read_timeout: None,
+            tls,
+            entries: Vec::new(),
+            interval: None,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                if upstream.replace(arg.clone()).is_some() {
+                    return Err(USAGE.to_owned());
+                }
+                continue;
+            }
+            let value = args.next().ok_or(USAGE)?;
+            match arg.as_str() {
+                "--cache-dir" => result.cache_dir = PathBuf::from(value),
+                "--host" => result.host.clone_from(value),
+                "--port" => {
+                    result.port = value.parse().map_err(|_| format!("invalid port {value}"))?
+                }
+                "--connect-timeout" => result.connect_timeout = Some(parse_timeout(value)?),
+                "--read-timeout" => result.read_timeout = Some(parse_timeout(value)?),
+                "--entries" => result.entries.push(value.clone()),
+                "--interval" => result.interval = Some(Interval::parse(value)?),
+                _ => return Err(USAGE.to_owned()),
+            }
+        }
+        result.upstream = upstream.ok_or(USAGE)?;
+        Ok(result)
+    }
+
+    fn upstream(&self) -> Result<Box<dyn FallibleDataSource + Send + Sync + 'static>, String> {
+        #[cfg(feature = "client")]
+        if let Ok(url) = url::Url::parse(&self.upstream) {
+            if url.scheme() == "http" || url.scheme() == "https" {
+                let timeouts =
+                    HTTPTimeouts::with_overrides(self.connect_timeout, self.read_timeout);
+                let data_source = CachingDeferredDataSource::new(
+                    HTTPClientDataSource::with_options(url, timeouts, &self.tls)?,
+                    &self.cache_dir,
+                );
+                return Ok(self.serve(data_source, timeouts.read));
+            }
+        }
+        Ok(self.serve(
+            CachingDeferredDataSource::new(
+                DeferredDataSourceWrapper::new(FileDataSource::new(&self.upstream)),
+                &self.cache_dir,
+            ),
+            None,
+        ))
+    }
+
+    fn serve<T: DeferredDataSource + Send + 'static>(
+        &self,
+        data_source: T,
+        read_timeout: Option<Duration>,
+    ) -> Box<dyn FallibleDataSource + Send + Sync + 'static> {
+        fn blocking<T: DeferredDataSource + Send + 'static>(
+            data_source: T,
+            read_timeout: Option<Duration>,
+        ) -> Box<dyn FallibleDataSource + Send + Sync + 'static> {
+            let mut data_source = BlockingDataSource::new(data_source);
+            if let Some(read_timeout) = read_timeout {
+                data_source.timeout = data_source.timeout.max(read_timeout);
+            }
+            Box::new(data_source)
+        }
+
+        // Filter in front of the cache, so that it always holds the
+        // unfiltered data
+        if self.entries.is_empty() && self.interval.is_none() {
+            return blocking(data_source, read_timeout);
+        }
+        let patterns = self
+            .entries
+            .iter()
+            .map(|p| EntryPattern::parse(p))
+            .collect();
+        let data_source = FilteredDeferredDataSource::new(data_source, patterns, self.interval);
+        blocking(data_source, read_timeout)
+    }
+}
+
+// Entry point for the proxy subcommand, given the arguments following it
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = ProxyOptions::parse(args)?;
+    let data_source = options.upstream()?;
+    DataSourceHTTPServer::with_fallible(options.host, options.port, data_source)
+        .run()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fault_data::{FaultConfig, FaultInjectingDeferredDataSource};
+    use crate::test_data::TestDeferredDataSource;
+    use crate::timestamp::Timestamp;
+
+    fn parse(s: &str) -> Result<ProxyOptions, String> {
+        let args: Vec<_> = s.split_whitespace().map(str::to_owned).collect();
+        ProxyOptions::parse(&args)
+    }
+
+    #[test]
+    fn test_parse_proxy_options() {
+        assert_eq!(
+            parse("http://example.com/prof --port 9000 --cache-dir /tmp/cache"),
+            Ok(ProxyOptions {
+                upstream: "http://example.com/prof".to_owned(),
+                cache_dir: PathBuf::from("/tmp/cache"),
+                host: "127.0.0.1".to_owned(),
+                port: 9000,
+                connect_timeout: None,
+                read_timeout: None,
+                tls: TlsOptions::default(),
+                entries: Vec::new(),
+                interval: None,
+            })
+        );
+        let options = parse("a --connect-timeout 5 --read-timeout 120").unwrap();
+        assert_eq!(options.connect_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(options.read_timeout, Some(Duration::from_secs(120)));
+        assert!(parse("a --read-timeout 0").is_err());
+        assert!(parse("a --read-timeout x").is_err());
+        let options = parse("a --insecure --port 9000").unwrap();
+        assert!(options.tls.accept_invalid_certs);
+        assert_eq!(options.port, 9000);
+        let options = parse("a --ca-bundle=ca.pem").unwrap();
+        assert_eq!(options.tls.ca_bundle, Some(PathBuf::from("ca.pem")));
+        let options = parse("a --entries n0/cpu --entries */gpu --interval 1s:2s").unwrap();
+        assert_eq!(options.entries, vec!["n0/cpu", "*/gpu"]);
+        assert_eq!(
+            options.interval,
+            Some(Interval::new(
+                Timestamp(1_000_000_000),
+                Timestamp(2_000_000_000)
+            ))
+        );
+        assert!(parse("a --interval 2s:1s").is_err());
+        assert!(parse("").is_err());
+        assert!(parse("a b").is_err());
+        assert!(parse("a --port").is_err());
+        assert!(parse("a --port x").is_err());
+        assert!(parse("a --bogus 1").is_err());
+    }
+
+    #[test]
+    fn test_proxy_updates() {
+        let proxy = BlockingDataSource::new(TestDeferredDataSource::default());
+        let push = |update: DataSourceUpdate| {
+            proxy.state.lock().unwrap().data_source.updates.push(update);
+        };
+
+        // Version 0 only asks for the current version
+        let start = proxy.fetch_updates(0);
+        assert!(start.updates.is_empty());
+        assert_ne!(start.version, 0);
+
+        push(DataSourceUpdate::Info);
+        let first = proxy.fetch_updates(start.version);
+        assert!(matches!(first.updates[..], [DataSourceUpdate::Info]));
+
+        // Clients that are further behind get everything they missed
+        push(DataSourceUpdate::Info);
+        assert_eq!(proxy.fetch_updates(first.version).updates.len(), 1);
+        assert_eq!(proxy.fetch_updates(start.version).updates.len(), 2);
+
+        // Versions from an earlier run force a reload
+        assert!(matches!(
+            proxy.fetch_updates(100).updates[..],
+            [DataSourceUpdate::Info]
+        ));
+    }
+
+    #[test]
+    fn test_proxy_active_entries() {
+        let proxy = BlockingDataSource::new(TestDeferredDataSource::default());
+        let entries = |ids: &[u64]| -> BTreeSet<EntryID> {
+            ids.iter().map(|i| EntryID::root().child(*i)).collect()
+        };
+
+        // Upstream is told about the entries active in any client
+        proxy.set_active_entries(ClientID(1), Some(&entries(&[0])));
+        proxy.set_active_entries(ClientID(2), Some(&entries(&[1])));
+        proxy.set_active_entries(ClientID(1), Some(&entries(&[2])));
+        proxy.set_active_entries(ClientID(3), None);
+        proxy.set_active_entries(ClientID(3), Some(&entries(&[])));
+        assert_eq!(
+            proxy.state.lock().unwrap().data_source.active_entries,
+            vec![
+                Some(entries(&[0])),
+                Some(entries(&[0, 1])),
+                Some(entries(&[1, 2])),
+                None,
+                Some(entries(&[1, 2])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_proxy_errors() {
+        let tile_id = TileID(Interval::new(Timestamp(0), Timestamp(1)), 0);
+
+        // Requests that fail upstream fail here too, rather than being
+        // answered with empty tiles
+        let config = FaultConfig {
+            error_rate: 1.0,
+            ..Default::default()
+        };
+        let proxy = BlockingDataSource::new(FaultInjectingDeferredDataSource::new(
+            TestDeferredDataSource::default(),
+            config,
+        ));
+        assert!(matches!(
+            proxy.fetch_slot_tile(&EntryID::root(), tile_id, false),
+            Err(RequestError::Other(_))
+        ));
+
+        let mut proxy = BlockingDataSource::new(TestDeferredDataSource::held());
+        proxy.timeout = Duration::from_millis(10);
+        assert_eq!(
+            proxy
+                .fetch_slot_tile(&EntryID::root(), tile_id, false)
+                .err(),
+            Some(RequestError::Timeout)
+        );
+
+        // Late responses go to the next request for the same tile
+        proxy.state.lock().unwrap().data_source.release();
+        proxy.state.lock().unwrap().data_source.hold = false;
+        let tile = proxy.fetch_slot_tile(&EntryID::root(), tile_id, false);
+        assert_eq!(tile.map(|tile| tile.tile_id), Ok(tile_id));
+    }
+}
@@ -1,6 +1,15 @@
-use serde::Deserialize;
+use std::collections::BTreeSet;
 
-use crate::data::{EntryID, EntryIDSlug, SlugParseError, TileID, TileIDSlug};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{
+    ClientID, EntryID, EntryIDSlug, ItemUID, SearchQuery, SlugParseError, TileID, TileIDSlug,
+};
+use crate::timestamp::Interval;
+
+// Response header carrying the server's PROTOCOL_VERSION, so that the client
+// can explain a mismatch even when it cannot decode the response
+pub const PROTOCOL_VERSION_HEADER: &str = "X-Legion-Prof-Protocol-Version";
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct TileRequestPath {
@@ -20,11 +29,56 @@ pub struct TileRequestRef<'a> {
     pub tile_id: TileID,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemRequestPath {
+    pub entry_id: String,
+    pub tile_id: String,
+    pub item_uid: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemRequest {
+    pub entry_id: EntryID,
+    pub tile_id: TileID,
+    pub item_uid: ItemUID,
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemRequestRef<'a> {
+    pub entry_id: &'a EntryID,
+    pub tile_id: TileID,
+    pub item_uid: ItemUID,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct TileQuery {
     pub full: bool,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdatesQuery {
+    pub version: u64,
+}
+
+// Queries can hold any text, so searches are posted with this as the body,
+// encoded like the responses
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SearchRequest {
+    pub query: SearchQuery,
+    pub interval: Interval,
+    pub limit: usize,
+}
+
+// Hints of the active entries are posted with this as the body, encoded like
+// the responses. Each client numbers its hints in increasing order, so that
+// hints that arrive out of order can be dropped
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ActiveEntriesRequest {
+    pub client: ClientID,
+    pub sequence: u64,
+    pub entries: Option<BTreeSet<EntryID>>,
+}
+
 impl TileRequestPath {
     pub fn parse(&self) -> Result<TileRequest, SlugParseError> {
         Ok(TileRequest {
@@ -43,3 +97,24 @@ impl<'a> TileRequestRef<'a> {
         )
     }
 }
+
+impl ItemRequestPath {
+    pub fn parse(&self) -> Result<ItemRequest, SlugParseError> {
+        Ok(ItemRequest {
+            entry_id: EntryID::from_slug(&self.entry_id)?,
+            tile_id: TileID::from_slug(&self.tile_id)?,
+            item_uid: ItemUID(self.item_uid),
+        })
+    }
+}
+
+impl<'a> ItemRequestRef<'a> {
+    pub fn to_slug(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            EntryIDSlug(self.entry_id),
+            TileIDSlug(self.tile_id),
+            self.item_uid.0
+        )
+    }
+}
@@ -1,19 +1,146 @@
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use actix_cors::Cors;
 use actix_web::{
-    error, get, http, middleware,
+    error, get, http, middleware, post,
     web::{self, Data},
-    App, HttpServer, Responder, Result,
+    App, HttpResponse, HttpServer, Responder, Result,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::data::DataSource;
-use crate::http::schema::{TileQuery, TileRequestPath};
+use crate::data::{
+    ClientID, DataSource, DataSourceInfo, DataSourceUpdates, EntryID, ItemUID, SearchQuery,
+    SearchResults, SlotMetaItem, SlotMetaTile, SlotTile, SummaryTile, TileID, PROTOCOL_VERSION,
+};
+use crate::deferred_data::RequestError;
+use crate::http::schema::{
+    ActiveEntriesRequest, ItemRequestPath, SearchRequest, TileQuery, TileRequestPath, UpdatesQuery,
+    PROTOCOL_VERSION_HEADER,
+};
+use crate::timestamp::Interval;
+
+// What the server needs of its data source. Unlike a DataSource, requests
+// may fail (e.g., when they are forwarded to another server, see the proxy),
+// in which case the client is sent an error rather than empty data
+pub trait FallibleDataSource {
+    fn fetch_info(&self) -> Result<DataSourceInfo, RequestError>;
+    fn fetch_summary_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SummaryTile, RequestError>;
+    fn fetch_slot_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SlotTile, RequestError>;
+    fn fetch_slot_meta_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SlotMetaTile, RequestError>;
+    fn fetch_slot_meta_item(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: ItemUID,
+    ) -> Result<SlotMetaItem, RequestError>;
+    // See DataSource. Searches report their own errors
+    fn fetch_updates(&self, version: u64) -> DataSourceUpdates;
+    fn set_active_entries(&self, client: ClientID, entries: Option<&BTreeSet<EntryID>>);
+    fn fetch_search(&self, query: &SearchQuery, interval: Interval, limit: usize) -> SearchResults;
+}
+
+// A DataSource always answers
+struct Infallible(Box<dyn DataSource + Send + Sync + 'static>);
+
+impl FallibleDataSource for Infallible {
+    fn fetch_info(&self) -> Result<DataSourceInfo, RequestError> {
+        Ok(self.0.fetch_info())
+    }
+
+    fn fetch_summary_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SummaryTile, RequestError> {
+        Ok(self.0.fetch_summary_tile(entry_id, tile_id, full))
+    }
+
+    fn fetch_slot_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SlotTile, RequestError> {
+        Ok(self.0.fetch_slot_tile(entry_id, tile_id, full))
+    }
+
+    fn fetch_slot_meta_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        full: bool,
+    ) -> Result<SlotMetaTile, RequestError> {
+        Ok(self.0.fetch_slot_meta_tile(entry_id, tile_id, full))
+    }
+
+    fn fetch_slot_meta_item(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: ItemUID,
+    ) -> Result<SlotMetaItem, RequestError> {
+        Ok(self.0.fetch_slot_meta_item(entry_id, tile_id, item_uid))
+    }
+
+    fn fetch_updates(&self, version: u64) -> DataSourceUpdates {
+        self.0.fetch_updates(version)
+    }
+
+    fn set_active_entries(&self, client: ClientID, entries: Option<&BTreeSet<EntryID>>) {
+        self.0.set_active_entries(client, entries)
+    }
+
+    fn fetch_search(&self, query: &SearchQuery, interval: Interval, limit: usize) -> SearchResults {
+        self.0.fetch_search(query, interval, limit)
+    }
+}
 
 struct AppState {
-    data_source: Box<dyn DataSource + Send + Sync + 'static>,
+    data_source: Box<dyn FallibleDataSource + Send + Sync + 'static>,
+    // Sequence number of the latest hint from each client
+    hint_sequences: Mutex<BTreeMap<ClientID, u64>>,
+}
+
+impl AppState {
+    fn new(data_source: Box<dyn FallibleDataSource + Send + Sync + 'static>) -> Self {
+        Self {
+            data_source,
+            hint_sequences: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    // Hints are posted without waiting for the previous ones, so they may
+    // arrive out of order. The lock is held while the hint is passed on, so
+    // that concurrent hints are passed on in order too
+    fn set_active_entries(&self, req: &ActiveEntriesRequest) {
+        let mut hint_sequences = self.hint_sequences.lock().unwrap();
+        let latest = hint_sequences.entry(req.client).or_default();
+        if req.sequence <= *latest {
+            return;
+        }
+        *latest = req.sequence;
+        self.data_source
+            .set_active_entries(req.client, req.entries.as_ref());
+    }
 }
 
 pub struct DataSourceHTTPServer {
@@ -32,9 +159,31 @@ where
     Ok(f)
 }
 
+fn decode<T>(data: &[u8]) -> Result<T, String>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let f = zstd::Decoder::new(data).map_err(|e| e.to_string())?;
+    ciborium::from_reader(f).map_err(|e| e.to_string())
+}
+
+// Requests that couldn't be answered upstream are a bad gateway, or a gateway
+// timeout, as appropriate
+fn upstream_error(e: RequestError) -> error::Error {
+    match e {
+        RequestError::Timeout => error::ErrorGatewayTimeout(format!("upstream {e}")),
+        _ => error::ErrorBadGateway(format!("upstream request failed: {e}")),
+    }
+}
+
+// Data sources may block (e.g., the proxy waits for its upstream), so they
+// are called on the blocking thread pool, where slow requests can't hold up
+// the workers answering everything else
 #[get("/info")]
 async fn fetch_info(state: web::Data<AppState>) -> Result<impl Responder> {
-    let result = state.data_source.fetch_info();
+    let result = web::block(move || state.data_source.fetch_info())
+        .await?
+        .map_err(upstream_error)?;
     encode(result)
 }
 
@@ -47,9 +196,14 @@ async fn fetch_summary_tile(
     let path = path
         .parse()
         .map_err(|e| error::ErrorBadRequest(format!("bad request: {}", e)))?;
-    let result = state
-        .data_source
-        .fetch_summary_tile(&path.entry_id, path.tile_id, query.full);
+    let full = query.full;
+    let result = web::block(move || {
+        state
+            .data_source
+            .fetch_summary_tile(&path.entry_id, path.tile_id, full)
+    })
+    .await?
+    .map_err(upstream_error)?;
     encode(result)
 }
 
@@ -62,9 +216,14 @@ async fn fetch_slot_tile(
     let path = path
         .parse()
         .map_err(|e| error::ErrorBadRequest(format!("bad request: {}", e)))?;
-    let result = state
-        .data_source
-        .fetch_slot_tile(&path.entry_id, path.tile_id, query.full);
+    let full = query.full;
+    let result = web::block(move || {
+        state
+            .data_source
+            .fetch_slot_tile(&path.entry_id, path.tile_id, full)
+    })
+    .await?
+    .map_err(upstream_error)?;
     encode(result)
 }
 
@@ -77,22 +236,104 @@ async fn fetch_slot_meta_tile(
     let path = path
         .parse()
         .map_err(|e| error::ErrorBadRequest(format!("bad request: {}", e)))?;
-    let result = state
-        .data_source
-        .fetch_slot_meta_tile(&path.entry_id, path.tile_id, query.full);
+    let full = query.full;
+    let result = web::block(move || {
+        state
+            .data_source
+            .fetch_slot_meta_tile(&path.entry_id, path.tile_id, full)
+    })
+    .await?
+    .map_err(upstream_error)?;
+    encode(result)
+}
+
+#[get("/slot_meta_item/{entry_id}/{tile_id}/{item_uid}")]
+async fn fetch_slot_meta_item(
+    path: web::Path<ItemRequestPath>,
+    state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let path = path
+        .parse()
+        .map_err(|e| error::ErrorBadRequest(format!("bad request: {}", e)))?;
+    let result = web::block(move || {
+        state
+            .data_source
+            .fetch_slot_meta_item(&path.entry_id, path.tile_id, path.item_uid)
+    })
+    .await?
+    .map_err(upstream_error)?;
+    encode(result)
+}
+
+// See DataSource::set_active_entries and ActiveEntriesRequest
+#[post("/active_entries")]
+async fn set_active_entries(
+    body: web::Bytes,
+    state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let req: ActiveEntriesRequest =
+        decode(&body).map_err(|e| error::ErrorBadRequest(format!("bad request: {}", e)))?;
+    state.set_active_entries(&req);
+    Ok(HttpResponse::NoContent())
+}
+
+#[post("/search")]
+async fn fetch_search(body: web::Bytes, state: web::Data<AppState>) -> Result<impl Responder> {
+    let req: SearchRequest =
+        decode(&body).map_err(|e| error::ErrorBadRequest(format!("bad request: {}", e)))?;
+    let result = web::block(move || {
+        state
+            .data_source
+            .fetch_search(&req.query, req.interval, req.limit)
+    })
+    .await?;
     encode(result)
 }
 
+// Requests for updates are held until there are any, so that clients learn
+// about them right away without polling. This must stay below the client's
+// request timeout
+const UPDATES_TIMEOUT: Duration = Duration::from_secs(20);
+const UPDATES_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[get("/updates")]
+async fn fetch_updates(
+    query: web::Query<UpdatesQuery>,
+    state: web::Data<AppState>,
+) -> Result<impl Responder> {
+    let start = Instant::now();
+    let version = query.version;
+    loop {
+        let state = state.clone();
+        let result = web::block(move || state.data_source.fetch_updates(version)).await?;
+        if !result.updates.is_empty()
+            || result.version != version
+            || start.elapsed() >= UPDATES_TIMEOUT
+        {
+            return encode(result);
+        }
+        actix_web::rt::time::sleep(UPDATES_POLL_INTERVAL).await;
+    }
+}
+
 impl DataSourceHTTPServer {
     pub fn new(
         host: String,
         port: u16,
         data_source: Box<dyn DataSource + Send + Sync + 'static>,
+    ) -> Self {
+        Self::with_fallible(host, port, Box::new(Infallible(data_source)))
+    }
+
+    pub fn with_fallible(
+        host: String,
+        port: u16,
+        data_source: Box<dyn FallibleDataSource + Send + Sync + 'static>,
     ) -> Self {
         Self {
             host,
             port,
-            state: AppState { data_source },
+            state: AppState::new(data_source),
         }
     }
 
@@ -106,18 +347,229 @@ impl DataSourceHTTPServer {
                 .allowed_methods(vec!["GET", "POST"])
                 .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
                 .allowed_header(http::header::CONTENT_TYPE)
+                .expose_headers(vec![PROTOCOL_VERSION_HEADER])
                 .max_age(3600);
+            let headers = middleware::DefaultHeaders::new()
+                .add((PROTOCOL_VERSION_HEADER, PROTOCOL_VERSION.to_string()));
             App::new()
                 .wrap(middleware::Logger::default())
                 .wrap(cors)
+                .wrap(headers)
                 .app_data(state.clone())
                 .service(fetch_info)
                 .service(fetch_summary_tile)
                 .service(fetch_slot_tile)
                 .service(fetch_slot_meta_tile)
+                .service(fetch_slot_meta_item)
+                .service(fetch_updates)
+                .service(set_active_entries)
+                .service(fetch_search)
         })
-        .bind((self.host.as_str(), self.port))?
+        // Accept HTTP/2 without TLS too (i.e., h2c), e.g., from reverse
+        // proxies that multiplex requests to it
+        .bind_auto_h2c((self.host.as_str(), self.port))?
         .run()
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Condvar;
+
+    use actix_web::test::{call_and_read_body, call_service, init_service, TestRequest};
+
+    use crate::http::schema::TileRequestRef;
+    use crate::test_data::TestDataSource;
+    use crate::timestamp::Timestamp;
+
+    // Slot tiles are only answered once the info has been, or else time out
+    #[derive(Default)]
+    struct SlowDataSource {
+        data_source: TestDataSource,
+        info_sent: Mutex<bool>,
+        sent: Condvar,
+        // Slot tile requests made so far
+        waiting: Arc<AtomicUsize>,
+    }
+
+    impl FallibleDataSource for SlowDataSource {
+        fn fetch_info(&self) -> Result<DataSourceInfo, RequestError> {
+            *self.info_sent.lock().unwrap() = true;
+            self.sent.notify_all();
+            Ok(self.data_source.fetch_info())
+        }
+
+        fn fetch_summary_tile(
+            &self,
+            _: &EntryID,
+            _: TileID,
+            _: bool,
+        ) -> Result<SummaryTile, RequestError> {
+            unreachable!()
+        }
+
+        fn fetch_slot_tile(
+            &self,
+            entry_id: &EntryID,
+            tile_id: TileID,
+            full: bool,
+        ) -> Result<SlotTile, RequestError> {
+            self.waiting.fetch_add(1, Ordering::SeqCst);
+            let info_sent = self.info_sent.lock().unwrap();
+            let wait = self
+                .sent
+                .wait_timeout_while(info_sent, Duration::from_secs(5), |sent| !*sent)
+                .unwrap()
+                .1;
+            if wait.timed_out() {
+                return Err(RequestError::Timeout);
+            }
+            Ok(self.data_source.fetch_slot_tile(entry_id, tile_id, full))
+        }
+
+        fn fetch_slot_meta_tile(
+            &self,
+            _: &EntryID,
+            _: TileID,
+            _: bool,
+        ) -> Result<SlotMetaTile, RequestError> {
+            unreachable!()
+        }
+
+        fn fetch_slot_meta_item(
+            &self,
+            _: &EntryID,
+            _: TileID,
+            _: ItemUID,
+        ) -> Result<SlotMetaItem, RequestError> {
+            unreachable!()
+        }
+
+        fn fetch_updates(&self, _: u64) -> DataSourceUpdates {
+            unreachable!()
+        }
+
+        fn set_active_entries(&self, _: ClientID, _: Option<&BTreeSet<EntryID>>) {}
+
+        fn fetch_search(&self, _: &SearchQuery, _: Interval, _: usize) -> SearchResults {
+            unreachable!()
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_slow_requests() {
+        // Test services run on a single thread, i.e., a single worker
+        let data_source = SlowDataSource::default();
+        let waiting = data_source.waiting.clone();
+        let state = Data::new(AppState::new(Box::new(data_source)));
+        let app = Rc::new(
+            init_service(
+                App::new()
+                    .app_data(state)
+                    .service(fetch_info)
+                    .service(fetch_slot_tile),
+            )
+            .await,
+        );
+
+        // More slow requests than workers don't keep the info from being
+        // answered, which in turn answers them
+        let tile_id = TileID(Interval::new(Timestamp(0), Timestamp(1000)), 0);
+        let tiles: Vec<_> = (0..4)
+            .map(|i| {
+                let req = TileRequestRef {
+                    entry_id: &EntryID::root().child(i),
+                    tile_id,
+                };
+                let req = TestRequest::get()
+                    .uri(&format!("/slot_tile/{}?full=false", req.to_slug()))
+                    .to_request();
+                let app = app.clone();
+                actix_web::rt::spawn(async move { call_service(&*app, req).await.status() })
+            })
+            .collect();
+        while waiting.load(Ordering::SeqCst) < tiles.len() {
+            actix_web::rt::time::sleep(Duration::from_millis(1)).await;
+        }
+        let req = TestRequest::get().uri("/info").to_request();
+        assert!(call_service(&*app, req).await.status().is_success());
+        for tile in tiles {
+            assert_eq!(tile.await.unwrap(), http::StatusCode::OK);
+        }
+    }
+
+    #[test]
+    fn test_stale_hints() {
+        let data_source = TestDataSource::default();
+        let active_entries = data_source.active_entries.clone();
+        let state = AppState::new(Box::new(Infallible(Box::new(data_source))));
+        let hint = |client, sequence, entries: Option<&[u64]>| ActiveEntriesRequest {
+            client: ClientID(client),
+            sequence,
+            entries: entries.map(|ids| ids.iter().map(|i| EntryID::root().child(*i)).collect()),
+        };
+
+        // Hints that arrive after a later one from the same client are
+        // dropped, but other clients are numbered on their own
+        state.set_active_entries(&hint(1, 2, Some(&[0])));
+        state.set_active_entries(&hint(1, 1, None));
+        state.set_active_entries(&hint(2, 1, Some(&[1])));
+        state.set_active_entries(&hint(1, 3, None));
+        let received: Vec<_> = active_entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(client, entries)| (client.0, entries.as_ref().map(BTreeSet::len)))
+            .collect();
+        assert_eq!(received, vec![(1, Some(1)), (2, Some(1)), (1, None)]);
+    }
+
+    #[actix_web::test]
+    async fn test_search_endpoint() {
+        let data_source = TestDataSource {
+            search: true,
+            ..Default::default()
+        };
+        let state = Data::new(AppState::new(Box::new(Infallible(Box::new(data_source)))));
+        let app = init_service(App::new().app_data(state).service(fetch_search)).await;
+
+        let search = |text: &str| {
+            let req = SearchRequest {
+                query: SearchQuery {
+                    text: text.to_owned(),
+                    field: None,
+                    whole_word: false,
+                },
+                interval: Interval::new(Timestamp(0), Timestamp(1000)),
+                limit: 10,
+            };
+            TestRequest::post()
+                .uri("/search")
+                .set_payload(encode(req).unwrap())
+                .to_request()
+        };
+        let body = call_and_read_body(&app, search("Item")).await;
+        let results: SearchResults = decode(&body).unwrap();
+        assert_eq!(results.query.text, "Item");
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(results.matches[0].item_uid, ItemUID(0));
+        assert!(results.error.is_none());
+
+        let body = call_and_read_body(&app, search("Nothing")).await;
+        let results: SearchResults = decode(&body).unwrap();
+        assert!(results.matches.is_empty());
+
+        // Malformed requests are rejected
+        let req = TestRequest::post()
+            .uri("/search")
+            .set_payload("bogus")
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+// How the HTTP data source verifies servers. Profiles are often served from
+// ad-hoc servers inside clusters, with self-signed certificates, which can
+// either be trusted explicitly (preferred) or not verified at all. Browsers
+// verify servers themselves, so these are ignored on the web. Given on the
+// command line as:
+//
+//     --ca-bundle <path>    PEM file of extra certificates to trust (or
+//                           --ca-bundle=<path>)
+//     --insecure            skip verification entirely
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TlsOptions {
+    pub ca_bundle: Option<PathBuf>,
+    // Never saved, so that it has to be asked for every time
+    #[serde(skip)]
+    pub accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    // Removes the TLS options from the arguments, leaving the rest for the
+    // caller
+    pub fn extract_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut result = Self::default();
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--ca-bundle" => {
+                    if i + 1 == args.len() {
+                        return Err("missing value for --ca-bundle".to_owned());
+                    }
+                    result.ca_bundle = Some(PathBuf::from(args.remove(i + 1)));
+                    args.remove(i);
+                }
+                "--insecure" => {
+                    result.accept_invalid_certs = true;
+                    args.remove(i);
+                }
+                arg => match arg.strip_prefix("--ca-bundle=") {
+                    Some("") => return Err("missing value for --ca-bundle".to_owned()),
+                    Some(path) => {
+                        result.ca_bundle = Some(PathBuf::from(path));
+                        args.remove(i);
+                    }
+                    None => i += 1,
+                },
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(s: &str) -> (Result<TlsOptions, String>, Vec<String>) {
+        let mut args: Vec<_> = s.split_whitespace().map(str::to_owned).collect();
+        let result = TlsOptions::extract_args(&mut args);
+        (result, args)
+    }
+
+    #[test]
+    fn test_extract_tls_args() {
+        let (result, rest) = extract("http://a --insecure info --ca-bundle ca.pem");
+        assert_eq!(
+            result,
+            Ok(TlsOptions {
+                ca_bundle: Some(PathBuf::from("ca.pem")),
+                accept_invalid_certs: true,
+            })
+        );
+        assert_eq!(rest, ["http://a", "info"]);
+
+        let (result, rest) = extract("http://a info");
+        assert_eq!(result, Ok(TlsOptions::default()));
+        assert_eq!(rest.len(), 2);
+
+        let (result, rest) = extract("--ca-bundle=ca.pem http://a --ca-bundler x");
+        assert_eq!(result.unwrap().ca_bundle, Some(PathBuf::from("ca.pem")));
+        assert_eq!(rest, ["http://a", "--ca-bundler", "x"]);
+
+        assert!(extract("http://a --ca-bundle").0.is_err());
+        assert!(extract("http://a --ca-bundle=").0.is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::data::{Field, UtilPoint};
+
+// Writes a hand-picked set of items (e.g., the selected and watched items)
+// with all their fields, so that they can be passed on to other people or
+// scripts. JSON keeps fields as they are in the data source; CSV has one
+// column per field name (or several, for fields that repeat within an item),
+// formatted for display. The utilization of a summary can be written as CSV
+// too.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedField {
+    pub name: String,
+    pub value: Field,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedItem {
+    pub item_uid: u64,
+    pub slot: String,
+    pub title: String,
+    // Nanoseconds
+    pub start: i64,
+    pub stop: i64,
+    pub selected: bool,
+    pub watched: bool,
+    // Empty if the item's metadata hasn't been loaded
+    pub fields: Vec<ExportedField>,
+}
+
+fn write_json(mut out: impl Write, items: &[ExportedItem]) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut out, items)?;
+    writeln!(out)
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn write_csv_row(out: &mut impl Write, values: impl IntoIterator<Item = String>) -> io::Result<()> {
+    let values: Vec<_> = values.into_iter().map(|v| csv_escape(&v)).collect();
+    writeln!(out, "{}", values.join(","))
+}
+
+fn write_csv(mut out: impl Write, items: &[ExportedItem]) -> io::Result<()> {
+    // Field columns in order of first appearance, with as many columns for
+    // each name as the most times it appears in one item
+    let mut names: Vec<(&str, usize)> = Vec::new();
+    for item in items {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for field in &item.fields {
+            match counts.iter_mut().find(|(name, _)| *name == field.name) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((&field.name, 1)),
+            }
+        }
+        for (name, count) in counts {
+            match names.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, max)) => *max = (*max).max(count),
+                None => names.push((name, count)),
+            }
+        }
+    }
+    let field_columns: Vec<(&str, usize)> = names
+        .iter()
+        .flat_map(|(name, count)| (0..*count).map(move |i| (*name, i)))
+        .collect();
+
+    let header = [
+        "Item UID",
+        "Slot",
+        "Title",
+        "Start (ns)",
+        "Stop (ns)",
+        "Selected",
+        "Watched",
+    ];
+    let header = header
+        .into_iter()
+        .map(str::to_owned)
+        .chain(field_columns.iter().map(|(name, i)| match i {
+            0 => name.to_string(),
+            _ => format!("{name} ({})", i + 1),
+        }));
+    write_csv_row(&mut out, header)?;
+    for item in items {
+        let columns = [
+            item.item_uid.to_string(),
+            item.slot.clone(),
+            item.title.clone(),
+            item.start.to_string(),
+            item.stop.to_string(),
+            item.selected.to_string(),
+            item.watched.to_string(),
+        ];
+        let fields = field_columns.iter().map(|(name, i)| {
+            item.fields
+                .iter()
+                .filter(|field| field.name == *name)
+                .nth(*i)
+                .map_or_else(String::new, |field| field.value.to_string())
+        });
+        write_csv_row(&mut out, columns.into_iter().chain(fields))?;
+    }
+    Ok(())
+}
+
+pub fn write_items(
+    out: impl Write,
+    items: &[ExportedItem],
+    format: ExportFormat,
+) -> io::Result<()> {
+    let mut out = io::BufWriter::new(out);
+    match format {
+        ExportFormat::Json => write_json(&mut out, items)?,
+        ExportFormat::Csv => write_csv(&mut out, items)?,
+    }
+    out.flush()
+}
+
+// Never overwrite an earlier export: try name.ext, then name.1.ext, etc.
+fn create_unique_file(dir: &Path, name: &str, extension: &str) -> io::Result<(File, PathBuf)> {
+    const RETRY_LIMIT: usize = 100;
+    let mut i = 0;
+    loop {
+        let path = if i == 0 {
+            dir.join(format!("{name}.{extension}"))
+        } else {
+            dir.join(format!("{name}.{i}.{extension}"))
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((file, path)),
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists || i >= RETRY_LIMIT => {
+                return Err(e)
+            }
+            Err(_) => i += 1,
+        }
+    }
+}
+
+// Exports go to the chosen directory, or else the current one
+fn export_dir(dir: Option<&Path>) -> io::Result<PathBuf> {
+    match dir {
+        Some(dir) => Ok(dir.to_owned()),
+        None => std::env::current_dir(),
+    }
+}
+
+// Writes the items to a new file in the directory
+pub fn export_items(
+    dir: Option<&Path>,
+    items: &[ExportedItem],
+    format: ExportFormat,
+) -> io::Result<PathBuf> {
+    let dir = export_dir(dir)?;
+    let (file, path) = create_unique_file(&dir, "legion_prof_items", format.extension())?;
+    write_items(file, items, format)?;
+    Ok(path)
+}
+
+// The utilization of a summary, as CSV with one point per row
+pub fn write_utilization(out: impl Write, points: &[UtilPoint]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(out);
+    write_csv_row(&mut out, ["Time (ns)", "Utilization"].map(str::to_owned))?;
+    for point in points {
+        write_csv_row(&mut out, [point.time.0.to_string(), point.util.to_string()])?;
+    }
+    out.flush()
+}
+
+// Writes the utilization to a new file in the directory
+pub fn export_utilization(dir: Option<&Path>, points: &[UtilPoint]) -> io::Result<PathBuf> {
+    let dir = export_dir(dir)?;
+    let extension = ExportFormat::Csv.extension();
+    let (file, path) = create_unique_file(&dir, "legion_prof_utilization", extension)?;
+    write_utilization(file, points)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::timestamp::Timestamp;
+
+    fn item(item_uid: u64, title: &str, fields: Vec<(&str, Field)>) -> ExportedItem {
+        ExportedItem {
+            item_uid,
+            slot: "Node 0 CPU 1".to_owned(),
+            title: title.to_owned(),
+            start: 100,
+            stop: 200,
+            selected: true,
+            watched: false,
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| ExportedField {
+                    name: name.to_owned(),
+                    value,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let items = [
+            item(1, "task, \"quoted\"", vec![("Size", Field::U64(8))]),
+            item(2, "copy", vec![("Kind", Field::String("a\nb".to_owned()))]),
+        ];
+        let mut out = Vec::new();
+        write_items(&mut out, &items, ExportFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Item UID,Slot,Title,Start (ns),Stop (ns),Selected,Watched,Size,Kind\n\
+             1,Node 0 CPU 1,\"task, \"\"quoted\"\"\",100,200,true,false,8,\n\
+             2,Node 0 CPU 1,copy,100,200,true,false,,\"a\nb\"\n"
+        );
+    }
+
+    #[test]
+    fn test_write_csv_repeated_fields() {
+        let items = [
+            item(1, "a", vec![("Req", Field::U64(1)), ("Req", Field::U64(2))]),
+            item(
+                2,
+                "b",
+                vec![
+                    ("Size", Field::U64(8)),
+                    ("Req", Field::U64(3)),
+                    ("Req", Field::U64(4)),
+                    ("Req", Field::U64(5)),
+                ],
+            ),
+        ];
+        let mut out = Vec::new();
+        write_items(&mut out, &items, ExportFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Item UID,Slot,Title,Start (ns),Stop (ns),Selected,Watched,Req,Req (2),Req (3),Size\n\
+             1,Node 0 CPU 1,a,100,200,true,false,1,2,,\n\
+             2,Node 0 CPU 1,b,100,200,true,false,3,4,5,8\n"
+        );
+    }
+
+    #[test]
+    fn test_export_dir() {
+        let dir = std::env::temp_dir().join(format!("legion_prof_export_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let items = [item(1, "task", Vec::new())];
+        let first = export_items(Some(&dir), &items, ExportFormat::Json).unwrap();
+        let second = export_items(Some(&dir), &items, ExportFormat::Json).unwrap();
+        assert_eq!(first, dir.join("legion_prof_items.json"));
+        assert_eq!(second, dir.join("legion_prof_items.1.json"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_utilization() {
+        let points = [
+            UtilPoint {
+                time: Timestamp(0),
+                util: 0.0,
+            },
+            UtilPoint {
+                time: Timestamp(500),
+                util: 0.75,
+            },
+        ];
+        let mut out = Vec::new();
+        write_utilization(&mut out, &points).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Time (ns),Utilization\n0,0\n500,0.75\n"
+        );
+    }
+
+    #[test]
+    fn test_write_json() {
+        let items = [item(1, "task", vec![("Size", Field::U64(8))])];
+        let mut out = Vec::new();
+        write_items(&mut out, &items, ExportFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value[0]["item_uid"], 1);
+        assert_eq!(value[0]["fields"][0]["name"], "Size");
+        assert_eq!(value[0]["fields"][0]["value"]["U64"], 8);
+    }
+}
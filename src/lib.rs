@@ -3,14 +3,34 @@
 pub mod app;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod archive_data;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cache_data;
+pub mod compact_data;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod control;
+pub mod crop_data;
 pub mod data;
 pub mod deferred_data;
+#[cfg(target_arch = "wasm32")]
+pub mod directory_data;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dump;
+pub mod fault_data;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod file_data;
+pub mod filter_data;
 pub mod http;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod item_export;
+pub mod memory_data;
 pub mod merge_data;
 #[cfg(feature = "nvtxw")]
 pub mod nvtxw;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod parallel_data;
+#[cfg(test)]
+mod test_data;
 pub mod timestamp;
+pub mod view_link;
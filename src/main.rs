@@ -8,17 +8,21 @@ use std::sync::Mutex;
 
 use legion_prof_viewer::data::{
     DataSource, DataSourceDescription, DataSourceInfo, EntryID, EntryInfo, Field, FieldID,
-    FieldSchema, Item, ItemMeta, ItemUID, SlotMetaTile, SlotMetaTileData, SlotTile, SlotTileData,
-    SummaryTile, SummaryTileData, TileID, TileSet, UtilPoint,
+    FieldSchema, Item, ItemMeta, ItemUID, SlotMetaItem, SlotMetaTile, SlotMetaTileData, SlotTile,
+    SlotTileData, SummaryTile, SummaryTileData, TileID, TileSet, UtilPoint, PROTOCOL_VERSION,
 };
 
 #[cfg(not(target_arch = "wasm32"))]
-use legion_prof_viewer::deferred_data::DeferredDataSourceWrapper;
+use legion_prof_viewer::control::ViewOptions;
+#[cfg(not(target_arch = "wasm32"))]
+use legion_prof_viewer::deferred_data::{DeferredDataSource, DeferredDataSourceWrapper};
 use legion_prof_viewer::timestamp::{Interval, Timestamp};
 
 #[cfg(target_arch = "wasm32")]
 use legion_prof_viewer::http::client::HTTPClientDataSource;
 #[cfg(target_arch = "wasm32")]
+use legion_prof_viewer::view_link::ViewLink;
+#[cfg(target_arch = "wasm32")]
 use url::Url;
 
 #[cfg(target_arch = "wasm32")]
@@ -26,9 +30,44 @@ const DEFAULT_URL: &str = "http://127.0.0.1:8080";
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    legion_prof_viewer::app::start(vec![Box::new(DeferredDataSourceWrapper::new(
-        RandomDataSource::new(),
-    ))]);
+    let args: Vec<_> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("dump") {
+        if let Err(e) = legion_prof_viewer::dump::run(&args[2..]) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    #[cfg(feature = "server")]
+    if args.get(1).map(String::as_str) == Some("proxy") {
+        if let Err(e) = legion_prof_viewer::http::proxy::run(&args[2..]) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let data_sources: Vec<Box<dyn DeferredDataSource>> = vec![Box::new(
+        DeferredDataSourceWrapper::new(RandomDataSource::new()),
+    )];
+
+    let options = match ViewOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // Opt-in, since it takes over stdin
+    let control_stdin = std::env::args().any(|arg| arg == "--control-stdin");
+
+    legion_prof_viewer::app::start_with_handle(data_sources, move |handle| {
+        options.apply(&handle);
+        if control_stdin {
+            legion_prof_viewer::control::spawn_stdin_control(handle);
+        }
+    });
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -37,17 +76,42 @@ fn main() {
     let href: String = loc.href().expect("Unable to get window URL");
     let browser_url = Url::parse(&href).expect("Unable to parse location URL");
 
-    let url = Url::parse(
-        browser_url
-            .query_pairs()
-            .find(|(key, _)| key == "url")
-            .map(|(_, value)| value)
-            .as_deref()
-            .unwrap_or(DEFAULT_URL),
-    )
-    .expect("Unable to parse query URL");
-
-    legion_prof_viewer::app::start(vec![Box::new(HTTPClientDataSource::new(url))]);
+    // Otherwise the messages below are lost
+    eframe::WebLogger::init(log::LevelFilter::Debug).ok();
+
+    // The query may also describe a view to restore (see ViewLink). If it
+    // is malformed, still open the profiles in it
+    let mut link =
+        ViewLink::parse_query(browser_url.query().unwrap_or_default()).unwrap_or_else(|e| {
+            log::warn!("unable to parse view link: {e}");
+            ViewLink {
+                sources: browser_url
+                    .query_pairs()
+                    .filter(|(key, _)| key == "url")
+                    .map(|(_, value)| value.into_owned())
+                    .collect(),
+                ..ViewLink::default()
+            }
+        });
+    if link.sources.is_empty() {
+        link.sources.push(DEFAULT_URL.to_owned());
+    }
+
+    let data_sources = std::mem::take(&mut link.sources)
+        .iter()
+        .filter_map(|url| match Url::parse(url) {
+            Ok(url) => Some(Box::new(HTTPClientDataSource::new(url)) as _),
+            Err(e) => {
+                log::warn!("unable to parse profile URL {url}: {e}");
+                None
+            }
+        })
+        .collect();
+
+    // Without its sources, the link applies to the profiles opened above
+    legion_prof_viewer::app::start_with_handle(data_sources, move |handle| {
+        handle.open_link(link);
+    });
 }
 
 type SlotCacheTile = (Vec<Vec<Item>>, Vec<Vec<ItemMeta>>);
@@ -98,6 +162,9 @@ impl RandomDataSource {
             tile_set: TileSet::default(),
             field_schema,
             warning_message: Some("Demo only. The data in this profile is synthetic.".to_string()),
+            protocol_version: PROTOCOL_VERSION,
+            supports_search: false,
+            unavailable: false,
         };
 
         let state = RandomState {
@@ -267,6 +334,7 @@ impl RandomDataSource {
                         ),
                         long_name: format!("Node {node} {kind} {proc}"),
                         max_rows: rows,
+                        row_names: Vec::new(),
                     });
                 }
                 kind_slots.push(EntryInfo::Panel {
@@ -382,9 +450,9 @@ impl DataSource for RandomDataSource {
         let (items, item_metas) = self.generate_slot(entry_id);
 
         let mut slot_items = Vec::new();
-        for (row, row_meta) in items.iter().zip(item_metas.into_iter()) {
+        for (row, row_meta) in items.iter().zip(item_metas) {
             let mut slot_row = Vec::new();
-            for (item, item_meta) in row.iter().zip(row_meta.into_iter()) {
+            for (item, item_meta) in row.iter().zip(row_meta) {
                 // When the item straddles a tile boundary, it has to be
                 // sliced to fit
                 if tile_id.0.overlaps(item.interval) {
@@ -400,4 +468,25 @@ impl DataSource for RandomDataSource {
             data: SlotMetaTileData { items: slot_items },
         }
     }
+
+    fn fetch_slot_meta_item(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        item_uid: ItemUID,
+    ) -> SlotMetaItem {
+        let (_, item_metas) = self.generate_slot(entry_id);
+
+        let data = item_metas
+            .into_iter()
+            .flatten()
+            .find(|item_meta| item_meta.item_uid == item_uid);
+
+        SlotMetaItem {
+            entry_id: entry_id.clone(),
+            tile_id,
+            item_uid,
+            data,
+        }
+    }
 }
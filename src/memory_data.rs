@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use serde::Deserialize;
+
+use crate::data::{
+    DataSource, DataSourceDescription, DataSourceInfo, EntryID, SlotMetaTile, SlotTile,
+    SummaryTile, TileID, PROTOCOL_VERSION,
+};
+use crate::http::schema::TileRequestRef;
+
+// Serves an archive (as written by DataSourceArchiveWriter) that has been
+// packed into a tar file and loaded into memory, e.g., a file dropped onto
+// the web viewer, where there is no file system to read the archive from.
+// The tar file may itself be compressed with zstd.
+pub struct MemoryDataSource {
+    name: String,
+    // Contents of the archive, by path relative to its root directory
+    files: BTreeMap<String, Vec<u8>>,
+    error: Option<String>,
+}
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const BLOCK_SIZE: usize = 512;
+
+// Reads the regular files out of a tar file. Both the POSIX (ustar) and GNU
+// ways of storing long names are supported, which covers what tar writes by
+// default
+fn read_tar(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let mut files = BTreeMap::new();
+    let mut long_name = None;
+    let mut offset = 0;
+    while offset + BLOCK_SIZE <= bytes.len() {
+        let header = &bytes[offset..offset + BLOCK_SIZE];
+        // The end of the archive is marked by blocks of zeros
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let field = |range: Range<usize>| {
+            let field = &header[range];
+            let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+            String::from_utf8_lossy(&field[..end]).into_owned()
+        };
+
+        let size = field(124..136);
+        let size = usize::from_str_radix(size.trim(), 8)
+            .map_err(|_| format!("invalid file size {size:?} in tar file"))?;
+        let start = offset + BLOCK_SIZE;
+        let data = bytes
+            .get(start..start + size)
+            .ok_or("tar file is truncated")?;
+        offset = start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+
+        let mut name = field(0..100);
+        if header[257..263] == *b"ustar\0" {
+            let prefix = field(345..500);
+            if !prefix.is_empty() {
+                name = format!("{prefix}/{name}");
+            }
+        }
+        match header[156] {
+            b'L' => {
+                let name = String::from_utf8_lossy(data);
+                long_name = Some(name.trim_end_matches('\0').to_owned());
+            }
+            b'0' | 0 => {
+                let name = long_name.take().unwrap_or(name);
+                let name = name.trim_start_matches("./").to_owned();
+                files.insert(name, data.to_vec());
+            }
+            // Directories, links, etc.
+            _ => long_name = None,
+        }
+    }
+    Ok(files)
+}
+
+// The root of the archive is the directory containing the info file, which
+// is usually the directory that was packed
+fn strip_root(files: BTreeMap<String, Vec<u8>>) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let root = files
+        .keys()
+        .filter_map(|path| path.strip_suffix("info"))
+        .filter(|root| root.is_empty() || root.ends_with('/'))
+        .min_by_key(|root| root.len())
+        .ok_or("no info file found (is this a profile archive?)")?
+        .to_owned();
+    Ok(files
+        .into_iter()
+        .filter_map(|(path, data)| Some((path.strip_prefix(&root)?.to_owned(), data)))
+        .collect())
+}
+
+fn read_archive(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    if bytes.starts_with(&ZSTD_MAGIC) {
+        let bytes =
+            zstd::decode_all(bytes).map_err(|e| format!("zstd decompression failed: {e}"))?;
+        return read_tar(&bytes).and_then(strip_root);
+    }
+    read_tar(bytes).and_then(strip_root)
+}
+
+impl MemoryDataSource {
+    pub fn new(name: &str, bytes: &[u8]) -> Self {
+        let (files, error) = match read_archive(bytes) {
+            Ok(files) => (files, None),
+            Err(e) => (BTreeMap::new(), Some(e)),
+        };
+        Self {
+            name: name.to_owned(),
+            files,
+            error,
+        }
+    }
+
+    fn read_file<T>(&self, path: &str) -> Result<T, String>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        let data = self
+            .files
+            .get(path)
+            .ok_or_else(|| format!("{path} not found"))?;
+        let f = zstd::Decoder::new(&data[..]).map_err(|e| e.to_string())?;
+        ciborium::from_reader(f).map_err(|e| e.to_string())
+    }
+
+    fn read_tile<T>(&self, kind: &str, entry_id: &EntryID, tile_id: TileID) -> T
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        let req = TileRequestRef { entry_id, tile_id };
+        let path = format!("{kind}/{}", req.to_slug());
+        self.read_file(&path)
+            .unwrap_or_else(|e| panic!("reading {path} failed: {e}"))
+    }
+}
+
+impl DataSource for MemoryDataSource {
+    fn fetch_description(&self) -> DataSourceDescription {
+        DataSourceDescription {
+            source_locator: vec![self.name.clone()],
+        }
+    }
+
+    fn fetch_info(&self) -> DataSourceInfo {
+        let decoded = match &self.error {
+            Some(e) => Err(e.clone()),
+            None => self.read_file("info"),
+        };
+        decoded.unwrap_or_else(|e| {
+            DataSourceInfo::unavailable(format!(
+                "Unable to load profile from {}: {e}. Profiles can be opened from a tar file \
+                 (optionally compressed with zstd) of an archive written by this viewer \
+                 (protocol version {PROTOCOL_VERSION}).",
+                self.name
+            ))
+        })
+    }
+
+    fn fetch_summary_tile(&self, entry_id: &EntryID, tile_id: TileID, _full: bool) -> SummaryTile {
+        self.read_tile("summary_tile", entry_id, tile_id)
+    }
+
+    fn fetch_slot_tile(&self, entry_id: &EntryID, tile_id: TileID, _full: bool) -> SlotTile {
+        self.read_tile("slot_tile", entry_id, tile_id)
+    }
+
+    fn fetch_slot_meta_tile(
+        &self,
+        entry_id: &EntryID,
+        tile_id: TileID,
+        _full: bool,
+    ) -> SlotMetaTile {
+        self.read_tile("slot_meta_tile", entry_id, tile_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::data::{EntryInfo, SlotTileData};
+    use crate::timestamp::{Interval, Timestamp};
+
+    fn tar_file(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut header = vec![0; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        let size = format!("{:011o}", data.len());
+        header[124..135].copy_from_slice(size.as_bytes());
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+
+        let mut result = header;
+        result.extend_from_slice(data);
+        result.resize(result.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        result
+    }
+
+    fn encode(data: &impl serde::Serialize) -> Vec<u8> {
+        let mut f = zstd::Encoder::new(Vec::new(), 1).unwrap();
+        ciborium::into_writer(data, &mut f).unwrap();
+        f.finish().unwrap()
+    }
+
+    #[test]
+    fn test_memory_data_source() {
+        let mut info = DataSourceInfo::unavailable(String::new());
+        info.interval = Interval::new(Timestamp(0), Timestamp(1000));
+        info.warning_message = None;
+
+        let entry_id = EntryID::root().child(0);
+        let tile_id = TileID(info.interval, 0);
+        let tile = SlotTile {
+            entry_id: entry_id.clone(),
+            tile_id,
+            data: SlotTileData { items: Vec::new() },
+        };
+
+        let slug = TileRequestRef {
+            entry_id: &entry_id,
+            tile_id,
+        }
+        .to_slug();
+        let mut tar = tar_file("./prof/info", &encode(&info));
+        tar.extend(tar_file(&format!("prof/slot_tile/{slug}"), &encode(&tile)));
+        tar.extend(vec![0; 2 * BLOCK_SIZE]);
+
+        for bytes in [tar.clone(), zstd::encode_all(&tar[..], 1).unwrap()] {
+            let data_source = MemoryDataSource::new("prof.tar", &bytes);
+            let result = data_source.fetch_info();
+            assert_eq!(result.warning_message, None);
+            assert_eq!(result.interval, info.interval);
+            assert!(matches!(result.entry_info, EntryInfo::Panel { .. }));
+            let result = data_source.fetch_slot_tile(&entry_id, tile_id, false);
+            assert_eq!(result.entry_id, entry_id);
+        }
+
+        let data_source = MemoryDataSource::new("bogus", &tar_file("prof/other", b"x"));
+        assert!(data_source.fetch_info().warning_message.is_some());
+    }
+}
@@ -1,26 +1,39 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
 
 use crate::data::{
-    DataSourceDescription, DataSourceInfo, EntryID, EntryIndex, EntryInfo, Field, ItemLink,
-    ItemUID, SlotMetaTile, SlotTile, SummaryTile, TileID,
+    DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID, EntryIndex, EntryInfo, Field,
+    ItemLink, ItemUID, SearchMatch, SearchQuery, SearchResults, SlotMetaItem, SlotMetaTile,
+    SlotTile, SummaryTile, TileID,
 };
-use crate::deferred_data::DeferredDataSource;
+use crate::deferred_data::{DeferredDataSource, FailedRequest, RequestError, RequestKind, Waker};
 use crate::timestamp::Interval;
 
 pub struct MergeDeferredDataSource {
     data_sources: Vec<Box<dyn DeferredDataSource>>,
     infos: Vec<VecDeque<DataSourceInfo>>,
+    search_results: Vec<VecDeque<SearchResults>>,
     mapping: Vec<u64>,
+    source_locators: Vec<Vec<String>>,
+    // Requests that could not be passed on to any source
+    failures: Vec<FailedRequest>,
 }
 
 impl MergeDeferredDataSource {
     pub fn new(data_sources: Vec<Box<dyn DeferredDataSource>>) -> Self {
         assert!(!data_sources.is_empty());
         let infos = vec![VecDeque::new(); data_sources.len()];
+        let search_results = vec![VecDeque::new(); data_sources.len()];
+        let source_locators = data_sources
+            .iter()
+            .map(|x| x.fetch_description().source_locator)
+            .collect();
         Self {
             data_sources,
             infos,
+            search_results,
             mapping: Vec::new(),
+            source_locators,
+            failures: Vec::new(),
         }
     }
 
@@ -86,16 +99,38 @@ impl MergeDeferredDataSource {
         // Some fields can't be meaningfully merged, so just assert they're
         // all equivalent.
         let first_info = source_infos.first().unwrap();
-        let tile_set = first_info.tile_set.clone();
+        let mut tile_set = first_info.tile_set.clone();
         let field_schema = first_info.field_schema.clone();
-        let warning_message = first_info.warning_message.clone();
 
         for info in &source_infos {
-            assert_eq!(tile_set, info.tile_set);
+            assert_eq!(tile_set.tiles, info.tile_set.tiles);
             assert_eq!(field_schema, info.field_schema);
-            assert_eq!(warning_message, info.warning_message);
         }
 
+        // Sources may come from different builds, which the viewer can only
+        // warn about, so report the oldest version along with the mix
+        let mut warnings: Vec<String> = Vec::new();
+        for info in &source_infos {
+            if let Some(message) = &info.warning_message {
+                if !warnings.contains(message) {
+                    warnings.push(message.clone());
+                }
+            }
+        }
+        let versions: BTreeSet<_> = source_infos
+            .iter()
+            .map(|info| info.protocol_version)
+            .collect();
+        let protocol_version = *versions.first().unwrap();
+        if versions.len() > 1 {
+            let versions: Vec<_> = versions.iter().map(|v| v.to_string()).collect();
+            warnings.push(format!(
+                "The merged data sources use different protocol versions ({}).",
+                versions.join(", ")
+            ));
+        }
+        let warning_message = (!warnings.is_empty()).then(|| warnings.join(" "));
+
         // Merge remaining fields
         // IMPORTANT: entry_info must be kept consistent with compute_mapping
         let interval = source_infos
@@ -103,6 +138,22 @@ impl MergeDeferredDataSource {
             .map(|info| info.interval)
             .reduce(Interval::union)
             .unwrap();
+        // Each source fetches its own tiles, so plan for the largest of them
+        for info in &source_infos[1..] {
+            let level_meta = &info.tile_set.level_meta;
+            if level_meta.len() != tile_set.level_meta.len() {
+                tile_set.level_meta.clear();
+                break;
+            }
+            for (meta, other) in tile_set.level_meta.iter_mut().zip(level_meta) {
+                meta.items_per_tile = meta.items_per_tile.max(other.items_per_tile);
+                meta.bytes_per_tile = meta.bytes_per_tile.max(other.bytes_per_tile);
+            }
+        }
+        // Every source is searched, so all of them must support it
+        let supports_search = source_infos.iter().all(|info| info.supports_search);
+        // Only nothing at all to show is unavailable
+        let unavailable = source_infos.iter().all(|info| info.unavailable);
         let entry_info = source_infos
             .iter()
             .map(|info| info.entry_info.clone())
@@ -115,6 +166,9 @@ impl MergeDeferredDataSource {
             tile_set,
             field_schema,
             warning_message,
+            protocol_version,
+            supports_search,
+            unavailable,
         }
     }
 
@@ -135,6 +189,11 @@ impl MergeDeferredDataSource {
         ItemUID(item_uid.0 * (self.mapping.len() as u64) + (idx as u64))
     }
 
+    fn map_dst_to_src_item_uid(&self, item_uid: ItemUID) -> (usize, ItemUID) {
+        let len = self.mapping.len() as u64;
+        ((item_uid.0 % len) as usize, ItemUID(item_uid.0 / len))
+    }
+
     fn map_src_to_dst_summary(&self, idx: usize, tile: SummaryTile) -> SummaryTile {
         SummaryTile {
             entry_id: self.map_src_to_dst_entry(idx, &tile.entry_id),
@@ -162,16 +221,31 @@ impl MergeDeferredDataSource {
             Field::ItemLink(ItemLink {
                 ref mut item_uid,
                 ref mut entry_id,
+                ref mut profile,
                 ..
             }) => {
-                *item_uid = self.map_src_to_dst_item_uid(idx, *item_uid);
-                *entry_id = self.map_src_to_dst_entry(idx, entry_id);
+                // Links into another merged profile become local links;
+                // links into profiles outside the merge are left alone
+                let target = match profile {
+                    Some(profile) => self.source_locators.iter().position(|x| x == profile),
+                    None => Some(idx),
+                };
+                if let Some(target) = target {
+                    *item_uid = self.map_src_to_dst_item_uid(target, *item_uid);
+                    *entry_id = self.map_src_to_dst_entry(target, entry_id);
+                    *profile = None;
+                }
             }
             Field::Vec(elts) => {
                 for elt in elts {
                     self.map_src_to_dst_field(idx, elt);
                 }
             }
+            Field::Group(_, elts) => {
+                for (_, elt) in elts {
+                    self.map_src_to_dst_field(idx, elt);
+                }
+            }
             _ => (),
         }
     }
@@ -192,6 +266,50 @@ impl MergeDeferredDataSource {
             data: tile.data,
         }
     }
+
+    // Takes the results of one search from each source, in order
+    fn merge_search_results(&self, source_results: Vec<SearchResults>) -> SearchResults {
+        let mut result: Option<SearchResults> = None;
+        for (idx, source_result) in source_results.into_iter().enumerate() {
+            let matches = source_result.matches.into_iter().map(|m| SearchMatch {
+                entry_id: self.map_src_to_dst_entry(idx, &m.entry_id),
+                item_uid: self.map_src_to_dst_item_uid(idx, m.item_uid),
+                ..m
+            });
+            match &mut result {
+                Some(result) => {
+                    result.matches.extend(matches);
+                    result.truncated |= source_result.truncated;
+                    if result.error.is_none() {
+                        result.error = source_result.error;
+                    }
+                }
+                None => {
+                    result = Some(SearchResults {
+                        matches: matches.collect(),
+                        ..source_result
+                    })
+                }
+            }
+        }
+        result.unwrap()
+    }
+
+    fn map_src_to_dst_slot_meta_item(&self, idx: usize, mut item: SlotMetaItem) -> SlotMetaItem {
+        if let Some(ref mut data) = item.data {
+            data.item_uid = self.map_src_to_dst_item_uid(idx, data.item_uid);
+            for (_, field, _) in &mut data.fields {
+                self.map_src_to_dst_field(idx, field);
+            }
+        }
+
+        SlotMetaItem {
+            entry_id: self.map_src_to_dst_entry(idx, &item.entry_id),
+            tile_id: item.tile_id,
+            item_uid: self.map_src_to_dst_item_uid(idx, item.item_uid),
+            data: item.data,
+        }
+    }
 }
 
 impl DeferredDataSource for MergeDeferredDataSource {
@@ -302,13 +420,177 @@ impl DeferredDataSource for MergeDeferredDataSource {
             .map(|(idx, tile)| self.map_src_to_dst_slot_meta(idx, tile))
             .collect()
     }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        let (idx, src_entry) = self.map_dst_to_src_entry(entry_id);
+        let (item_idx, src_item_uid) = self.map_dst_to_src_item_uid(item_uid);
+        if idx != item_idx {
+            self.failures.push(FailedRequest {
+                kind: RequestKind::SlotMetaItem,
+                entry_id: Some(entry_id.clone()),
+                tile_id: Some(tile_id),
+                item_uid: Some(item_uid),
+                error: RequestError::Other(format!(
+                    "item {} does not belong to the profile of entry {entry_id:?}",
+                    item_uid.0
+                )),
+            });
+            return;
+        }
+
+        self.data_sources[idx].fetch_slot_meta_item(&src_entry, tile_id, src_item_uid);
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        let mut items = Vec::new();
+        for (idx, data_source) in self.data_sources.iter_mut().enumerate() {
+            items.extend(
+                data_source
+                    .get_slot_meta_items()
+                    .into_iter()
+                    .map(|item| (idx, item)),
+            );
+        }
+
+        // Hack: doing this in two stages to avoid mutability conflict
+        items
+            .into_iter()
+            .map(|(idx, item)| self.map_src_to_dst_slot_meta_item(idx, item))
+            .collect()
+    }
+
+    fn fetch_updates(&mut self) {
+        for data_source in &mut self.data_sources {
+            data_source.fetch_updates();
+        }
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        for data_source in &mut self.data_sources {
+            data_source.set_waker(waker.clone());
+        }
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        let mut updates = Vec::new();
+        for (idx, data_source) in self.data_sources.iter_mut().enumerate() {
+            updates.extend(
+                data_source
+                    .get_updates()
+                    .into_iter()
+                    .map(|update| (idx, update)),
+            );
+        }
+
+        // Hack: doing this in two stages to avoid mutability conflict
+        updates
+            .into_iter()
+            .map(|(idx, update)| match update {
+                // Entries can't be merged without the info of every source,
+                // so all of them are fetched again
+                DataSourceUpdate::Info => DataSourceUpdate::Info,
+                // The root of one source (and its summary) has no
+                // counterpart in the merged source, so invalidate
+                // everything instead
+                DataSourceUpdate::Tiles { entry_id, interval } => DataSourceUpdate::Tiles {
+                    entry_id: if entry_id.slot_index(0).is_none() {
+                        EntryID::root()
+                    } else {
+                        self.map_src_to_dst_entry(idx, &entry_id)
+                    },
+                    interval,
+                },
+                DataSourceUpdate::Items { tile, meta } => DataSourceUpdate::Items {
+                    tile: self.map_src_to_dst_slot(idx, tile),
+                    meta: self.map_src_to_dst_slot_meta(idx, meta),
+                },
+            })
+            .collect()
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        let mut result = std::mem::take(&mut self.failures);
+        let mut failures = Vec::new();
+        for (idx, data_source) in self.data_sources.iter_mut().enumerate() {
+            failures.extend(
+                data_source
+                    .get_failures()
+                    .into_iter()
+                    .map(|failure| (idx, failure)),
+            );
+        }
+
+        // Hack: doing this in two stages to avoid mutability conflict
+        result.extend(failures.into_iter().map(|(idx, failure)| {
+            FailedRequest {
+                entry_id: failure
+                    .entry_id
+                    .map(|entry_id| self.map_src_to_dst_entry(idx, &entry_id)),
+                item_uid: failure
+                    .item_uid
+                    .map(|item_uid| self.map_src_to_dst_item_uid(idx, item_uid)),
+                ..failure
+            }
+        }));
+        result
+    }
+
+    // Each source is sent the entries that belong to it
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        let mut src_entries = vec![BTreeSet::new(); self.data_sources.len()];
+        for entry_id in entries.into_iter().flatten() {
+            if entry_id.slot_index(0).is_none() {
+                // The root (or its summary) has no counterpart in the
+                // sources, so it covers all of them
+                for src_entries in &mut src_entries {
+                    src_entries.insert(EntryID::root());
+                }
+            } else {
+                let (idx, src_entry) = self.map_dst_to_src_entry(entry_id);
+                src_entries[idx].insert(src_entry);
+            }
+        }
+        for (data_source, src_entries) in self.data_sources.iter_mut().zip(&src_entries) {
+            data_source.set_active_entries(entries.map(|_| src_entries));
+        }
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        for data_source in &mut self.data_sources {
+            data_source.fetch_search(query, interval, limit);
+        }
+    }
+
+    // Each search is answered once every source has answered it
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        for (data_source, results) in self
+            .data_sources
+            .iter_mut()
+            .zip(self.search_results.iter_mut())
+        {
+            results.extend(data_source.get_search_results());
+        }
+
+        let available = self.search_results.iter().map(|x| x.len()).min().unwrap();
+
+        let mut result = Vec::new();
+        for _ in 0..available {
+            let source_results: Vec<_> = self
+                .search_results
+                .iter_mut()
+                .map(|results| results.pop_front().unwrap())
+                .collect();
+            result.push(self.merge_search_results(source_results));
+        }
+        result
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::data::{FieldSchema, TileSet};
+    use crate::data::{FieldSchema, TileLevelMeta, TileSet, PROTOCOL_VERSION};
     use crate::timestamp::Timestamp;
 
     #[test]
@@ -321,6 +603,7 @@ mod tests {
                 short_name: "S1".to_string(),
                 long_name: "Slot 1".to_string(),
                 max_rows: 1,
+                row_names: Vec::new(),
             }],
         };
         let second = EntryInfo::Panel {
@@ -331,6 +614,7 @@ mod tests {
                 short_name: "S2".to_string(),
                 long_name: "Slot 2".to_string(),
                 max_rows: 2,
+                row_names: Vec::new(),
             }],
         };
 
@@ -372,6 +656,16 @@ mod tests {
 
     #[test]
     fn test_merge_info() {
+        let tile_set = |items_per_tile, bytes_per_tile| TileSet {
+            tiles: vec![vec![TileID(
+                Interval::new(Timestamp(0), Timestamp(2000)),
+                0,
+            )]],
+            level_meta: vec![TileLevelMeta {
+                items_per_tile,
+                bytes_per_tile,
+            }],
+        };
         let first = DataSourceInfo {
             entry_info: EntryInfo::Panel {
                 short_name: "F".to_string(),
@@ -382,18 +676,23 @@ mod tests {
                         short_name: "S1".to_string(),
                         long_name: "Slot 1".to_string(),
                         max_rows: 1,
+                        row_names: Vec::new(),
                     },
                     EntryInfo::Slot {
                         short_name: "S2".to_string(),
                         long_name: "Slot 2".to_string(),
                         max_rows: 1,
+                        row_names: Vec::new(),
                     },
                 ],
             },
             interval: Interval::new(Timestamp(0), Timestamp(1000)),
-            tile_set: TileSet { tiles: Vec::new() },
+            tile_set: tile_set(10, 400),
             field_schema: FieldSchema::new(),
             warning_message: None,
+            protocol_version: PROTOCOL_VERSION,
+            supports_search: true,
+            unavailable: false,
         };
         let second = DataSourceInfo {
             entry_info: EntryInfo::Panel {
@@ -404,12 +703,16 @@ mod tests {
                     short_name: "S3".to_string(),
                     long_name: "Slot 3".to_string(),
                     max_rows: 2,
+                    row_names: Vec::new(),
                 }],
             },
             interval: Interval::new(Timestamp(0), Timestamp(2000)),
-            tile_set: TileSet { tiles: Vec::new() },
+            tile_set: tile_set(30, 200),
             field_schema: FieldSchema::new(),
             warning_message: None,
+            protocol_version: PROTOCOL_VERSION,
+            supports_search: false,
+            unavailable: false,
         };
 
         let infos = vec![first, second];
@@ -420,7 +723,10 @@ mod tests {
         let merge = MergeDeferredDataSource::merge_infos(infos);
 
         assert_eq!(merge.interval, Interval::new(Timestamp(0), Timestamp(2000)));
-        assert!(merge.tile_set.tiles.is_empty());
+        assert_eq!(merge.tile_set, tile_set(30, 400));
+        assert!(!merge.supports_search);
+        assert_eq!(merge.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(merge.warning_message, None);
 
         let EntryInfo::Panel {
             short_name,
@@ -464,4 +770,125 @@ mod tests {
         };
         assert_eq!(slot2_short_name, "S3");
     }
+
+    #[test]
+    fn test_merge_protocol_versions() {
+        let mut first = DataSourceInfo::unavailable("First failed.".to_owned());
+        first.protocol_version = 0;
+        let second = DataSourceInfo::unavailable("Second failed.".to_owned());
+        let third = DataSourceInfo::unavailable("First failed.".to_owned());
+
+        let merge = MergeDeferredDataSource::merge_infos(vec![first, second, third]);
+        assert_eq!(merge.protocol_version, 0);
+        assert_eq!(
+            merge.warning_message.as_deref(),
+            Some(&*format!(
+                "First failed. Second failed. The merged data sources use different \
+                 protocol versions (0, {PROTOCOL_VERSION})."
+            ))
+        );
+    }
+
+    #[test]
+    fn test_item_uid_round_trip() {
+        let merge = MergeDeferredDataSource {
+            data_sources: Vec::new(),
+            infos: Vec::new(),
+            search_results: Vec::new(),
+            mapping: vec![0, 2, 5],
+            source_locators: Vec::new(),
+            failures: Vec::new(),
+        };
+
+        for idx in 0..3 {
+            for uid in [0, 1, 17, 1 << 40] {
+                let dst = merge.map_src_to_dst_item_uid(idx, ItemUID(uid));
+                assert_eq!(merge.map_dst_to_src_item_uid(dst), (idx, ItemUID(uid)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_mismatched_item_fails() {
+        let mut merge = MergeDeferredDataSource {
+            data_sources: Vec::new(),
+            infos: Vec::new(),
+            search_results: Vec::new(),
+            mapping: vec![0, 2],
+            source_locators: Vec::new(),
+            failures: Vec::new(),
+        };
+
+        // The entry belongs to the first source, the item to the second
+        let entry_id = EntryID::root().child(0).child(0);
+        let tile_id = TileID(Interval::new(Timestamp(0), Timestamp(10)), 0);
+        let item_uid = merge.map_src_to_dst_item_uid(1, ItemUID(3));
+        merge.fetch_slot_meta_item(&entry_id, tile_id, item_uid);
+
+        let failures = merge.get_failures();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].is_for(
+            RequestKind::SlotMetaItem,
+            &entry_id,
+            tile_id,
+            Some(item_uid)
+        ));
+        assert!(merge.get_failures().is_empty());
+    }
+
+    #[test]
+    fn test_merge_search_results() {
+        let merge = MergeDeferredDataSource {
+            data_sources: Vec::new(),
+            infos: Vec::new(),
+            search_results: Vec::new(),
+            mapping: vec![0, 2],
+            source_locators: Vec::new(),
+            failures: Vec::new(),
+        };
+
+        let query = SearchQuery {
+            text: "task".to_owned(),
+            field: None,
+            whole_word: false,
+        };
+        let interval = Interval::new(Timestamp(0), Timestamp(1000));
+        let result = |entry_id: EntryID, truncated| SearchResults {
+            query: query.clone(),
+            interval,
+            matches: vec![SearchMatch {
+                entry_id,
+                tile_id: TileID(interval, 0),
+                item_uid: ItemUID(7),
+                row: 0,
+                title: "task".to_owned(),
+                original_interval: interval,
+            }],
+            truncated,
+            error: None,
+        };
+        let slot = EntryID::root().child(0).child(1);
+        let mut second = result(slot.clone(), true);
+        second.error = Some("failed".to_owned());
+
+        let merged = merge.merge_search_results(vec![result(slot.clone(), false), second]);
+        assert_eq!(merged.query, query);
+        assert!(merged.truncated);
+        assert_eq!(merged.error.as_deref(), Some("failed"));
+        let matches: Vec<_> = merged
+            .matches
+            .iter()
+            .map(|m| (m.entry_id.clone(), m.item_uid))
+            .collect();
+        assert_eq!(
+            matches,
+            vec![
+                (slot.clone(), merge.map_src_to_dst_item_uid(0, ItemUID(7))),
+                (
+                    slot.shift_level0(2),
+                    merge.map_src_to_dst_item_uid(1, ItemUID(7))
+                ),
+            ]
+        );
+    }
 }
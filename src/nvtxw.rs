@@ -222,7 +222,7 @@ impl<T: DeferredDataSource> NVTXW<T> {
 
         let entry_ids = walk_entry_list(&info.entry_info);
 
-        let full_range_tile_id = TileID(info.interval);
+        let full_range_tile_id = TileID(info.interval, 0);
         let full = true;
 
         // For now, this only works on dynamic data sources
@@ -1,10 +1,12 @@
+use std::collections::BTreeSet;
 use std::sync::{Arc, Mutex};
 
 use crate::data::{
-    DataSource, DataSourceDescription, DataSourceInfo, EntryID, SlotMetaTile, SlotTile,
-    SummaryTile, TileID,
+    ClientID, DataSource, DataSourceDescription, DataSourceInfo, DataSourceUpdate, EntryID,
+    ItemUID, SearchQuery, SearchResults, SlotMetaItem, SlotMetaTile, SlotTile, SummaryTile, TileID,
 };
 use crate::deferred_data::DeferredDataSource;
+use crate::timestamp::Interval;
 
 pub struct ParallelDeferredDataSource<T: DataSource + Send + Sync + 'static> {
     data_source: Arc<T>,
@@ -12,6 +14,10 @@ pub struct ParallelDeferredDataSource<T: DataSource + Send + Sync + 'static> {
     summary_tiles: Arc<Mutex<Vec<SummaryTile>>>,
     slot_tiles: Arc<Mutex<Vec<SlotTile>>>,
     slot_meta_tiles: Arc<Mutex<Vec<SlotMetaTile>>>,
+    slot_meta_items: Arc<Mutex<Vec<SlotMetaItem>>>,
+    update_version: u64,
+    updates: Vec<DataSourceUpdate>,
+    search_results: Arc<Mutex<Vec<SearchResults>>>,
 }
 
 impl<T: DataSource + Send + Sync + 'static> ParallelDeferredDataSource<T> {
@@ -22,6 +28,10 @@ impl<T: DataSource + Send + Sync + 'static> ParallelDeferredDataSource<T> {
             summary_tiles: Arc::new(Mutex::new(Vec::new())),
             slot_tiles: Arc::new(Mutex::new(Vec::new())),
             slot_meta_tiles: Arc::new(Mutex::new(Vec::new())),
+            slot_meta_items: Arc::new(Mutex::new(Vec::new())),
+            update_version: 0,
+            updates: Vec::new(),
+            search_results: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
@@ -85,4 +95,49 @@ impl<T: DataSource + Send + Sync + 'static> DeferredDataSource for ParallelDefer
     fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
         std::mem::take(&mut self.slot_meta_tiles.lock().unwrap())
     }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        let entry_id = entry_id.clone();
+        let data_source = self.data_source.clone();
+        let slot_meta_items = self.slot_meta_items.clone();
+        rayon::spawn(move || {
+            let result = data_source.fetch_slot_meta_item(&entry_id, tile_id, item_uid);
+            slot_meta_items.lock().unwrap().push(result);
+        });
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        std::mem::take(&mut self.slot_meta_items.lock().unwrap())
+    }
+
+    // Checking for updates is meant to be cheap, so it's done in place
+    fn fetch_updates(&mut self) {
+        let result = self.data_source.fetch_updates(self.update_version);
+        self.update_version = result.version;
+        self.updates.extend(result.updates);
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        std::mem::take(&mut self.updates)
+    }
+
+    // See DeferredDataSourceWrapper::set_active_entries
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.data_source
+            .set_active_entries(ClientID::default(), entries)
+    }
+
+    fn fetch_search(&mut self, query: &SearchQuery, interval: Interval, limit: usize) {
+        let query = query.clone();
+        let data_source = self.data_source.clone();
+        let search_results = self.search_results.clone();
+        rayon::spawn(move || {
+            let result = data_source.fetch_search(&query, interval, limit);
+            search_results.lock().unwrap().push(result);
+        });
+    }
+
+    fn get_search_results(&mut self) -> Vec<SearchResults> {
+        std::mem::take(&mut self.search_results.lock().unwrap())
+    }
 }
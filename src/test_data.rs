@@ -0,0 +1,369 @@
+// Data sources shared by the tests of the data source wrappers
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use crate::data::{
+    ClientID, Color32, DataSource, DataSourceDescription, DataSourceInfo, DataSourceUpdate,
+    EntryID, EntryInfo, FieldSchema, Item, ItemMeta, ItemUID, SearchMatch, SearchQuery,
+    SearchResults, SlotMetaItem, SlotMetaTile, SlotMetaTileData, SlotTile, SlotTileData,
+    SummaryTile, SummaryTileData, TileID, TileSet, PROTOCOL_VERSION,
+};
+use crate::deferred_data::{DeferredDataSource, FailedRequest, RequestError, RequestKind};
+use crate::timestamp::{Interval, Timestamp};
+
+pub fn slot(name: &str) -> EntryInfo {
+    EntryInfo::Slot {
+        short_name: name.to_string(),
+        long_name: format!("Long {name}"),
+        max_rows: 1,
+        row_names: Vec::new(),
+    }
+}
+
+pub fn panel(name: &str, slots: Vec<EntryInfo>) -> EntryInfo {
+    EntryInfo::Panel {
+        short_name: name.to_string(),
+        long_name: format!("Long {name}"),
+        summary: None,
+        slots,
+    }
+}
+
+// A hint passed to set_active_entries
+pub type ActiveEntries = (ClientID, Option<BTreeSet<EntryID>>);
+
+// One slot with one item per tile, covering the tile. A broken source leaves
+// out the meta rows. A searching source finds the item of the tile covering
+// the interval searched, if its title matches
+#[derive(Default)]
+pub struct TestDataSource {
+    pub broken: bool,
+    pub search: bool,
+    // Every hint received, in order
+    pub active_entries: Arc<Mutex<Vec<ActiveEntries>>>,
+}
+
+impl DataSource for TestDataSource {
+    fn fetch_description(&self) -> DataSourceDescription {
+        DataSourceDescription {
+            source_locator: Vec::new(),
+        }
+    }
+
+    fn fetch_info(&self) -> DataSourceInfo {
+        DataSourceInfo {
+            entry_info: EntryInfo::Panel {
+                short_name: "root".to_owned(),
+                long_name: "root".to_owned(),
+                summary: None,
+                slots: vec![EntryInfo::Slot {
+                    short_name: "S".to_owned(),
+                    long_name: "Slot".to_owned(),
+                    max_rows: 1,
+                    row_names: Vec::new(),
+                }],
+            },
+            interval: Interval::new(Timestamp(0), Timestamp(1000)),
+            tile_set: TileSet::default(),
+            field_schema: FieldSchema::new(),
+            warning_message: None,
+            protocol_version: PROTOCOL_VERSION,
+            supports_search: self.search,
+            unavailable: false,
+        }
+    }
+
+    fn fetch_summary_tile(&self, entry_id: &EntryID, tile_id: TileID, _: bool) -> SummaryTile {
+        SummaryTile {
+            entry_id: entry_id.clone(),
+            tile_id,
+            data: SummaryTileData {
+                utilization: Vec::new(),
+            },
+        }
+    }
+
+    fn fetch_slot_tile(&self, entry_id: &EntryID, tile_id: TileID, _: bool) -> SlotTile {
+        SlotTile {
+            entry_id: entry_id.clone(),
+            tile_id,
+            data: SlotTileData {
+                items: vec![vec![Item {
+                    item_uid: ItemUID(tile_id.0.start.0 as u64),
+                    interval: tile_id.0,
+                    color: Color32::RED,
+                }]],
+            },
+        }
+    }
+
+    fn fetch_slot_meta_tile(&self, entry_id: &EntryID, tile_id: TileID, _: bool) -> SlotMetaTile {
+        let items = if self.broken {
+            Vec::new()
+        } else {
+            vec![vec![ItemMeta {
+                item_uid: ItemUID(tile_id.0.start.0 as u64),
+                original_interval: tile_id.0,
+                title: "Item".to_owned(),
+                fields: Vec::new(),
+            }]]
+        };
+        SlotMetaTile {
+            entry_id: entry_id.clone(),
+            tile_id,
+            data: SlotMetaTileData { items },
+        }
+    }
+
+    fn set_active_entries(&self, client: ClientID, entries: Option<&BTreeSet<EntryID>>) {
+        let mut active_entries = self.active_entries.lock().unwrap();
+        active_entries.push((client, entries.cloned()));
+    }
+
+    fn fetch_search(&self, query: &SearchQuery, interval: Interval, _: usize) -> SearchResults {
+        if !self.search {
+            return SearchResults::failed(
+                query.clone(),
+                interval,
+                "search is not supported".to_owned(),
+            );
+        }
+        let matches = if "Item".contains(&query.text) {
+            vec![SearchMatch {
+                entry_id: EntryID::root().child(0),
+                tile_id: TileID(interval, 0),
+                item_uid: ItemUID(interval.start.0 as u64),
+                row: 0,
+                title: "Item".to_owned(),
+                original_interval: interval,
+            }]
+        } else {
+            Vec::new()
+        };
+        SearchResults {
+            query: query.clone(),
+            interval,
+            matches,
+            truncated: false,
+            error: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Request {
+    Info,
+    SummaryTile(EntryID, TileID, bool),
+    SlotTile(EntryID, TileID, bool),
+    SlotMetaTile(EntryID, TileID, bool),
+    SlotMetaItem(EntryID, TileID, ItemUID),
+}
+
+// Answers requests from a TestDataSource, either right away or (if held)
+// only when released, and reports the updates pushed into it
+#[derive(Default)]
+pub struct TestDeferredDataSource {
+    pub data_source: TestDataSource,
+    // Returned instead of the data source's info, if set
+    pub info: Option<DataSourceInfo>,
+    pub hold: bool,
+    // Answer every request twice
+    pub duplicate: bool,
+    // Fail every request but the info's instead of answering it
+    pub fail_tiles: bool,
+    // Drop every request but the info's, never answering it
+    pub drop_tiles: bool,
+    pub updates: Vec<DataSourceUpdate>,
+    // Every hint received, in order
+    pub active_entries: Vec<Option<BTreeSet<EntryID>>>,
+    pending: Vec<Request>,
+    infos: Vec<DataSourceInfo>,
+    summary_tiles: Vec<SummaryTile>,
+    slot_tiles: Vec<SlotTile>,
+    slot_meta_tiles: Vec<SlotMetaTile>,
+    slot_meta_items: Vec<SlotMetaItem>,
+    failures: Vec<FailedRequest>,
+}
+
+impl TestDeferredDataSource {
+    pub fn held() -> Self {
+        Self {
+            hold: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_info(self, info: DataSourceInfo) -> Self {
+        Self {
+            info: Some(info),
+            ..self
+        }
+    }
+
+    // Number of requests waiting to be released
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn release(&mut self) {
+        for request in std::mem::take(&mut self.pending) {
+            self.respond(request);
+        }
+    }
+
+    // Answer only the oldest request
+    pub fn release_one(&mut self) {
+        if !self.pending.is_empty() {
+            let request = self.pending.remove(0);
+            self.respond(request);
+        }
+    }
+
+    fn respond(&mut self, request: Request) {
+        if self.duplicate {
+            self.answer(request.clone());
+        }
+        self.answer(request);
+    }
+
+    fn answer(&mut self, request: Request) {
+        let source = &self.data_source;
+        match request {
+            Request::Info => self
+                .infos
+                .push(self.info.clone().unwrap_or_else(|| source.fetch_info())),
+            Request::SummaryTile(entry_id, tile_id, full) => self
+                .summary_tiles
+                .push(source.fetch_summary_tile(&entry_id, tile_id, full)),
+            Request::SlotTile(entry_id, tile_id, full) => self
+                .slot_tiles
+                .push(source.fetch_slot_tile(&entry_id, tile_id, full)),
+            Request::SlotMetaTile(entry_id, tile_id, full) => self
+                .slot_meta_tiles
+                .push(source.fetch_slot_meta_tile(&entry_id, tile_id, full)),
+            Request::SlotMetaItem(entry_id, tile_id, item_uid) => {
+                let tile = source.fetch_slot_meta_tile(&entry_id, tile_id, true);
+                let data = tile
+                    .data
+                    .items
+                    .into_iter()
+                    .flatten()
+                    .find(|item| item.item_uid == item_uid);
+                self.slot_meta_items.push(SlotMetaItem {
+                    entry_id,
+                    tile_id,
+                    item_uid,
+                    data,
+                });
+            }
+        }
+    }
+
+    pub fn fail(&mut self, error: RequestError) {
+        for request in self.pending.drain(..) {
+            let (kind, entry_id, tile_id, item_uid) = match request {
+                Request::Info => (RequestKind::Info, None, None, None),
+                Request::SummaryTile(entry_id, tile_id, _) => (
+                    RequestKind::SummaryTile,
+                    Some(entry_id),
+                    Some(tile_id),
+                    None,
+                ),
+                Request::SlotTile(entry_id, tile_id, _) => {
+                    (RequestKind::SlotTile, Some(entry_id), Some(tile_id), None)
+                }
+                Request::SlotMetaTile(entry_id, tile_id, _) => (
+                    RequestKind::SlotMetaTile,
+                    Some(entry_id),
+                    Some(tile_id),
+                    None,
+                ),
+                Request::SlotMetaItem(entry_id, tile_id, item_uid) => (
+                    RequestKind::SlotMetaItem,
+                    Some(entry_id),
+                    Some(tile_id),
+                    Some(item_uid),
+                ),
+            };
+            self.failures.push(FailedRequest {
+                kind,
+                entry_id,
+                tile_id,
+                item_uid,
+                error: error.clone(),
+            });
+        }
+    }
+
+    fn request(&mut self, request: Request) {
+        if self.drop_tiles && !matches!(request, Request::Info) {
+            return;
+        }
+        let fail = self.fail_tiles && !matches!(request, Request::Info);
+        self.pending.push(request);
+        if fail {
+            self.fail(RequestError::Other("tile unavailable".to_owned()));
+        } else if !self.hold {
+            self.release();
+        }
+    }
+}
+
+impl DeferredDataSource for TestDeferredDataSource {
+    fn fetch_description(&self) -> DataSourceDescription {
+        self.data_source.fetch_description()
+    }
+
+    fn fetch_info(&mut self) {
+        self.request(Request::Info);
+    }
+
+    fn get_infos(&mut self) -> Vec<DataSourceInfo> {
+        std::mem::take(&mut self.infos)
+    }
+
+    fn fetch_summary_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.request(Request::SummaryTile(entry_id.clone(), tile_id, full));
+    }
+
+    fn get_summary_tiles(&mut self) -> Vec<SummaryTile> {
+        std::mem::take(&mut self.summary_tiles)
+    }
+
+    fn fetch_slot_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.request(Request::SlotTile(entry_id.clone(), tile_id, full));
+    }
+
+    fn get_slot_tiles(&mut self) -> Vec<SlotTile> {
+        std::mem::take(&mut self.slot_tiles)
+    }
+
+    fn fetch_slot_meta_tile(&mut self, entry_id: &EntryID, tile_id: TileID, full: bool) {
+        self.request(Request::SlotMetaTile(entry_id.clone(), tile_id, full));
+    }
+
+    fn get_slot_meta_tiles(&mut self) -> Vec<SlotMetaTile> {
+        std::mem::take(&mut self.slot_meta_tiles)
+    }
+
+    fn fetch_slot_meta_item(&mut self, entry_id: &EntryID, tile_id: TileID, item_uid: ItemUID) {
+        self.request(Request::SlotMetaItem(entry_id.clone(), tile_id, item_uid));
+    }
+
+    fn get_slot_meta_items(&mut self) -> Vec<SlotMetaItem> {
+        std::mem::take(&mut self.slot_meta_items)
+    }
+
+    fn get_updates(&mut self) -> Vec<DataSourceUpdate> {
+        std::mem::take(&mut self.updates)
+    }
+
+    fn get_failures(&mut self) -> Vec<FailedRequest> {
+        std::mem::take(&mut self.failures)
+    }
+
+    fn set_active_entries(&mut self, entries: Option<&BTreeSet<EntryID>>) {
+        self.active_entries.push(entries.cloned());
+    }
+}
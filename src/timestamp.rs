@@ -10,11 +10,98 @@ pub enum TimestampParseError {
     InvalidValue,
     NoUnit,
     InvalidUnit,
+    InvalidClock,
+    ClockOutOfRange,
+    EmptyOffset,
+    UnknownKeyword,
 }
 
 impl Timestamp {
+    // Parses an absolute timestamp, either as a value with a unit (e.g.,
+    // 1.5 ms) or as a clock time ([HH:]MM:SS[.frac])
     pub fn parse(s: &str) -> Result<Timestamp, TimestampParseError> {
         let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, s),
+        };
+        let ns = if s.contains(':') {
+            Self::parse_clock(s)?
+        } else {
+            Self::parse_value_unit(s)?
+        };
+        Ok(Timestamp(if negative { -ns } else { ns }))
+    }
+
+    // Parses a timestamp as typed into the interval boxes. In addition to
+    // the forms accepted by parse, this accepts +<duration> (relative to
+    // origin) and the keywords start and end (of bounds)
+    pub fn parse_expr(
+        s: &str,
+        origin: Timestamp,
+        bounds: Interval,
+    ) -> Result<Timestamp, TimestampParseError> {
+        let s = s.trim();
+        if let Some(offset) = s.strip_prefix('+') {
+            if offset.trim().is_empty() {
+                return Err(TimestampParseError::EmptyOffset);
+            }
+            let offset = Self::parse(offset)?;
+            return Ok(Timestamp(origin.0.saturating_add(offset.0)));
+        }
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphabetic()) {
+            return match s.to_lowercase().as_str() {
+                "start" => Ok(bounds.start),
+                "end" => Ok(bounds.stop),
+                // A bare unit is a missing value, not a keyword
+                "ns" | "us" | "ms" | "s" => Err(TimestampParseError::InvalidValue),
+                _ => Err(TimestampParseError::UnknownKeyword),
+            };
+        }
+        Self::parse(s)
+    }
+
+    fn parse_clock(s: &str) -> Result<i64, TimestampParseError> {
+        let fields: Vec<_> = s.split(':').map(str::trim).collect();
+        if fields.len() > 3 {
+            return Err(TimestampParseError::InvalidClock);
+        }
+        let (seconds_s, leading) = fields.split_last().unwrap();
+        if !seconds_s.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return Err(TimestampParseError::InvalidClock);
+        }
+        let seconds = seconds_s
+            .parse::<f64>()
+            .map_err(|_| TimestampParseError::InvalidClock)?;
+        if seconds >= 60.0 {
+            return Err(TimestampParseError::ClockOutOfRange);
+        }
+
+        let mut total: i64 = 0;
+        for (i, field) in leading.iter().enumerate() {
+            if field.is_empty() || !field.chars().all(|c| c.is_ascii_digit()) {
+                return Err(TimestampParseError::InvalidClock);
+            }
+            let value = field
+                .parse::<i64>()
+                .map_err(|_| TimestampParseError::ClockOutOfRange)?;
+            // Only the leading field may exceed its natural range
+            if i > 0 && value >= 60 {
+                return Err(TimestampParseError::ClockOutOfRange);
+            }
+            total = total
+                .checked_mul(60)
+                .and_then(|t| t.checked_add(value))
+                .ok_or(TimestampParseError::ClockOutOfRange)?;
+        }
+        // The leading fields are now in minutes
+        total
+            .checked_mul(60_000_000_000)
+            .and_then(|t| t.checked_add((seconds * 1e9) as i64))
+            .ok_or(TimestampParseError::ClockOutOfRange)
+    }
+
+    fn parse_value_unit(s: &str) -> Result<i64, TimestampParseError> {
         let split_idx = s
             .find(|c| !(char::is_ascii_digit(&c) || c == '.'))
             .ok_or(TimestampParseError::NoUnit)?;
@@ -33,7 +120,7 @@ impl Timestamp {
             _ => return Err(TimestampParseError::InvalidUnit),
         };
 
-        Ok(Timestamp((value * factor as f64) as i64))
+        Ok((value * factor as f64) as i64)
     }
 }
 
@@ -60,19 +147,35 @@ pub struct Interval {
 
 impl fmt::Display for Interval {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let units: TimestampUnits = (*self).into();
-        let duration = Timestamp(self.duration_ns());
-        let duration_units: TimestampUnits = duration.into();
+        write!(f, "{}", DisplayUnits::Auto.interval(*self))
+    }
+}
+
+// Interval formatted with the user's choice of units
+#[derive(Debug, Copy, Clone)]
+pub struct IntervalDisplay {
+    pub interval: Interval,
+    pub display_units: DisplayUnits,
+}
+
+impl fmt::Display for IntervalDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let interval = self.interval;
+        let units = self.display_units.resolve(interval.into());
+        let duration = Timestamp(interval.duration_ns());
+        let duration_units = self.display_units.resolve(duration.into());
+        let start = interval.start;
+        let stop = interval.stop;
         write!(
             f,
             "from {} to {} (duration: {})",
             TimestampDisplay {
-                timestamp: self.start,
+                timestamp: start,
                 units,
                 include_units: false
             },
             TimestampDisplay {
-                timestamp: self.stop,
+                timestamp: stop,
                 units,
                 include_units: true
             },
@@ -89,11 +192,26 @@ impl Interval {
     pub fn new(start: Timestamp, stop: Timestamp) -> Self {
         Self { start, stop }
     }
+    // Parses an interval of the form <start>:<stop>, e.g., 1.2s:1.5s. Clock
+    // times contain colons too, so try each split until both halves parse
+    pub fn parse(s: &str) -> Result<Self, String> {
+        for (i, _) in s.match_indices(':') {
+            let (Ok(start), Ok(stop)) = (Timestamp::parse(&s[..i]), Timestamp::parse(&s[i + 1..]))
+            else {
+                continue;
+            };
+            if start >= stop {
+                return Err("start must be before stop".to_owned());
+            }
+            return Ok(Self::new(start, stop));
+        }
+        Err(format!("invalid interval: {s} (expected <start>:<stop>)"))
+    }
     pub fn center(self) -> Timestamp {
         Timestamp(self.start.0 + self.duration_ns() / 2)
     }
     pub fn duration_ns(self) -> i64 {
-        self.stop.0 - self.start.0
+        self.stop.0.saturating_sub(self.start.0)
     }
     pub fn contains(self, point: Timestamp) -> bool {
         point >= self.start && point < self.stop
@@ -135,6 +253,53 @@ impl Interval {
             stop: Timestamp(self.stop.0 + duration_ns),
         }
     }
+    // Like lerp, but saturates instead of overflowing.
+    pub fn saturating_lerp(self, value: f32) -> Timestamp {
+        let offset = (value as f64 * self.duration_ns() as f64).round() as i64;
+        Timestamp(self.start.0.saturating_add(offset))
+    }
+    // Like unlerp, but returns None for empty or inverted intervals.
+    pub fn checked_unlerp(self, time: Timestamp) -> Option<f32> {
+        if self.stop <= self.start {
+            return None;
+        }
+        Some(((time.0 as f64 - self.start.0 as f64) / self.duration_ns() as f64) as f32)
+    }
+    // Like grow, but returns None on overflow or if the result would be
+    // inverted.
+    pub fn checked_grow(self, duration_ns: i64) -> Option<Self> {
+        let start = self.start.0.checked_sub(duration_ns)?;
+        let stop = self.stop.0.checked_add(duration_ns)?;
+        (start <= stop).then_some(Self::new(Timestamp(start), Timestamp(stop)))
+    }
+    // Like grow, but saturates on overflow. Shrinking by more than half the
+    // duration collapses to an empty interval at the center.
+    pub fn saturating_grow(self, duration_ns: i64) -> Self {
+        let start = self.start.0.saturating_sub(duration_ns);
+        let stop = self.stop.0.saturating_add(duration_ns);
+        if start > stop {
+            let center = self.center();
+            return Self::new(center, center);
+        }
+        Self::new(Timestamp(start), Timestamp(stop))
+    }
+    // Like translate, but returns None on overflow.
+    pub fn checked_translate(self, duration_ns: i64) -> Option<Self> {
+        Some(Self::new(
+            Timestamp(self.start.0.checked_add(duration_ns)?),
+            Timestamp(self.stop.0.checked_add(duration_ns)?),
+        ))
+    }
+    // Like translate, but limits the shift so that neither end overflows.
+    // The duration is preserved.
+    pub fn saturating_translate(self, duration_ns: i64) -> Self {
+        let duration_ns = if duration_ns >= 0 {
+            duration_ns.min(i64::MAX.saturating_sub(self.stop.0.max(self.start.0)))
+        } else {
+            duration_ns.max(i64::MIN.saturating_sub(self.start.0.min(self.stop.0)))
+        };
+        self.translate(duration_ns)
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -147,7 +312,7 @@ pub struct TimestampUnits {
 impl From<Timestamp> for TimestampUnits {
     fn from(timestamp: Timestamp) -> TimestampUnits {
         // Time is stored in nanoseconds. But display in larger units if possible.
-        let ns = timestamp.0;
+        let ns = timestamp.0.saturating_abs();
         const NS_PER_NS: i64 = 1;
         const NS_PER_US: i64 = 1_000;
         const NS_PER_MS: i64 = 1_000_000;
@@ -183,7 +348,11 @@ impl From<Timestamp> for TimestampUnits {
 impl From<Interval> for TimestampUnits {
     fn from(interval: Interval) -> TimestampUnits {
         // Time is stored in nanoseconds. But display in larger units if possible.
-        let ns = interval.stop.0;
+        let ns = interval
+            .start
+            .0
+            .saturating_abs()
+            .max(interval.stop.0.saturating_abs());
         let duration = interval.duration_ns();
         const NS_PER_NS: i64 = 1;
         const NS_PER_US: i64 = 1_000;
@@ -227,6 +396,74 @@ impl From<Interval> for TimestampUnits {
     }
 }
 
+// Units to display timestamps in, as chosen by the user
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum DisplayUnits {
+    // Pick units based on the magnitude of the value
+    #[default]
+    Auto,
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+    // Seconds with the given number of digits after the separator (must be
+    // 0, 3, 6 or 9, other values are rounded down into that range)
+    SecondsFixed(i64),
+}
+
+// Digits are printed in groups of three, and there are none past nanoseconds
+fn clamp_fixed_digits(digits: i64) -> i64 {
+    digits.clamp(0, 9) / 3 * 3
+}
+
+impl DisplayUnits {
+    pub fn resolve(self, auto: TimestampUnits) -> TimestampUnits {
+        let (divisor, digits_after_separator, unit_name) = match self {
+            DisplayUnits::Auto => return auto,
+            DisplayUnits::Nanoseconds => (1, 0, "ns"),
+            DisplayUnits::Microseconds => (1_000, 3, "us"),
+            DisplayUnits::Milliseconds => (1_000_000, 3, "ms"),
+            DisplayUnits::Seconds => (1_000_000_000, 3, "s"),
+            DisplayUnits::SecondsFixed(digits) => (1_000_000_000, clamp_fixed_digits(digits), "s"),
+        };
+        TimestampUnits {
+            divisor,
+            digits_after_separator,
+            unit_name,
+        }
+    }
+
+    pub fn timestamp(self, timestamp: Timestamp) -> TimestampDisplay {
+        TimestampDisplay {
+            timestamp,
+            units: self.resolve(timestamp.into()),
+            include_units: true,
+        }
+    }
+
+    pub fn interval(self, interval: Interval) -> IntervalDisplay {
+        IntervalDisplay {
+            interval,
+            display_units: self,
+        }
+    }
+}
+
+impl fmt::Display for DisplayUnits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayUnits::Auto => write!(f, "Auto"),
+            DisplayUnits::Nanoseconds => write!(f, "ns"),
+            DisplayUnits::Microseconds => write!(f, "us"),
+            DisplayUnits::Milliseconds => write!(f, "ms"),
+            DisplayUnits::Seconds => write!(f, "s"),
+            DisplayUnits::SecondsFixed(digits) => {
+                write!(f, "s ({} decimals)", clamp_fixed_digits(*digits))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct TimestampDisplay {
     pub timestamp: Timestamp,
@@ -242,6 +479,11 @@ impl fmt::Display for TimestampDisplay {
             digits_after_separator,
         } = self.units;
         let ns = self.timestamp.0;
+        if ns < 0 {
+            write!(f, "-")?;
+        }
+        let ns = ns.unsigned_abs();
+        let divisor = divisor as u64;
         let units = ns / divisor;
         write!(f, "{units}")?;
         if digits_after_separator > 0 {
@@ -294,6 +536,20 @@ mod tests {
             assert_eq!(Timestamp::parse("567.0 ns"), Ok(Timestamp(567)));
         }
 
+        #[test]
+        fn test_negative() {
+            assert_eq!(Timestamp::parse("-234.5 ms"), Ok(Timestamp(-234_500_000)));
+            assert_eq!(Timestamp::parse("- 567 ns"), Ok(Timestamp(-567)));
+        }
+
+        #[test]
+        fn test_negative_no_value() {
+            assert_eq!(
+                Timestamp::parse("-ms"),
+                Err(TimestampParseError::InvalidValue)
+            );
+        }
+
         #[test]
         fn test_s_upper() {
             assert_eq!(Timestamp::parse("123.4 S"), Ok(Timestamp(123_400_000_000)));
@@ -412,6 +668,89 @@ mod tests {
                 Err(TimestampParseError::InvalidUnit)
             );
         }
+
+        #[test]
+        fn test_clock() {
+            assert_eq!(
+                Timestamp::parse("00:01:02.5"),
+                Ok(Timestamp(62_500_000_000))
+            );
+            assert_eq!(Timestamp::parse("1:02"), Ok(Timestamp(62_000_000_000)));
+            assert_eq!(
+                Timestamp::parse("2:00:00"),
+                Ok(Timestamp(7_200_000_000_000))
+            );
+            assert_eq!(Timestamp::parse("90:00"), Ok(Timestamp(5_400_000_000_000)));
+            assert_eq!(Timestamp::parse("-00:00:01"), Ok(Timestamp(-1_000_000_000)));
+        }
+
+        #[test]
+        fn test_clock_invalid() {
+            assert_eq!(
+                Timestamp::parse("1:2:3:4"),
+                Err(TimestampParseError::InvalidClock)
+            );
+            assert_eq!(
+                Timestamp::parse(":30"),
+                Err(TimestampParseError::InvalidClock)
+            );
+            assert_eq!(
+                Timestamp::parse("1:30 ms"),
+                Err(TimestampParseError::InvalidClock)
+            );
+            assert_eq!(
+                Timestamp::parse("1.5:30"),
+                Err(TimestampParseError::InvalidClock)
+            );
+        }
+
+        #[test]
+        fn test_clock_out_of_range() {
+            assert_eq!(
+                Timestamp::parse("1:60"),
+                Err(TimestampParseError::ClockOutOfRange)
+            );
+            assert_eq!(
+                Timestamp::parse("1:60:00"),
+                Err(TimestampParseError::ClockOutOfRange)
+            );
+        }
+    }
+
+    mod timestamp_parse_expr {
+        use super::*;
+
+        const ORIGIN: Timestamp = Timestamp(1_000);
+        const BOUNDS: Interval = Interval {
+            start: Timestamp(-5),
+            stop: Timestamp(9_000),
+        };
+
+        fn parse(s: &str) -> Result<Timestamp, TimestampParseError> {
+            Timestamp::parse_expr(s, ORIGIN, BOUNDS)
+        }
+
+        #[test]
+        fn test_absolute() {
+            assert_eq!(parse("1.5 us"), Ok(Timestamp(1_500)));
+            assert_eq!(parse("00:00:01"), Ok(Timestamp(1_000_000_000)));
+        }
+
+        #[test]
+        fn test_offset() {
+            assert_eq!(parse("+500ns"), Ok(Timestamp(1_500)));
+            assert_eq!(parse(" + 2 us "), Ok(Timestamp(3_000)));
+            assert_eq!(parse("+"), Err(TimestampParseError::EmptyOffset));
+            assert_eq!(parse("+500"), Err(TimestampParseError::NoUnit));
+        }
+
+        #[test]
+        fn test_keywords() {
+            assert_eq!(parse("start"), Ok(BOUNDS.start));
+            assert_eq!(parse(" END "), Ok(BOUNDS.stop));
+            assert_eq!(parse("middle"), Err(TimestampParseError::UnknownKeyword));
+            assert_eq!(parse("ms"), Err(TimestampParseError::InvalidValue));
+        }
     }
 
     mod interval {
@@ -505,6 +844,139 @@ mod tests {
             let expect = Interval::new(Timestamp(750), Timestamp(1750));
             assert_eq!(origin.translate(-250), expect);
         }
+
+        #[test]
+        fn test_duration_ns_saturates() {
+            let i0 = Interval::new(Timestamp(i64::MIN), Timestamp(i64::MAX));
+            assert_eq!(i0.duration_ns(), i64::MAX);
+        }
+
+        #[test]
+        fn test_saturating_lerp() {
+            let i0 = Interval::new(Timestamp(0), Timestamp(10));
+            assert_eq!(i0.saturating_lerp(0.5), Timestamp(5));
+            assert_eq!(i0.saturating_lerp(2.0), Timestamp(20));
+
+            let i1 = Interval::new(Timestamp(i64::MAX - 10), Timestamp(i64::MAX));
+            assert_eq!(i1.saturating_lerp(1e9), Timestamp(i64::MAX));
+
+            let i2 = Interval::new(Timestamp(i64::MIN), Timestamp(i64::MIN + 10));
+            assert_eq!(i2.saturating_lerp(-1e9), Timestamp(i64::MIN));
+        }
+
+        #[test]
+        fn test_checked_unlerp() {
+            let i0 = Interval::new(Timestamp(0), Timestamp(10));
+            assert_eq!(i0.checked_unlerp(Timestamp(5)), Some(0.5));
+            assert_eq!(i0.checked_unlerp(Timestamp(20)), Some(2.0));
+
+            let i1 = Interval::new(Timestamp(5), Timestamp(5));
+            assert_eq!(i1.checked_unlerp(Timestamp(5)), None);
+
+            let i2 = Interval::new(Timestamp(5), Timestamp(0));
+            assert_eq!(i2.checked_unlerp(Timestamp(5)), None);
+        }
+
+        #[test]
+        fn test_checked_grow() {
+            let i0 = Interval::new(Timestamp(5), Timestamp(10));
+            assert_eq!(
+                i0.checked_grow(2),
+                Some(Interval::new(Timestamp(3), Timestamp(12)))
+            );
+            assert_eq!(
+                i0.checked_grow(-2),
+                Some(Interval::new(Timestamp(7), Timestamp(8)))
+            );
+            assert_eq!(i0.checked_grow(-3), None); // Would be inverted
+
+            let i1 = Interval::new(Timestamp(0), Timestamp(i64::MAX - 1));
+            assert_eq!(i1.checked_grow(2), None); // Would overflow
+        }
+
+        #[test]
+        fn test_saturating_grow() {
+            let i0 = Interval::new(Timestamp(5), Timestamp(10));
+            assert_eq!(
+                i0.saturating_grow(2),
+                Interval::new(Timestamp(3), Timestamp(12))
+            );
+            assert_eq!(
+                i0.saturating_grow(-3),
+                Interval::new(Timestamp(7), Timestamp(7))
+            );
+
+            let i1 = Interval::new(Timestamp(i64::MIN + 1), Timestamp(i64::MAX - 1));
+            assert_eq!(
+                i1.saturating_grow(2),
+                Interval::new(Timestamp(i64::MIN), Timestamp(i64::MAX))
+            );
+        }
+
+        #[test]
+        fn test_checked_translate() {
+            let i0 = Interval::new(Timestamp(1000), Timestamp(2000));
+            assert_eq!(
+                i0.checked_translate(-250),
+                Some(Interval::new(Timestamp(750), Timestamp(1750)))
+            );
+            assert_eq!(i0.checked_translate(i64::MAX), None);
+            assert_eq!(i0.checked_translate(i64::MIN), Some(i0.translate(i64::MIN)));
+        }
+
+        #[test]
+        fn test_saturating_translate() {
+            let i0 = Interval::new(Timestamp(1000), Timestamp(2000));
+            assert_eq!(i0.saturating_translate(250), i0.translate(250));
+            assert_eq!(
+                i0.saturating_translate(i64::MAX),
+                Interval::new(Timestamp(i64::MAX - 1000), Timestamp(i64::MAX))
+            );
+
+            let i1 = Interval::new(Timestamp(-2000), Timestamp(-1000));
+            assert_eq!(
+                i1.saturating_translate(i64::MIN),
+                Interval::new(Timestamp(i64::MIN), Timestamp(i64::MIN + 1000))
+            );
+        }
+
+        #[test]
+        fn test_saturating_invariants() {
+            // Saturating operations never overflow and never invert
+            let values = [
+                i64::MIN,
+                i64::MIN + 1,
+                -1000,
+                -1,
+                0,
+                1,
+                1000,
+                i64::MAX - 1,
+                i64::MAX,
+            ];
+            for &start in &values {
+                for &stop in &values {
+                    if start > stop {
+                        continue;
+                    }
+                    let i = Interval::new(Timestamp(start), Timestamp(stop));
+                    for &delta in &values {
+                        let grown = i.saturating_grow(delta);
+                        assert!(grown.start <= grown.stop, "{i:?} grow {delta}");
+
+                        let moved = i.saturating_translate(delta);
+                        assert!(moved.start <= moved.stop, "{i:?} translate {delta}");
+                        assert!(moved.duration_ns() <= i.duration_ns());
+                        if let Some(moved) = i.checked_translate(delta) {
+                            assert_eq!(moved.duration_ns(), i.duration_ns());
+                        }
+                        if let Some(grown) = i.checked_grow(delta) {
+                            assert!(grown.start <= grown.stop);
+                        }
+                    }
+                }
+            }
+        }
     }
 
     mod timestamp_units_from_timestamp {
@@ -719,6 +1191,76 @@ mod tests {
             let t0 = Timestamp(123);
             assert_eq!(&format!("{}", t0), "123 ns");
         }
+
+        #[test]
+        fn test_negative_ms() {
+            let t0 = Timestamp(-123_456_789);
+            assert_eq!(&format!("{}", t0), "-123.456 ms");
+        }
+
+        #[test]
+        fn test_negative_fraction() {
+            let t0 = Timestamp(-500_000_000);
+            assert_eq!(&format!("{}", t0), "-500.000 ms");
+            let t1 = Timestamp(-1_500_000_000);
+            assert_eq!(&format!("{}", t1), "-1.500 s");
+        }
+
+        #[test]
+        fn test_min() {
+            let t0 = Timestamp(i64::MIN);
+            assert_eq!(&format!("{}", t0), "-9223372036.854 s");
+        }
+    }
+
+    mod display_units {
+        use super::*;
+
+        #[test]
+        fn test_auto() {
+            let t0 = Timestamp(123_456_789);
+            assert_eq!(
+                &format!("{}", DisplayUnits::Auto.timestamp(t0)),
+                "123.456 ms"
+            );
+        }
+
+        #[test]
+        fn test_fixed() {
+            let t0 = Timestamp(123_456_789);
+            let ns = DisplayUnits::Nanoseconds.timestamp(t0);
+            assert_eq!(&format!("{}", ns), "123456789 ns");
+            let us = DisplayUnits::Microseconds.timestamp(t0);
+            assert_eq!(&format!("{}", us), "123456.789 us");
+            let s = DisplayUnits::Seconds.timestamp(t0);
+            assert_eq!(&format!("{}", s), "0.123 s");
+        }
+
+        #[test]
+        fn test_seconds_fixed() {
+            let t0 = Timestamp(123_456_789);
+            let s = DisplayUnits::SecondsFixed(9).timestamp(t0);
+            assert_eq!(&format!("{}", s), "0.123 456 789 s");
+            let s = DisplayUnits::SecondsFixed(5).timestamp(t0);
+            assert_eq!(&format!("{}", s), "0.123 s");
+            let s = DisplayUnits::SecondsFixed(12).timestamp(t0);
+            assert_eq!(&format!("{}", s), "0.123 456 789 s");
+            let s = DisplayUnits::SecondsFixed(-1).timestamp(t0);
+            assert_eq!(&format!("{}", s), "0 s");
+        }
+
+        #[test]
+        fn test_interval() {
+            let i0 = Interval::new(Timestamp(0), Timestamp(123_456));
+            assert_eq!(
+                &format!("{}", DisplayUnits::Milliseconds.interval(i0)),
+                "from 0.000 to 0.123 ms (duration: 0.123 ms)"
+            );
+            assert_eq!(
+                &format!("{}", DisplayUnits::Auto.interval(i0)),
+                &format!("{}", i0)
+            );
+        }
     }
 
     mod interval_display {
@@ -801,5 +1343,23 @@ mod tests {
             let i0 = Interval::new(Timestamp(0), Timestamp(123));
             assert_eq!(&format!("{}", i0), "from 0 to 123 ns (duration: 123 ns)");
         }
+
+        #[test]
+        fn test_negative_start() {
+            let i0 = Interval::new(Timestamp(-2_000_000_000), Timestamp(500_000_000));
+            assert_eq!(
+                &format!("{}", i0),
+                "from -2.000 to 0.500 s (duration: 2.500 s)"
+            );
+        }
+
+        #[test]
+        fn test_negative() {
+            let i0 = Interval::new(Timestamp(-123_456), Timestamp(-123));
+            assert_eq!(
+                &format!("{}", i0),
+                "from -123.456 to -0.123 us (duration: 123.333 us)"
+            );
+        }
     }
 }
@@ -0,0 +1,221 @@
+use crate::data::{EntryID, EntryIDSlug, ItemUID};
+use crate::timestamp::Interval;
+
+// A view of one or more profiles (the interval, the expanded entries and the
+// selected items) that can be passed on to someone else. It is written as a
+// query string, so that it can be the query of a link to the web viewer, or
+// follow TOKEN_PREFIX in a token pasted into the native viewer:
+//
+//     url=<locator>        one per profile, in order
+//     interval=<start>:<stop>
+//     expand=<pattern>     see EntryPattern, repeatable
+//     select=<profile>/<entry>/<item uid>[/<row>]
+//
+// where the profile is the index of its url, and the entry is given as a
+// slug. Unknown keys are ignored, so that the web viewer can have its own.
+
+pub const TOKEN_PREFIX: &str = "legion-prof:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedItem {
+    pub profile: usize,
+    pub entry_id: EntryID,
+    pub item_uid: ItemUID,
+    // Reversed, as in screen space
+    pub irow: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViewLink {
+    pub sources: Vec<String>,
+    pub interval: Option<Interval>,
+    pub expand: Vec<String>,
+    pub selected: Vec<LinkedItem>,
+}
+
+// Leave the characters that are common in locators and patterns readable
+fn encode(s: &str) -> String {
+    let mut result = String::new();
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~/:*".contains(&b) {
+            result.push(b as char);
+        } else {
+            result.push_str(&format!("%{b:02X}"));
+        }
+    }
+    result
+}
+
+fn decode(s: &str) -> Result<String, String> {
+    let mut result = Vec::new();
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'+' => result.push(b' '),
+            b'%' => {
+                let hex = [bytes.next(), bytes.next()];
+                let hex = hex.iter().flatten().map(|b| *b as char).collect::<String>();
+                let b = u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 2)
+                    .ok_or_else(|| format!("invalid escape %{hex}"))?;
+                result.push(b);
+            }
+            _ => result.push(b),
+        }
+    }
+    String::from_utf8(result).map_err(|e| e.to_string())
+}
+
+impl LinkedItem {
+    fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid item {s} (expected <profile>/<entry>/<uid>[/<row>])");
+        let fields: Vec<_> = s.split('/').collect();
+        let (profile, entry_id, item_uid, irow) = match fields[..] {
+            [profile, entry_id, item_uid] => (profile, entry_id, item_uid, None),
+            [profile, entry_id, item_uid, irow] => (profile, entry_id, item_uid, Some(irow)),
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            profile: profile.parse().map_err(|_| invalid())?,
+            entry_id: EntryID::from_slug(entry_id).map_err(|_| invalid())?,
+            item_uid: ItemUID(item_uid.parse().map_err(|_| invalid())?),
+            irow: irow
+                .map(|irow| irow.parse().map_err(|_| invalid()))
+                .transpose()?,
+        })
+    }
+
+    fn to_slug(&self) -> String {
+        let mut result = format!(
+            "{}/{}/{}",
+            self.profile,
+            EntryIDSlug(&self.entry_id),
+            self.item_uid.0
+        );
+        if let Some(irow) = self.irow {
+            result.push_str(&format!("/{irow}"));
+        }
+        result
+    }
+}
+
+impl ViewLink {
+    pub fn to_query(&self) -> String {
+        let mut pairs = Vec::new();
+        for source in &self.sources {
+            pairs.push(("url", source.clone()));
+        }
+        if let Some(interval) = self.interval {
+            // In nanoseconds, so that the interval is exact
+            let value = format!("{}ns:{}ns", interval.start.0, interval.stop.0);
+            pairs.push(("interval", value));
+        }
+        for pattern in &self.expand {
+            pairs.push(("expand", pattern.clone()));
+        }
+        for item in &self.selected {
+            pairs.push(("select", item.to_slug()));
+        }
+        pairs
+            .iter()
+            .map(|(key, value)| format!("{key}={}", encode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    pub fn parse_query(query: &str) -> Result<Self, String> {
+        let mut result = Self::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode(value)?;
+            match key {
+                "url" => result.sources.push(value),
+                "interval" => result.interval = Some(Interval::parse(&value)?),
+                "expand" => result.expand.push(value),
+                "select" => result.selected.push(LinkedItem::parse(&value)?),
+                _ => (),
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn to_token(&self) -> String {
+        format!("{TOKEN_PREFIX}{}", self.to_query())
+    }
+
+    // Accepts links to the web viewer too, so that they can be opened
+    // natively
+    pub fn parse_token(token: &str) -> Result<Self, String> {
+        let token = token.trim();
+        let query = if let Some(query) = token.strip_prefix(TOKEN_PREFIX) {
+            query
+        } else if let Some((_, query)) = token.split_once('?') {
+            query
+        } else {
+            return Err(format!("not a link to a view (expected {TOKEN_PREFIX}...)"));
+        };
+        // Drop any fragment of a web link
+        let query = query.split('#').next().unwrap_or_default();
+        Self::parse_query(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::timestamp::Timestamp;
+
+    #[test]
+    fn test_view_link_round_trip() {
+        let link = ViewLink {
+            sources: vec![
+                "http://127.0.0.1:8080/?a=1&b=2".to_owned(),
+                "/tmp/legion prof".to_owned(),
+            ],
+            interval: Some(Interval::new(
+                Timestamp(1_234_567_891),
+                Timestamp(2_000_000_000),
+            )),
+            expand: vec!["n0/cpu/*".to_owned()],
+            selected: vec![
+                LinkedItem {
+                    profile: 1,
+                    entry_id: EntryID::root().child(0).child(1),
+                    item_uid: ItemUID(42),
+                    irow: Some(3),
+                },
+                LinkedItem {
+                    profile: 0,
+                    entry_id: EntryID::root().child(2),
+                    item_uid: ItemUID(7),
+                    irow: None,
+                },
+            ],
+        };
+        let token = link.to_token();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(ViewLink::parse_token(&token), Ok(link.clone()));
+
+        let web = format!("https://example.com/viewer/?{}#dev", link.to_query());
+        assert_eq!(ViewLink::parse_token(&web), Ok(link));
+    }
+
+    #[test]
+    fn test_parse_view_link() {
+        let link = ViewLink::parse_query("url=a+b&dev&interval=1s%3A2s").unwrap();
+        assert_eq!(link.sources, vec!["a b"]);
+        assert_eq!(
+            link.interval,
+            Some(Interval::new(
+                Timestamp(1_000_000_000),
+                Timestamp(2_000_000_000)
+            ))
+        );
+        assert!(ViewLink::parse_query("url=%4").is_err());
+        assert!(ViewLink::parse_query("select=0/x/1").is_err());
+        assert!(ViewLink::parse_query("interval=2s:1s").is_err());
+        assert!(ViewLink::parse_token("http://example.com/").is_err());
+    }
+}